@@ -0,0 +1,273 @@
+//! Dynamic credential providers
+//!
+//! `ConnectionConfig::password` is fixed for the lifetime of the config, which does
+//! not work for short-lived credentials (IAM auth tokens, Vault dynamic users).
+//! A [`CredentialProvider`] is asked for credentials on every `Connection::startup`,
+//! so new connections always authenticate with a current user and password.
+
+use crate::Result;
+use futures::future::BoxFuture;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// User and password resolved by a [`CredentialProvider`]
+#[derive(Clone)]
+pub struct Credentials {
+    /// Username sent in the startup message
+    pub user: String,
+    /// Password (None for trust or certificate authentication)
+    pub password: Option<String>,
+    /// When these credentials stop being valid (None = never)
+    pub expires_at: Option<Instant>,
+}
+
+impl Credentials {
+    /// Create credentials without an expiry
+    pub fn new(user: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            user: user.into(),
+            password: Some(password.into()),
+            expires_at: None,
+        }
+    }
+
+    /// Set the expiry as a duration from now
+    pub fn expires_in(mut self, ttl: Duration) -> Self {
+        self.expires_at = Some(Instant::now() + ttl);
+        self
+    }
+
+    /// Check whether the credentials are still valid `margin` from now
+    pub fn is_valid_for(&self, margin: Duration) -> bool {
+        match self.expires_at {
+            Some(expires_at) => Instant::now() + margin < expires_at,
+            None => true,
+        }
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("user", &self.user)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+/// Source of credentials for new connections
+///
+/// Implementations typically call out to a secret store or token service.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[derive(Debug)]
+/// struct VaultProvider { /* ... */ }
+///
+/// impl CredentialProvider for VaultProvider {
+///     fn credentials(&self) -> BoxFuture<'_, Result<Credentials>> {
+///         Box::pin(async move {
+///             let lease = self.fetch_lease().await?;
+///             Ok(Credentials::new(lease.user, lease.password).expires_in(lease.ttl))
+///         })
+///     }
+/// }
+/// ```
+pub trait CredentialProvider: fmt::Debug + Send + Sync {
+    /// Resolve credentials for a new connection
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials>>;
+
+    /// Discard any cached credentials
+    ///
+    /// Called when the server rejects the credentials, so the next connection
+    /// fetches fresh ones. Default: no-op.
+    fn invalidate(&self) {}
+}
+
+/// Caching wrapper around a [`CredentialProvider`]
+///
+/// Reuses credentials until they are within `refresh_margin` of their expiry, then
+/// fetches new ones. Concurrent callers share a single refresh.
+pub struct CachedCredentialProvider<P> {
+    inner: P,
+    refresh_margin: Duration,
+    cached: Mutex<Option<Credentials>>,
+    refresh_lock: tokio::sync::Mutex<()>,
+}
+
+impl<P: CredentialProvider> CachedCredentialProvider<P> {
+    /// Default margin before expiry at which credentials are refreshed
+    pub const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+    /// Wrap a provider with the default refresh margin
+    pub fn new(inner: P) -> Self {
+        Self::with_refresh_margin(inner, Self::DEFAULT_REFRESH_MARGIN)
+    }
+
+    /// Wrap a provider, refreshing `margin` before credentials expire
+    pub fn with_refresh_margin(inner: P, margin: Duration) -> Self {
+        Self {
+            inner,
+            refresh_margin: margin,
+            cached: Mutex::new(None),
+            refresh_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn cached_if_valid(&self) -> Option<Credentials> {
+        let cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+        cached
+            .as_ref()
+            .filter(|c| c.is_valid_for(self.refresh_margin))
+            .cloned()
+    }
+}
+
+impl<P: CredentialProvider> fmt::Debug for CachedCredentialProvider<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedCredentialProvider")
+            .field("inner", &self.inner)
+            .field("refresh_margin", &self.refresh_margin)
+            .finish()
+    }
+}
+
+impl<P: CredentialProvider> CredentialProvider for CachedCredentialProvider<P> {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials>> {
+        Box::pin(async move {
+            if let Some(creds) = self.cached_if_valid() {
+                return Ok(creds);
+            }
+
+            let _guard = self.refresh_lock.lock().await;
+            // Another caller may have refreshed while we waited
+            if let Some(creds) = self.cached_if_valid() {
+                return Ok(creds);
+            }
+
+            tracing::debug!("refreshing credentials");
+            let creds = self.inner.credentials().await?;
+            *self.cached.lock().unwrap_or_else(|e| e.into_inner()) = Some(creds.clone());
+            Ok(creds)
+        })
+    }
+
+    fn invalidate(&self) {
+        self.cached.lock().unwrap_or_else(|e| e.into_inner()).take();
+        self.inner.invalidate();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Stub provider issuing a new password on every fetch
+    #[derive(Debug)]
+    struct StubProvider {
+        fetches: Arc<AtomicUsize>,
+        ttl: Option<Duration>,
+    }
+
+    impl CredentialProvider for StubProvider {
+        fn credentials(&self) -> BoxFuture<'_, Result<Credentials>> {
+            Box::pin(async move {
+                let n = self.fetches.fetch_add(1, Ordering::SeqCst);
+                let creds = Credentials::new("app", format!("token-{}", n));
+                Ok(match self.ttl {
+                    Some(ttl) => creds.expires_in(ttl),
+                    None => creds,
+                })
+            })
+        }
+    }
+
+    fn stub(ttl: Option<Duration>) -> (StubProvider, Arc<AtomicUsize>) {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        (
+            StubProvider {
+                fetches: fetches.clone(),
+                ttl,
+            },
+            fetches,
+        )
+    }
+
+    #[test]
+    fn test_credentials_debug_redacts_password() {
+        let creds = Credentials::new("app", "hunter2");
+        let debug = format!("{:?}", creds);
+        assert!(!debug.contains("hunter2"));
+        assert!(debug.contains("<redacted>"));
+    }
+
+    #[test]
+    fn test_credentials_validity() {
+        let creds = Credentials::new("app", "pw");
+        assert!(creds.is_valid_for(Duration::from_secs(3600)));
+
+        let creds = creds.expires_in(Duration::from_secs(10));
+        assert!(creds.is_valid_for(Duration::from_secs(1)));
+        assert!(!creds.is_valid_for(Duration::from_secs(60)));
+    }
+
+    #[tokio::test]
+    async fn test_cached_provider_reuses_valid_credentials() {
+        let (inner, fetches) = stub(Some(Duration::from_secs(3600)));
+        let provider = CachedCredentialProvider::new(inner);
+
+        let first = provider.credentials().await.unwrap();
+        let second = provider.credentials().await.unwrap();
+        assert_eq!(first.password, second.password);
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cached_provider_refreshes_near_expiry() {
+        // TTL shorter than the refresh margin: every call refreshes
+        let (inner, fetches) = stub(Some(Duration::from_secs(5)));
+        let provider =
+            CachedCredentialProvider::with_refresh_margin(inner, Duration::from_secs(30));
+
+        let first = provider.credentials().await.unwrap();
+        let second = provider.credentials().await.unwrap();
+        assert_eq!(first.password.as_deref(), Some("token-0"));
+        assert_eq!(second.password.as_deref(), Some("token-1"));
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cached_provider_invalidate() {
+        let (inner, fetches) = stub(None);
+        let provider = CachedCredentialProvider::new(inner);
+
+        provider.credentials().await.unwrap();
+        provider.invalidate();
+        let creds = provider.credentials().await.unwrap();
+        assert_eq!(creds.password.as_deref(), Some("token-1"));
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cached_provider_concurrent_callers_share_refresh() {
+        let (inner, fetches) = stub(None);
+        let provider = Arc::new(CachedCredentialProvider::new(inner));
+
+        let mut handles = Vec::new();
+        for _ in 0..8 {
+            let provider = provider.clone();
+            handles.push(tokio::spawn(async move {
+                provider.credentials().await.unwrap()
+            }));
+        }
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().password.as_deref(), Some("token-0"));
+        }
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    }
+}
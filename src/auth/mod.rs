@@ -2,9 +2,12 @@
 //!
 //! Supports SCRAM-SHA-256 (Postgres 10+) as the primary authentication method.
 
+pub mod credentials;
 pub mod scram;
 
-pub use scram::{ScramClient, ScramError};
+pub use credentials::{CachedCredentialProvider, CredentialProvider, Credentials};
+
+pub use scram::{negotiate_mechanism, ChannelBinding, ChannelBindingMode, ScramClient, ScramError};
 
 use std::fmt;
 
@@ -15,6 +18,8 @@ pub enum AuthError {
     Scram(ScramError),
     /// Server doesn't support required mechanism
     MechanismNotSupported(String),
+    /// Channel binding was required but cannot be used
+    ChannelBindingRequired(String),
     /// Invalid server message format
     InvalidServerMessage(String),
     /// UTF-8 encoding error
@@ -28,6 +33,9 @@ impl fmt::Display for AuthError {
             AuthError::MechanismNotSupported(mech) => {
                 write!(f, "server does not support mechanism: {}", mech)
             }
+            AuthError::ChannelBindingRequired(reason) => {
+                write!(f, "channel binding required but unavailable: {}", reason)
+            }
             AuthError::InvalidServerMessage(msg) => {
                 write!(f, "invalid server message format: {}", msg)
             }
@@ -10,6 +10,8 @@ use rand::Rng;
 use sha2::{Digest, Sha256};
 use std::fmt;
 
+use super::AuthError;
+
 type HmacSha256 = Hmac<Sha256>;
 
 /// SCRAM authentication error types
@@ -38,15 +40,112 @@ impl fmt::Display for ScramError {
 
 impl std::error::Error for ScramError {}
 
+/// SASL mechanism name for SCRAM-SHA-256 without channel binding
+pub const SCRAM_SHA_256: &str = "SCRAM-SHA-256";
+
+/// SASL mechanism name for SCRAM-SHA-256 with channel binding
+pub const SCRAM_SHA_256_PLUS: &str = "SCRAM-SHA-256-PLUS";
+
 /// Channel binding type for SCRAM authentication
 #[derive(Clone, Debug)]
 pub enum ChannelBinding {
-    /// No channel binding
+    /// No channel binding (GS2 flag `n`)
     None,
+    /// Client supports channel binding, but the server did not advertise
+    /// SCRAM-SHA-256-PLUS (GS2 flag `y`)
+    ///
+    /// Lets the server detect a downgrade if it actually supports channel binding.
+    ClientSupported,
     /// tls-server-end-point: SHA-256 hash of the server's DER-encoded certificate
     TlsServerEndPoint(Vec<u8>),
 }
 
+/// Channel binding policy matching the libpq `channel_binding` parameter.
+///
+/// Controls whether SCRAM-SHA-256-PLUS is used and whether its absence is fatal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChannelBindingMode {
+    /// Never use channel binding
+    Disable,
+    /// Use channel binding when both the transport and the server support it
+    #[default]
+    Prefer,
+    /// Fail the connection unless the server is authenticated with channel binding
+    ///
+    /// Protects against TLS-terminating proxies relaying SCRAM exchanges.
+    Require,
+}
+
+impl std::fmt::Display for ChannelBindingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Disable => write!(f, "disable"),
+            Self::Prefer => write!(f, "prefer"),
+            Self::Require => write!(f, "require"),
+        }
+    }
+}
+
+impl std::str::FromStr for ChannelBindingMode {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "disable" => Ok(Self::Disable),
+            "prefer" => Ok(Self::Prefer),
+            "require" => Ok(Self::Require),
+            _ => Err(crate::Error::Config(format!(
+                "invalid channel_binding '{}': expected disable, prefer, or require",
+                s
+            ))),
+        }
+    }
+}
+
+/// Select the SASL mechanism and channel binding for a SCRAM exchange
+///
+/// # Arguments
+///
+/// * `mechanisms` - Mechanisms advertised by the server in `AuthenticationSASL`
+/// * `binding_data` - `tls-server-end-point` data from the transport (None without TLS)
+/// * `mode` - Channel binding policy
+///
+/// Under `ChannelBindingMode::Require` this fails closed when either the transport
+/// or the server cannot bind.
+pub fn negotiate_mechanism(
+    mechanisms: &[String],
+    binding_data: Option<Vec<u8>>,
+    mode: ChannelBindingMode,
+) -> Result<(&'static str, ChannelBinding), AuthError> {
+    let offers = |name: &str| mechanisms.iter().any(|m| m == name);
+
+    if mode != ChannelBindingMode::Disable && offers(SCRAM_SHA_256_PLUS) {
+        if let Some(data) = binding_data {
+            return Ok((SCRAM_SHA_256_PLUS, ChannelBinding::TlsServerEndPoint(data)));
+        }
+    }
+
+    if mode == ChannelBindingMode::Require {
+        let reason = if binding_data.is_none() {
+            "the connection is not using TLS"
+        } else {
+            "server does not offer SCRAM-SHA-256-PLUS"
+        };
+        return Err(AuthError::ChannelBindingRequired(reason.to_string()));
+    }
+
+    if !offers(SCRAM_SHA_256) {
+        return Err(AuthError::MechanismNotSupported(mechanisms.join(", ")));
+    }
+
+    // We can bind, but the server did not offer PLUS: say so with the `y` flag
+    let binding = match (mode, binding_data) {
+        (ChannelBindingMode::Prefer, Some(_)) => ChannelBinding::ClientSupported,
+        _ => ChannelBinding::None,
+    };
+    Ok((SCRAM_SHA_256, binding))
+}
+
 /// Internal state needed for SCRAM authentication
 #[derive(Clone, Debug)]
 pub struct ScramState {
@@ -92,13 +191,22 @@ impl ScramClient {
     fn gs2_header(&self) -> &'static str {
         match self.channel_binding {
             ChannelBinding::None => "n",
+            ChannelBinding::ClientSupported => "y",
             ChannelBinding::TlsServerEndPoint(_) => "p=tls-server-end-point",
         }
     }
 
+    /// Client-first-message-bare (RFC 5802): `n=<saslname>,r=<nonce>`
+    fn client_first_bare(&self) -> String {
+        let saslname = self.username.replace('=', "=3D").replace(',', "=2C");
+        format!("n={},r={}", saslname, self.nonce)
+    }
+
     /// Generate client first message
+    ///
+    /// The GS2 header carries no authorization identity; Postgres rejects one.
     pub fn client_first(&self) -> String {
-        format!("{},a={},r={}", self.gs2_header(), self.username, self.nonce)
+        format!("{},,{}", self.gs2_header(), self.client_first_bare())
     }
 
     /// Process server first message and generate client final message
@@ -130,6 +238,10 @@ impl ScramClient {
                 // No channel binding: c = base64("n,,")
                 b"n,,".to_vec()
             }
+            ChannelBinding::ClientSupported => {
+                // Binding supported but not offered: c = base64("y,,")
+                b"y,,".to_vec()
+            }
             ChannelBinding::TlsServerEndPoint(data) => {
                 // tls-server-end-point: c = base64("p=tls-server-end-point,," + cb_data)
                 let mut buf = b"p=tls-server-end-point,,".to_vec();
@@ -143,7 +255,7 @@ impl ScramClient {
         let client_final_without_proof = format!("c={},r={}", channel_binding, server_nonce);
 
         // Build auth message for signature calculation
-        let client_first_bare = format!("a={},r={}", self.username, self.nonce);
+        let client_first_bare = self.client_first_bare();
         let auth_message = format!(
             "{},{},{}",
             client_first_bare, server_first, client_final_without_proof
@@ -316,7 +428,7 @@ mod tests {
         let client = ScramClient::new("alice".to_string(), "secret".to_string());
         let first = client.client_first();
 
-        assert!(first.starts_with("n,a=alice,r="));
+        assert!(first.starts_with("n,,n=alice,r="));
         assert!(first.len() > 20);
     }
 
@@ -366,7 +478,7 @@ mod tests {
         );
         let first = client.client_first();
         // GS2 header should be p=tls-server-end-point
-        assert!(first.starts_with("p=tls-server-end-point,a=alice,r="));
+        assert!(first.starts_with("p=tls-server-end-point,,n=alice,r="));
     }
 
     #[test]
@@ -374,7 +486,7 @@ mod tests {
         let client = ScramClient::new("alice".to_string(), "secret".to_string());
         let first = client.client_first();
         // GS2 header should be n (no channel binding)
-        assert!(first.starts_with("n,a=alice,r="));
+        assert!(first.starts_with("n,,n=alice,r="));
     }
 
     #[test]
@@ -683,4 +795,117 @@ mod tests {
         let result = client.client_final(&server_first);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_client_first_escapes_saslname() {
+        let client = ScramClient::new("a=b,c".to_string(), "pass".to_string());
+        assert!(client.client_first().starts_with("n,,n=a=3Db=2Cc,r="));
+    }
+
+    // ── Channel Binding Negotiation ──────────────────────────────────
+
+    fn mechs(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_channel_binding_mode_parse() {
+        assert_eq!(
+            "disable".parse::<ChannelBindingMode>().unwrap(),
+            ChannelBindingMode::Disable
+        );
+        assert_eq!(
+            "prefer".parse::<ChannelBindingMode>().unwrap(),
+            ChannelBindingMode::Prefer
+        );
+        assert_eq!(
+            "require".parse::<ChannelBindingMode>().unwrap(),
+            ChannelBindingMode::Require
+        );
+        assert!("always".parse::<ChannelBindingMode>().is_err());
+        assert_eq!(ChannelBindingMode::default(), ChannelBindingMode::Prefer);
+        assert_eq!(ChannelBindingMode::Require.to_string(), "require");
+    }
+
+    #[test]
+    fn test_negotiate_prefer_selects_plus_with_tls() {
+        let offered = mechs(&[SCRAM_SHA_256_PLUS, SCRAM_SHA_256]);
+        let (mech, cb) =
+            negotiate_mechanism(&offered, Some(vec![1, 2, 3]), ChannelBindingMode::Prefer).unwrap();
+        assert_eq!(mech, SCRAM_SHA_256_PLUS);
+        assert!(matches!(cb, ChannelBinding::TlsServerEndPoint(ref d) if d == &[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_negotiate_prefer_without_tls_falls_back() {
+        let offered = mechs(&[SCRAM_SHA_256_PLUS, SCRAM_SHA_256]);
+        let (mech, cb) = negotiate_mechanism(&offered, None, ChannelBindingMode::Prefer).unwrap();
+        assert_eq!(mech, SCRAM_SHA_256);
+        assert!(matches!(cb, ChannelBinding::None));
+    }
+
+    #[test]
+    fn test_negotiate_prefer_server_without_plus_sends_y_flag() {
+        let offered = mechs(&[SCRAM_SHA_256]);
+        let (mech, cb) =
+            negotiate_mechanism(&offered, Some(vec![1]), ChannelBindingMode::Prefer).unwrap();
+        assert_eq!(mech, SCRAM_SHA_256);
+        assert!(matches!(cb, ChannelBinding::ClientSupported));
+
+        let client = ScramClient::with_channel_binding("u".into(), "p".into(), cb);
+        assert!(client.client_first().starts_with("y,,n=u,r="));
+    }
+
+    #[test]
+    fn test_negotiate_disable_never_binds() {
+        let offered = mechs(&[SCRAM_SHA_256_PLUS, SCRAM_SHA_256]);
+        let (mech, cb) =
+            negotiate_mechanism(&offered, Some(vec![1]), ChannelBindingMode::Disable).unwrap();
+        assert_eq!(mech, SCRAM_SHA_256);
+        assert!(matches!(cb, ChannelBinding::None));
+    }
+
+    #[test]
+    fn test_negotiate_require_fails_closed() {
+        let offered = mechs(&[SCRAM_SHA_256_PLUS, SCRAM_SHA_256]);
+        let err = negotiate_mechanism(&offered, None, ChannelBindingMode::Require).unwrap_err();
+        assert!(matches!(err, AuthError::ChannelBindingRequired(_)));
+
+        let offered = mechs(&[SCRAM_SHA_256]);
+        let err =
+            negotiate_mechanism(&offered, Some(vec![1]), ChannelBindingMode::Require).unwrap_err();
+        assert!(err.to_string().contains("SCRAM-SHA-256-PLUS"));
+
+        let offered = mechs(&[SCRAM_SHA_256_PLUS]);
+        let (mech, _) =
+            negotiate_mechanism(&offered, Some(vec![1]), ChannelBindingMode::Require).unwrap();
+        assert_eq!(mech, SCRAM_SHA_256_PLUS);
+    }
+
+    #[test]
+    fn test_negotiate_unsupported_mechanism() {
+        let offered = mechs(&["OAUTHBEARER"]);
+        let err = negotiate_mechanism(&offered, None, ChannelBindingMode::Prefer).unwrap_err();
+        assert!(matches!(err, AuthError::MechanismNotSupported(_)));
+    }
+
+    #[test]
+    fn test_client_final_client_supported_binding() {
+        let mut client = ScramClient::with_channel_binding(
+            "user".to_string(),
+            "pass".to_string(),
+            ChannelBinding::ClientSupported,
+        );
+        let _first = client.client_first();
+
+        let server_nonce = format!("{}server_ext", client.nonce);
+        let server_first = format!("r={},s={},i=4096", server_nonce, BASE64.encode(b"salty"));
+        let (client_final, _) = client.client_final(&server_first).unwrap();
+
+        let c_value = client_final
+            .split(',')
+            .find_map(|p| p.strip_prefix("c="))
+            .unwrap();
+        assert_eq!(BASE64.decode(c_value).unwrap(), b"y,,");
+    }
 }
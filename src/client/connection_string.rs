@@ -5,6 +5,7 @@
 //! * postgres:///database (Unix socket, local)
 //! * postgres:///database?host=/path/to/socket (Unix socket, custom directory)
 
+use crate::auth::ChannelBindingMode;
 use crate::connection::{ConnectionConfig, SslMode};
 use crate::{Error, Result};
 use std::path::{Path, PathBuf};
@@ -34,6 +35,8 @@ pub struct ConnectionInfo {
     pub sslcert: Option<String>,
     /// Path to client private key (from sslkey param, for mTLS)
     pub sslkey: Option<String>,
+    /// SCRAM channel binding policy (from channel_binding param)
+    pub channel_binding: ChannelBindingMode,
 }
 
 /// Transport type
@@ -76,6 +79,14 @@ fn parse_query_param(query_string: &str, param: &str) -> Option<String> {
     None
 }
 
+/// Parse the `channel_binding` query parameter (default: prefer)
+fn parse_channel_binding(query_string: &str) -> Result<ChannelBindingMode> {
+    match parse_query_param(query_string, "channel_binding") {
+        Some(mode) => mode.parse(),
+        None => Ok(ChannelBindingMode::default()),
+    }
+}
+
 /// Construct the full Unix socket path
 fn construct_socket_path(socket_dir: &str, port: u16) -> PathBuf {
     PathBuf::from(format!("{}/.s.PGSQL.{}", socket_dir, port))
@@ -143,6 +154,9 @@ impl ConnectionInfo {
 
         let unix_socket = Some(construct_socket_path(&socket_dir, port));
 
+        // Honoured so that channel_binding=require fails closed without TLS
+        let channel_binding = parse_channel_binding(query_string)?;
+
         Ok(Self {
             transport: TransportType::Unix,
             host: None,
@@ -155,6 +169,7 @@ impl ConnectionInfo {
             sslrootcert: None,
             sslcert: None,
             sslkey: None,
+            channel_binding,
         })
     }
 
@@ -212,6 +227,7 @@ impl ConnectionInfo {
         let sslrootcert = parse_query_param(query_string, "sslrootcert");
         let sslcert = parse_query_param(query_string, "sslcert");
         let sslkey = parse_query_param(query_string, "sslkey");
+        let channel_binding = parse_channel_binding(query_string)?;
 
         Ok(Self {
             transport: TransportType::Tcp,
@@ -225,6 +241,7 @@ impl ConnectionInfo {
             sslrootcert,
             sslcert,
             sslkey,
+            channel_binding,
         })
     }
 
@@ -270,6 +287,7 @@ impl ConnectionInfo {
             config = config.password(password);
         }
         config.sslmode = self.sslmode;
+        config.channel_binding = self.channel_binding;
         config
     }
 }
@@ -431,6 +449,7 @@ mod tests {
             sslrootcert: None,
             sslcert: None,
             sslkey: None,
+            channel_binding: ChannelBindingMode::default(),
         };
         let tls = info.to_tls_config().unwrap();
         assert!(tls.is_some());
@@ -452,11 +471,38 @@ mod tests {
             sslrootcert: None,
             sslcert: None,
             sslkey: None,
+            channel_binding: ChannelBindingMode::default(),
         };
         let tls = info.to_tls_config().unwrap();
         assert!(tls.is_none());
     }
 
+    #[test]
+    fn test_parse_channel_binding() {
+        let info = ConnectionInfo::parse("postgres://localhost/mydb").unwrap();
+        assert_eq!(info.channel_binding, ChannelBindingMode::Prefer);
+
+        let info = ConnectionInfo::parse(
+            "postgres://localhost/mydb?sslmode=verify-full&channel_binding=require",
+        )
+        .unwrap();
+        assert_eq!(info.channel_binding, ChannelBindingMode::Require);
+        assert_eq!(
+            info.to_config().channel_binding,
+            ChannelBindingMode::Require
+        );
+
+        let info =
+            ConnectionInfo::parse("postgres:///mydb?host=/tmp&channel_binding=disable").unwrap();
+        assert_eq!(info.channel_binding, ChannelBindingMode::Disable);
+    }
+
+    #[test]
+    fn test_parse_invalid_channel_binding() {
+        let result = ConnectionInfo::parse("postgres://localhost/mydb?channel_binding=always");
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_parse_unix_ignores_sslmode() {
         use crate::connection::SslMode;
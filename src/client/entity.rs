@@ -0,0 +1,231 @@
+//! Entity references
+//!
+//! The relation named after `FROM` is never interpolated as raw text: entity
+//! names are parsed into an [`EntityRef`], validated like field names and
+//! double-quoted, so `users; DROP TABLE users` is rejected and `MyView` keeps
+//! its case. Clients can also qualify unqualified names with a default schema
+//! and restrict queries to an allowlist (see [`EntityPolicy`]).
+
+use crate::{Error, Result};
+use std::fmt;
+
+/// Longest identifier Postgres keeps (`NAMEDATALEN - 1`); longer names are
+/// silently truncated by the server
+const MAX_IDENTIFIER_LEN: usize = 63;
+
+/// A relation name, optionally schema-qualified
+///
+/// Renders as `"schema"."name"`. Identifiers are case-sensitive: `MyView`
+/// refers to the view created as `"MyView"`, not `myview`.
+///
+/// # Examples
+///
+/// ```
+/// use fraiseql_wire::client::EntityRef;
+///
+/// let entity = EntityRef::parse("reporting.v_Orders").unwrap();
+/// assert_eq!(entity.to_sql(), r#""reporting"."v_Orders""#);
+/// assert_eq!(entity.to_string(), "reporting.v_Orders");
+///
+/// assert!(EntityRef::parse("users; DROP TABLE users").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EntityRef {
+    schema: Option<String>,
+    name: String,
+}
+
+impl EntityRef {
+    /// An unqualified entity
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            schema: None,
+            name: name.into(),
+        }
+    }
+
+    /// A schema-qualified entity
+    pub fn qualified(schema: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            schema: Some(schema.into()),
+            name: name.into(),
+        }
+    }
+
+    /// Parse `name` or `schema.name` and validate it
+    pub fn parse(entity: &str) -> Result<Self> {
+        let parsed = Self::split(entity);
+        parsed.validate().map_err(Error::Config)?;
+        Ok(parsed)
+    }
+
+    /// Split `schema.name` without validating
+    pub(crate) fn split(entity: &str) -> Self {
+        match entity.split_once('.') {
+            Some((schema, name)) => Self::qualified(schema, name),
+            None => Self::new(entity),
+        }
+    }
+
+    /// The schema, if qualified
+    pub fn schema(&self) -> Option<&str> {
+        self.schema.as_deref()
+    }
+
+    /// The relation name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Validate both identifiers
+    ///
+    /// Each must start with a letter or underscore, continue with letters,
+    /// digits or underscores (as `Field::validate`), and fit in 63 bytes.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        for identifier in self.schema.iter().chain(std::iter::once(&self.name)) {
+            if !is_valid_identifier(identifier) {
+                return Err(format!("Invalid entity name: {}", self));
+            }
+        }
+        Ok(())
+    }
+
+    /// Qualify with `schema` unless already qualified
+    pub fn or_schema(self, schema: Option<&str>) -> Self {
+        match (self.schema, schema) {
+            (None, Some(schema)) => Self::qualified(schema, self.name),
+            (schema, _) => Self {
+                schema,
+                name: self.name,
+            },
+        }
+    }
+
+    /// The quoted SQL identifier: `"schema"."name"`
+    pub fn to_sql(&self) -> String {
+        let quote = |identifier: &str| format!("\"{}\"", identifier.replace('"', "\"\""));
+        match self.schema {
+            Some(ref schema) => format!("{}.{}", quote(schema), quote(&self.name)),
+            None => quote(&self.name),
+        }
+    }
+}
+
+impl fmt::Display for EntityRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.schema {
+            Some(ref schema) => write!(f, "{}.{}", schema, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+fn is_valid_identifier(identifier: &str) -> bool {
+    let mut chars = identifier.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && identifier.len() <= MAX_IDENTIFIER_LEN
+}
+
+/// How a client resolves and restricts entities
+///
+/// Configured with `FraiseClient::default_schema()` and
+/// `FraiseClient::allow_entities()`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EntityPolicy {
+    /// Schema for unqualified entities
+    pub(crate) default_schema: Option<String>,
+    /// Permitted entities, qualified with the default schema when checked
+    ///
+    /// Entries are not validated; invalid ones never match.
+    pub(crate) allowlist: Option<Vec<EntityRef>>,
+}
+
+impl EntityPolicy {
+    /// Parse, qualify and check an entity name
+    pub(crate) fn resolve(&self, entity: &str) -> Result<EntityRef> {
+        let schema = self.default_schema.as_deref();
+        let entity = EntityRef::parse(entity)?.or_schema(schema);
+        entity.validate().map_err(Error::Config)?;
+        if let Some(ref allowlist) = self.allowlist {
+            if !allowlist
+                .iter()
+                .any(|allowed| allowed.clone().or_schema(schema) == entity)
+            {
+                return Err(Error::Config(format!(
+                    "entity '{}' is not in the client's allowlist",
+                    entity
+                )));
+            }
+        }
+        Ok(entity)
+    }
+
+    /// Metrics label for a resolved entity
+    ///
+    /// Only allowlisted names are bounded, so without an allowlist the label
+    /// is left to the connection (see `Connection::set_metrics_entity`).
+    pub(crate) fn metrics_label(&self, entity: &EntityRef) -> Option<String> {
+        self.allowlist.as_ref().map(|_| entity.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_quote() {
+        let entity = EntityRef::parse("v_user").unwrap();
+        assert_eq!(entity.to_sql(), "\"v_user\"");
+        assert_eq!(entity.schema(), None);
+
+        let entity = EntityRef::parse("Reporting.MyView").unwrap();
+        assert_eq!(entity.to_sql(), "\"Reporting\".\"MyView\"");
+        assert_eq!(entity.to_string(), "Reporting.MyView");
+
+        for bad in [
+            "",
+            "a.b.c",
+            ".users",
+            "users.",
+            "1users",
+            "users; DROP TABLE users",
+            "\"users\"",
+            "(SELECT 1) t",
+            "x".repeat(64).as_str(),
+        ] {
+            assert!(EntityRef::parse(bad).is_err(), "{}", bad);
+        }
+        assert!(EntityRef::parse(&"x".repeat(63)).is_ok());
+    }
+
+    #[test]
+    fn test_policy() {
+        let open = EntityPolicy::default();
+        assert_eq!(open.resolve("users").unwrap(), EntityRef::new("users"));
+        assert_eq!(open.metrics_label(&EntityRef::new("users")), None);
+
+        let policy = EntityPolicy {
+            default_schema: Some("app".into()),
+            allowlist: Some(vec![
+                EntityRef::new("users"),
+                EntityRef::qualified("audit", "events"),
+            ]),
+        };
+        let users = policy.resolve("users").unwrap();
+        assert_eq!(users.to_sql(), "\"app\".\"users\"");
+        assert_eq!(policy.metrics_label(&users).as_deref(), Some("app.users"));
+        assert!(policy.resolve("app.users").is_ok());
+        assert!(policy.resolve("audit.events").is_ok());
+        assert!(policy.resolve("events").is_err());
+        assert!(policy.resolve("public.users").is_err());
+        assert!(policy.resolve("orders").is_err());
+
+        let bad_schema = EntityPolicy {
+            default_schema: Some("app; --".into()),
+            allowlist: None,
+        };
+        assert!(bad_schema.resolve("users").is_err());
+    }
+}
@@ -0,0 +1,400 @@
+//! EXPLAIN plans and cost guards
+//!
+//! `QueryBuilder::explain()` returns the parsed `EXPLAIN (FORMAT JSON)` plan of
+//! the query the builder would run. A cost guard (`max_estimated_cost()`,
+//! `max_estimated_rows()`, `forbid_seq_scan_on()`) plans the query before
+//! `execute()` and refuses it with `Error::QueryRejected` when the estimate is
+//! too large, so an unbounded consumer-driven filter never reaches the
+//! executor. Client-side limits such as `max_memory()` only protect the client.
+
+use super::entity::EntityRef;
+use crate::{Error, Result};
+use serde_json::Value;
+use std::fmt;
+
+/// Options for `QueryBuilder::explain()`
+///
+/// Plans are always requested in JSON format. `analyze()` runs the query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExplainOptions {
+    /// Run the query and report actual rows and timings (`ANALYZE`)
+    pub analyze: bool,
+
+    /// Include output columns and schema names (`VERBOSE`)
+    pub verbose: bool,
+
+    /// Include buffer usage (`BUFFERS`)
+    pub buffers: bool,
+
+    /// Include non-default planner settings (`SETTINGS`)
+    pub settings: bool,
+}
+
+impl ExplainOptions {
+    /// Estimated plan only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run the query and report actual rows and timings
+    pub fn analyze(mut self) -> Self {
+        self.analyze = true;
+        self
+    }
+
+    /// Include output columns and schema names
+    pub fn verbose(mut self) -> Self {
+        self.verbose = true;
+        self
+    }
+
+    /// Include buffer usage
+    pub fn buffers(mut self) -> Self {
+        self.buffers = true;
+        self
+    }
+
+    /// Include non-default planner settings
+    pub fn settings(mut self) -> Self {
+        self.settings = true;
+        self
+    }
+
+    /// `EXPLAIN (FORMAT JSON, ...) query`
+    pub(crate) fn explain_sql(&self, query: &str) -> String {
+        let mut options = vec!["FORMAT JSON"];
+        for (enabled, option) in [
+            (self.analyze, "ANALYZE"),
+            (self.verbose, "VERBOSE"),
+            (self.buffers, "BUFFERS"),
+            (self.settings, "SETTINGS"),
+        ] {
+            if enabled {
+                options.push(option);
+            }
+        }
+        format!("EXPLAIN ({}) {}", options.join(", "), query)
+    }
+}
+
+/// A parsed `EXPLAIN (FORMAT JSON)` plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryPlan {
+    /// The top plan node
+    pub root: PlanNode,
+
+    /// Planning time in milliseconds (with `analyze()`)
+    pub planning_time_ms: Option<f64>,
+
+    /// Execution time in milliseconds (with `analyze()`)
+    pub execution_time_ms: Option<f64>,
+
+    /// The full EXPLAIN output, for fields not parsed into [`PlanNode`]
+    pub json: Value,
+}
+
+/// A node of a [`QueryPlan`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanNode {
+    /// Node type (e.g. `Seq Scan`, `Index Scan`, `Hash Join`)
+    pub node_type: String,
+
+    /// Scanned relation, for scan nodes
+    pub relation: Option<String>,
+
+    /// Schema of the scanned relation (with `verbose()`)
+    pub schema: Option<String>,
+
+    /// Estimated cost before the first row
+    pub startup_cost: f64,
+
+    /// Estimated total cost
+    pub total_cost: f64,
+
+    /// Estimated number of rows
+    pub plan_rows: f64,
+
+    /// Actual number of rows per loop (with `analyze()`)
+    pub actual_rows: Option<f64>,
+
+    /// Child nodes
+    pub children: Vec<PlanNode>,
+}
+
+impl QueryPlan {
+    /// Parse the JSON document returned by `EXPLAIN (FORMAT JSON)`
+    pub fn parse(json: Value) -> Result<Self> {
+        let top = json
+            .get(0)
+            .ok_or_else(|| Error::Protocol("EXPLAIN returned an empty plan".into()))?;
+        let root = PlanNode::parse(
+            top.get("Plan")
+                .ok_or_else(|| Error::Protocol("EXPLAIN output has no 'Plan'".into()))?,
+        )?;
+        Ok(Self {
+            root,
+            planning_time_ms: top.get("Planning Time").and_then(Value::as_f64),
+            execution_time_ms: top.get("Execution Time").and_then(Value::as_f64),
+            json,
+        })
+    }
+
+    /// Estimated total cost of the query
+    pub fn total_cost(&self) -> f64 {
+        self.root.total_cost
+    }
+
+    /// Estimated number of rows returned
+    pub fn estimated_rows(&self) -> f64 {
+        self.root.plan_rows
+    }
+
+    /// All nodes, depth-first from the root
+    pub fn nodes(&self) -> Vec<&PlanNode> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            nodes.push(node);
+            stack.extend(node.children.iter().rev());
+        }
+        nodes
+    }
+
+    /// Sequential scan nodes
+    pub fn seq_scans(&self) -> Vec<&PlanNode> {
+        self.nodes()
+            .into_iter()
+            .filter(|node| node.node_type == "Seq Scan")
+            .collect()
+    }
+}
+
+impl PlanNode {
+    fn parse(node: &Value) -> Result<Self> {
+        let number = |key: &str| {
+            node.get(key).and_then(Value::as_f64).ok_or_else(|| {
+                Error::Protocol(format!("EXPLAIN plan node has no numeric '{}'", key))
+            })
+        };
+        let text = |key: &str| node.get(key).and_then(Value::as_str).map(str::to_string);
+        let children = match node.get("Plans").and_then(Value::as_array) {
+            Some(plans) => plans.iter().map(PlanNode::parse).collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            node_type: text("Node Type")
+                .ok_or_else(|| Error::Protocol("EXPLAIN plan node has no 'Node Type'".into()))?,
+            relation: text("Relation Name"),
+            schema: text("Schema"),
+            startup_cost: number("Startup Cost")?,
+            total_cost: number("Total Cost")?,
+            plan_rows: number("Plan Rows")?,
+            actual_rows: node.get("Actual Rows").and_then(Value::as_f64),
+            children,
+        })
+    }
+}
+
+/// Why a cost guard refused a query (see `Error::QueryRejected`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum GuardViolation {
+    /// The estimated total cost exceeds `max_estimated_cost()`
+    EstimatedCost {
+        /// Planner estimate
+        estimated: f64,
+        /// Configured maximum
+        limit: f64,
+    },
+
+    /// The estimated row count exceeds `max_estimated_rows()`
+    EstimatedRows {
+        /// Planner estimate
+        estimated: f64,
+        /// Configured maximum
+        limit: u64,
+    },
+
+    /// The plan sequentially scans a relation given to `forbid_seq_scan_on()`
+    SeqScan {
+        /// The scanned relation, schema-qualified when known
+        relation: String,
+    },
+}
+
+impl fmt::Display for GuardViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GuardViolation::EstimatedCost { estimated, limit } => write!(
+                f,
+                "estimated cost {} exceeds the maximum of {}",
+                estimated, limit
+            ),
+            GuardViolation::EstimatedRows { estimated, limit } => write!(
+                f,
+                "estimated {} rows exceeds the maximum of {}",
+                estimated, limit
+            ),
+            GuardViolation::SeqScan { relation } => {
+                write!(f, "plan contains a sequential scan on {}", relation)
+            }
+        }
+    }
+}
+
+/// Limits checked against the plan before a query runs
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CostGuard {
+    pub(crate) max_estimated_cost: Option<f64>,
+    pub(crate) max_estimated_rows: Option<u64>,
+    /// Relations (`name` or `schema.name`) that must not be scanned sequentially
+    pub(crate) forbid_seq_scan_on: Vec<EntityRef>,
+}
+
+impl CostGuard {
+    /// Whether any limit is set
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.max_estimated_cost.is_some()
+            || self.max_estimated_rows.is_some()
+            || !self.forbid_seq_scan_on.is_empty()
+    }
+
+    /// EXPLAIN options for the check (schemas are needed to match qualified
+    /// relations)
+    pub(crate) fn explain_options(&self) -> ExplainOptions {
+        ExplainOptions {
+            verbose: self.forbid_seq_scan_on.iter().any(|r| r.schema().is_some()),
+            ..ExplainOptions::default()
+        }
+    }
+
+    /// Check `plan` against the limits
+    pub(crate) fn check(&self, plan: &QueryPlan) -> std::result::Result<(), GuardViolation> {
+        if let Some(limit) = self.max_estimated_cost {
+            if plan.total_cost() > limit {
+                return Err(GuardViolation::EstimatedCost {
+                    estimated: plan.total_cost(),
+                    limit,
+                });
+            }
+        }
+        if let Some(limit) = self.max_estimated_rows {
+            if plan.estimated_rows() > limit as f64 {
+                return Err(GuardViolation::EstimatedRows {
+                    estimated: plan.estimated_rows(),
+                    limit,
+                });
+            }
+        }
+        for scan in plan.seq_scans() {
+            let Some(ref relation) = scan.relation else {
+                continue;
+            };
+            let forbidden = self.forbid_seq_scan_on.iter().any(|forbidden| {
+                forbidden.name() == relation
+                    && forbidden
+                        .schema()
+                        .map_or(true, |schema| scan.schema.as_deref() == Some(schema))
+            });
+            if forbidden {
+                let relation = match scan.schema {
+                    Some(ref schema) => format!("{}.{}", schema, relation),
+                    None => relation.clone(),
+                };
+                return Err(GuardViolation::SeqScan { relation });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn plan() -> QueryPlan {
+        QueryPlan::parse(json!([{
+            "Plan": {
+                "Node Type": "Hash Join",
+                "Startup Cost": 1.5,
+                "Total Cost": 1200.25,
+                "Plan Rows": 5000,
+                "Plans": [
+                    {"Node Type": "Seq Scan", "Relation Name": "orders", "Schema": "app",
+                     "Startup Cost": 0.0, "Total Cost": 900.0, "Plan Rows": 40000},
+                    {"Node Type": "Hash", "Startup Cost": 1.0, "Total Cost": 1.0, "Plan Rows": 10,
+                     "Plans": [{"Node Type": "Index Scan", "Relation Name": "users",
+                                "Startup Cost": 0.1, "Total Cost": 1.0, "Plan Rows": 10}]}
+                ]
+            },
+            "Planning Time": 0.2
+        }]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_plan() {
+        let plan = plan();
+        assert_eq!(plan.total_cost(), 1200.25);
+        assert_eq!(plan.estimated_rows(), 5000.0);
+        assert_eq!(plan.planning_time_ms, Some(0.2));
+        assert_eq!(plan.execution_time_ms, None);
+        let types: Vec<_> = plan.nodes().iter().map(|n| n.node_type.as_str()).collect();
+        assert_eq!(types, ["Hash Join", "Seq Scan", "Hash", "Index Scan"]);
+        assert_eq!(plan.seq_scans()[0].relation.as_deref(), Some("orders"));
+
+        assert!(QueryPlan::parse(json!([])).is_err());
+        assert!(QueryPlan::parse(json!([{"Plan": {"Node Type": "Result"}}])).is_err());
+    }
+
+    #[test]
+    fn test_explain_sql() {
+        assert_eq!(
+            ExplainOptions::new().explain_sql("SELECT 1"),
+            "EXPLAIN (FORMAT JSON) SELECT 1"
+        );
+        assert_eq!(
+            ExplainOptions::new()
+                .analyze()
+                .buffers()
+                .explain_sql("SELECT 1"),
+            "EXPLAIN (FORMAT JSON, ANALYZE, BUFFERS) SELECT 1"
+        );
+    }
+
+    #[test]
+    fn test_cost_guard() {
+        let plan = plan();
+        let guard = CostGuard::default();
+        assert!(!guard.is_enabled());
+        assert_eq!(guard.check(&plan), Ok(()));
+
+        let guard = CostGuard {
+            max_estimated_cost: Some(1000.0),
+            ..CostGuard::default()
+        };
+        assert!(matches!(
+            guard.check(&plan),
+            Err(GuardViolation::EstimatedCost { limit, .. }) if limit == 1000.0
+        ));
+
+        let guard = CostGuard {
+            max_estimated_rows: Some(5000),
+            ..CostGuard::default()
+        };
+        assert_eq!(guard.check(&plan), Ok(()));
+
+        for (forbidden, rejected) in [
+            ("orders", true),
+            ("app.orders", true),
+            ("public.orders", false),
+            ("users", false),
+        ] {
+            let guard = CostGuard {
+                forbid_seq_scan_on: vec![EntityRef::split(forbidden)],
+                ..CostGuard::default()
+            };
+            assert_eq!(guard.check(&plan).is_err(), rejected, "{}", forbidden);
+        }
+    }
+}
@@ -1,15 +1,32 @@
 //! FraiseClient implementation
 
 use super::connection_string::{ConnectionInfo, TransportType};
+use super::entity::{EntityPolicy, EntityRef};
+use super::explain::{ExplainOptions, QueryPlan};
 use super::query_builder::QueryBuilder;
-use crate::connection::{Connection, ConnectionConfig, SslMode, Transport};
+use crate::connection::{
+    Connection, ConnectionConfig, ExecutionMode, IsolationLevel, ShutdownHandle, SnapshotId,
+    SslMode, TlsConfig, TransactionStatus, Transport,
+};
 use crate::stream::JsonStream;
 use crate::Result;
 use serde::de::DeserializeOwned;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::Instrument;
 
 /// FraiseQL wire protocol client
 pub struct FraiseClient {
     conn: Connection,
+    target: Arc<ConnectTarget>,
+    entities: Arc<EntityPolicy>,
+}
+
+/// Everything needed to open another connection like this one
+struct ConnectTarget {
+    info: ConnectionInfo,
+    config: ConnectionConfig,
+    tls_config: Option<TlsConfig>,
 }
 
 impl FraiseClient {
@@ -32,25 +49,14 @@ impl FraiseClient {
     pub async fn connect(connection_string: &str) -> Result<Self> {
         let info = ConnectionInfo::parse(connection_string)?;
         let tls_config = info.to_tls_config()?;
-
-        let transport = match info.transport {
-            TransportType::Tcp => {
-                let host = info.host.as_ref().expect("TCP requires host");
-                let port = info.port.expect("TCP requires port");
-                Transport::connect_tcp(host, port).await?
-            }
-            TransportType::Unix => {
-                let path = info.unix_socket.as_ref().expect("Unix requires path");
-                Transport::connect_unix(path).await?
-            }
-        };
-
-        let mut conn = Connection::new(transport);
         let config = info.to_config();
-        let hostname = info.host.as_deref();
-        conn.startup(&config, tls_config.as_ref(), hostname).await?;
 
-        Ok(Self { conn })
+        Self::open(Arc::new(ConnectTarget {
+            info,
+            config,
+            tls_config,
+        }))
+        .await
     }
 
     /// Connect to Postgres with TLS encryption
@@ -75,28 +81,17 @@ impl FraiseClient {
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn connect_tls(
-        connection_string: &str,
-        tls_config: crate::connection::TlsConfig,
-    ) -> Result<Self> {
+    pub async fn connect_tls(connection_string: &str, tls_config: TlsConfig) -> Result<Self> {
         let info = ConnectionInfo::parse(connection_string)?;
+        let mut config = info.to_config();
+        config.sslmode = SslMode::Require;
 
-        match info.transport {
-            TransportType::Tcp => {
-                let host = info.host.as_ref().expect("TCP requires host");
-                let port = info.port.expect("TCP requires port");
-                // Start with plain TCP — SSLRequest negotiation upgrades to TLS
-                let transport = Transport::connect_tcp(host, port).await?;
-                let mut conn = Connection::new(transport);
-                let mut config = info.to_config();
-                config.sslmode = SslMode::Require;
-                conn.startup(&config, Some(&tls_config), Some(host)).await?;
-                Ok(Self { conn })
-            }
-            TransportType::Unix => Err(crate::Error::Config(
-                "TLS is only supported for TCP connections".into(),
-            )),
-        }
+        Self::open(Arc::new(ConnectTarget {
+            info,
+            config,
+            tls_config: Some(tls_config),
+        }))
+        .await
     }
 
     /// Connect to Postgres with custom connection configuration
@@ -133,23 +128,12 @@ impl FraiseClient {
         // Build TLS config from the ConnectionConfig's sslmode + connection string cert paths
         let tls_config = info.to_tls_config()?;
 
-        let transport = match info.transport {
-            TransportType::Tcp => {
-                let host = info.host.as_ref().expect("TCP requires host");
-                let port = info.port.expect("TCP requires port");
-                Transport::connect_tcp(host, port).await?
-            }
-            TransportType::Unix => {
-                let path = info.unix_socket.as_ref().expect("Unix requires path");
-                Transport::connect_unix(path).await?
-            }
-        };
-
-        let mut conn = Connection::new(transport);
-        let hostname = info.host.as_deref();
-        conn.startup(&config, tls_config.as_ref(), hostname).await?;
-
-        Ok(Self { conn })
+        Self::open(Arc::new(ConnectTarget {
+            info,
+            config,
+            tls_config,
+        }))
+        .await
     }
 
     /// Connect to Postgres with both custom configuration and TLS encryption
@@ -188,24 +172,365 @@ impl FraiseClient {
     pub async fn connect_with_config_and_tls(
         connection_string: &str,
         config: ConnectionConfig,
-        tls_config: crate::connection::TlsConfig,
+        tls_config: TlsConfig,
     ) -> Result<Self> {
         let info = ConnectionInfo::parse(connection_string)?;
 
-        match info.transport {
-            TransportType::Tcp => {
-                let host = info.host.as_ref().expect("TCP requires host");
-                let port = info.port.expect("TCP requires port");
-                // Start with plain TCP — SSLRequest negotiation upgrades to TLS
-                let transport = Transport::connect_tcp(host, port).await?;
-                let mut conn = Connection::new(transport);
-                conn.startup(&config, Some(&tls_config), Some(host)).await?;
-                Ok(Self { conn })
-            }
-            TransportType::Unix => Err(crate::Error::Config(
-                "TLS is only supported for TCP connections".into(),
-            )),
+        Self::open(Arc::new(ConnectTarget {
+            info,
+            config,
+            tls_config: Some(tls_config),
+        }))
+        .await
+    }
+
+    /// Open a connection to `target` and run startup
+    async fn open(target: Arc<ConnectTarget>) -> Result<Self> {
+        let info = &target.info;
+        let span = match info.transport {
+            TransportType::Tcp => tracing::info_span!(
+                "connect",
+                db.system = "postgresql",
+                db.name = %info.database,
+                server.address = info.host.as_deref().unwrap_or_default(),
+                server.port = info.port.unwrap_or_default(),
+                network.transport = "tcp",
+            ),
+            TransportType::Unix => tracing::info_span!(
+                "connect",
+                db.system = "postgresql",
+                db.name = %info.database,
+                server.address = %info.unix_socket.as_deref().unwrap_or(Path::new("")).display(),
+                network.transport = "unix",
+            ),
+        };
+
+        let conn = async {
+            let conn = match info.transport {
+                TransportType::Tcp => {
+                    let host = info.host.as_ref().expect("TCP requires host");
+                    let port = info.port.expect("TCP requires port");
+                    // Start with plain TCP — SSLRequest negotiation upgrades to TLS
+                    let transport = Transport::connect_tcp(host, port).await?;
+                    let mut conn = Connection::new(transport);
+                    conn.startup(&target.config, target.tls_config.as_ref(), Some(host))
+                        .await?;
+                    conn
+                }
+                TransportType::Unix => {
+                    if target.tls_config.is_some() {
+                        return Err(crate::Error::Config(
+                            "TLS is only supported for TCP connections".into(),
+                        ));
+                    }
+                    let path = info.unix_socket.as_ref().expect("Unix requires path");
+                    let transport = Transport::connect_unix(path).await?;
+                    let mut conn = Connection::new(transport);
+                    conn.startup(&target.config, None, None).await?;
+                    conn
+                }
+            };
+            Ok::<_, crate::Error>(conn)
+        }
+        .instrument(span)
+        .await?;
+
+        Ok(Self {
+            conn,
+            target,
+            entities: Arc::default(),
+        })
+    }
+
+    /// A client over a socket that is never started up, for building queries in
+    /// unit tests
+    #[cfg(test)]
+    pub(crate) fn unconnected() -> Self {
+        let (socket, _peer) = tokio::net::UnixStream::pair().expect("socket pair");
+        let info = ConnectionInfo::parse("postgres://localhost/test").expect("connection string");
+        Self {
+            conn: Connection::new(Transport::Unix(socket)),
+            target: Arc::new(ConnectTarget {
+                config: info.to_config(),
+                info,
+                tls_config: None,
+            }),
+            entities: Arc::default(),
+        }
+    }
+
+    /// Open another connection with the same target, configuration and TLS settings
+    ///
+    /// Credential providers are consulted again, so siblings pick up rotated
+    /// credentials. With `snapshot`, the sibling opens a `REPEATABLE READ`
+    /// read-only transaction and imports it.
+    pub(crate) async fn connect_sibling(&self, snapshot: Option<&SnapshotId>) -> Result<Self> {
+        let mut sibling = Self::open(Arc::clone(&self.target)).await?;
+        sibling.entities = Arc::clone(&self.entities);
+        if let Some(handle) = self.conn.shutdown_handle() {
+            sibling.conn.set_shutdown_handle(handle.clone());
+        }
+        if let Some(snapshot) = snapshot {
+            sibling.conn.begin(IsolationLevel::RepeatableRead).await?;
+            sibling.conn.set_transaction_snapshot(snapshot).await?;
         }
+        Ok(sibling)
+    }
+
+    /// Export the snapshot of the open transaction, if any
+    ///
+    /// Transactions are only opened by `begin_snapshot()` and
+    /// `query_in_snapshot()`, which both hold one snapshot for the transaction.
+    pub(crate) async fn transaction_snapshot(&mut self) -> Result<Option<SnapshotId>> {
+        if self.conn.transaction_status() != TransactionStatus::InTransaction {
+            return Ok(None);
+        }
+        self.conn.export_snapshot().await.map(Some)
+    }
+
+    /// Attach a shutdown handle to streams started from this client
+    ///
+    /// `ShutdownHandle::shutdown(deadline)` then cancels the stream's query on the
+    /// server, closes the connection and ends the stream with `Error::Cancelled`.
+    /// Parallel scans attach the handle to every partition connection.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let shutdown = ShutdownHandle::new();
+    /// let client = FraiseClient::connect(url).await?.with_shutdown(shutdown.clone());
+    /// ```
+    pub fn with_shutdown(mut self, handle: ShutdownHandle) -> Self {
+        self.conn.set_shutdown_handle(handle);
+        self
+    }
+
+    /// Qualify unqualified entity names with `schema`
+    ///
+    /// `query("users")` then reads `"schema"."users"` instead of resolving
+    /// `users` through the server's `search_path`. The schema name is
+    /// validated like an entity name when a query is built.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let client = FraiseClient::connect(url).await?.default_schema("reporting");
+    /// let stream = client.query::<Value>("v_orders").execute().await?; // "reporting"."v_orders"
+    /// ```
+    pub fn default_schema(mut self, schema: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.entities).default_schema = Some(schema.into());
+        self
+    }
+
+    /// Restrict queries to the given entities (`name` or `schema.name`)
+    ///
+    /// Other entities, and `query_from_sql()`, are rejected with
+    /// `Error::Config` before anything is sent. Unqualified entries match in
+    /// the default schema. With an allowlist, metrics are labelled with the
+    /// qualified entity name instead of a label parsed from the SQL.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let client = FraiseClient::connect(url)
+    ///     .await?
+    ///     .allow_entities(["v_user", "audit.v_event"]);
+    /// ```
+    pub fn allow_entities<S: AsRef<str>>(mut self, entities: impl IntoIterator<Item = S>) -> Self {
+        Arc::make_mut(&mut self.entities).allowlist = Some(
+            entities
+                .into_iter()
+                .map(|entity| EntityRef::split(entity.as_ref()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Entity resolution settings for queries built from this client
+    pub(crate) fn entity_policy(&self) -> &EntityPolicy {
+        &self.entities
+    }
+
+    /// Check that the server still answers (empty query round trip)
+    ///
+    /// Suitable for readiness probes and pool health checks: the server replies
+    /// without parsing or planning anything.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// if client.is_closed() || client.ping().await.is_err() {
+    ///     client = FraiseClient::connect(url).await?;
+    /// }
+    /// ```
+    pub async fn ping(&mut self) -> Result<()> {
+        self.conn.ping().await
+    }
+
+    /// Check whether the connection has been closed, without blocking
+    ///
+    /// Detects a transport closed by the server or a proxy. A `false` result does
+    /// not guarantee the next query succeeds; use `ping()` for a round trip.
+    pub fn is_closed(&mut self) -> bool {
+        self.conn.is_closed()
+    }
+
+    /// Run a query returning a single value, as text (see `QueryBuilder::count()`)
+    pub(crate) async fn query_scalar(
+        &mut self,
+        sql: &str,
+        params: &[crate::operators::Value],
+    ) -> Result<Option<String>> {
+        self.conn.query_scalar(sql, params).await
+    }
+
+    /// Run `EXPLAIN (FORMAT JSON)` on `sql` and parse the plan
+    pub(crate) async fn explain(
+        &mut self,
+        sql: &str,
+        params: &[crate::operators::Value],
+        options: ExplainOptions,
+    ) -> Result<QueryPlan> {
+        let json = self
+            .conn
+            .query_scalar(&options.explain_sql(sql), params)
+            .await?
+            .ok_or_else(|| crate::Error::Protocol("EXPLAIN returned no plan".into()))?;
+        QueryPlan::parse(serde_json::from_str(&json)?)
+    }
+
+    /// Whether the database's default collation orders strings byte-wise, as
+    /// the client-side merge of jsonb sort keys does
+    ///
+    /// True for the libc `C`/`POSIX` locales and the builtin provider (Postgres
+    /// 17+), false for ICU and other libc locales.
+    pub(crate) async fn default_collation_is_c(&mut self) -> Result<bool> {
+        let value = self
+            .conn
+            .query_scalar(
+                "SELECT CASE coalesce(to_jsonb(d)->>'datlocprovider', 'c') \
+                 WHEN 'c' THEN d.datcollate IN ('C', 'POSIX') \
+                 WHEN 'b' THEN true ELSE false END \
+                 FROM pg_database d WHERE d.datname = current_database()",
+                &[],
+            )
+            .await?;
+        Ok(value.as_deref() == Some("t"))
+    }
+
+    /// Number of heap blocks in `entity`, used for `ctid` range partitioning
+    pub(crate) async fn relation_block_count(&mut self, entity: &str) -> Result<u64> {
+        let value = self
+            .conn
+            .query_scalar(&super::partition::block_count_sql(entity), &[])
+            .await?
+            .ok_or_else(|| crate::Error::Protocol("block count query returned no row".into()))?;
+        value
+            .parse()
+            .map_err(|_| crate::Error::Protocol(format!("invalid block count '{}'", value)))
+    }
+
+    /// Server session parameters reported during startup
+    ///
+    /// Includes the parsed server version, encodings, `TimeZone`, `DateStyle`,
+    /// hot standby status and the backend process ID.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let info = client.session_info();
+    /// if info.server_version.map_or(false, |v| v.major >= 14) {
+    ///     // use features from Postgres 14+
+    /// }
+    /// ```
+    pub fn session_info(&self) -> crate::connection::SessionInfo {
+        self.conn.session_info()
+    }
+
+    /// Open a read-only transaction and export its snapshot
+    ///
+    /// Other clients pass the returned `SnapshotId` to `query_in_snapshot()` so
+    /// streams on separate connections all read identical data. The snapshot is
+    /// only importable while this client's transaction is open: keep the client
+    /// alive (or stream from it) until every other client has started its query.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Config` for `IsolationLevel::ReadCommitted`, which does not
+    /// hold a single snapshot for the transaction.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let mut leader = FraiseClient::connect(url).await?;
+    /// let snapshot = leader.begin_snapshot(IsolationLevel::RepeatableRead).await?;
+    ///
+    /// let users = FraiseClient::connect(url).await?
+    ///     .query_in_snapshot::<Value>(&snapshot, "user").await?
+    ///     .execute().await?;
+    /// let orders = FraiseClient::connect(url).await?
+    ///     .query_in_snapshot::<Value>(&snapshot, "order").await?
+    ///     .execute().await?;
+    ///
+    /// // Both streams now see the same MVCC snapshot
+    /// leader.commit().await?;
+    /// ```
+    pub async fn begin_snapshot(&mut self, isolation: IsolationLevel) -> Result<SnapshotId> {
+        if !isolation.uses_transaction_snapshot() {
+            return Err(crate::Error::Config(format!(
+                "begin_snapshot requires REPEATABLE READ or SERIALIZABLE, got {}",
+                isolation
+            )));
+        }
+        self.conn.begin(isolation).await?;
+        self.conn.export_snapshot().await
+    }
+
+    /// Start a query that reads from a snapshot exported by `begin_snapshot()`
+    ///
+    /// Opens a `REPEATABLE READ` read-only transaction on this client and imports
+    /// the snapshot before returning the query builder. The transaction ends when
+    /// the stream is dropped.
+    pub async fn query_in_snapshot<T: DeserializeOwned + std::marker::Unpin + 'static>(
+        mut self,
+        snapshot: &SnapshotId,
+        entity: impl Into<String>,
+    ) -> Result<QueryBuilder<T>> {
+        self.conn.begin(IsolationLevel::RepeatableRead).await?;
+        self.conn.set_transaction_snapshot(snapshot).await?;
+        Ok(QueryBuilder::new(self, entity))
+    }
+
+    /// Start a query reading from a raw SQL `FROM` item
+    ///
+    /// `from` is interpolated as-is (e.g. a subquery with an alias), so it must
+    /// never contain untrusted input. Prefer `query()`, which validates and
+    /// quotes the entity name. Rejected when the client has an entity
+    /// allowlist.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let stream = client
+    ///     .query_from_sql::<Value>("(SELECT jsonb_build_object('n', n) AS data FROM generate_series(1, 10) n) t")
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    pub fn query_from_sql<T: DeserializeOwned + std::marker::Unpin + 'static>(
+        self,
+        from: impl Into<String>,
+    ) -> QueryBuilder<T> {
+        QueryBuilder::from_sql(self, from)
+    }
+
+    /// Commit the transaction opened by `begin_snapshot()`
+    pub async fn commit(&mut self) -> Result<()> {
+        self.conn.commit().await
+    }
+
+    /// Roll back the transaction opened by `begin_snapshot()`
+    pub async fn rollback(&mut self) -> Result<()> {
+        self.conn.rollback().await
     }
 
     /// Start building a query for an entity with automatic deserialization
@@ -271,25 +596,50 @@ impl FraiseClient {
     }
 
     /// Execute a raw SQL query (must match fraiseql-wire constraints)
+    ///
+    /// `metrics_entity` labels the query's metrics; without it the label is
+    /// parsed from the SQL. `params` are bound to the query's `$n`
+    /// placeholders.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn execute_query(
-        self,
+        mut self,
         sql: &str,
+        metrics_entity: Option<String>,
+        params: Vec<crate::operators::Value>,
+        mode: ExecutionMode,
         chunk_size: usize,
         max_memory: Option<usize>,
         soft_limit_warn_threshold: Option<f32>,
         soft_limit_fail_threshold: Option<f32>,
     ) -> Result<JsonStream> {
-        self.conn
-            .streaming_query(
-                sql,
-                chunk_size,
-                max_memory,
-                soft_limit_warn_threshold,
-                soft_limit_fail_threshold,
-                false, // enable_adaptive_chunking: disabled by default for backward compatibility
-                None,  // adaptive_min_chunk_size
-                None,  // adaptive_max_chunk_size
-            )
-            .await
+        self.conn.set_metrics_entity(metrics_entity);
+        self.conn.set_query_params(params);
+        match mode {
+            ExecutionMode::Streaming => {
+                self.conn
+                    .streaming_query(
+                        sql,
+                        chunk_size,
+                        max_memory,
+                        soft_limit_warn_threshold,
+                        soft_limit_fail_threshold,
+                        false, // enable_adaptive_chunking: disabled by default for backward compatibility
+                        None,  // adaptive_min_chunk_size
+                        None,  // adaptive_max_chunk_size
+                    )
+                    .await
+            }
+            ExecutionMode::Cursor => {
+                self.conn
+                    .cursor_query(
+                        sql,
+                        chunk_size,
+                        max_memory,
+                        soft_limit_warn_threshold,
+                        soft_limit_fail_threshold,
+                    )
+                    .await
+            }
+        }
     }
 }
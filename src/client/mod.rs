@@ -3,8 +3,16 @@
 //! This module provides the user-facing API for fraiseql-wire.
 
 mod connection_string;
+mod entity;
+mod explain;
 mod fraise_client;
+mod partition;
 mod query_builder;
+mod rendered;
 
+pub use entity::EntityRef;
+pub use explain::{ExplainOptions, GuardViolation, PlanNode, QueryPlan};
 pub use fraise_client::FraiseClient;
+pub use partition::PartitionStrategy;
 pub use query_builder::QueryBuilder;
+pub use rendered::RenderedQuery;
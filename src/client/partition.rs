@@ -0,0 +1,248 @@
+//! Partitioning strategies for parallel scans
+//!
+//! `QueryBuilder::parallel(n, strategy)` splits one query into `n` disjoint
+//! partitions, each streamed on its own connection. A strategy only produces
+//! the extra `WHERE` predicate for each partition.
+
+use crate::operators::Value;
+use crate::{Error, Result};
+
+/// How rows are split across parallel connections
+#[derive(Debug, Clone)]
+pub enum PartitionStrategy {
+    /// Hash partitioning on a key expression
+    ///
+    /// Partition `i` of `n` gets `abs(hashtext(<key>)) % n = i`; rows with a NULL
+    /// key go to the first partition. The key should be well distributed.
+    Hash {
+        /// SQL key expression, e.g. `data->>'id'`
+        key: String,
+    },
+
+    /// Explicit ranges on a key expression
+    ///
+    /// `bounds` must be sorted ascending and yield `bounds.len() + 1` partitions:
+    /// `key < b0`, `b0 <= key < b1`, ..., `key >= bN`; rows with a NULL key go to
+    /// the last partition. Casts belong in the key expression (e.g.
+    /// `(data->>'created_at')::timestamptz`).
+    Range {
+        /// SQL key expression
+        key: String,
+        /// Partition boundaries, ascending
+        bounds: Vec<Value>,
+    },
+
+    /// Physical block ranges using `ctid` (plain tables only)
+    ///
+    /// The table's block count is read from `pg_relation_size()` at execution time
+    /// and split evenly; the last partition is open-ended so rows added to new
+    /// blocks are still read. Uses TID range scans on Postgres 14+.
+    CtidBlocks,
+}
+
+impl PartitionStrategy {
+    /// Hash partitioning on a key expression
+    pub fn hash(key: impl Into<String>) -> Self {
+        Self::Hash { key: key.into() }
+    }
+
+    /// Range partitioning on a key expression with ascending `bounds`
+    pub fn range(key: impl Into<String>, bounds: Vec<Value>) -> Self {
+        Self::Range {
+            key: key.into(),
+            bounds,
+        }
+    }
+
+    /// Validate the strategy for `n` partitions
+    pub(crate) fn validate(&self, n: usize) -> Result<()> {
+        if n == 0 {
+            return Err(Error::Config("parallel scan requires n >= 1".into()));
+        }
+        match self {
+            Self::Hash { key } | Self::Range { key, .. } if key.trim().is_empty() => Err(
+                Error::Config("partition key expression must not be empty".into()),
+            ),
+            Self::Range { bounds, .. } if bounds.len() + 1 != n => Err(Error::Config(format!(
+                "range partitioning with {} bounds yields {} partitions, but parallel({}) was requested",
+                bounds.len(),
+                bounds.len() + 1,
+                n
+            ))),
+            Self::Range { bounds, .. } => {
+                if bounds
+                    .iter()
+                    .any(|b| matches!(b, Value::Null | Value::Array(_) | Value::FloatArray(_)))
+                {
+                    return Err(Error::Config(
+                        "range bounds must be scalar, non-NULL values".into(),
+                    ));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the strategy needs the relation's block count
+    pub(crate) fn needs_block_count(&self) -> bool {
+        matches!(self, Self::CtidBlocks)
+    }
+
+    /// Build the WHERE predicate for each of `n` partitions
+    ///
+    /// Every row matches exactly one partition, including rows whose key is
+    /// NULL. `block_count` is required for `CtidBlocks`.
+    pub(crate) fn predicates(&self, n: usize, block_count: Option<u64>) -> Result<Vec<String>> {
+        self.validate(n)?;
+        let predicates = match self {
+            Self::Hash { key } => (0..n)
+                .map(|i| match i {
+                    0 => format!("abs(hashtext({})) % {} = 0 OR ({}) IS NULL", key, n, key),
+                    _ => format!("abs(hashtext({})) % {} = {}", key, n, i),
+                })
+                .collect(),
+            Self::Range { key, bounds } => {
+                let literals: Vec<String> = bounds.iter().map(|b| b.to_sql_literal()).collect();
+                (0..n)
+                    .map(
+                        |i| match (i.checked_sub(1).map(|j| &literals[j]), literals.get(i)) {
+                            (None, Some(upper)) => format!("({}) < {}", key, upper),
+                            (Some(lower), Some(upper)) => {
+                                format!("({}) >= {} AND ({}) < {}", key, lower, key, upper)
+                            }
+                            (Some(lower), None) => {
+                                format!("({}) >= {} OR ({}) IS NULL", key, lower, key)
+                            }
+                            // n == 1 with no bounds: single partition covers everything
+                            (None, None) => "TRUE".to_string(),
+                        },
+                    )
+                    .collect()
+            }
+            Self::CtidBlocks => {
+                let blocks = block_count.ok_or_else(|| {
+                    Error::Protocol("ctid partitioning requires the relation block count".into())
+                })?;
+                let per_partition = blocks.div_ceil(n as u64).max(1);
+                (0..n as u64)
+                    .map(|i| {
+                        let start = i * per_partition;
+                        let end = start + per_partition;
+                        match (i, i + 1 == n as u64) {
+                            (_, true) if i == 0 => "TRUE".to_string(),
+                            (0, false) => format!("ctid < '({},0)'::tid", end),
+                            (_, true) => format!("ctid >= '({},0)'::tid", start),
+                            _ => format!(
+                                "ctid >= '({},0)'::tid AND ctid < '({},0)'::tid",
+                                start, end
+                            ),
+                        }
+                    })
+                    .collect()
+            }
+        };
+        Ok(predicates)
+    }
+}
+
+/// Query returning the number of blocks in `entity`
+pub(crate) fn block_count_sql(entity: &str) -> String {
+    format!(
+        "SELECT pg_relation_size('{}'::regclass) / current_setting('block_size')::bigint",
+        entity.replace('\'', "''")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_predicates() {
+        let preds = PartitionStrategy::hash("data->>'id'")
+            .predicates(3, None)
+            .unwrap();
+        assert_eq!(
+            preds,
+            vec![
+                "abs(hashtext(data->>'id')) % 3 = 0 OR (data->>'id') IS NULL",
+                "abs(hashtext(data->>'id')) % 3 = 1",
+                "abs(hashtext(data->>'id')) % 3 = 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_range_predicates() {
+        let strategy = PartitionStrategy::range(
+            "(data->>'age')::int",
+            vec![Value::Number(18.0), Value::Number(65.0)],
+        );
+        let preds = strategy.predicates(3, None).unwrap();
+        assert_eq!(
+            preds,
+            vec![
+                "((data->>'age')::int) < 18",
+                "((data->>'age')::int) >= 18 AND ((data->>'age')::int) < 65",
+                "((data->>'age')::int) >= 65 OR ((data->>'age')::int) IS NULL",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_range_quotes_string_bounds() {
+        let strategy = PartitionStrategy::range("data->>'name'", vec![Value::String("O'N".into())]);
+        let preds = strategy.predicates(2, None).unwrap();
+        assert_eq!(preds[0], "(data->>'name') < 'O''N'");
+    }
+
+    #[test]
+    fn test_range_bound_count_mismatch() {
+        let strategy = PartitionStrategy::range("data->>'k'", vec![Value::Number(1.0)]);
+        assert!(strategy.predicates(4, None).is_err());
+    }
+
+    #[test]
+    fn test_range_rejects_null_bound() {
+        let strategy = PartitionStrategy::range("data->>'k'", vec![Value::Null]);
+        assert!(strategy.validate(2).is_err());
+    }
+
+    #[test]
+    fn test_ctid_predicates() {
+        let preds = PartitionStrategy::CtidBlocks
+            .predicates(3, Some(10))
+            .unwrap();
+        assert_eq!(
+            preds,
+            vec![
+                "ctid < '(4,0)'::tid",
+                "ctid >= '(4,0)'::tid AND ctid < '(8,0)'::tid",
+                "ctid >= '(8,0)'::tid",
+            ]
+        );
+        assert!(PartitionStrategy::CtidBlocks.predicates(3, None).is_err());
+    }
+
+    #[test]
+    fn test_ctid_single_partition() {
+        let preds = PartitionStrategy::CtidBlocks
+            .predicates(1, Some(0))
+            .unwrap();
+        assert_eq!(preds, vec!["TRUE"]);
+    }
+
+    #[test]
+    fn test_zero_partitions_rejected() {
+        assert!(PartitionStrategy::hash("data->>'id'").validate(0).is_err());
+    }
+
+    #[test]
+    fn test_block_count_sql_escapes() {
+        assert_eq!(
+            block_count_sql("o'k"),
+            "SELECT pg_relation_size('o''k'::regclass) / current_setting('block_size')::bigint"
+        );
+    }
+}
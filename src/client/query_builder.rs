@@ -5,7 +5,7 @@
 //! **IMPORTANT**: Type T is **consumer-side only**.
 //!
 //! Type T does NOT affect:
-//! - SQL generation (always `SELECT data FROM {entity}`)
+//! - SQL generation (always `SELECT data FROM "{entity}"`)
 //! - Filtering (where_sql, where_rust, order_by)
 //! - Wire protocol (identical for all T)
 //!
@@ -13,9 +13,19 @@
 //! - Consumer-side deserialization at poll_next()
 //! - Error messages (type name included)
 
-use crate::client::FraiseClient;
-use crate::stream::QueryStream;
-use crate::Result;
+use super::explain::CostGuard;
+use super::rendered::{RenderedQuery, SelectStatement};
+use crate::client::{EntityRef, ExplainOptions, FraiseClient, PartitionStrategy, QueryPlan};
+use crate::connection::{ExecutionMode, ShutdownHandle};
+use crate::operators::keyset::{self, SeekDirection};
+use crate::operators::param::{renumber_placeholders, ParamBinder};
+use crate::operators::{
+    self, Aggregate, DistanceMetric, Field, Headline, KeysetCursor, OrderByClause, Projection,
+    ToSqlParam,
+};
+use crate::stream::{ParallelStream, QueryStream, SortKey};
+use crate::{Error, Result};
+use futures::future::try_join_all;
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 use std::marker::PhantomData;
@@ -23,6 +33,24 @@ use std::marker::PhantomData;
 /// Type alias for a Rust-side predicate function
 type RustPredicate = Box<dyn Fn(&Value) -> bool + Send>;
 
+/// What a query reads from
+enum Source {
+    /// Entity name, resolved with the client's default schema and allowlist
+    Entity(String),
+    /// Raw `FROM` item (`FraiseClient::query_from_sql`)
+    Sql(String),
+}
+
+/// A resolved `FROM` item
+struct FromItem {
+    /// Quoted entity or raw SQL
+    sql: String,
+    /// Label for the query metrics
+    label: String,
+    /// Label passed to the connection, if bounded by an allowlist
+    metrics_entity: Option<String>,
+}
+
 /// Generic query builder
 ///
 /// The type parameter T controls consumer-side deserialization only.
@@ -54,10 +82,13 @@ type RustPredicate = Box<dyn Fn(&Value) -> bool + Send>;
 /// ```
 pub struct QueryBuilder<T: DeserializeOwned + Unpin + 'static = serde_json::Value> {
     client: FraiseClient,
-    entity: String,
-    sql_predicates: Vec<String>,
+    source: Source,
+    sql_predicates: Vec<(String, Vec<operators::Value>)>,
     rust_predicate: Option<RustPredicate>,
     order_by: Option<String>,
+    order_clauses: Vec<OrderByClause>,
+    strict_ordering: bool,
+    seek: Option<(SeekDirection, String)>,
     limit: Option<usize>,
     offset: Option<usize>,
     chunk_size: usize,
@@ -68,18 +99,36 @@ pub struct QueryBuilder<T: DeserializeOwned + Unpin + 'static = serde_json::Valu
     adaptive_min_chunk_size: Option<usize>,
     adaptive_max_chunk_size: Option<usize>,
     custom_select: Option<String>, // Optional custom SELECT clause for SQL projection
+    projection: Option<Projection>,
+    distance_key: Option<String>,
+    headlines: Vec<(String, Headline)>,
+    parallel: Option<(usize, PartitionStrategy)>,
+    cost_guard: CostGuard,
+    execution_mode: ExecutionMode,
     _phantom: PhantomData<T>,
 }
 
 impl<T: DeserializeOwned + Unpin + 'static> QueryBuilder<T> {
     /// Create new query builder
     pub(crate) fn new(client: FraiseClient, entity: impl Into<String>) -> Self {
+        Self::with_source(client, Source::Entity(entity.into()))
+    }
+
+    /// Create a query builder reading from a raw `FROM` item
+    pub(crate) fn from_sql(client: FraiseClient, from: impl Into<String>) -> Self {
+        Self::with_source(client, Source::Sql(from.into()))
+    }
+
+    fn with_source(client: FraiseClient, source: Source) -> Self {
         Self {
             client,
-            entity: entity.into(),
+            source,
             sql_predicates: Vec::new(),
             rust_predicate: None,
             order_by: None,
+            order_clauses: Vec::new(),
+            strict_ordering: false,
+            seek: None,
             limit: None,
             offset: None,
             chunk_size: 256,
@@ -90,6 +139,12 @@ impl<T: DeserializeOwned + Unpin + 'static> QueryBuilder<T> {
             adaptive_min_chunk_size: None,
             adaptive_max_chunk_size: None,
             custom_select: None,
+            projection: None,
+            distance_key: None,
+            headlines: Vec::new(),
+            parallel: None,
+            cost_guard: CostGuard::default(),
+            execution_mode: ExecutionMode::Streaming,
             _phantom: PhantomData,
         }
     }
@@ -99,7 +154,33 @@ impl<T: DeserializeOwned + Unpin + 'static> QueryBuilder<T> {
     /// Type T does NOT affect SQL generation.
     /// Multiple predicates are AND'ed together.
     pub fn where_sql(mut self, predicate: impl Into<String>) -> Self {
-        self.sql_predicates.push(predicate.into());
+        self.sql_predicates.push((predicate.into(), Vec::new()));
+        self
+    }
+
+    /// Add SQL WHERE clause predicate with bound parameters
+    ///
+    /// Placeholders are numbered from `$1` within `predicate` and renumbered
+    /// so they never clash with other predicates. The values are sent with
+    /// the extended protocol, never interpolated into the SQL. Every parameter
+    /// must be used, and plain `where_sql()` predicates of the same query may
+    /// not contain placeholders. See [`ToSqlParam`] for the supported types.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let stream = client.query::<Project>("projects")
+    ///     .where_sql_params("data->>'region' = $1 AND (data->>'score')::int > $2", &[&region, &10])
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    pub fn where_sql_params(
+        mut self,
+        predicate: impl Into<String>,
+        params: &[&dyn ToSqlParam],
+    ) -> Self {
+        let params = params.iter().map(|p| p.to_sql_param()).collect();
+        self.sql_predicates.push((predicate.into(), params));
         self
     }
 
@@ -119,11 +200,105 @@ impl<T: DeserializeOwned + Unpin + 'static> QueryBuilder<T> {
     /// Set ORDER BY clause
     ///
     /// Type T does NOT affect ordering.
+    /// With `strict_ordering(true)`, the string must parse as a list of
+    /// validated `OrderByClause`s (see `OrderByClause::parse_list`).
     pub fn order_by(mut self, order: impl Into<String>) -> Self {
         self.order_by = Some(order.into());
         self
     }
 
+    /// Add a typed ORDER BY clause
+    ///
+    /// Repeatable: clauses are applied in the order they are added. Each clause
+    /// is validated at `execute()`, so field and collation names taken from API
+    /// input cannot inject SQL. Cannot be combined with `order_by()`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let stream = client
+    ///     .query::<Project>("projects")
+    ///     .order_by_clause(OrderByClause::jsonb_field("priority", SortOrder::Desc))
+    ///     .order_by_clause(
+    ///         OrderByClause::jsonb_field("name", SortOrder::Asc).with_collation("C"),
+    ///     )
+    ///     .execute()
+    ///     .await?;
+    /// // ORDER BY (data->'priority') DESC, (data->>'name') COLLATE "C" ASC
+    /// ```
+    pub fn order_by_clause(mut self, clause: OrderByClause) -> Self {
+        self.order_clauses.push(clause);
+        self
+    }
+
+    /// Reject raw `order_by()` strings that are not plain validated clauses
+    ///
+    /// In strict mode, `order_by()` only accepts what `OrderByClause::parse_list`
+    /// accepts (column names, `data->'field'` keys and collated
+    /// `data->>'field' COLLATE "name"` keys, with direction and NULLS
+    /// modifiers); anything else fails `execute()` with `Error::Config`.
+    /// Off by default.
+    pub fn strict_ordering(mut self, enabled: bool) -> Self {
+        self.strict_ordering = enabled;
+        self
+    }
+
+    /// Order by typed clauses and enable keyset pagination
+    ///
+    /// Replaces any `order_by_clause()`s; combining with `order_by()` is an
+    /// error at `execute()`. When every clause is a JSONB payload field, the
+    /// stream tracks the sort keys of the rows it yields, available as opaque
+    /// tokens from `QueryStream::first_cursor()`/`last_cursor()`, to pass to
+    /// `after()` or `before()`. The last clause should be unique (e.g. `id`) so
+    /// that ties are broken.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let order = [
+    ///     OrderByClause::jsonb_field("created_at", SortOrder::Desc),
+    ///     OrderByClause::jsonb_field("id", SortOrder::Desc),
+    /// ];
+    /// let mut page = client
+    ///     .query::<Project>("projects")
+    ///     .keyset_order(order.clone())
+    ///     .limit(50)
+    ///     .execute()
+    ///     .await?;
+    /// // ... drain the page ...
+    /// let next = page.last_cursor();
+    ///
+    /// let page2 = client
+    ///     .query::<Project>("projects")
+    ///     .keyset_order(order)
+    ///     .after(next.unwrap())
+    ///     .limit(50)
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    pub fn keyset_order(mut self, clauses: impl IntoIterator<Item = OrderByClause>) -> Self {
+        self.order_clauses = clauses.into_iter().collect();
+        self
+    }
+
+    /// Return only rows after `cursor` in the `keyset_order()` ordering
+    ///
+    /// `cursor` is a token from `QueryStream::last_cursor()` taken with the same
+    /// ordering. Replaces a previous `after()`/`before()`.
+    pub fn after(mut self, cursor: impl Into<String>) -> Self {
+        self.seek = Some((SeekDirection::After, cursor.into()));
+        self
+    }
+
+    /// Return only rows before `cursor` in the `keyset_order()` ordering
+    ///
+    /// Rows still arrive in `keyset_order()` order. With `limit(n)`, the page
+    /// holds the `n` rows closest to the cursor.
+    pub fn before(mut self, cursor: impl Into<String>) -> Self {
+        self.seek = Some((SeekDirection::Before, cursor.into()));
+        self
+    }
+
     /// Set a custom SELECT clause for SQL projection optimization
     ///
     /// When provided, this replaces the default `SELECT data` with a projection SQL
@@ -157,6 +332,96 @@ impl<T: DeserializeOwned + Unpin + 'static> QueryBuilder<T> {
         self
     }
 
+    /// Set a typed projection for the `data` column
+    ///
+    /// The typed alternative to `select_projection()`: keys are quoted and field
+    /// paths are validated when the query is built. Cannot be combined with
+    /// `select_projection()`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let stream = client
+    ///     .query::<Project>("projects")
+    ///     .projection(
+    ///         Projection::new()
+    ///             .field("id")
+    ///             .nested("owner", |p| p.field("name")),
+    ///     )
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    pub fn projection(mut self, projection: Projection) -> Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    /// Return the `k` rows nearest to `vector`, nearest first
+    ///
+    /// Orders by `OrderByClause::distance(column, metric, vector)` with
+    /// `LIMIT k`, the form pgvector HNSW and IVFFlat indexes serve. The vector
+    /// is bound once as a `$n::vector` parameter, shared with
+    /// `project_distance()`. Replaces any `order_by_clause()`s and `limit()`;
+    /// combining with `order_by()` is an error.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let stream = client
+    ///     .query::<Document>("documents")
+    ///     .nearest(10, "embedding", DistanceMetric::Cosine, query_embedding)
+    ///     .project_distance("distance")
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    pub fn nearest(
+        mut self,
+        k: usize,
+        column: impl Into<String>,
+        metric: DistanceMetric,
+        vector: Vec<f32>,
+    ) -> Self {
+        self.order_clauses = vec![OrderByClause::distance(column, metric, vector)];
+        self.limit = Some(k);
+        self
+    }
+
+    /// Add the vector distance to each row under `key`
+    ///
+    /// Merges `{key: distance}` into the `data` column, or into the projection
+    /// if one is set. Requires a distance ordering (`nearest()` or an
+    /// `OrderByClause::distance` clause).
+    pub fn project_distance(mut self, key: impl Into<String>) -> Self {
+        self.distance_key = Some(key.into());
+        self
+    }
+
+    /// Add a `ts_headline` snippet to each row under `key`
+    ///
+    /// Merges `{key: snippet}` into the `data` column, or into the projection
+    /// if one is set. The search text and options are bound as parameters.
+    /// Can be called several times with different keys.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let query = TextQuery::websearch(&input);
+    /// let stream = client
+    ///     .query::<SearchHit>("posts")
+    ///     .order_by_clause(OrderByClause::text_rank("body", TextRank::new(query.clone())))
+    ///     .headline(
+    ///         "snippet",
+    ///         Headline::new(Field::JsonbField("body".into()), query).options("MaxWords=20"),
+    ///     )
+    ///     .limit(20)
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    pub fn headline(mut self, key: impl Into<String>, headline: Headline) -> Self {
+        self.headlines.push((key.into(), headline));
+        self
+    }
+
     /// Set LIMIT clause to restrict result set size
     ///
     /// # Example
@@ -327,6 +592,143 @@ impl<T: DeserializeOwned + Unpin + 'static> QueryBuilder<T> {
         self
     }
 
+    /// Choose how rows are read from the server (default: `ExecutionMode::Streaming`)
+    ///
+    /// `ExecutionMode::Cursor` declares a server-side cursor and fetches
+    /// `chunk_size` rows at a time as the stream is drained. Use it behind
+    /// transaction-mode poolers such as PgBouncer, or when streams may stay
+    /// paused for a long time. Pause/resume, memory limits and stats behave the
+    /// same in both modes.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let stream = client
+    ///     .query::<Project>("projects")
+    ///     .execution_mode(ExecutionMode::Cursor)
+    ///     .chunk_size(1000)  // rows per FETCH
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    pub fn execution_mode(mut self, mode: ExecutionMode) -> Self {
+        self.execution_mode = mode;
+        self
+    }
+
+    /// Stop this query when `handle` shuts down
+    ///
+    /// Overrides a handle attached with `FraiseClient::with_shutdown()`. On
+    /// shutdown the query is cancelled on the server, the connection closed
+    /// within the deadline, and the stream yields a final `Error::Cancelled`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let shutdown = ShutdownHandle::new();
+    /// let stream = client
+    ///     .query::<Project>("projects")
+    ///     .shutdown(&shutdown)
+    ///     .execute()
+    ///     .await?;
+    ///
+    /// let report = shutdown.shutdown(Duration::from_secs(5)).await;
+    /// ```
+    pub fn shutdown(mut self, handle: &ShutdownHandle) -> Self {
+        self.client = self.client.with_shutdown(handle.clone());
+        self
+    }
+
+    /// Split the scan into `n` partitions streamed over `n` connections
+    ///
+    /// The client opens `n - 1` additional connections with the same settings,
+    /// runs one partition per connection and merges the results into a single
+    /// stream. Without `order_by()`, rows arrive in no particular order. With
+    /// `order_by()`, each partition is sorted by Postgres and the partitions are
+    /// k-way merged, which requires sort keys the client can evaluate on `data`
+    /// (see below).
+    ///
+    /// - `max_memory()` is a global budget across all partitions, and `stats()`
+    ///   reports combined figures.
+    /// - `limit()` applies to the merged stream; `offset()` is not supported.
+    /// - Partitions are separate statements and, by default, read from separate
+    ///   snapshots. When the client is in a snapshot transaction
+    ///   (`query_in_snapshot()`, or after `begin_snapshot()`), the snapshot is
+    ///   exported and every partition connection imports it, so all partitions
+    ///   read identical data.
+    ///
+    /// Ordered merges accept `data->`/`data->>`/`#>`/`#>>` paths, optionally cast
+    /// to a numeric type, with `ASC`/`DESC` and `NULLS FIRST/LAST`. The client
+    /// compares strings byte-wise, so text keys must be `COLLATE "C"`, and jsonb
+    /// keys (`data->'k'`) are refused unless the database's default collation
+    /// is `C`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let stream = client
+    ///     .query::<Event>("events")
+    ///     .where_sql("data->>'kind' = 'click'")
+    ///     .parallel(4, PartitionStrategy::hash("data->>'id'"))
+    ///     .max_memory(200_000_000)
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    pub fn parallel(mut self, n: usize, strategy: PartitionStrategy) -> Self {
+        self.parallel = Some((n, strategy));
+        self
+    }
+
+    /// Refuse to run the query if the planner's estimated total cost exceeds
+    /// `cost`
+    ///
+    /// The query is planned with `EXPLAIN` before `execute()` (one extra round
+    /// trip) and rejected with `Error::QueryRejected` on violation. Costs are in
+    /// the planner's arbitrary units; calibrate against `explain()` output.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let stream = client
+    ///     .query::<Value>("orders")
+    ///     .where_sql(user_filter)
+    ///     .max_estimated_cost(50_000.0)
+    ///     .max_estimated_rows(1_000_000)
+    ///     .forbid_seq_scan_on(["orders"])
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    pub fn max_estimated_cost(mut self, cost: f64) -> Self {
+        self.cost_guard.max_estimated_cost = Some(cost);
+        self
+    }
+
+    /// Refuse to run the query if the planner estimates more than `rows` rows
+    ///
+    /// See `max_estimated_cost()`. Estimates depend on table statistics and may
+    /// be far off for complex predicates.
+    pub fn max_estimated_rows(mut self, rows: u64) -> Self {
+        self.cost_guard.max_estimated_rows = Some(rows);
+        self
+    }
+
+    /// Refuse to run the query if its plan sequentially scans any of the given
+    /// relations
+    ///
+    /// Relations are the tables behind the entity (views are expanded by the
+    /// planner), as `name` or `schema.name`. An unqualified name matches the
+    /// relation in any schema. See `max_estimated_cost()`.
+    pub fn forbid_seq_scan_on<S: AsRef<str>>(
+        mut self,
+        relations: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.cost_guard.forbid_seq_scan_on.extend(
+            relations
+                .into_iter()
+                .map(|relation| EntityRef::split(relation.as_ref())),
+        );
+        self
+    }
+
     /// Execute query and return typed stream
     ///
     /// Type T ONLY affects consumer-side deserialization at poll_next().
@@ -359,22 +761,31 @@ impl<T: DeserializeOwned + Unpin + 'static> QueryBuilder<T> {
     /// let stats = stream.stats();  // Get statistics
     /// stream.resume().await?;  // Resume the stream
     /// ```
-    pub async fn execute(self) -> Result<QueryStream<T>> {
-        let sql = self.build_sql()?;
+    pub async fn execute(mut self) -> Result<QueryStream<T>> {
+        if let Some((n, strategy)) = self.parallel.take() {
+            return self.execute_parallel(n, strategy).await;
+        }
+
+        let from = self.resolve_from()?;
+        let (sql, params) = self.build_partition_sql(&from.sql, None)?;
+        self.check_cost_guard(&from, &sql, &params).await?;
         tracing::debug!("executing query: {}", sql);
 
         // Record query submission metrics
         crate::metrics::counters::query_submitted(
-            &self.entity,
+            &from.label,
             !self.sql_predicates.is_empty(),
             self.rust_predicate.is_some(),
-            self.order_by.is_some(),
+            self.has_order(),
         );
 
         let stream = self
             .client
             .execute_query(
                 &sql,
+                from.metrics_entity,
+                params,
+                self.execution_mode,
                 self.chunk_size,
                 self.max_memory,
                 self.soft_limit_warn_threshold,
@@ -383,194 +794,844 @@ impl<T: DeserializeOwned + Unpin + 'static> QueryBuilder<T> {
             .await?;
 
         // Create QueryStream with optional Rust predicate
-        Ok(QueryStream::new(stream, self.rust_predicate))
+        let mut stream = QueryStream::new(stream, self.rust_predicate);
+        if keyset::tracks_cursor(&self.order_clauses) {
+            stream.track_cursor(self.order_clauses);
+        }
+        Ok(stream)
     }
 
-    /// Build SQL query
-    fn build_sql(&self) -> Result<String> {
-        // Use custom SELECT clause if provided, otherwise default to "SELECT data"
-        let select_clause = if let Some(ref projection) = self.custom_select {
-            format!("SELECT {} as data", projection)
+    /// Count the rows matching the builder's filters
+    ///
+    /// Runs `SELECT count(*)` over the same predicates (including `after()`/
+    /// `before()`), honouring `limit()`/`offset()`, instead of streaming rows.
+    /// `where_rust()` predicates cannot be applied and are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let active = client.query::<Value>("projects")
+    ///     .where_sql_params("data->>'status' = $1", &[&"active"])
+    ///     .count()
+    ///     .await?;
+    /// ```
+    pub async fn count(self) -> Result<u64> {
+        let value = self
+            .query_scalar(|rows| format!("SELECT count(*) FROM ({}) AS matched", rows))
+            .await?
+            .ok_or_else(|| Error::Protocol("count(*) returned no value".into()))?;
+        value
+            .parse()
+            .map_err(|_| Error::Protocol(format!("invalid count '{}'", value)))
+    }
+
+    /// Check whether any row matches the builder's filters
+    ///
+    /// Runs `SELECT EXISTS (...)`, which stops at the first matching row.
+    /// `where_rust()` predicates cannot be applied and are rejected.
+    pub async fn exists(self) -> Result<bool> {
+        let value = self
+            .query_scalar(|rows| format!("SELECT EXISTS ({})", rows))
+            .await?;
+        match value.as_deref() {
+            Some("t") => Ok(true),
+            Some("f") => Ok(false),
+            other => Err(Error::Protocol(format!(
+                "invalid EXISTS result: {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Aggregate a field over the rows matching the builder's filters
+    ///
+    /// Returns the result as JSON, `Value::Null` when no row has a value. See
+    /// [`Aggregate`] for how JSONB fields are compared and summed.
+    /// `where_rust()` predicates cannot be applied and are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let max = client.query::<Value>("orders")
+    ///     .where_sql("data->>'status' = 'paid'")
+    ///     .aggregate(Field::JsonbField("total".into()), Aggregate::Max)
+    ///     .await?;
+    /// ```
+    pub async fn aggregate(self, field: Field, aggregate: Aggregate) -> Result<Value> {
+        field.validate().map_err(Error::Config)?;
+        let value = self
+            .query_scalar(|rows| aggregate.query_sql(&field, rows))
+            .await?;
+        match value {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(Value::Null),
+        }
+    }
+
+    /// Plan the query with `EXPLAIN (FORMAT JSON)` and return the parsed plan
+    ///
+    /// Explains the SQL `execute()` would run, with the same bound parameters.
+    /// For `parallel()` queries this is the unpartitioned query. With
+    /// `ExplainOptions::analyze()` the query is executed and its rows discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let plan = client.query::<Value>("orders")
+    ///     .where_sql("data->>'status' = 'paid'")
+    ///     .explain(ExplainOptions::new())
+    ///     .await?;
+    /// println!("cost {} for ~{} rows", plan.total_cost(), plan.estimated_rows());
+    /// ```
+    pub async fn explain(mut self, options: ExplainOptions) -> Result<QueryPlan> {
+        let from = self.resolve_from()?;
+        let (sql, params) = self.build_partition_sql(&from.sql, None)?;
+        self.client.explain(&sql, &params, options).await
+    }
+
+    /// Render the SQL and parameters `execute()` would send, without executing
+    ///
+    /// Entity names are resolved against the client's default schema and
+    /// allowlist exactly as in `execute()`. For `parallel()` queries this is the
+    /// unpartitioned query; `where_rust()` predicates run client-side and do not
+    /// appear. Use `RenderedQuery::pretty()` for a multi-line rendering.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let rendered = client.query::<Value>("orders")
+    ///     .where_sql_params("data->>'region' = $1", &[&"eu"])
+    ///     .to_sql()?;
+    /// assert_eq!(rendered.sql, "SELECT data FROM \"orders\" WHERE data->>'region' = $1");
+    /// ```
+    pub fn to_sql(&self) -> Result<RenderedQuery> {
+        let from = self.resolve_from()?;
+        let (statement, params) = self.build_statement(&from.sql, None, None)?;
+        Ok(RenderedQuery::new(statement, params, from.label))
+    }
+
+    /// Plan `sql` and refuse it if it violates the cost guard
+    async fn check_cost_guard(
+        &mut self,
+        from: &FromItem,
+        sql: &str,
+        params: &[operators::Value],
+    ) -> Result<()> {
+        if !self.cost_guard.is_enabled() {
+            return Ok(());
+        }
+        let plan = self
+            .client
+            .explain(sql, params, self.cost_guard.explain_options())
+            .await?;
+        self.cost_guard.check(&plan).map_err(|violation| {
+            tracing::warn!("query on {} rejected: {}", from.label, violation);
+            let error = Error::QueryRejected(violation);
+            crate::metrics::counters::query_error(&from.label, error.category());
+            error
+        })
+    }
+
+    /// Run `outer` over the rows matching the builder's filters and return
+    /// its single value
+    async fn query_scalar(mut self, outer: impl FnOnce(&str) -> String) -> Result<Option<String>> {
+        if self.rust_predicate.is_some() {
+            return Err(Error::Config(
+                "count(), exists() and aggregate() cannot apply where_rust() predicates".into(),
+            ));
+        }
+        let from = self.resolve_from()?;
+        let (rows, params) = self.build_rows_sql(&from.sql, None, Some("SELECT *"))?;
+        let sql = outer(&rows);
+        tracing::debug!("executing scalar query: {}", sql);
+        self.client.query_scalar(&sql, &params).await
+    }
+
+    /// Execute as `n` partitions on separate connections and merge the results
+    async fn execute_parallel(
+        mut self,
+        n: usize,
+        strategy: PartitionStrategy,
+    ) -> Result<QueryStream<T>> {
+        strategy.validate(n)?;
+        if self.offset.is_some() {
+            return Err(Error::Config(
+                "offset() is not supported with parallel scans".into(),
+            ));
+        }
+        if matches!(self.seek, Some((SeekDirection::Before, _))) && self.limit.is_some() {
+            return Err(Error::Config(
+                "before() with limit() is not supported with parallel scans".into(),
+            ));
+        }
+        if self.order_clauses.iter().any(OrderByClause::is_computed) {
+            return Err(Error::Config(
+                "distance and rank ordering cannot be merged across parallel partitions".into(),
+            ));
+        }
+        let mut binder = ParamBinder::new(0);
+        let sort_keys = match self.order_clause(&mut binder)? {
+            Some(ref order) if self.select_expr(&mut binder)?.is_some() => {
+                return Err(Error::Config(format!(
+                    "ORDER BY '{}' cannot be merged across parallel partitions \
+                     when a projection is set",
+                    order
+                )))
+            }
+            Some(ref order) => Some(SortKey::parse_order_by(order)?),
+            None => None,
+        };
+
+        let from = self.resolve_from()?;
+        let (sql, params) = self.build_partition_sql(&from.sql, None)?;
+        self.check_cost_guard(&from, &sql, &params).await?;
+
+        let jsonb_keys = sort_keys.iter().flatten().any(SortKey::needs_c_database);
+        if jsonb_keys && !self.client.default_collation_is_c().await? {
+            return Err(Error::Config(
+                "jsonb ORDER BY keys compare strings in the database collation, which is not \
+                 \"C\"; order by data->>'key' COLLATE \"C\" (or a numeric cast) to merge \
+                 parallel partitions"
+                    .into(),
+            ));
+        }
+
+        let block_count = if strategy.needs_block_count() {
+            Some(self.client.relation_block_count(&from.sql).await?)
         } else {
-            "SELECT data".to_string()
+            None
+        };
+        let sqls = strategy
+            .predicates(n, block_count)?
+            .into_iter()
+            .map(|predicate| Ok(self.build_partition_sql(&from.sql, Some(&predicate))?.0))
+            .collect::<Result<Vec<_>>>()?;
+        tracing::debug!("executing parallel query over {} partitions: {:?}", n, sqls);
+
+        crate::metrics::counters::query_submitted(
+            &from.label,
+            !self.sql_predicates.is_empty(),
+            self.rust_predicate.is_some(),
+            self.has_order(),
+        );
+
+        // Partitions inside a snapshot all read from it
+        let snapshot = self.client.transaction_snapshot().await?;
+        let siblings =
+            try_join_all((1..n).map(|_| self.client.connect_sibling(snapshot.as_ref()))).await?;
+        let clients = std::iter::once(self.client).chain(siblings);
+
+        // Memory limits are enforced globally by ParallelStream, not per partition
+        let (mode, chunk_size) = (self.execution_mode, self.chunk_size);
+        let streams = try_join_all(clients.zip(&sqls).map(|(client, sql)| {
+            let metrics_entity = from.metrics_entity.clone();
+            let params = params.clone();
+            client.execute_query(
+                sql,
+                metrics_entity,
+                params,
+                mode,
+                chunk_size,
+                None,
+                None,
+                None,
+            )
+        }))
+        .await?;
+
+        let merged = ParallelStream::new(
+            from.label,
+            streams,
+            sort_keys,
+            self.limit,
+            self.max_memory,
+            self.soft_limit_fail_threshold,
+        );
+        let mut stream = QueryStream::parallel(merged, self.rust_predicate);
+        if keyset::tracks_cursor(&self.order_clauses) {
+            stream.track_cursor(self.order_clauses);
+        }
+        Ok(stream)
+    }
+
+    /// Resolve the `FROM` item against the client's entity policy
+    fn resolve_from(&self) -> Result<FromItem> {
+        let policy = self.client.entity_policy();
+        match self.source {
+            Source::Entity(ref entity) => {
+                let entity = policy.resolve(entity)?;
+                let metrics_entity = policy.metrics_label(&entity);
+                Ok(FromItem {
+                    sql: entity.to_sql(),
+                    label: entity.to_string(),
+                    metrics_entity,
+                })
+            }
+            Source::Sql(_) if policy.allowlist.is_some() => Err(Error::Config(
+                "query_from_sql() is not permitted when the client has an entity allowlist".into(),
+            )),
+            Source::Sql(ref sql) => Ok(FromItem {
+                sql: sql.clone(),
+                label: sql.clone(),
+                metrics_entity: None,
+            }),
+        }
+    }
+
+    /// Whether the query has an ORDER BY
+    fn has_order(&self) -> bool {
+        self.order_by.is_some() || !self.order_clauses.is_empty()
+    }
+
+    /// ORDER BY clause, validating typed clauses and the keyset configuration;
+    /// computed values are bound through `binder`
+    fn order_clause(&self, binder: &mut ParamBinder) -> Result<Option<String>> {
+        if self.order_clauses.is_empty() {
+            if self.seek.is_some() {
+                return Err(Error::Config(
+                    "after()/before() require keyset_order()".into(),
+                ));
+            }
+            return match self.order_by {
+                Some(ref order) if self.strict_ordering => {
+                    let clauses = OrderByClause::parse_list(order).map_err(|e| {
+                        Error::Config(format!("ORDER BY rejected in strict mode: {}", e))
+                    })?;
+                    render_order(&clauses, binder).map(Some)
+                }
+                ref order => Ok(order.clone()),
+            };
+        }
+        if self.order_by.is_some() {
+            return Err(Error::Config(
+                "order_by() cannot be combined with order_by_clause()/keyset_order()".into(),
+            ));
+        }
+        if self.seek.is_some() {
+            keyset::validate_keyset_order(&self.order_clauses)?;
+        }
+        render_order(&self.order_clauses, binder).map(Some)
+    }
+
+    /// Projection expression for the `data` column, if any; computed values
+    /// are bound through `binder`
+    fn select_expr(&self, binder: &mut ParamBinder) -> Result<Option<String>> {
+        let projection = match (&self.custom_select, &self.projection) {
+            (Some(_), Some(_)) => {
+                return Err(Error::Config(
+                    "select_projection() cannot be combined with projection()".into(),
+                ))
+            }
+            (Some(sql), None) => Some(sql.clone()),
+            (None, Some(projection)) => Some(projection.to_sql()?),
+            (None, None) => None,
         };
 
-        let mut sql = format!("{} FROM {}", select_clause, self.entity);
+        // Computed values merged into the document
+        let mut extra = Vec::new();
+        if let Some(ref key) = self.distance_key {
+            let clause = self
+                .order_clauses
+                .iter()
+                .find(|c| c.distance.is_some())
+                .ok_or_else(|| {
+                    Error::Config(
+                        "project_distance() requires nearest() or a distance order_by_clause()"
+                            .into(),
+                    )
+                })?;
+            clause.validate().map_err(Error::Config)?;
+            extra.push((key, clause.expression_with(binder)));
+        }
+        for (key, headline) in &self.headlines {
+            headline.validate().map_err(Error::Config)?;
+            extra.push((key, headline.to_sql(binder)));
+        }
+        if extra.is_empty() {
+            return Ok(projection);
+        }
 
-        if !self.sql_predicates.is_empty() {
-            sql.push_str(" WHERE ");
-            sql.push_str(&self.sql_predicates.join(" AND "));
+        let mut args = Vec::new();
+        for (key, expr) in extra {
+            if key.contains('\0') {
+                return Err(Error::Config(format!("Invalid projected key: {:?}", key)));
+            }
+            args.push(format!("'{}', {}", key.replace('\'', "''"), expr));
         }
+        Ok(Some(format!(
+            "{} || jsonb_build_object({})",
+            projection.map_or_else(|| "data".to_string(), |p| format!("({})", p)),
+            args.join(", ")
+        )))
+    }
 
-        if let Some(ref order) = self.order_by {
-            sql.push_str(" ORDER BY ");
-            sql.push_str(order);
+    /// SQL predicates with placeholders renumbered, and the parameters to bind
+    fn where_predicates(&self) -> Result<(Vec<String>, Vec<operators::Value>)> {
+        let mut predicates = Vec::with_capacity(self.sql_predicates.len());
+        let mut params = Vec::new();
+        if self.sql_predicates.iter().all(|(_, p)| p.is_empty()) {
+            predicates.extend(self.sql_predicates.iter().map(|(sql, _)| sql.clone()));
+            return Ok((predicates, params));
+        }
+        for (sql, values) in &self.sql_predicates {
+            let renumbered =
+                renumber_placeholders(sql, params.len(), values.len()).map_err(|e| {
+                    let hint = if values.is_empty() {
+                        " (use where_sql_params() to bind values)"
+                    } else {
+                        ""
+                    };
+                    Error::Config(format!("predicate '{}': {}{}", sql, e, hint))
+                })?;
+            for value in values {
+                value.to_param().map_err(Error::Config)?;
+            }
+            predicates.push(renumbered);
+            params.extend(values.iter().cloned());
         }
+        Ok((predicates, params))
+    }
 
-        if let Some(limit) = self.limit {
-            sql.push_str(&format!(" LIMIT {}", limit));
+    /// Keyset seek predicate, with its cursor values numbered after
+    /// `first_param` bound parameters
+    fn seek_predicate(
+        &self,
+        first_param: usize,
+    ) -> Result<Option<(String, Vec<operators::Value>)>> {
+        let Some((direction, ref token)) = self.seek else {
+            return Ok(None);
+        };
+        let cursor = KeysetCursor::decode(token)?;
+        keyset::seek_predicate(&self.order_clauses, &cursor, direction, first_param).map(Some)
+    }
+
+    /// Build SQL query from `from` with an extra partition predicate, and the
+    /// parameters to bind
+    fn build_partition_sql(
+        &self,
+        from: &str,
+        partition: Option<&str>,
+    ) -> Result<(String, Vec<operators::Value>)> {
+        self.build_rows_sql(from, partition, None)
+    }
+
+    /// Build SQL query from `from`, selecting `select` instead of the `data`
+    /// column when given; ORDER BY is then kept only for LIMIT/OFFSET
+    fn build_rows_sql(
+        &self,
+        from: &str,
+        partition: Option<&str>,
+        select: Option<&str>,
+    ) -> Result<(String, Vec<operators::Value>)> {
+        let (statement, params) = self.build_statement(from, partition, select)?;
+        Ok((statement.to_sql(), params))
+    }
+
+    /// Build the statement behind `build_rows_sql()` and its parameters:
+    /// `where_sql_params()` values, keyset cursor values, then the values of
+    /// computed expressions (distance vectors, search text)
+    fn build_statement(
+        &self,
+        from: &str,
+        partition: Option<&str>,
+        select: Option<&str>,
+    ) -> Result<(SelectStatement, Vec<operators::Value>)> {
+        let (predicates, mut params) = self.where_predicates()?;
+        let seek = self.seek_predicate(params.len())?;
+        if let Some((_, ref values)) = seek {
+            params.extend(values.iter().cloned());
         }
+        let mut binder = ParamBinder::new(params.len());
 
-        if let Some(offset) = self.offset {
-            sql.push_str(&format!(" OFFSET {}", offset));
+        // Use custom SELECT clause if provided, otherwise default to "SELECT data"
+        let select_clause = match select {
+            Some(select) => select.to_string(),
+            None => match self.select_expr(&mut binder)? {
+                Some(projection) => format!("SELECT {} as data", projection),
+                None => "SELECT data".to_string(),
+            },
+        };
+        let order = if select.is_some() && self.limit.is_none() && self.offset.is_none() {
+            // Validated, but not sent
+            self.order_clause(&mut ParamBinder::inline())?;
+            None
+        } else {
+            self.order_clause(&mut binder)?
+        };
+        params.extend(binder.into_params());
+        let mut predicates: Vec<String> = predicates
+            .into_iter()
+            .map(|p| {
+                if partition.is_some() || self.seek.is_some() {
+                    format!("({})", p)
+                } else {
+                    p
+                }
+            })
+            .collect();
+        if let Some(partition) = partition {
+            predicates.push(format!("({})", partition));
+        }
+        if let Some((seek, _)) = seek {
+            predicates.push(format!("({})", seek));
+        }
+
+        // A limited page before the cursor takes the rows closest to it, i.e. the
+        // first rows in reverse order, then restores the requested order
+        if matches!(self.seek, Some((SeekDirection::Before, _))) && self.limit.is_some() {
+            // Keyset orderings have no computed values to bind
+            let reversed = render_order(
+                &keyset::reversed(&self.order_clauses),
+                &mut ParamBinder::inline(),
+            )?;
+            let inner = SelectStatement {
+                select: "SELECT data".to_string(),
+                from: from.to_string(),
+                predicates,
+                order: Some(reversed),
+                limit: self.limit,
+                offset: self.offset,
+            };
+            let statement = SelectStatement {
+                select: select_clause,
+                from: format!("({}) AS page", inner.to_sql()),
+                predicates: Vec::new(),
+                order,
+                limit: None,
+                offset: None,
+            };
+            return Ok((statement, params));
         }
 
-        Ok(sql)
+        let statement = SelectStatement {
+            select: select_clause,
+            from: from.to_string(),
+            predicates,
+            order,
+            limit: self.limit,
+            offset: self.offset,
+        };
+        Ok((statement, params))
     }
 }
 
+/// Render typed clauses as a comma-separated ORDER BY list
+fn render_order(clauses: &[OrderByClause], binder: &mut ParamBinder) -> Result<String> {
+    let rendered = clauses
+        .iter()
+        .map(|clause| clause.to_sql_with(binder).map_err(Error::Config))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(rendered.join(", "))
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
 
-    fn build_test_sql(entity: &str, predicates: Vec<&str>, order_by: Option<&str>) -> String {
-        let mut sql = format!("SELECT data FROM {}", entity);
-        if !predicates.is_empty() {
-            sql.push_str(" WHERE ");
-            sql.push_str(&predicates.join(" AND "));
-        }
-        if let Some(order) = order_by {
-            sql.push_str(" ORDER BY ");
-            sql.push_str(order);
-        }
-        sql
+    fn query(entity: &str) -> QueryBuilder {
+        FraiseClient::unconnected().query(entity)
     }
 
-    #[test]
-    fn test_build_sql_simple() {
-        let sql = build_test_sql("user", vec![], None);
-        assert_eq!(sql, "SELECT data FROM user");
+    #[tokio::test]
+    async fn test_build_sql_simple() {
+        let rendered = query("user").to_sql().unwrap();
+        assert_eq!(rendered.sql, "SELECT data FROM \"user\"");
+        assert_eq!(rendered.entity, "user");
+        assert!(rendered.params.is_empty());
     }
 
-    #[test]
-    fn test_build_sql_with_where() {
-        let sql = build_test_sql("user", vec!["data->>'status' = 'active'"], None);
+    #[tokio::test]
+    async fn test_build_sql_with_where() {
+        let sql = query("user")
+            .where_sql("data->>'status' = 'active'")
+            .to_sql()
+            .unwrap()
+            .sql;
         assert_eq!(
             sql,
-            "SELECT data FROM user WHERE data->>'status' = 'active'"
+            "SELECT data FROM \"user\" WHERE data->>'status' = 'active'"
         );
     }
 
-    #[test]
-    fn test_build_sql_with_order() {
-        let sql = build_test_sql("user", vec![], Some("data->>'name' ASC"));
-        assert_eq!(sql, "SELECT data FROM user ORDER BY data->>'name' ASC");
+    #[tokio::test]
+    async fn test_build_sql_with_order() {
+        let sql = query("user")
+            .order_by("data->>'name' ASC")
+            .to_sql()
+            .unwrap()
+            .sql;
+        assert_eq!(sql, "SELECT data FROM \"user\" ORDER BY data->>'name' ASC");
     }
 
-    #[test]
-    fn test_build_sql_with_limit() {
-        let mut sql = "SELECT data FROM user".to_string();
-        sql.push_str(" LIMIT 10");
-        assert_eq!(sql, "SELECT data FROM user LIMIT 10");
+    #[tokio::test]
+    async fn test_build_sql_with_limit() {
+        let sql = query("user").limit(10).to_sql().unwrap().sql;
+        assert_eq!(sql, "SELECT data FROM \"user\" LIMIT 10");
     }
 
-    #[test]
-    fn test_build_sql_with_offset() {
-        let mut sql = "SELECT data FROM user".to_string();
-        sql.push_str(" OFFSET 20");
-        assert_eq!(sql, "SELECT data FROM user OFFSET 20");
+    #[tokio::test]
+    async fn test_build_sql_with_offset() {
+        let sql = query("user").offset(20).to_sql().unwrap().sql;
+        assert_eq!(sql, "SELECT data FROM \"user\" OFFSET 20");
     }
 
-    #[test]
-    fn test_build_sql_with_limit_and_offset() {
-        let mut sql = "SELECT data FROM user".to_string();
-        sql.push_str(" LIMIT 10");
-        sql.push_str(" OFFSET 20");
-        assert_eq!(sql, "SELECT data FROM user LIMIT 10 OFFSET 20");
+    #[tokio::test]
+    async fn test_build_sql_with_limit_and_offset() {
+        let sql = query("user").limit(10).offset(20).to_sql().unwrap().sql;
+        assert_eq!(sql, "SELECT data FROM \"user\" LIMIT 10 OFFSET 20");
     }
 
-    #[test]
-    fn test_build_sql_complete() {
-        let mut sql = "SELECT data FROM user".to_string();
-        sql.push_str(" WHERE data->>'status' = 'active'");
-        sql.push_str(" ORDER BY data->>'name' ASC");
-        sql.push_str(" LIMIT 10");
-        sql.push_str(" OFFSET 20");
+    #[tokio::test]
+    async fn test_build_sql_complete() {
+        let sql = query("user")
+            .where_sql("data->>'status' = 'active'")
+            .order_by("data->>'name' ASC")
+            .limit(10)
+            .offset(20)
+            .to_sql()
+            .unwrap()
+            .sql;
         assert_eq!(
             sql,
-            "SELECT data FROM user WHERE data->>'status' = 'active' ORDER BY data->>'name' ASC LIMIT 10 OFFSET 20"
+            "SELECT data FROM \"user\" WHERE data->>'status' = 'active' ORDER BY data->>'name' ASC LIMIT 10 OFFSET 20"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_sql_with_params() {
+        let rendered = FraiseClient::unconnected()
+            .default_schema("app")
+            .query::<Value>("orders")
+            .where_sql_params("data->>'region' = $1", &[&"eu"])
+            .where_sql("data ? 'total'")
+            .where_sql_params("(data->>'total')::int > $1", &[&100])
+            .limit(5)
+            .to_sql()
+            .unwrap();
+        assert_eq!(
+            rendered.sql,
+            "SELECT data FROM \"app\".\"orders\" WHERE data->>'region' = $1 \
+             AND data ? 'total' AND (data->>'total')::int > $2 LIMIT 5"
+        );
+        assert_eq!(rendered.entity, "app.orders");
+        assert!(matches!(rendered.params[..], [
+            operators::Value::String(ref region),
+            operators::Value::Int(100),
+        ] if region == "eu"));
+        assert_eq!(
+            rendered.pretty(),
+            "SELECT data\n\
+             FROM \"app\".\"orders\"\n\
+             WHERE data->>'region' = $1\n  \
+             AND data ? 'total'\n  \
+             AND (data->>'total')::int > $2\n\
+             LIMIT 5\n\
+             -- $1 = 'eu'\n\
+             -- $2 = 100"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pretty_keeps_params_on_one_line() {
+        let tags = operators::Value::Array(vec![
+            operators::Value::String("x\ny".into()),
+            operators::Value::String("plain".into()),
+        ]);
+        let rendered = query("notes")
+            .where_sql_params(
+                "data->>'body' = $1 AND data->'tags' ?| $2",
+                &[&"it's\r\nDROP TABLE notes; -- \\", &tags],
+            )
+            .where_sql_params("data->>'title' = $1", &[&"no break"])
+            .to_sql()
+            .unwrap();
+        assert_eq!(
+            rendered.pretty(),
+            "SELECT data\n\
+             FROM \"notes\"\n\
+             WHERE data->>'body' = $1 AND data->'tags' ?| $2\n  \
+             AND data->>'title' = $3\n\
+             -- $1 = E'it\\'s\\r\\nDROP TABLE notes; -- \\\\'\n\
+             -- $2 = ARRAY[E'x\\ny', E'plain']\n\
+             -- $3 = 'no break'"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_sql_keyset_params() {
+        let order = [
+            OrderByClause::jsonb_field("score", operators::SortOrder::Desc),
+            OrderByClause::jsonb_field("id", operators::SortOrder::Desc),
+        ];
+        let cursor = KeysetCursor::from_row(&order, &serde_json::json!({"score": 5, "id": "x'"}));
+        let rendered = query("orders")
+            .where_sql_params("data->>'region' = $1", &[&"eu"])
+            .keyset_order(order)
+            .after(cursor.encode())
+            .limit(10)
+            .to_sql()
+            .unwrap();
+        assert_eq!(
+            rendered.sql,
+            "SELECT data FROM \"orders\" WHERE (data->>'region' = $1) \
+             AND (((data->'score'), (data->'id')) < ($2, $3)) \
+             ORDER BY (data->'score') DESC, (data->'id') DESC LIMIT 10"
         );
+        assert!(matches!(&rendered.params[..], [
+            operators::Value::String(_),
+            operators::Value::Json(score),
+            operators::Value::Json(id),
+        ] if *score == serde_json::json!(5) && *id == serde_json::json!("x'")));
+    }
+
+    #[tokio::test]
+    async fn test_build_sql_distance_params() {
+        let rendered = query("docs")
+            .where_sql_params("data->>'lang' = $1", &[&"en"])
+            .nearest(
+                3,
+                "embedding",
+                operators::DistanceMetric::Cosine,
+                vec![0.5, -1.0],
+            )
+            .project_distance("distance")
+            .to_sql()
+            .unwrap();
+        // The vector is bound once and shared by the projection and ORDER BY
+        assert_eq!(
+            rendered.sql,
+            "SELECT data || jsonb_build_object('distance', (embedding <=> $2::vector)) as data \
+             FROM \"docs\" WHERE data->>'lang' = $1 \
+             ORDER BY (embedding <=> $2::vector) ASC LIMIT 3"
+        );
+        assert!(matches!(&rendered.params[..], [
+            operators::Value::String(_),
+            operators::Value::FloatArray(vector),
+        ] if *vector == [0.5, -1.0]));
+
+        // count() without a limit drops the ORDER BY, and its vector with it
+        let (sql, params) = query("docs")
+            .order_by_clause(OrderByClause::distance(
+                "embedding",
+                operators::DistanceMetric::Cosine,
+                vec![0.5],
+            ))
+            .build_rows_sql("\"docs\"", None, Some("SELECT *"))
+            .unwrap();
+        assert_eq!(sql, "SELECT * FROM \"docs\"");
+        assert!(params.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_build_sql_rejected() {
+        let client = FraiseClient::unconnected().allow_entities(["orders"]);
+        assert!(matches!(
+            client.query::<Value>("users").to_sql(),
+            Err(Error::Config(_))
+        ));
+        assert!(matches!(
+            query("user").where_sql_params("a = $2", &[&1]).to_sql(),
+            Err(Error::Config(_))
+        ));
     }
 
     // Projection tests
-    #[test]
-    fn test_build_sql_default_select() {
-        let sql = build_test_sql("users", vec![], None);
+    #[tokio::test]
+    async fn test_build_sql_default_select() {
+        let sql = query("users").to_sql().unwrap().sql;
         assert!(sql.starts_with("SELECT data FROM"));
-        assert_eq!(sql, "SELECT data FROM users");
+        assert_eq!(sql, "SELECT data FROM \"users\"");
     }
 
-    #[test]
-    fn test_projection_single_field() {
-        let sql = "SELECT jsonb_build_object('id', data->>'id') as data FROM users".to_string();
-        assert!(sql.contains("as data"));
-        assert!(sql.starts_with("SELECT jsonb_build_object("));
-        assert!(sql.contains("FROM users"));
+    fn projected(projection: &str) -> QueryBuilder {
+        query("users").select_projection(projection)
     }
 
-    #[test]
-    fn test_projection_multiple_fields() {
+    #[tokio::test]
+    async fn test_projection_single_field() {
+        let sql = projected("jsonb_build_object('id', data->>'id')")
+            .to_sql()
+            .unwrap()
+            .sql;
+        assert_eq!(
+            sql,
+            "SELECT jsonb_build_object('id', data->>'id') as data FROM \"users\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_projection_multiple_fields() {
         let projection =
             "jsonb_build_object('id', data->>'id', 'name', data->>'name', 'email', data->>'email')";
-        let sql = format!("SELECT {} as data FROM users", projection);
-        assert!(sql.contains("as data FROM users"));
-        assert!(sql.contains("jsonb_build_object("));
-        assert!(sql.contains("'id'"));
-        assert!(sql.contains("'name'"));
-        assert!(sql.contains("'email'"));
+        let sql = projected(projection).to_sql().unwrap().sql;
+        assert_eq!(sql, format!("SELECT {} as data FROM \"users\"", projection));
     }
 
-    #[test]
-    fn test_projection_with_where_clause() {
-        let projection = "jsonb_build_object('id', data->>'id')";
-        let mut sql = format!("SELECT {} as data FROM users", projection);
-        sql.push_str(" WHERE data->>'status' = 'active'");
-        assert!(sql.contains("SELECT jsonb_build_object("));
-        assert!(sql.contains("as data FROM users"));
-        assert!(sql.contains("WHERE data->>'status' = 'active'"));
+    #[tokio::test]
+    async fn test_projection_with_where_clause() {
+        let sql = projected("jsonb_build_object('id', data->>'id')")
+            .where_sql("data->>'status' = 'active'")
+            .to_sql()
+            .unwrap()
+            .sql;
+        assert_eq!(
+            sql,
+            "SELECT jsonb_build_object('id', data->>'id') as data FROM \"users\" \
+             WHERE data->>'status' = 'active'"
+        );
     }
 
-    #[test]
-    fn test_projection_with_order_by() {
-        let projection = "jsonb_build_object('id', data->>'id')";
-        let mut sql = format!("SELECT {} as data FROM users", projection);
-        sql.push_str(" ORDER BY data->>'name' ASC");
-        assert!(sql.contains("SELECT jsonb_build_object("));
-        assert!(sql.contains("ORDER BY data->>'name' ASC"));
+    #[tokio::test]
+    async fn test_projection_with_order_by() {
+        let sql = projected("jsonb_build_object('id', data->>'id')")
+            .order_by("data->>'name' ASC")
+            .to_sql()
+            .unwrap()
+            .sql;
+        assert!(sql.starts_with("SELECT jsonb_build_object("));
+        assert!(sql.ends_with(" ORDER BY data->>'name' ASC"));
     }
 
-    #[test]
-    fn test_projection_with_limit() {
-        let projection = "jsonb_build_object('id', data->>'id')";
-        let mut sql = format!("SELECT {} as data FROM users", projection);
-        sql.push_str(" LIMIT 1000");
-        assert!(sql.contains("as data FROM users"));
-        assert!(sql.contains("LIMIT 1000"));
+    #[tokio::test]
+    async fn test_projection_with_limit() {
+        let sql = projected("jsonb_build_object('id', data->>'id')")
+            .limit(1000)
+            .to_sql()
+            .unwrap()
+            .sql;
+        assert!(sql.ends_with("as data FROM \"users\" LIMIT 1000"));
     }
 
-    #[test]
-    fn test_projection_with_offset() {
-        let projection = "jsonb_build_object('id', data->>'id')";
-        let mut sql = format!("SELECT {} as data FROM users", projection);
-        sql.push_str(" OFFSET 500");
-        assert!(sql.contains("as data FROM users"));
-        assert!(sql.contains("OFFSET 500"));
+    #[tokio::test]
+    async fn test_projection_with_offset() {
+        let sql = projected("jsonb_build_object('id', data->>'id')")
+            .offset(500)
+            .to_sql()
+            .unwrap()
+            .sql;
+        assert!(sql.ends_with("as data FROM \"users\" OFFSET 500"));
     }
 
-    #[test]
-    fn test_projection_full_pipeline() {
-        let projection =
-            "jsonb_build_object('user_id', data->>'user_id', 'event_type', data->>'event_type')";
-        let mut sql = format!("SELECT {} as data FROM events", projection);
-        sql.push_str(" WHERE event_type IN ('purchase', 'view')");
-        sql.push_str(" ORDER BY timestamp DESC");
-        sql.push_str(" LIMIT 5000");
-        assert!(sql.contains("SELECT jsonb_build_object("));
-        assert!(sql.contains("'user_id'"));
-        assert!(sql.contains("'event_type'"));
-        assert!(sql.contains("as data FROM events"));
-        assert!(sql.contains("WHERE event_type IN ('purchase', 'view')"));
-        assert!(sql.contains("ORDER BY timestamp DESC"));
-        assert!(sql.contains("LIMIT 5000"));
+    #[tokio::test]
+    async fn test_projection_full_pipeline() {
+        let rendered = FraiseClient::unconnected()
+            .query::<Value>("events")
+            .select_projection(
+                "jsonb_build_object('user_id', data->>'user_id', 'event_type', data->>'event_type')",
+            )
+            .where_sql("event_type IN ('purchase', 'view')")
+            .order_by("timestamp DESC")
+            .limit(5000)
+            .to_sql()
+            .unwrap();
+        assert_eq!(
+            rendered.pretty(),
+            "SELECT jsonb_build_object('user_id', data->>'user_id', 'event_type', data->>'event_type') as data\n\
+             FROM \"events\"\n\
+             WHERE event_type IN ('purchase', 'view')\n\
+             ORDER BY timestamp DESC\n\
+             LIMIT 5000"
+        );
     }
 
     // Stream pipeline integration tests
@@ -0,0 +1,141 @@
+//! Dry-run SQL rendering
+//!
+//! `QueryBuilder::to_sql()` returns the SQL `execute()` would send, with its
+//! bound parameters, without touching the connection. Useful for logging and
+//! for snapshot tests of the SQL that typed filters produce:
+//!
+//! ```ignore
+//! let rendered = client
+//!     .query::<Value>("orders")
+//!     .where_sql_params("data->>'region' = $1", &[&"eu"])
+//!     .limit(10)
+//!     .to_sql()?;
+//! assert_eq!(
+//!     rendered.pretty(),
+//!     "SELECT data\nFROM \"orders\"\nWHERE data->>'region' = $1\nLIMIT 10\n-- $1 = 'eu'"
+//! );
+//! ```
+
+use crate::operators::Value;
+
+/// A query rendered without executing it
+#[derive(Debug, Clone)]
+pub struct RenderedQuery {
+    /// SQL sent to the server, exactly as `execute()` would send it
+    pub sql: String,
+
+    /// Values bound to `$1`, `$2`, ... (empty for the simple query protocol)
+    pub params: Vec<Value>,
+
+    /// The entity or raw `FROM` item, as reported in metrics
+    pub entity: String,
+
+    statement: SelectStatement,
+}
+
+impl RenderedQuery {
+    pub(crate) fn new(statement: SelectStatement, params: Vec<Value>, entity: String) -> Self {
+        Self {
+            sql: statement.to_sql(),
+            params,
+            entity,
+            statement,
+        }
+    }
+
+    /// Multi-line rendering for logs and snapshots
+    ///
+    /// One clause per line, one `WHERE` predicate per line, followed by a
+    /// `-- $n = literal` comment per bound parameter. Literals holding line
+    /// breaks are written as `E'...'` strings so each comment stays on one line.
+    /// The layout only depends on the builder's input, so it is stable across
+    /// runs.
+    pub fn pretty(&self) -> String {
+        let mut out = self.statement.pretty();
+        for (i, param) in self.params.iter().enumerate() {
+            let literal = single_line(&param.to_sql_literal());
+            out.push_str(&format!("\n-- ${} = {}", i + 1, literal));
+        }
+        out
+    }
+}
+
+/// `literal` with its quoted strings rewritten as `E'...'` escape strings if
+/// any line break occurs, so that it fits in a `--` comment
+fn single_line(literal: &str) -> String {
+    if !literal.contains(['\n', '\r']) {
+        return literal.to_string();
+    }
+    let mut out = String::with_capacity(literal.len() + 8);
+    let mut chars = literal.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\'' {
+            out.push(c);
+            continue;
+        }
+        out.push_str("E'");
+        loop {
+            match chars.next() {
+                Some('\'') if chars.peek() == Some(&'\'') => {
+                    chars.next();
+                    out.push_str("\\'");
+                }
+                Some('\'') | None => break,
+                Some('\\') => out.push_str("\\\\"),
+                Some('\n') => out.push_str("\\n"),
+                Some('\r') => out.push_str("\\r"),
+                Some(c) => out.push(c),
+            }
+        }
+        out.push('\'');
+    }
+    out
+}
+
+/// `SELECT ... FROM ... [WHERE] [ORDER BY] [LIMIT] [OFFSET]`
+#[derive(Debug, Clone)]
+pub(crate) struct SelectStatement {
+    pub(crate) select: String,
+    pub(crate) from: String,
+    pub(crate) predicates: Vec<String>,
+    pub(crate) order: Option<String>,
+    pub(crate) limit: Option<usize>,
+    pub(crate) offset: Option<usize>,
+}
+
+impl SelectStatement {
+    /// Single-line SQL
+    pub(crate) fn to_sql(&self) -> String {
+        self.render(" ", " AND ")
+    }
+
+    fn pretty(&self) -> String {
+        self.render("\n", "\n  AND ")
+    }
+
+    fn render(&self, clause_separator: &str, predicate_separator: &str) -> String {
+        let mut sql = format!("{}{}FROM {}", self.select, clause_separator, self.from);
+
+        if !self.predicates.is_empty() {
+            sql.push_str(clause_separator);
+            sql.push_str("WHERE ");
+            sql.push_str(&self.predicates.join(predicate_separator));
+        }
+
+        if let Some(ref order) = self.order {
+            sql.push_str(clause_separator);
+            sql.push_str("ORDER BY ");
+            sql.push_str(order);
+        }
+
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!("{}LIMIT {}", clause_separator, limit));
+        }
+
+        if let Some(offset) = self.offset {
+            sql.push_str(&format!("{}OFFSET {}", clause_separator, offset));
+        }
+
+        sql
+    }
+}
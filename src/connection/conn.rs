@@ -1,18 +1,35 @@
 //! Core connection type
 
+use super::cursor::{
+    declare_cursor_sql, fetch_sql, finish_cursor_sql, ExecutionMode, CURSOR_BEGIN_SQL,
+};
+use super::notice::{dispatch_notice, NoticeHandler, NOTICE_CHANNEL_CAPACITY};
+use super::session::{apply_parameter_status, SessionInfo, SharedSessionInfo};
+use super::shutdown::{wait_for_shutdown, ShutdownHandle};
 use super::state::ConnectionState;
+use super::statement_cache::{Lookup, Recovery, StatementCache};
+use super::telemetry::{query_span, record_query_totals};
 use super::tls::SslMode;
+use super::transaction::{
+    begin_sql, set_snapshot_sql, IsolationLevel, SnapshotId, TransactionStatus,
+};
 use super::transport::Transport;
-use crate::auth::scram::ChannelBinding;
-use crate::auth::ScramClient;
+use crate::auth::{
+    negotiate_mechanism, ChannelBindingMode, CredentialProvider, Credentials, ScramClient,
+};
+use crate::operators::Value;
 use crate::protocol::{
-    decode_message, encode_message, AuthenticationMessage, BackendMessage, FrontendMessage,
+    decode_message, encode_message, AuthenticationMessage, BackendMessage, ErrorFields,
+    FrontendMessage,
 };
+use crate::stream::{RowChunk, StreamState};
 use crate::{Error, Result};
 use bytes::{Buf, BytesMut};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
+use tokio::sync::{mpsc, Mutex, Notify};
 use tracing::Instrument;
 
 // Global counter for chunk metrics sampling (1 per 10 chunks)
@@ -45,6 +62,16 @@ pub struct ConnectionConfig {
     pub extra_float_digits: Option<i32>,
     /// SSL/TLS mode
     pub sslmode: SslMode,
+    /// SCRAM channel binding policy (default: prefer)
+    pub channel_binding: ChannelBindingMode,
+    /// Dynamic credential source; overrides `user` and `password` when set
+    pub credential_provider: Option<Arc<dyn CredentialProvider>>,
+    /// Callback for server notices (`RAISE NOTICE`, warnings)
+    pub notice_handler: Option<NoticeHandler>,
+    /// Query run after authentication to validate a new connection
+    pub check_on_connect: Option<String>,
+    /// Number of prepared statements cached per connection (0 disables the cache)
+    pub statement_cache_capacity: usize,
 }
 
 impl ConnectionConfig {
@@ -62,6 +89,8 @@ impl ConnectionConfig {
     /// - `keepalive_idle`: None
     /// - `application_name`: None
     /// - `extra_float_digits`: None
+    /// - `channel_binding`: prefer
+    /// - `statement_cache_capacity`: 0 (disabled)
     ///
     /// For configured timeouts and keepalive, use `builder()` instead.
     pub fn new(database: impl Into<String>, user: impl Into<String>) -> Self {
@@ -76,6 +105,11 @@ impl ConnectionConfig {
             application_name: None,
             extra_float_digits: None,
             sslmode: SslMode::default(),
+            channel_binding: ChannelBindingMode::default(),
+            credential_provider: None,
+            notice_handler: None,
+            check_on_connect: None,
+            statement_cache_capacity: 0,
         }
     }
 
@@ -106,6 +140,11 @@ impl ConnectionConfig {
             application_name: None,
             extra_float_digits: None,
             sslmode: SslMode::default(),
+            channel_binding: ChannelBindingMode::default(),
+            credential_provider: None,
+            notice_handler: None,
+            check_on_connect: None,
+            statement_cache_capacity: 0,
         }
     }
 
@@ -120,6 +159,27 @@ impl ConnectionConfig {
         self.params.insert(key.into(), value.into());
         self
     }
+
+    /// Set a dynamic credential provider
+    pub fn credential_provider(mut self, provider: Arc<dyn CredentialProvider>) -> Self {
+        self.credential_provider = Some(provider);
+        self
+    }
+
+    /// Resolve the credentials for a new connection
+    ///
+    /// Asks the credential provider when one is configured, otherwise returns
+    /// the static `user` and `password`.
+    pub async fn resolve_credentials(&self) -> Result<Credentials> {
+        match &self.credential_provider {
+            Some(provider) => provider.credentials().await,
+            None => Ok(Credentials {
+                user: self.user.clone(),
+                password: self.password.clone(),
+                expires_at: None,
+            }),
+        }
+    }
 }
 
 /// Builder for creating `ConnectionConfig` with advanced options
@@ -149,6 +209,11 @@ pub struct ConnectionConfigBuilder {
     application_name: Option<String>,
     extra_float_digits: Option<i32>,
     sslmode: SslMode,
+    channel_binding: ChannelBindingMode,
+    credential_provider: Option<Arc<dyn CredentialProvider>>,
+    notice_handler: Option<NoticeHandler>,
+    check_on_connect: Option<String>,
+    statement_cache_capacity: usize,
 }
 
 impl ConnectionConfigBuilder {
@@ -230,6 +295,93 @@ impl ConnectionConfigBuilder {
         self
     }
 
+    /// Set SCRAM channel binding policy
+    ///
+    /// Default: `ChannelBindingMode::Prefer`
+    ///
+    /// With `ChannelBindingMode::Require` the connection fails unless the server
+    /// authenticates with SCRAM-SHA-256-PLUS over TLS. Cleartext passwords and
+    /// servers that skip authentication are rejected.
+    pub fn channel_binding(mut self, mode: ChannelBindingMode) -> Self {
+        self.channel_binding = mode;
+        self
+    }
+
+    /// Set a dynamic credential provider
+    ///
+    /// The provider is called on every `Connection::startup`, so long-lived
+    /// applications pick up rotated passwords or tokens for each new connection.
+    /// Wrap it in `CachedCredentialProvider` to avoid a fetch per connection.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let provider = Arc::new(CachedCredentialProvider::new(VaultProvider::new()));
+    /// let config = ConnectionConfig::builder("mydb", "ignored")
+    ///     .credential_provider(provider)
+    ///     .build();
+    /// ```
+    pub fn credential_provider(mut self, provider: Arc<dyn CredentialProvider>) -> Self {
+        self.credential_provider = Some(provider);
+        self
+    }
+
+    /// Set a callback for server notices
+    ///
+    /// Called for every `NoticeResponse` on connections built from this config,
+    /// including notices raised mid-stream by `RAISE NOTICE` in view functions.
+    /// Runs on the reader task, so it must not block.
+    pub fn notice_handler(mut self, handler: NoticeHandler) -> Self {
+        self.notice_handler = Some(handler);
+        self
+    }
+
+    /// Run a validation query after authentication
+    ///
+    /// Startup fails with `Error::Connection` if the query returns an error, e.g.
+    /// `SELECT 1` to catch misrouted connections or `SELECT pg_is_in_recovery() = false`
+    /// style checks implemented as a function that raises.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let config = ConnectionConfig::builder("mydb", "user")
+    ///     .check_on_connect("SELECT 1")
+    ///     .build();
+    /// ```
+    pub fn check_on_connect(mut self, query: impl Into<String>) -> Self {
+        self.check_on_connect = Some(query.into());
+        self
+    }
+
+    /// Cache up to `capacity` prepared statements per connection
+    ///
+    /// Queries with bound parameters then run as named prepared statements
+    /// keyed by their whitespace-normalized SQL and parameter types: parsed
+    /// once, bound and executed on later calls with any values, and closed
+    /// least-recently-used first. Queries without parameters carry their
+    /// values in the SQL text and are not cached. Statements invalidated by a
+    /// schema change are prepared again. Behind a pooler in transaction mode
+    /// the connection falls back to unnamed statements on the first sign of
+    /// a lost statement. Default: 0 (disabled).
+    ///
+    /// The cache lives as long as the connection, so it only pays off for a
+    /// `Connection` that runs many queries, e.g. with `query_with_params()`.
+    /// A `FraiseClient` runs a single builder query per connection, which
+    /// never hits the cache.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let config = ConnectionConfig::builder("mydb", "user")
+    ///     .statement_cache_capacity(64)
+    ///     .build();
+    /// ```
+    pub fn statement_cache_capacity(mut self, capacity: usize) -> Self {
+        self.statement_cache_capacity = capacity;
+        self
+    }
+
     /// Build the configuration
     pub fn build(self) -> ConnectionConfig {
         ConnectionConfig {
@@ -243,6 +395,11 @@ impl ConnectionConfigBuilder {
             application_name: self.application_name,
             extra_float_digits: self.extra_float_digits,
             sslmode: self.sslmode,
+            channel_binding: self.channel_binding,
+            credential_provider: self.credential_provider,
+            notice_handler: self.notice_handler,
+            check_on_connect: self.check_on_connect,
+            statement_cache_capacity: self.statement_cache_capacity,
         }
     }
 }
@@ -254,6 +411,15 @@ pub struct Connection {
     read_buf: BytesMut,
     process_id: Option<i32>,
     secret_key: Option<i32>,
+    sasl_mechanism: Option<&'static str>,
+    session: SharedSessionInfo,
+    notice_handler: Option<NoticeHandler>,
+    transaction_status: TransactionStatus,
+    shutdown: Option<ShutdownHandle>,
+    database: Option<String>,
+    metrics_entity: Option<String>,
+    query_params: Vec<Value>,
+    statements: StatementCache,
 }
 
 impl Connection {
@@ -265,6 +431,15 @@ impl Connection {
             read_buf: BytesMut::with_capacity(8192),
             process_id: None,
             secret_key: None,
+            sasl_mechanism: None,
+            session: Arc::new(RwLock::new(SessionInfo::default())),
+            notice_handler: None,
+            transaction_status: TransactionStatus::Idle,
+            shutdown: None,
+            database: None,
+            metrics_entity: None,
+            query_params: Vec::new(),
+            statements: StatementCache::default(),
         }
     }
 
@@ -273,6 +448,63 @@ impl Connection {
         self.state
     }
 
+    /// Snapshot of the server session parameters
+    ///
+    /// Populated during startup from `ParameterStatus` and `BackendKeyData`.
+    pub fn session_info(&self) -> SessionInfo {
+        self.session
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Transaction status from the last `ReadyForQuery`
+    pub fn transaction_status(&self) -> TransactionStatus {
+        self.transaction_status
+    }
+
+    /// SASL mechanism negotiated during startup
+    ///
+    /// Returns `Some("SCRAM-SHA-256-PLUS")` when the session is channel-bound,
+    /// `Some("SCRAM-SHA-256")` for plain SCRAM, and `None` for non-SASL authentication.
+    pub fn sasl_mechanism(&self) -> Option<&'static str> {
+        self.sasl_mechanism
+    }
+
+    /// Attach a shutdown handle to streams started from this connection
+    ///
+    /// When `ShutdownHandle::shutdown()` is called, the stream's query is
+    /// cancelled on the server and the connection closed within the deadline.
+    pub fn set_shutdown_handle(&mut self, handle: ShutdownHandle) {
+        self.shutdown = Some(handle);
+    }
+
+    /// Label queries with `entity` in metrics instead of parsing their SQL
+    ///
+    /// Set by `FraiseClient` from its entity allowlist so labels stay bounded.
+    pub(crate) fn set_metrics_entity(&mut self, entity: Option<String>) {
+        self.metrics_entity = entity;
+    }
+
+    /// Bind `params` to the `$n` placeholders of the next `streaming_query()`
+    /// or `cursor_query()`, which then uses the extended protocol
+    pub(crate) fn set_query_params(&mut self, params: Vec<Value>) {
+        self.query_params = params;
+    }
+
+    /// Entity label for metrics
+    fn entity_label(&self, query: &str) -> String {
+        self.metrics_entity
+            .clone()
+            .or_else(|| extract_entity_from_query(query))
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    /// Shutdown handle attached with `set_shutdown_handle()`
+    pub fn shutdown_handle(&self) -> Option<&ShutdownHandle> {
+        self.shutdown.as_ref()
+    }
+
     /// Negotiate TLS upgrade with the server via the SSLRequest protocol.
     ///
     /// Sends the 8-byte SSLRequest message and reads the server's single-byte response.
@@ -334,6 +566,10 @@ impl Connection {
         hostname: Option<&str>,
     ) -> Result<()> {
         async {
+            self.notice_handler = config.notice_handler.clone();
+            self.database = Some(config.database.clone());
+            self.statements = StatementCache::new(config.statement_cache_capacity);
+
             // TLS negotiation (if requested)
             if config.sslmode != SslMode::Disable {
                 let tls = tls_config.ok_or_else(|| {
@@ -344,14 +580,20 @@ impl Connection {
                 })?;
                 let host = hostname
                     .ok_or_else(|| Error::Config("TLS negotiation requires a hostname".into()))?;
-                self.negotiate_tls(tls, host, config.sslmode).await?;
+                self.negotiate_tls(tls, host, config.sslmode)
+                    .instrument(tracing::info_span!("tls", sslmode = %config.sslmode))
+                    .await?;
             }
 
             self.state.transition(ConnectionState::AwaitingAuth)?;
 
+            // Resolve credentials (static or from the provider) for this connection
+            let credentials = config.resolve_credentials().await?;
+            tracing::Span::current().record("user", credentials.user.as_str());
+
             // Build startup parameters
             let mut params = vec![
-                ("user".to_string(), config.user.clone()),
+                ("user".to_string(), credentials.user.clone()),
                 ("database".to_string(), config.database.clone()),
             ];
 
@@ -368,6 +610,11 @@ impl Connection {
                 ));
             }
 
+            // JSON rows are decoded as UTF-8; ask for it explicitly (verified after auth)
+            if !config.params.contains_key("client_encoding") {
+                params.push(("client_encoding".to_string(), "UTF8".to_string()));
+            }
+
             // Add extra_float_digits if specified
             if let Some(digits) = config.extra_float_digits {
                 params.push(("extra_float_digits".to_string(), digits.to_string()));
@@ -387,22 +634,50 @@ impl Connection {
 
             // Authentication loop
             self.state.transition(ConnectionState::Authenticating)?;
-            self.authenticate(config).await?;
+            if let Err(e) = self
+                .authenticate(config, &credentials)
+                .instrument(tracing::info_span!(
+                    "auth",
+                    mechanism = tracing::field::Empty
+                ))
+                .await
+            {
+                // Rejected credentials may have been rotated; fetch fresh ones next time
+                if let (Error::Authentication(_), Some(provider)) =
+                    (&e, &config.credential_provider)
+                {
+                    provider.invalidate();
+                }
+                return Err(e);
+            }
+
+            self.session_info().ensure_utf8()?;
 
             self.state.transition(ConnectionState::Idle)?;
+
+            if let Some(query) = &config.check_on_connect {
+                self.execute_command(query).await.map_err(|e| {
+                    Error::Connection(format!("check_on_connect query failed: {}", e))
+                })?;
+            }
+
             tracing::info!("startup complete");
             Ok(())
         }
         .instrument(tracing::info_span!(
             "startup",
-            user = %config.user,
+            user = tracing::field::Empty,
             database = %config.database
         ))
         .await
     }
 
     /// Handle authentication
-    async fn authenticate(&mut self, config: &ConnectionConfig) -> Result<()> {
+    async fn authenticate(
+        &mut self,
+        config: &ConnectionConfig,
+        credentials: &Credentials,
+    ) -> Result<()> {
         let auth_start = std::time::Instant::now();
         let mut auth_mechanism = "unknown";
 
@@ -412,7 +687,19 @@ impl Connection {
             match msg {
                 BackendMessage::Authentication(auth) => match auth {
                     AuthenticationMessage::Ok => {
+                        if config.channel_binding == ChannelBindingMode::Require
+                            && self.sasl_mechanism != Some(crate::auth::scram::SCRAM_SHA_256_PLUS)
+                        {
+                            crate::metrics::counters::auth_failed(
+                                auth_mechanism,
+                                "channel_binding_required",
+                            );
+                            return Err(Error::Authentication(
+                                "channel_binding=require but server authenticated without channel binding".into(),
+                            ));
+                        }
                         tracing::debug!("authentication successful");
+                        tracing::Span::current().record("mechanism", auth_mechanism);
                         crate::metrics::counters::auth_successful(auth_mechanism);
                         crate::metrics::histograms::auth_duration(
                             auth_mechanism,
@@ -424,7 +711,18 @@ impl Connection {
                         auth_mechanism = crate::metrics::labels::MECHANISM_CLEARTEXT;
                         crate::metrics::counters::auth_attempted(auth_mechanism);
 
-                        let password = config
+                        if config.channel_binding == ChannelBindingMode::Require {
+                            crate::metrics::counters::auth_failed(
+                                auth_mechanism,
+                                "channel_binding_required",
+                            );
+                            return Err(Error::Authentication(
+                                "channel_binding=require but server requested a cleartext password"
+                                    .into(),
+                            ));
+                        }
+
+                        let password = credentials
                             .password
                             .as_ref()
                             .ok_or_else(|| Error::Authentication("password required".into()))?;
@@ -439,7 +737,7 @@ impl Connection {
                     AuthenticationMessage::Sasl { mechanisms } => {
                         auth_mechanism = crate::metrics::labels::MECHANISM_SCRAM;
                         crate::metrics::counters::auth_attempted(auth_mechanism);
-                        self.handle_sasl(&mechanisms, config).await?;
+                        self.handle_sasl(&mechanisms, config, credentials).await?;
                     }
                     AuthenticationMessage::SaslContinue { .. } => {
                         return Err(Error::Protocol(
@@ -458,11 +756,19 @@ impl Connection {
                 } => {
                     self.process_id = Some(process_id);
                     self.secret_key = Some(secret_key);
+                    self.session
+                        .write()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .process_id = Some(process_id);
                 }
                 BackendMessage::ParameterStatus { name, value } => {
-                    tracing::debug!("parameter status: {} = {}", name, value);
+                    apply_parameter_status(&self.session, &name, &value)?;
                 }
-                BackendMessage::ReadyForQuery { status: _ } => {
+                BackendMessage::NoticeResponse(notice) => {
+                    dispatch_notice(notice, self.notice_handler.as_ref(), None);
+                }
+                BackendMessage::ReadyForQuery { status } => {
+                    self.transaction_status = TransactionStatus::from_byte(status)?;
                     break;
                 }
                 BackendMessage::ErrorResponse(err) => {
@@ -486,6 +792,7 @@ impl Connection {
         &mut self,
         mechanisms: &[String],
         config: &ConnectionConfig,
+        credentials: &Credentials,
     ) -> Result<()> {
         // Determine channel binding and mechanism
         let channel_binding_data = self
@@ -493,33 +800,18 @@ impl Connection {
             .as_ref()
             .and_then(|t| t.channel_binding_data());
 
-        let (mechanism, channel_binding) = if mechanisms.contains(&"SCRAM-SHA-256-PLUS".to_string())
-        {
-            if let Some(cb_data) = channel_binding_data {
-                (
-                    "SCRAM-SHA-256-PLUS",
-                    ChannelBinding::TlsServerEndPoint(cb_data),
-                )
-            } else {
-                ("SCRAM-SHA-256", ChannelBinding::None)
-            }
-        } else if mechanisms.contains(&"SCRAM-SHA-256".to_string()) {
-            ("SCRAM-SHA-256", ChannelBinding::None)
-        } else {
-            return Err(Error::Authentication(format!(
-                "server does not support SCRAM-SHA-256. Available: {}",
-                mechanisms.join(", ")
-            )));
-        };
+        let (mechanism, channel_binding) =
+            negotiate_mechanism(mechanisms, channel_binding_data, config.channel_binding)
+                .map_err(|e| Error::Authentication(e.to_string()))?;
 
         // Get password
-        let password = config.password.as_ref().ok_or_else(|| {
+        let password = credentials.password.as_ref().ok_or_else(|| {
             Error::Authentication("password required for SCRAM authentication".into())
         })?;
 
         // Create SCRAM client with channel binding support
         let mut scram = ScramClient::with_channel_binding(
-            config.user.clone(),
+            credentials.user.clone(),
             password.clone(),
             channel_binding,
         );
@@ -587,12 +879,43 @@ impl Connection {
             .verify_server_final(&server_final, &scram_state)
             .map_err(|e| Error::Authentication(format!("SCRAM verification failed: {}", e)))?;
 
-        tracing::debug!("SCRAM-SHA-256 authentication successful");
+        self.sasl_mechanism = Some(mechanism);
+        tracing::debug!("{} authentication successful", mechanism);
         Ok(())
     }
 
     /// Execute a simple query (returns all backend messages)
     pub async fn simple_query(&mut self, query: &str) -> Result<Vec<BackendMessage>> {
+        self.run_query(query, &[]).await
+    }
+
+    /// Execute a single statement with `params` bound to its `$n` placeholders
+    /// (returns all backend messages)
+    ///
+    /// Uses the extended protocol, and a cached prepared statement when
+    /// `statement_cache_capacity` is set. Without parameters this is a Simple
+    /// Query.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let messages = conn
+    ///     .query_with_params("SELECT data FROM v_user WHERE data->>'id' = $1", &[Value::String(id)])
+    ///     .await?;
+    /// ```
+    pub async fn query_with_params(
+        &mut self,
+        query: &str,
+        params: &[Value],
+    ) -> Result<Vec<BackendMessage>> {
+        self.run_query(query, params).await
+    }
+
+    /// Execute a query, binding `params` with the extended protocol if any
+    ///
+    /// A query with parameters goes through the statement cache when it is
+    /// enabled, and is retried once if the cache recovers from its error.
+    async fn run_query(&mut self, query: &str, params: &[Value]) -> Result<Vec<BackendMessage>> {
         if self.state != ConnectionState::Idle {
             return Err(Error::ConnectionBusy(format!(
                 "connection in state: {}",
@@ -600,27 +923,248 @@ impl Connection {
             )));
         }
 
-        self.state.transition(ConnectionState::QueryInProgress)?;
+        let mut retried = false;
+        loop {
+            let query_msgs = self.query_messages(query, params, false)?;
 
-        let query_msg = FrontendMessage::Query(query.to_string());
-        self.send_message(&query_msg).await?;
+            self.state.transition(ConnectionState::QueryInProgress)?;
 
-        self.state.transition(ConnectionState::ReadingResults)?;
+            self.send_messages(&query_msgs).await?;
 
-        let mut messages = Vec::new();
+            self.state.transition(ConnectionState::ReadingResults)?;
 
-        loop {
-            let msg = self.receive_message().await?;
-            let is_ready = matches!(msg, BackendMessage::ReadyForQuery { .. });
-            messages.push(msg);
+            let mut messages = Vec::new();
+            let mut recovery = Recovery::None;
+
+            loop {
+                let msg = self.receive_message().await?;
+                match &msg {
+                    BackendMessage::ParameterStatus { name, value } => {
+                        apply_parameter_status(&self.session, name, value)?;
+                    }
+                    BackendMessage::NoticeResponse(notice) => {
+                        dispatch_notice(notice.clone(), self.notice_handler.as_ref(), None);
+                    }
+                    BackendMessage::ReadyForQuery { status } => {
+                        self.transaction_status = TransactionStatus::from_byte(*status)?;
+                    }
+                    BackendMessage::ParseComplete => self.statements.parsed(),
+                    BackendMessage::ErrorResponse(err) => recovery = self.recover(err),
+                    _ => {}
+                }
+                let is_ready = matches!(msg, BackendMessage::ReadyForQuery { .. });
+                messages.push(msg);
 
-            if is_ready {
-                break;
+                if is_ready {
+                    break;
+                }
+            }
+
+            self.state.transition(ConnectionState::Idle)?;
+            if !self.should_retry(recovery, &mut retried) {
+                return Ok(messages);
             }
         }
+    }
 
-        self.state.transition(ConnectionState::Idle)?;
-        Ok(messages)
+    /// Messages that run `query`
+    ///
+    /// With bound parameters and the statement cache enabled, a cached named
+    /// statement (parsed on first use) is executed, after closing evicted
+    /// statements. SQL without parameters has its values inline, so caching it
+    /// would only churn the cache.
+    /// Otherwise see the free function `query_messages()`.
+    fn query_messages(
+        &mut self,
+        query: &str,
+        params: &[Value],
+        describe: bool,
+    ) -> Result<Vec<FrontendMessage>> {
+        if params.is_empty() || !self.statements.is_enabled() {
+            self.statements.clear_in_flight();
+            return query_messages(query, params, describe, None);
+        }
+
+        let param_types: Vec<u32> = params.iter().map(Value::type_oid).collect();
+        let statement = self.statements.lookup(query, &param_types);
+        let entity = self.entity_label(query);
+        match statement {
+            Lookup::Hit(_) => crate::metrics::counters::statement_cache_hit(&entity),
+            Lookup::Miss { .. } => crate::metrics::counters::statement_cache_miss(&entity),
+        }
+        match query_messages(query, params, describe, Some(statement)) {
+            Ok(query_msgs) => {
+                let mut msgs: Vec<_> = self
+                    .statements
+                    .take_evicted()
+                    .into_iter()
+                    .map(FrontendMessage::CloseStatement)
+                    .collect();
+                msgs.extend(query_msgs);
+                Ok(msgs)
+            }
+            Err(e) => {
+                // Nothing is sent, so the new statement never exists
+                self.statements.failed(None);
+                Err(e)
+            }
+        }
+    }
+
+    /// Let the statement cache react to a failed query
+    fn recover(&mut self, err: &ErrorFields) -> Recovery {
+        let recovery = self.statements.failed(err.code.as_deref());
+        let reason = match recovery {
+            Recovery::None => return recovery,
+            Recovery::Reprepare => "result_type_changed",
+            Recovery::Unnamed => "pooler",
+        };
+        tracing::debug!("statement cache invalidated ({}): {}", reason, err);
+        crate::metrics::counters::statement_cache_invalidated(reason);
+        recovery
+    }
+
+    /// Whether to run a query again after the statement cache recovered from
+    /// its error (not within a transaction block, which the error aborted)
+    fn should_retry(&self, recovery: Recovery, retried: &mut bool) -> bool {
+        if recovery == Recovery::None
+            || *retried
+            || self.transaction_status != TransactionStatus::Idle
+        {
+            return false;
+        }
+        *retried = true;
+        true
+    }
+
+    /// Check that the server still answers, using an empty query round trip
+    ///
+    /// An empty query string makes the server reply with `EmptyQueryResponse`
+    /// and `ReadyForQuery` without parsing or planning anything.
+    pub async fn ping(&mut self) -> Result<()> {
+        self.execute_command("").await?;
+        Ok(())
+    }
+
+    /// Check whether the transport has been closed, without blocking
+    ///
+    /// Polls the socket once: an end-of-file or read error (e.g. the server
+    /// terminated the backend, or a proxy dropped the connection) marks the
+    /// connection closed. Data the server sent unprompted stays buffered for the
+    /// next query. A `false` result does not guarantee the next query succeeds;
+    /// use `ping()` for that.
+    pub fn is_closed(&mut self) -> bool {
+        use futures::FutureExt;
+
+        if self.state == ConnectionState::Closed {
+            return true;
+        }
+        let Some(transport) = self.transport.as_mut() else {
+            return true;
+        };
+        loop {
+            match transport.read_buf(&mut self.read_buf).now_or_never() {
+                // Would block: nothing to read, connection still open
+                None => return false,
+                Some(Ok(0)) | Some(Err(_)) => {
+                    let _ = self.state.transition(ConnectionState::Closed);
+                    return true;
+                }
+                Some(Ok(_)) => continue,
+            }
+        }
+    }
+
+    /// Execute a command, turning an `ErrorResponse` into `Error::Sql`
+    async fn execute_command(&mut self, sql: &str) -> Result<Vec<BackendMessage>> {
+        let messages = self.run_query(sql, &[]).await?;
+        sql_error(messages)
+    }
+
+    /// Execute a statement with bound parameters through the statement cache,
+    /// turning an `ErrorResponse` into `Error::Sql`
+    async fn execute_bound(&mut self, sql: &str, params: &[Value]) -> Result<Vec<BackendMessage>> {
+        let messages = self.run_query(sql, params).await?;
+        sql_error(messages)
+    }
+
+    /// Begin a read-only transaction
+    ///
+    /// Use `IsolationLevel::RepeatableRead` or `Serializable` to read all
+    /// subsequent queries from a single snapshot.
+    pub async fn begin(&mut self, isolation: IsolationLevel) -> Result<()> {
+        if self.transaction_status != TransactionStatus::Idle {
+            return Err(Error::ConnectionBusy(
+                "a transaction is already in progress".into(),
+            ));
+        }
+        self.execute_command(&begin_sql(isolation)).await?;
+        tracing::debug!("transaction started ({})", isolation);
+        Ok(())
+    }
+
+    /// Commit the current transaction
+    pub async fn commit(&mut self) -> Result<()> {
+        self.execute_command("COMMIT").await?;
+        Ok(())
+    }
+
+    /// Roll back the current transaction
+    pub async fn rollback(&mut self) -> Result<()> {
+        self.execute_command("ROLLBACK").await?;
+        Ok(())
+    }
+
+    /// Export the current transaction's snapshot with `pg_export_snapshot()`
+    ///
+    /// The snapshot stays importable only while this transaction is open.
+    pub async fn export_snapshot(&mut self) -> Result<SnapshotId> {
+        if self.transaction_status != TransactionStatus::InTransaction {
+            return Err(Error::Config(
+                "export_snapshot requires an open transaction".into(),
+            ));
+        }
+        let id = self
+            .query_scalar("SELECT pg_export_snapshot()", &[])
+            .await?
+            .ok_or_else(|| Error::Protocol("pg_export_snapshot() returned no row".into()))?;
+        SnapshotId::parse(id)
+    }
+
+    /// Run a query and return the first column of its first row as text
+    ///
+    /// Returns `None` if the query produced no rows or the value was NULL.
+    /// `params` are bound to the query's `$n` placeholders.
+    pub(crate) async fn query_scalar(
+        &mut self,
+        sql: &str,
+        params: &[Value],
+    ) -> Result<Option<String>> {
+        let messages = self.execute_bound(sql, params).await?;
+        let value = messages.into_iter().find_map(|msg| match msg {
+            BackendMessage::DataRow(fields) => Some(fields.into_iter().next().flatten()),
+            _ => None,
+        });
+        match value.flatten() {
+            Some(bytes) => String::from_utf8(bytes.to_vec())
+                .map(Some)
+                .map_err(|e| Error::Protocol(format!("invalid UTF-8 in query result: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    /// Import a snapshot exported by another connection
+    ///
+    /// Must be called right after `begin()` with `RepeatableRead` or `Serializable`,
+    /// before any query in the transaction.
+    pub async fn set_transaction_snapshot(&mut self, snapshot: &SnapshotId) -> Result<()> {
+        if self.transaction_status != TransactionStatus::InTransaction {
+            return Err(Error::Config(
+                "set_transaction_snapshot requires an open transaction".into(),
+            ));
+        }
+        self.execute_command(&set_snapshot_sql(snapshot)).await?;
+        Ok(())
     }
 
     /// Send a frontend message
@@ -632,6 +1176,18 @@ impl Connection {
         Ok(())
     }
 
+    /// Send several frontend messages with a single flush
+    async fn send_messages(&mut self, msgs: &[FrontendMessage]) -> Result<()> {
+        let mut buf = BytesMut::new();
+        for msg in msgs {
+            buf.extend_from_slice(&encode_message(msg)?);
+        }
+        let transport = self.transport.as_mut().expect("transport not available");
+        transport.write_all(&buf).await?;
+        transport.flush().await?;
+        Ok(())
+    }
+
     /// Receive a backend message
     async fn receive_message(&mut self) -> Result<BackendMessage> {
         loop {
@@ -659,6 +1215,344 @@ impl Connection {
         Ok(())
     }
 
+    /// Ask the server to cancel the query running on this connection
+    ///
+    /// Sends a CancelRequest on a separate connection to the same server, keyed by
+    /// the `BackendKeyData` received at startup. Cancellation is best effort: the
+    /// query may finish before the server acts on it.
+    async fn send_cancel_request(&self) -> Result<()> {
+        let (Some(process_id), Some(secret_key)) = (self.process_id, self.secret_key) else {
+            return Err(Error::Connection(
+                "no backend key data received; cannot cancel".into(),
+            ));
+        };
+        let addr = self
+            .transport
+            .as_ref()
+            .and_then(Transport::peer_address)
+            .ok_or_else(|| Error::Connection("server address unknown; cannot cancel".into()))?;
+
+        let mut transport = addr.connect().await?;
+        let buf = encode_message(&FrontendMessage::CancelRequest {
+            process_id,
+            secret_key,
+        })?;
+        transport.write_all(&buf).await?;
+        transport.flush().await?;
+        // The server closes the connection once it has read the request
+        let mut discard = BytesMut::new();
+        while transport.read_buf(&mut discard).await? > 0 {
+            discard.clear();
+        }
+        Ok(())
+    }
+
+    /// Stop the query for shutdown and close the connection
+    ///
+    /// With `query_running`, cancels the query and reads its remaining messages up
+    /// to `ReadyForQuery`; then sends `Terminate`. Returns `false` if this did not
+    /// complete before `deadline`, in which case the socket is simply dropped.
+    async fn close_for_shutdown(
+        &mut self,
+        deadline: std::time::Instant,
+        query_running: bool,
+    ) -> bool {
+        let closed = tokio::time::timeout_at(deadline.into(), async {
+            if query_running {
+                if let Err(e) = self.send_cancel_request().await {
+                    tracing::debug!("cancel request failed: {}", e);
+                }
+                while !matches!(
+                    self.receive_message().await?,
+                    BackendMessage::ReadyForQuery { .. }
+                ) {}
+            }
+            let _ = self.state.transition(ConnectionState::Closed);
+            self.send_message(&FrontendMessage::Terminate).await?;
+            let transport = self.transport.as_mut().ok_or(Error::ConnectionClosed)?;
+            transport.shutdown().await
+        })
+        .await;
+
+        match closed {
+            Ok(Ok(())) => true,
+            Ok(Err(e)) => {
+                tracing::debug!("connection failed during shutdown: {}", e);
+                false
+            }
+            Err(_) => {
+                tracing::debug!("shutdown deadline expired, dropping connection");
+                false
+            }
+        }
+    }
+
+    /// Execute a query through a server-side cursor
+    ///
+    /// Wraps `query` in `DECLARE ... NO SCROLL CURSOR` (inside a read-only
+    /// transaction unless one is already open) and issues `FETCH chunk_size` each
+    /// time the consumer has drained the previous chunk. Between fetches the
+    /// connection is idle in transaction, so nothing is left half-read on the
+    /// socket while the stream is paused. The cursor is closed, and a transaction
+    /// it opened committed, when the stream completes or is dropped.
+    ///
+    /// Like `streaming_query`, this consumes the connection.
+    pub async fn cursor_query(
+        mut self,
+        query: &str,
+        chunk_size: usize,
+        max_memory: Option<usize>,
+        soft_limit_warn_threshold: Option<f32>,
+        soft_limit_fail_threshold: Option<f32>,
+    ) -> Result<crate::stream::JsonStream> {
+        let span = self.query_span(query, ExecutionMode::Cursor, chunk_size);
+        async {
+            use crate::stream::{parse_json, ChunkingStrategy, JsonStream};
+            use serde_json::Value;
+
+            if chunk_size == 0 {
+                return Err(Error::Config("cursor mode requires chunk_size > 0".into()));
+            }
+
+            let startup_start = std::time::Instant::now();
+            let owns_transaction = self.transaction_status == TransactionStatus::Idle;
+            if owns_transaction {
+                self.execute_command(CURSOR_BEGIN_SQL).await?;
+            }
+            let params = std::mem::take(&mut self.query_params);
+            self.execute_bound(&declare_cursor_sql(query), &params)
+                .await?;
+
+            let entity = self.entity_label(query);
+            crate::metrics::histograms::query_startup_duration(
+                &entity,
+                startup_start.elapsed().as_millis() as u64,
+            );
+            // FETCH statements are labelled with the cursor's entity
+            self.metrics_entity = Some(entity.clone());
+
+            let (notice_tx, notice_rx) = mpsc::channel(NOTICE_CHANNEL_CAPACITY);
+            let (result_tx, result_rx) = mpsc::channel::<Result<Value>>(chunk_size);
+            let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
+
+            let mut stream = JsonStream::new(
+                result_rx,
+                cancel_tx,
+                entity.clone(),
+                max_memory,
+                soft_limit_warn_threshold,
+                soft_limit_fail_threshold,
+                Arc::clone(&self.session),
+                notice_rx,
+            );
+            // The fetch loop checks for pause before every FETCH, so it needs the
+            // pause/resume signals up front rather than on first pause()
+            stream.init_pause_resume();
+            stream.set_span(tracing::Span::current());
+            let state_lock = stream.clone_state();
+            let resume_signal = stream.clone_resume_signal();
+            let state_atomic = stream.clone_state_atomic();
+            let pause_timeout = stream.pause_timeout();
+            let mut shutdown_guard = self.shutdown.as_ref().map(|handle| {
+                stream.set_shutdown(handle.clone());
+                handle.register()
+            });
+
+            let query_start = std::time::Instant::now();
+
+            tokio::spawn(async move {
+                let strategy = ChunkingStrategy::new(chunk_size);
+                let fetch = fetch_sql(chunk_size);
+                let mut total_rows = 0u64;
+                let mut total_bytes = 0u64;
+                // False once a FETCH is abandoned midway; the connection is then dropped
+                // without cleanup and the server rolls the transaction back
+                let mut in_sync = true;
+                let mut shutdown_deadline = None;
+
+                let outcome = 'fetch: loop {
+                    if state_atomic.load(Ordering::Acquire) == 1 {
+                        if let (Some(state_lock), Some(resume_signal)) = (&state_lock, &resume_signal) {
+                            // Dropping a paused stream must still release the cursor
+                            tokio::select! {
+                                _ = cancel_rx.recv() => break 'fetch "cancelled",
+                                deadline = wait_for_shutdown(shutdown_guard.as_mut()) => {
+                                    shutdown_deadline = Some(deadline);
+                                    break 'fetch "cancelled";
+                                }
+                                _ = wait_while_paused(state_lock, resume_signal, pause_timeout, &entity) => {}
+                            }
+                        }
+                    }
+
+                    let fetched = tokio::select! {
+                        _ = cancel_rx.recv() => {
+                            in_sync = false;
+                            break 'fetch "cancelled";
+                        }
+                        deadline = wait_for_shutdown(shutdown_guard.as_mut()) => {
+                            in_sync = false;
+                            shutdown_deadline = Some(deadline);
+                            break 'fetch "cancelled";
+                        }
+                        fetched = self.fetch_chunk(&fetch, &strategy, &notice_tx) => fetched,
+                    };
+
+                    let chunk = match fetched {
+                        Ok(chunk) => chunk,
+                        Err(e) => {
+                            let kind = if matches!(e, Error::Sql(_)) { "server_error" } else { "protocol_error" };
+                            crate::metrics::counters::query_error(&entity, kind);
+                            let _ = result_tx.send(Err(e)).await;
+                            break 'fetch "error";
+                        }
+                    };
+
+                    // A short chunk means the cursor is exhausted
+                    let exhausted = !strategy.is_full(&chunk);
+                    let chunk_size_rows = chunk.len() as u64;
+                    let chunk_start = std::time::Instant::now();
+
+                    for row_bytes in chunk.into_rows() {
+                        total_bytes += row_bytes.len() as u64;
+                        let item = parse_json(row_bytes);
+                        let failed = item.is_err();
+                        if failed {
+                            crate::metrics::counters::json_parse_error(&entity);
+                        } else {
+                            total_rows += 1;
+                        }
+                        tokio::select! {
+                            _ = cancel_rx.recv() => break 'fetch "cancelled",
+                            deadline = wait_for_shutdown(shutdown_guard.as_mut()) => {
+                                shutdown_deadline = Some(deadline);
+                                break 'fetch "cancelled";
+                            }
+                            sent = result_tx.send(item) => {
+                                if sent.is_err() {
+                                    break 'fetch "cancelled";
+                                }
+                            }
+                        }
+                        if failed {
+                            break 'fetch "error";
+                        }
+                    }
+
+                    let chunk_idx = CHUNK_COUNT.fetch_add(1, Ordering::Relaxed);
+                    if chunk_idx % 10 == 0 {
+                        crate::metrics::histograms::chunk_processing_duration(
+                            &entity,
+                            chunk_start.elapsed().as_millis() as u64,
+                        );
+                        crate::metrics::histograms::chunk_size(&entity, chunk_size_rows);
+                    }
+
+                    if exhausted {
+                        break 'fetch "success";
+                    }
+                };
+
+                record_query_totals(total_rows, total_bytes);
+                if outcome == "success" {
+                    crate::metrics::counters::rows_processed(&entity, total_rows, "ok");
+                    crate::metrics::histograms::query_total_duration(
+                        &entity,
+                        query_start.elapsed().as_millis() as u64,
+                    );
+                }
+                crate::metrics::counters::query_completed(outcome, &entity);
+
+                if let Some(deadline) = shutdown_deadline {
+                    // Wake the consumer, which then yields Error::Cancelled. Closing
+                    // the connection rolls back the cursor's transaction.
+                    tracing::debug!("shutdown requested, closing cursor connection");
+                    drop(result_tx);
+                    if !self.close_for_shutdown(deadline, !in_sync).await {
+                        if let Some(guard) = shutdown_guard.as_mut() {
+                            guard.mark_forced();
+                        }
+                    }
+                } else if in_sync {
+                    if let Err(e) = self.execute_command(&finish_cursor_sql(owns_transaction)).await {
+                        tracing::debug!("failed to release cursor: {}", e);
+                    }
+                    if let Err(e) = self.close().await {
+                        tracing::debug!("failed to close connection: {}", e);
+                    }
+                }
+            }.instrument(tracing::Span::current()));
+
+            Ok(stream)
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Issue one `FETCH` and collect its rows
+    async fn fetch_chunk(
+        &mut self,
+        fetch: &str,
+        strategy: &crate::stream::ChunkingStrategy,
+        notices: &mpsc::Sender<ErrorFields>,
+    ) -> Result<RowChunk> {
+        use crate::json::validate_row_description;
+        use crate::stream::extract_json_bytes;
+
+        let fetch_msgs = self.query_messages(fetch, &[], true)?;
+        self.state.transition(ConnectionState::QueryInProgress)?;
+        self.send_messages(&fetch_msgs).await?;
+        self.state.transition(ConnectionState::ReadingResults)?;
+
+        let mut chunk = strategy.new_chunk();
+        let mut error = None;
+        loop {
+            let msg = self.receive_message().await?;
+            match msg {
+                BackendMessage::RowDescription(_) => {
+                    if let Err(e) = validate_row_description(&msg) {
+                        error.get_or_insert(e);
+                    }
+                }
+                BackendMessage::DataRow(_) => match extract_json_bytes(&msg) {
+                    Ok(bytes) => chunk.push(bytes),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                },
+                BackendMessage::CommandComplete(_) => {}
+                BackendMessage::ParseComplete => self.statements.parsed(),
+                BackendMessage::BindComplete | BackendMessage::CloseComplete => {}
+                BackendMessage::ErrorResponse(err) => {
+                    self.recover(&err);
+                    error.get_or_insert(Error::Sql(err.to_string()));
+                }
+                BackendMessage::NoticeResponse(notice) => {
+                    dispatch_notice(notice, self.notice_handler.as_ref(), Some(notices));
+                }
+                BackendMessage::ParameterStatus { name, value } => {
+                    if let Err(e) = apply_parameter_status(&self.session, &name, &value) {
+                        error.get_or_insert(e);
+                    }
+                }
+                BackendMessage::ReadyForQuery { status } => {
+                    self.transaction_status = TransactionStatus::from_byte(status)?;
+                    break;
+                }
+                other => {
+                    error
+                        .get_or_insert(Error::Protocol(format!("unexpected message: {:?}", other)));
+                }
+            }
+        }
+        self.state.transition(ConnectionState::Idle)?;
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(chunk),
+        }
+    }
+
     /// Execute a streaming query
     ///
     /// Note: This method consumes the connection. The stream maintains the connection
@@ -675,6 +1569,7 @@ impl Connection {
         adaptive_min_chunk_size: Option<usize>,
         adaptive_max_chunk_size: Option<usize>,
     ) -> Result<crate::stream::JsonStream> {
+        let span = self.query_span(query, ExecutionMode::Streaming, chunk_size);
         async {
             let startup_start = std::time::Instant::now();
 
@@ -690,74 +1585,100 @@ impl Connection {
                 )));
             }
 
-            self.state.transition(ConnectionState::QueryInProgress)?;
+            let params = std::mem::take(&mut self.query_params);
 
-            let query_msg = FrontendMessage::Query(query.to_string());
-            self.send_message(&query_msg).await?;
+            // Notices can arrive before RowDescription, so the side channel exists first
+            let (notice_tx, notice_rx) = mpsc::channel(NOTICE_CHANNEL_CAPACITY);
 
-            self.state.transition(ConnectionState::ReadingResults)?;
+            let mut retried = false;
+            let row_desc = 'attempt: loop {
+                let query_msgs = self.query_messages(query, &params, true)?;
 
-            // Read RowDescription, but handle other messages that may come first
-            // (e.g., ParameterStatus, BackendKeyData, ErrorResponse, NoticeResponse)
-            let row_desc;
-            loop {
-                let msg = self.receive_message().await?;
+                self.state.transition(ConnectionState::QueryInProgress)?;
 
-                match msg {
-                    BackendMessage::ErrorResponse(err) => {
-                        // Query failed - consume ReadyForQuery and return error
-                        tracing::debug!("PostgreSQL error response: {}", err);
-                        loop {
-                            let msg = self.receive_message().await?;
-                            if matches!(msg, BackendMessage::ReadyForQuery { .. }) {
-                                break;
+                self.send_messages(&query_msgs).await?;
+
+                self.state.transition(ConnectionState::ReadingResults)?;
+
+                // Read RowDescription, but handle other messages that may come first
+                // (e.g., ParameterStatus, BackendKeyData, ErrorResponse, NoticeResponse)
+                loop {
+                    let msg = self.receive_message().await?;
+
+                    match msg {
+                        BackendMessage::ErrorResponse(err) => {
+                            // Query failed - consume ReadyForQuery and return error
+                            tracing::debug!("PostgreSQL error response: {}", err);
+                            let recovery = self.recover(&err);
+                            loop {
+                                let msg = self.receive_message().await?;
+                                if let BackendMessage::ReadyForQuery { status } = msg {
+                                    self.transaction_status = TransactionStatus::from_byte(status)?;
+                                    break;
+                                }
+                            }
+                            if self.should_retry(recovery, &mut retried) {
+                                self.state.transition(ConnectionState::Idle)?;
+                                continue 'attempt;
                             }
+                            return Err(Error::Sql(err.to_string()));
+                        }
+                        BackendMessage::BackendKeyData { process_id, secret_key: _ } => {
+                            // This provides the key needed for cancel requests - store it and continue
+                            tracing::debug!("PostgreSQL backend key data received: pid={}", process_id);
+                            // Note: We would store this if we need to support cancellation
+                            continue;
+                        }
+                        BackendMessage::ParameterStatus { name, value } => {
+                            apply_parameter_status(&self.session, &name, &value)?;
+                            continue;
+                        }
+                        BackendMessage::NoticeResponse(notice) => {
+                            dispatch_notice(notice, self.notice_handler.as_ref(), Some(&notice_tx));
+                            continue;
+                        }
+                        BackendMessage::ParseComplete => {
+                            self.statements.parsed();
+                            continue;
+                        }
+                        BackendMessage::BindComplete | BackendMessage::CloseComplete => continue,
+                        BackendMessage::NoData => {
+                            // Extended query without a result set: drain to ReadyForQuery
+                            loop {
+                                let msg = self.receive_message().await?;
+                                if matches!(msg, BackendMessage::ReadyForQuery { .. }) {
+                                    break;
+                                }
+                            }
+                            return Err(Error::Protocol(
+                                "no result set received from query".into(),
+                            ));
+                        }
+                        BackendMessage::RowDescription(_) => break 'attempt msg,
+                        BackendMessage::ReadyForQuery { .. } => {
+                            // Received ReadyForQuery without RowDescription
+                            // This means the query didn't produce a result set
+                            return Err(Error::Protocol(
+                                "no result set received from query - \
+                                 check that the entity name is correct and the table/view exists"
+                                    .into(),
+                            ));
+                        }
+                        _ => {
+                            return Err(Error::Protocol(format!(
+                                "unexpected message type in query response: {:?}",
+                                msg
+                            )));
                         }
-                        return Err(Error::Sql(err.to_string()));
-                    }
-                    BackendMessage::BackendKeyData { process_id, secret_key: _ } => {
-                        // This provides the key needed for cancel requests - store it and continue
-                        tracing::debug!("PostgreSQL backend key data received: pid={}", process_id);
-                        // Note: We would store this if we need to support cancellation
-                        continue;
-                    }
-                    BackendMessage::ParameterStatus { .. } => {
-                        // Parameter status changes are informational - skip them
-                        tracing::debug!("PostgreSQL parameter status change received");
-                        continue;
-                    }
-                    BackendMessage::NoticeResponse(notice) => {
-                        // Notices are non-fatal warnings - skip them
-                        tracing::debug!("PostgreSQL notice: {}", notice);
-                        continue;
-                    }
-                    BackendMessage::RowDescription(_) => {
-                        row_desc = msg;
-                        break;
-                    }
-                    BackendMessage::ReadyForQuery { .. } => {
-                        // Received ReadyForQuery without RowDescription
-                        // This means the query didn't produce a result set
-                        return Err(Error::Protocol(
-                            "no result set received from query - \
-                             check that the entity name is correct and the table/view exists"
-                                .into(),
-                        ));
-                    }
-                    _ => {
-                        return Err(Error::Protocol(format!(
-                            "unexpected message type in query response: {:?}",
-                            msg
-                        )));
                     }
                 }
-            }
+            };
 
             validate_row_description(&row_desc)?;
 
             // Record startup timing
             let startup_duration = startup_start.elapsed().as_millis() as u64;
-            let entity = extract_entity_from_query(query).unwrap_or_else(|| "unknown".to_string());
+            let entity = self.entity_label(query);
             crate::metrics::histograms::query_startup_duration(&entity, startup_duration);
 
             // Create channels
@@ -765,16 +1686,18 @@ impl Connection {
             let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
 
             // Create stream instance first so we can clone its pause/resume signals
-            let entity_for_metrics = extract_entity_from_query(query).unwrap_or_else(|| "unknown".to_string());
+            let entity_for_metrics = self.entity_label(query);
             let entity_for_stream = entity_for_metrics.clone();  // Clone for stream
 
-            let stream = JsonStream::new(
+            let mut stream = JsonStream::new(
                 result_rx,
                 cancel_tx,
                 entity_for_stream,
                 max_memory,
                 soft_limit_warn_threshold,
                 soft_limit_fail_threshold,
+                Arc::clone(&self.session),
+                notice_rx,
             );
 
             // Clone pause/resume signals for background task (only if pause/resume is initialized)
@@ -791,10 +1714,20 @@ impl Connection {
             // Spawn background task to read rows
             let query_start = std::time::Instant::now();
 
+            stream.set_span(tracing::Span::current());
+
+            // Register before spawning so shutdown() waits for this stream
+            let mut shutdown_guard = self.shutdown.as_ref().map(|handle| {
+                stream.set_shutdown(handle.clone());
+                handle.register()
+            });
+
             tokio::spawn(async move {
+            let mut total_rows = 0u64;
+            let mut total_bytes = 0u64;
+            let reader = async {
                 let strategy = ChunkingStrategy::new(chunk_size);
                 let mut chunk = strategy.new_chunk();
-                let mut total_rows = 0u64;
 
             // Initialize adaptive chunking if enabled
             let _adaptive = if enable_adaptive_chunking {
@@ -821,32 +1754,7 @@ impl Connection {
                     if let (Some(ref state_lock), Some(ref _pause_signal), Some(ref resume_signal)) =
                         (&state_lock, &pause_signal, &resume_signal)
                     {
-                        let current_state = state_lock.lock().await;
-                        if *current_state == crate::stream::StreamState::Paused {
-                            tracing::debug!("stream paused, waiting for resume");
-                            drop(current_state); // Release lock before waiting
-
-                            // Wait with optional timeout
-                            if let Some(timeout) = pause_timeout {
-                                match tokio::time::timeout(timeout, resume_signal.notified()).await {
-                                    Ok(_) => {
-                                        tracing::debug!("stream resumed");
-                                    }
-                                    Err(_) => {
-                                        tracing::debug!("pause timeout expired, auto-resuming");
-                                        crate::metrics::counters::stream_pause_timeout_expired(&entity_for_metrics);
-                                    }
-                                }
-                            } else {
-                                // No timeout, wait indefinitely
-                                resume_signal.notified().await;
-                                tracing::debug!("stream resumed");
-                            }
-
-                            // Update state back to Running
-                            let mut state = state_lock.lock().await;
-                            *state = crate::stream::StreamState::Running;
-                        }
+                        wait_while_paused(state_lock, resume_signal, pause_timeout, &entity_for_metrics).await;
                     }
                 }
 
@@ -865,6 +1773,7 @@ impl Connection {
                                 BackendMessage::DataRow(_) => {
                                     match extract_json_bytes(&msg) {
                                         Ok(json_bytes) => {
+                                            total_bytes += json_bytes.len() as u64;
                                             chunk.push(json_bytes);
 
                                             if strategy.is_full(&chunk) {
@@ -1015,6 +1924,18 @@ impl Connection {
                                 BackendMessage::ReadyForQuery { .. } => {
                                     break;
                                 }
+                                BackendMessage::NoticeResponse(notice) => {
+                                    dispatch_notice(notice, self.notice_handler.as_ref(), Some(&notice_tx));
+                                }
+                                BackendMessage::ParameterStatus { name, value } => {
+                                    // e.g. a trigger or function ran SET; keep SessionInfo current
+                                    if let Err(e) = apply_parameter_status(&self.session, &name, &value) {
+                                        crate::metrics::counters::query_error(&entity_for_metrics, "protocol_error");
+                                        crate::metrics::counters::query_completed("error", &entity_for_metrics);
+                                        let _ = result_tx.send(Err(e)).await;
+                                        break;
+                                    }
+                                }
                                 BackendMessage::ErrorResponse(err) => {
                                     crate::metrics::counters::query_error(&entity_for_metrics, "server_error");
                                     crate::metrics::counters::query_completed("error", &entity_for_metrics);
@@ -1040,23 +1961,149 @@ impl Connection {
                     }
                 }
             }
-            });
+            };
+
+            let shutdown_deadline = tokio::select! {
+                biased;
+                _ = reader => None,
+                deadline = wait_for_shutdown(shutdown_guard.as_mut()) => Some(deadline),
+            };
+            record_query_totals(total_rows, total_bytes);
+            let Some(deadline) = shutdown_deadline else {
+                return;
+            };
+            tracing::debug!("shutdown requested, cancelling query");
+            crate::metrics::counters::query_completed("cancelled", &entity_for_metrics);
+            // Wake the consumer, which then yields Error::Cancelled
+            drop(result_tx);
+            if !self.close_for_shutdown(deadline, true).await {
+                if let Some(guard) = shutdown_guard.as_mut() {
+                    guard.mark_forced();
+                }
+            }
+            }.instrument(tracing::Span::current()));
 
             Ok(stream)
         }
-        .instrument(tracing::debug_span!(
-            "streaming_query",
-            query = %query,
-            chunk_size = %chunk_size
-        ))
+        .instrument(span)
         .await
     }
+
+    /// Span for a query on this connection
+    fn query_span(&self, query: &str, mode: ExecutionMode, chunk_size: usize) -> tracing::Span {
+        let entity = self.entity_label(query);
+        query_span(
+            self.database.as_deref().unwrap_or_default(),
+            &entity,
+            query,
+            mode,
+            chunk_size,
+        )
+    }
+}
+
+/// Block the reader task while the stream is paused
+///
+/// Returns when `resume()` is called or the optional pause timeout expires.
+async fn wait_while_paused(
+    state_lock: &Mutex<StreamState>,
+    resume_signal: &Notify,
+    pause_timeout: Option<Duration>,
+    entity: &str,
+) {
+    let current_state = state_lock.lock().await;
+    if *current_state != StreamState::Paused {
+        return;
+    }
+    tracing::debug!("stream paused, waiting for resume");
+    drop(current_state); // Release lock before waiting
+
+    // Wait with optional timeout
+    if let Some(timeout) = pause_timeout {
+        match tokio::time::timeout(timeout, resume_signal.notified()).await {
+            Ok(_) => {
+                tracing::debug!("stream resumed");
+            }
+            Err(_) => {
+                tracing::debug!("pause timeout expired, auto-resuming");
+                crate::metrics::counters::stream_pause_timeout_expired(entity);
+            }
+        }
+    } else {
+        // No timeout, wait indefinitely
+        resume_signal.notified().await;
+        tracing::debug!("stream resumed");
+    }
+
+    // Update state back to Running
+    let mut state = state_lock.lock().await;
+    *state = StreamState::Running;
+}
+
+/// Messages that run `query`: a Simple Query, or with `params` or a cached
+/// `statement` an extended query on the unnamed portal (`describe` requests
+/// the RowDescription before the rows)
+///
+/// Without `statement` the unnamed statement is parsed; a cache hit binds the
+/// named statement without parsing.
+fn query_messages(
+    query: &str,
+    params: &[Value],
+    describe: bool,
+    statement: Option<Lookup>,
+) -> Result<Vec<FrontendMessage>> {
+    if params.is_empty() && statement.is_none() {
+        return Ok(vec![FrontendMessage::Query(query.to_string())]);
+    }
+    let values = params
+        .iter()
+        .map(Value::to_param)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(Error::Config)?;
+    let param_types = params.iter().map(Value::type_oid).collect();
+    let (parse, statement) = match statement {
+        None => (Some((String::new(), query.to_string())), String::new()),
+        Some(Lookup::Miss { name, sql }) => (Some((name.clone(), sql)), name),
+        Some(Lookup::Hit(name)) => (None, name),
+    };
+    let mut msgs = Vec::new();
+    if let Some((name, query)) = parse {
+        msgs.push(FrontendMessage::Parse {
+            name,
+            query,
+            param_types,
+        });
+    }
+    msgs.push(FrontendMessage::Bind {
+        portal: String::new(),
+        statement,
+        params: values,
+    });
+    if describe {
+        msgs.push(FrontendMessage::DescribePortal(String::new()));
+    }
+    msgs.push(FrontendMessage::Execute {
+        portal: String::new(),
+        max_rows: 0,
+    });
+    msgs.push(FrontendMessage::Sync);
+    Ok(msgs)
+}
+
+/// `messages` as a result, failing with the first `ErrorResponse`
+fn sql_error(messages: Vec<BackendMessage>) -> Result<Vec<BackendMessage>> {
+    for msg in &messages {
+        if let BackendMessage::ErrorResponse(err) = msg {
+            return Err(Error::Sql(err.to_string()));
+        }
+    }
+    Ok(messages)
 }
 
 /// Extract entity name from query for metrics
 /// Query format: SELECT data FROM v_{entity} ...
 fn extract_entity_from_query(query: &str) -> Option<String> {
-    let query_lower = query.to_lowercase();
+    let query_lower = query.to_lowercase().replace('"', "");
     if let Some(from_pos) = query_lower.find("from") {
         let after_from = &query_lower[from_pos + 4..].trim_start();
         if let Some(entity_start) = after_from.find('v').or_else(|| after_from.find('t')) {
@@ -1080,6 +2127,95 @@ fn extract_entity_from_query(query: &str) -> Option<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_query_messages() {
+        let msgs = query_messages("SELECT 1", &[], true, None).unwrap();
+        assert!(matches!(msgs[..], [FrontendMessage::Query(_)]));
+
+        let params = [Value::String("eu".into()), Value::Int(10), Value::Null];
+        let msgs = query_messages("SELECT data FROM t WHERE a = $1", &params, true, None).unwrap();
+        assert!(matches!(
+            msgs[..],
+            [
+                FrontendMessage::Parse { .. },
+                FrontendMessage::Bind { .. },
+                FrontendMessage::DescribePortal(_),
+                FrontendMessage::Execute { max_rows: 0, .. },
+                FrontendMessage::Sync,
+            ]
+        ));
+        match (&msgs[0], &msgs[1]) {
+            (FrontendMessage::Parse { param_types, .. }, FrontendMessage::Bind { params, .. }) => {
+                assert_eq!(param_types, &[25, 20, 0]);
+                assert_eq!(
+                    params,
+                    &[Some("eu".to_string()), Some("10".to_string()), None]
+                );
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(
+            query_messages("DECLARE c", &params, false, None)
+                .unwrap()
+                .len(),
+            4
+        );
+
+        let raw = [Value::RawSql("now()".into())];
+        assert!(matches!(
+            query_messages("SELECT $1", &raw, false, None),
+            Err(Error::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_cached_statement_reused_across_values() {
+        let sql = "SELECT data FROM t WHERE a = $1";
+        let mut cache = StatementCache::new(4);
+
+        // The first execution parses the statement by name
+        let eu = [Value::String("eu".into())];
+        let lookup = cache.lookup(sql, &[eu[0].type_oid()]);
+        let msgs = query_messages(sql, &eu, true, Some(lookup)).unwrap();
+        let name = match &msgs[..] {
+            [FrontendMessage::Parse { name, query, .. }, FrontendMessage::Bind {
+                statement, params, ..
+            }, FrontendMessage::DescribePortal(_), FrontendMessage::Execute { .. }, FrontendMessage::Sync]
+                if query == sql && statement == name && params == &[Some("eu".to_string())] =>
+            {
+                name.clone()
+            }
+            other => panic!("unexpected messages: {:?}", other),
+        };
+        cache.parsed();
+
+        // A different value binds the same statement without parsing
+        let us = [Value::String("us".into())];
+        let lookup = cache.lookup(sql, &[us[0].type_oid()]);
+        assert_eq!(lookup, Lookup::Hit(name.clone()));
+        let msgs = query_messages(sql, &us, false, Some(lookup)).unwrap();
+        assert!(matches!(
+            &msgs[..],
+            [
+                FrontendMessage::Bind { statement, params, .. },
+                FrontendMessage::Execute { .. },
+                FrontendMessage::Sync,
+            ] if *statement == name && params == &[Some("us".to_string())]
+        ));
+    }
+
+    #[test]
+    fn test_extract_entity_from_quoted_query() {
+        assert_eq!(
+            extract_entity_from_query("SELECT data FROM \"v_user\" WHERE true").as_deref(),
+            Some("user")
+        );
+        assert_eq!(
+            extract_entity_from_query("SELECT data FROM \"app\".\"v_order\"").as_deref(),
+            Some("order")
+        );
+    }
+
     #[test]
     fn test_connection_config() {
         let config = ConnectionConfig::new("testdb", "testuser")
@@ -1128,6 +2264,17 @@ mod tests {
         assert_eq!(config.keepalive_idle, Some(keepalive_idle));
     }
 
+    #[test]
+    fn test_connection_config_builder_check_on_connect() {
+        let config = ConnectionConfig::builder("mydb", "myuser")
+            .check_on_connect("SELECT 1")
+            .build();
+        assert_eq!(config.check_on_connect.as_deref(), Some("SELECT 1"));
+        assert!(ConnectionConfig::new("mydb", "myuser")
+            .check_on_connect
+            .is_none());
+    }
+
     #[test]
     fn test_connection_config_builder_with_application_name() {
         let config = ConnectionConfig::builder("mydb", "myuser")
@@ -1179,6 +2326,50 @@ mod tests {
         assert_eq!(config.sslmode, super::SslMode::VerifyFull);
     }
 
+    #[test]
+    fn test_connection_config_builder_with_channel_binding() {
+        let config = ConnectionConfig::builder("mydb", "myuser")
+            .channel_binding(ChannelBindingMode::Require)
+            .build();
+
+        assert_eq!(config.channel_binding, ChannelBindingMode::Require);
+        assert_eq!(
+            ConnectionConfig::new("db", "user").channel_binding,
+            ChannelBindingMode::Prefer
+        );
+    }
+
+    /// Stub provider returning a rotated token
+    #[derive(Debug)]
+    struct StubProvider;
+
+    impl CredentialProvider for StubProvider {
+        fn credentials(&self) -> futures::future::BoxFuture<'_, Result<Credentials>> {
+            Box::pin(async { Ok(Credentials::new("token_user", "token-1")) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_credentials_static() {
+        let config = ConnectionConfig::new("db", "user").password("pw");
+        let creds = config.resolve_credentials().await.unwrap();
+
+        assert_eq!(creds.user, "user");
+        assert_eq!(creds.password.as_deref(), Some("pw"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_credentials_from_provider() {
+        let config = ConnectionConfig::builder("db", "ignored")
+            .password("stale")
+            .credential_provider(Arc::new(StubProvider))
+            .build();
+        let creds = config.resolve_credentials().await.unwrap();
+
+        assert_eq!(creds.user, "token_user");
+        assert_eq!(creds.password.as_deref(), Some("token-1"));
+    }
+
     // Verify that async functions return Send futures (compile-time check)
     // This ensures compatibility with async_trait and multi-threaded executors.
     // The actual assertion doesn't execute - it's type-checked at compile time.
@@ -0,0 +1,85 @@
+//! Server-side cursor execution
+//!
+//! Instead of reading one long result set, cursor mode declares a `NO SCROLL`
+//! cursor inside a read-only transaction and fetches `chunk_size` rows at a time
+//! as the consumer drains the stream. Between fetches the connection sits idle in
+//! the transaction, which works behind poolers in transaction mode (e.g.
+//! PgBouncer) and does not leave a half-read result set on the socket during
+//! long pauses.
+
+use std::fmt;
+
+/// Name of the cursor declared for a cursor-mode query
+///
+/// Each connection runs one query at a time, so a fixed name is sufficient.
+pub(crate) const CURSOR_NAME: &str = "fraiseql_cursor";
+
+/// Transaction opened for a cursor when none is active
+pub(crate) const CURSOR_BEGIN_SQL: &str = "BEGIN READ ONLY";
+
+/// How a query's rows are read from the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionMode {
+    /// Single Simple Query whose result set is read as it arrives (default)
+    #[default]
+    Streaming,
+    /// `DECLARE ... NO SCROLL CURSOR` and repeated `FETCH chunk_size`
+    ///
+    /// Opens a read-only transaction unless one is already active (e.g. from
+    /// `query_in_snapshot()`). The cursor is closed, and a transaction it opened
+    /// committed, when the stream completes or is dropped.
+    Cursor,
+}
+
+impl fmt::Display for ExecutionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Streaming => f.write_str("streaming"),
+            Self::Cursor => f.write_str("cursor"),
+        }
+    }
+}
+
+/// Build the `DECLARE` statement for a query
+pub(crate) fn declare_cursor_sql(query: &str) -> String {
+    format!("DECLARE {} NO SCROLL CURSOR FOR {}", CURSOR_NAME, query)
+}
+
+/// Build the `FETCH` statement for one chunk
+pub(crate) fn fetch_sql(chunk_size: usize) -> String {
+    format!("FETCH {} FROM {}", chunk_size, CURSOR_NAME)
+}
+
+/// Statement releasing the cursor once the stream ends
+///
+/// Commits the transaction if the cursor opened it, otherwise only closes the
+/// cursor and leaves the caller's transaction open.
+pub(crate) fn finish_cursor_sql(owns_transaction: bool) -> String {
+    if owns_transaction {
+        "COMMIT".to_string()
+    } else {
+        format!("CLOSE {}", CURSOR_NAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_sql() {
+        assert_eq!(
+            declare_cursor_sql("SELECT data FROM v_user"),
+            "DECLARE fraiseql_cursor NO SCROLL CURSOR FOR SELECT data FROM v_user"
+        );
+        assert_eq!(fetch_sql(256), "FETCH 256 FROM fraiseql_cursor");
+        assert_eq!(finish_cursor_sql(true), "COMMIT");
+        assert_eq!(finish_cursor_sql(false), "CLOSE fraiseql_cursor");
+    }
+
+    #[test]
+    fn test_execution_mode_default() {
+        assert_eq!(ExecutionMode::default(), ExecutionMode::Streaming);
+        assert_eq!(ExecutionMode::Cursor.to_string(), "cursor");
+    }
+}
@@ -4,14 +4,31 @@
 //! * Transport abstraction (TCP vs Unix socket)
 //! * Connection lifecycle (startup, auth, query execution)
 //! * State machine enforcement
+//! * Read-only transactions and exported snapshots
+//! * Server-side cursor execution (DECLARE/FETCH)
+//! * Per-connection cache of prepared statements
+//! * Graceful shutdown of in-flight streams (CancelRequest + Terminate)
 //! * TLS configuration and support
+//! * Tracing spans for connections and queries
 
 mod conn;
+mod cursor;
+mod notice;
+mod session;
+mod shutdown;
 mod state;
+mod statement_cache;
+mod telemetry;
 mod tls;
+mod transaction;
 mod transport;
 
 pub use conn::{Connection, ConnectionConfig, ConnectionConfigBuilder};
+pub use cursor::ExecutionMode;
+pub use notice::NoticeHandler;
+pub use session::{ServerVersion, SessionInfo};
+pub use shutdown::{ShutdownHandle, ShutdownReport};
 pub use state::ConnectionState;
 pub use tls::{parse_server_name, SslMode, TlsConfig};
+pub use transaction::{IsolationLevel, SnapshotId, TransactionStatus};
 pub use transport::Transport;
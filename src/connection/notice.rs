@@ -0,0 +1,119 @@
+//! NoticeResponse dispatch
+//!
+//! Postgres sends `NoticeResponse` for `RAISE NOTICE`/`RAISE WARNING` in functions
+//! and views, deprecation warnings, and similar non-fatal conditions.
+
+use crate::protocol::ErrorFields;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Capacity of the per-query notice channel; further notices are dropped
+pub(crate) const NOTICE_CHANNEL_CAPACITY: usize = 64;
+
+/// Callback invoked for every `NoticeResponse` received on a connection
+///
+/// Runs on the connection's reader task, so it must not block.
+///
+/// # Examples
+///
+/// ```ignore
+/// let config = ConnectionConfig::builder("mydb", "user")
+///     .notice_handler(NoticeHandler::new(|notice| {
+///         tracing::warn!(code = ?notice.code, "postgres notice: {}", notice);
+///     }))
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct NoticeHandler(Arc<dyn Fn(&ErrorFields) + Send + Sync>);
+
+impl NoticeHandler {
+    /// Create a handler from a closure
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&ErrorFields) + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    /// Invoke the handler
+    pub fn call(&self, notice: &ErrorFields) {
+        (self.0)(notice)
+    }
+}
+
+impl fmt::Debug for NoticeHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("NoticeHandler(..)")
+    }
+}
+
+/// Deliver a notice to the handler and, if present, the stream's notice channel
+///
+/// The channel is bounded; when the consumer is not draining notices they are
+/// dropped rather than stalling row delivery.
+pub(crate) fn dispatch_notice(
+    notice: ErrorFields,
+    handler: Option<&NoticeHandler>,
+    channel: Option<&mpsc::Sender<ErrorFields>>,
+) {
+    tracing::debug!(
+        severity = ?notice.severity,
+        code = ?notice.code,
+        "PostgreSQL notice: {}",
+        notice
+    );
+    if let Some(handler) = handler {
+        handler.call(&notice);
+    }
+    if let Some(tx) = channel {
+        if tx.try_send(notice).is_err() {
+            tracing::debug!("notice channel full or closed, dropping notice");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn notice(message: &str) -> ErrorFields {
+        ErrorFields {
+            severity: Some("WARNING".into()),
+            code: Some("01000".into()),
+            message: Some(message.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_dispatch_calls_handler() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let handler = NoticeHandler::new(move |n| {
+            assert_eq!(n.code.as_deref(), Some("01000"));
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        dispatch_notice(notice("a"), Some(&handler), None);
+        dispatch_notice(notice("b"), Some(&handler), None);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_dispatch_drops_when_channel_full() {
+        let (tx, mut rx) = mpsc::channel(1);
+        dispatch_notice(notice("first"), None, Some(&tx));
+        dispatch_notice(notice("second"), None, Some(&tx));
+
+        assert_eq!(rx.try_recv().unwrap().message.as_deref(), Some("first"));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_notice_handler_debug() {
+        let handler = NoticeHandler::new(|_| {});
+        assert_eq!(format!("{:?}", handler), "NoticeHandler(..)");
+    }
+}
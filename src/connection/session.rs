@@ -0,0 +1,248 @@
+//! Server session parameters reported via ParameterStatus
+
+use crate::{Error, Result};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// Session info shared between a connection and its reader task
+pub(crate) type SharedSessionInfo = Arc<RwLock<SessionInfo>>;
+
+/// Parsed Postgres server version
+///
+/// Postgres reports `server_version` as e.g. `15.4 (Debian 15.4-1.pgdg120+1)`,
+/// `9.6.24` or `17beta2`. Since Postgres 10 the version has two components.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ServerVersion {
+    /// Major version (e.g. 15, or 9 for 9.6)
+    pub major: u32,
+    /// Minor version (e.g. 4 for 15.4, or 6 for 9.6.24)
+    pub minor: u32,
+    /// Patch version (only pre-10 servers report three components)
+    pub patch: u32,
+    /// Raw `server_version` string as reported by the server
+    pub raw: String,
+}
+
+impl ServerVersion {
+    /// Parse a `server_version` parameter value
+    ///
+    /// Returns `None` if the string does not start with a numeric version.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let version = raw.split_whitespace().next()?;
+        // Strip suffixes such as "beta2", "rc1" or "devel"
+        let numeric_end = version
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(version.len());
+        let mut parts = version[..numeric_end]
+            .split('.')
+            .map(|p| p.parse::<u32>().ok());
+
+        let major = parts.next()??;
+        let minor = parts.next().flatten().unwrap_or(0);
+        let patch = parts.next().flatten().unwrap_or(0);
+
+        Some(Self {
+            major,
+            minor,
+            patch,
+            raw: raw.to_string(),
+        })
+    }
+
+    /// Numeric version in `server_version_num` format (e.g. 150004, 90624)
+    pub fn as_num(&self) -> u32 {
+        if self.major >= 10 {
+            self.major * 10000 + self.minor
+        } else {
+            self.major * 10000 + self.minor * 100 + self.patch
+        }
+    }
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.major >= 10 {
+            write!(f, "{}.{}", self.major, self.minor)
+        } else {
+            write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+        }
+    }
+}
+
+/// Server session parameters
+///
+/// Populated from `ParameterStatus` messages during startup and kept up to date
+/// when the server reports changes mid-stream (e.g. after `SET TimeZone`).
+#[derive(Debug, Clone, Default)]
+pub struct SessionInfo {
+    /// Parsed `server_version`
+    pub server_version: Option<ServerVersion>,
+    /// `server_encoding`
+    pub server_encoding: Option<String>,
+    /// `client_encoding` (always UTF8 on an established connection)
+    pub client_encoding: Option<String>,
+    /// `TimeZone`
+    pub time_zone: Option<String>,
+    /// `DateStyle`
+    pub date_style: Option<String>,
+    /// `integer_datetimes`
+    pub integer_datetimes: Option<bool>,
+    /// `in_hot_standby` (reported by Postgres 14+)
+    pub in_hot_standby: Option<bool>,
+    /// `application_name`
+    pub application_name: Option<String>,
+    /// Backend process ID (from BackendKeyData)
+    pub process_id: Option<i32>,
+}
+
+impl SessionInfo {
+    /// Apply a `ParameterStatus` update
+    ///
+    /// Parameters not tracked by `SessionInfo` are ignored.
+    pub fn apply(&mut self, name: &str, value: &str) {
+        match name {
+            "server_version" => self.server_version = ServerVersion::parse(value),
+            "server_encoding" => self.server_encoding = Some(value.to_string()),
+            "client_encoding" => self.client_encoding = Some(value.to_string()),
+            "TimeZone" => self.time_zone = Some(value.to_string()),
+            "DateStyle" => self.date_style = Some(value.to_string()),
+            "integer_datetimes" => self.integer_datetimes = parse_bool(value),
+            "in_hot_standby" => self.in_hot_standby = parse_bool(value),
+            "application_name" => self.application_name = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    /// Ensure the session uses UTF-8 client encoding
+    ///
+    /// JSON rows are handed to serde as raw bytes, which is only sound for UTF-8.
+    pub fn ensure_utf8(&self) -> Result<()> {
+        match self.client_encoding.as_deref() {
+            Some(enc) if is_utf8_encoding(enc) => Ok(()),
+            Some(enc) => Err(Error::Connection(format!(
+                "client_encoding is {}, but fraiseql-wire requires UTF8",
+                enc
+            ))),
+            None => Err(Error::Connection(
+                "server did not report client_encoding; fraiseql-wire requires UTF8".into(),
+            )),
+        }
+    }
+}
+
+/// Apply a `ParameterStatus` message to shared session info
+///
+/// Fails if the server switches `client_encoding` away from UTF-8.
+pub(crate) fn apply_parameter_status(
+    session: &SharedSessionInfo,
+    name: &str,
+    value: &str,
+) -> Result<()> {
+    tracing::debug!("parameter status: {} = {}", name, value);
+    let mut info = session.write().unwrap_or_else(|e| e.into_inner());
+    info.apply(name, value);
+    if name == "client_encoding" {
+        info.ensure_utf8()?;
+    }
+    Ok(())
+}
+
+/// Check whether an encoding name denotes UTF-8 (`UNICODE` is a Postgres alias)
+pub(crate) fn is_utf8_encoding(name: &str) -> bool {
+    name.eq_ignore_ascii_case("UTF8")
+        || name.eq_ignore_ascii_case("UTF-8")
+        || name.eq_ignore_ascii_case("UNICODE")
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "on" | "true" => Some(true),
+        "off" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_server_version_modern() {
+        let v = ServerVersion::parse("15.4 (Debian 15.4-1.pgdg120+1)").unwrap();
+        assert_eq!((v.major, v.minor, v.patch), (15, 4, 0));
+        assert_eq!(v.as_num(), 150004);
+        assert_eq!(v.to_string(), "15.4");
+        assert_eq!(v.raw, "15.4 (Debian 15.4-1.pgdg120+1)");
+    }
+
+    #[test]
+    fn test_parse_server_version_legacy() {
+        let v = ServerVersion::parse("9.6.24").unwrap();
+        assert_eq!((v.major, v.minor, v.patch), (9, 6, 24));
+        assert_eq!(v.as_num(), 90624);
+        assert_eq!(v.to_string(), "9.6.24");
+    }
+
+    #[test]
+    fn test_parse_server_version_prerelease() {
+        let v = ServerVersion::parse("17beta2").unwrap();
+        assert_eq!((v.major, v.minor), (17, 0));
+
+        let v = ServerVersion::parse("16devel").unwrap();
+        assert_eq!(v.major, 16);
+    }
+
+    #[test]
+    fn test_parse_server_version_invalid() {
+        assert!(ServerVersion::parse("").is_none());
+        assert!(ServerVersion::parse("unknown").is_none());
+    }
+
+    #[test]
+    fn test_server_version_ordering() {
+        let v14 = ServerVersion::parse("14.10").unwrap();
+        let v15 = ServerVersion::parse("15.1").unwrap();
+        assert!(v14 < v15);
+    }
+
+    #[test]
+    fn test_session_info_apply() {
+        let mut info = SessionInfo::default();
+        info.apply("server_version", "16.2");
+        info.apply("server_encoding", "UTF8");
+        info.apply("client_encoding", "UTF8");
+        info.apply("TimeZone", "UTC");
+        info.apply("DateStyle", "ISO, MDY");
+        info.apply("integer_datetimes", "on");
+        info.apply("in_hot_standby", "off");
+        info.apply("application_name", "fraiseql-wire");
+        info.apply("is_superuser", "on");
+
+        assert_eq!(info.server_version.as_ref().unwrap().major, 16);
+        assert_eq!(info.server_encoding.as_deref(), Some("UTF8"));
+        assert_eq!(info.time_zone.as_deref(), Some("UTC"));
+        assert_eq!(info.date_style.as_deref(), Some("ISO, MDY"));
+        assert_eq!(info.integer_datetimes, Some(true));
+        assert_eq!(info.in_hot_standby, Some(false));
+        assert_eq!(info.application_name.as_deref(), Some("fraiseql-wire"));
+
+        info.apply("TimeZone", "Europe/Paris");
+        assert_eq!(info.time_zone.as_deref(), Some("Europe/Paris"));
+    }
+
+    #[test]
+    fn test_ensure_utf8() {
+        let mut info = SessionInfo::default();
+        assert!(info.ensure_utf8().is_err());
+
+        info.apply("client_encoding", "UTF8");
+        assert!(info.ensure_utf8().is_ok());
+
+        info.apply("client_encoding", "UNICODE");
+        assert!(info.ensure_utf8().is_ok());
+
+        info.apply("client_encoding", "LATIN1");
+        let err = info.ensure_utf8().unwrap_err();
+        assert!(err.to_string().contains("LATIN1"));
+    }
+}
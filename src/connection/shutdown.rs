@@ -0,0 +1,256 @@
+//! Graceful shutdown of in-flight streams
+//!
+//! A `ShutdownHandle` is shared by every stream started with it. Calling
+//! `shutdown(deadline)` tells each stream's reader task to stop: it sends a
+//! CancelRequest to the server, reads the remaining protocol messages until
+//! `ReadyForQuery` and sends `Terminate`. Streams that finish before the
+//! deadline count as drained; the rest have their socket dropped and count as
+//! forcibly closed. Consumers see one final `Error::Cancelled`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Extra time `shutdown()` waits past the deadline for reader tasks to report
+const SHUTDOWN_GRACE: Duration = Duration::from_millis(100);
+
+/// Outcome of `ShutdownHandle::shutdown()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ShutdownReport {
+    /// Streams whose connection was closed cleanly before the deadline
+    pub drained: usize,
+    /// Streams whose connection was dropped at the deadline
+    pub forcibly_closed: usize,
+}
+
+struct Shared {
+    /// Deadline, set once shutdown starts
+    deadline: watch::Sender<Option<Instant>>,
+    /// Reader tasks still running
+    active: watch::Sender<usize>,
+    drained: AtomicUsize,
+    forcibly_closed: AtomicUsize,
+}
+
+/// Coordinator for shutting down in-flight streams
+///
+/// Cloning is cheap; all clones control the same set of streams. Attach it with
+/// `FraiseClient::with_shutdown()` or `QueryBuilder::shutdown()`.
+///
+/// # Examples
+///
+/// ```ignore
+/// let shutdown = ShutdownHandle::new();
+/// let stream = client.query::<Value>("user").shutdown(&shutdown).execute().await?;
+///
+/// // On SIGTERM:
+/// let report = shutdown.shutdown(Duration::from_secs(5)).await;
+/// tracing::info!(drained = report.drained, forced = report.forcibly_closed, "shut down");
+/// ```
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    shared: Arc<Shared>,
+}
+
+impl ShutdownHandle {
+    /// Create a handle with no streams attached
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                deadline: watch::Sender::new(None),
+                active: watch::Sender::new(0),
+                drained: AtomicUsize::new(0),
+                forcibly_closed: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Whether `shutdown()` has been called
+    pub fn is_shutdown(&self) -> bool {
+        self.shared.deadline.borrow().is_some()
+    }
+
+    /// Number of streams whose reader task is still running
+    pub fn active_streams(&self) -> usize {
+        *self.shared.active.borrow()
+    }
+
+    /// Stop every attached stream and wait until they are closed
+    ///
+    /// Each stream's connection gets until `deadline` to cancel its query and
+    /// close cleanly. Returns once all streams are closed or shortly after the
+    /// deadline. Calling it again (or from a clone) waits on the original deadline.
+    /// Streams started after shutdown are cancelled immediately.
+    pub async fn shutdown(&self, deadline: Duration) -> ShutdownReport {
+        let requested = Instant::now() + deadline;
+        self.shared.deadline.send_if_modified(|current| {
+            if current.is_none() {
+                *current = Some(requested);
+                true
+            } else {
+                false
+            }
+        });
+        let deadline = self.shared.deadline.borrow().unwrap_or(requested);
+        tracing::info!(
+            active = self.active_streams(),
+            "shutting down in-flight streams"
+        );
+
+        let mut active = self.shared.active.subscribe();
+        let _ = tokio::time::timeout_at(
+            (deadline + SHUTDOWN_GRACE).into(),
+            active.wait_for(|n| *n == 0),
+        )
+        .await;
+        let still_running = *active.borrow();
+
+        ShutdownReport {
+            drained: self.shared.drained.load(Ordering::Acquire),
+            forcibly_closed: self.shared.forcibly_closed.load(Ordering::Acquire) + still_running,
+        }
+    }
+
+    /// Register a reader task
+    pub(crate) fn register(&self) -> ShutdownGuard {
+        self.shared.active.send_modify(|n| *n += 1);
+        ShutdownGuard {
+            shared: Arc::clone(&self.shared),
+            deadline: self.shared.deadline.subscribe(),
+            forced: false,
+        }
+    }
+}
+
+impl Default for ShutdownHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ShutdownHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShutdownHandle")
+            .field("is_shutdown", &self.is_shutdown())
+            .field("active_streams", &self.active_streams())
+            .finish()
+    }
+}
+
+/// Registration of one reader task with a `ShutdownHandle`
+///
+/// Dropping the guard marks the task finished. If shutdown had started, the
+/// stream counts as drained unless `mark_forced()` was called.
+pub(crate) struct ShutdownGuard {
+    shared: Arc<Shared>,
+    deadline: watch::Receiver<Option<Instant>>,
+    forced: bool,
+}
+
+impl ShutdownGuard {
+    /// Wait until shutdown starts and return its deadline
+    pub(crate) async fn triggered(&mut self) -> Instant {
+        let deadline = self
+            .deadline
+            .wait_for(Option::is_some)
+            .await
+            .ok()
+            .and_then(|deadline| *deadline);
+        match deadline {
+            Some(deadline) => deadline,
+            // The guard keeps the sender alive, so the channel cannot close
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Record that the connection was dropped rather than closed cleanly
+    pub(crate) fn mark_forced(&mut self) {
+        self.forced = true;
+    }
+}
+
+impl Drop for ShutdownGuard {
+    fn drop(&mut self) {
+        if self.deadline.borrow().is_some() {
+            let counter = if self.forced {
+                &self.shared.forcibly_closed
+            } else {
+                &self.shared.drained
+            };
+            counter.fetch_add(1, Ordering::AcqRel);
+        }
+        self.shared.active.send_modify(|n| *n -= 1);
+    }
+}
+
+/// Wait for shutdown of an optional guard; never completes without one
+pub(crate) async fn wait_for_shutdown(guard: Option<&mut ShutdownGuard>) -> Instant {
+    match guard {
+        Some(guard) => guard.triggered().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_with_no_streams() {
+        let handle = ShutdownHandle::new();
+        assert!(!handle.is_shutdown());
+        let report = handle.shutdown(Duration::from_secs(5)).await;
+        assert!(handle.is_shutdown());
+        assert_eq!(report, ShutdownReport::default());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_counts_drained_and_forced() {
+        let handle = ShutdownHandle::new();
+        let mut clean = handle.register();
+        let mut forced = handle.register();
+        let finished_early = handle.register();
+        drop(finished_early);
+        assert_eq!(handle.active_streams(), 2);
+
+        tokio::spawn(async move {
+            clean.triggered().await;
+            drop(clean);
+        });
+        tokio::spawn(async move {
+            forced.triggered().await;
+            forced.mark_forced();
+            drop(forced);
+        });
+
+        let report = handle.shutdown(Duration::from_secs(5)).await;
+        assert_eq!(
+            report,
+            ShutdownReport {
+                drained: 1,
+                forcibly_closed: 1,
+            }
+        );
+        assert_eq!(handle.active_streams(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_reports_unfinished_streams_as_forced() {
+        let handle = ShutdownHandle::new();
+        let _stuck = handle.register();
+        let report = handle.shutdown(Duration::from_millis(10)).await;
+        assert_eq!(report.drained, 0);
+        assert_eq!(report.forcibly_closed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_register_after_shutdown_triggers_immediately() {
+        let handle = ShutdownHandle::new();
+        handle.shutdown(Duration::from_millis(10)).await;
+        let mut guard = handle.clone().register();
+        tokio::time::timeout(Duration::from_secs(1), guard.triggered())
+            .await
+            .expect("guard registered after shutdown should trigger");
+    }
+}
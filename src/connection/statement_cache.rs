@@ -0,0 +1,277 @@
+//! Prepared statement cache
+//!
+//! With `ConnectionConfigBuilder::statement_cache_capacity(n)`, queries with
+//! bound parameters run as named prepared statements: the first execution of a
+//! query shape sends `Parse`, later ones only `Bind`/`Execute` with their own
+//! values, so the server skips parsing and can reuse generic plans. Statements
+//! are keyed by the whitespace-normalized SQL and the parameter types, and the
+//! least recently used one is closed when the cache is full. Queries without
+//! parameters carry their values in the SQL text, so each would be a separate
+//! statement; they keep using unnamed statements. The cache belongs to one
+//! connection, so only connections running several queries (such as
+//! `Connection::query_with_params()` callers) reuse statements.
+//!
+//! Two kinds of server errors are recovered from:
+//! * `0A000` (`cached plan must not change result type`) after a schema
+//!   change: the statement is closed and prepared again.
+//! * `26000`/`42P05` (prepared statement missing or already existing): the
+//!   connection goes through a pooler in transaction mode (e.g. PgBouncer),
+//!   where statements do not survive between transactions. The cache is
+//!   switched off and unnamed statements are used from then on.
+//!
+//! In both cases the query is retried once, unless it ran inside a
+//! transaction block (which the error has aborted).
+
+use crate::operators::param::normalize_whitespace;
+use std::collections::HashMap;
+
+/// Prefix of the cached statements' names, followed by the cache's nonce and
+/// a counter
+const STATEMENT_PREFIX: &str = "fraiseql_s";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct StatementKey {
+    sql: String,
+    param_types: Vec<u32>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    name: String,
+    last_used: u64,
+}
+
+/// Result of `StatementCache::lookup()`
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Lookup {
+    /// Already prepared: bind to `name`
+    Hit(String),
+    /// Not prepared yet: parse `sql` as `name`, then bind to it
+    Miss {
+        /// Statement name
+        name: String,
+        /// Normalized SQL to parse
+        sql: String,
+    },
+}
+
+/// How the cache recovered from a failed query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Recovery {
+    /// Nothing to recover; report the error
+    None,
+    /// The statement's result type changed; it will be prepared again
+    Reprepare,
+    /// A pooler lost or shared the statement; the cache is now off
+    Unnamed,
+}
+
+/// Per-connection LRU cache of named prepared statements
+#[derive(Debug, Default)]
+pub(crate) struct StatementCache {
+    capacity: usize,
+    /// Set once a pooler is detected
+    bypassed: bool,
+    entries: HashMap<StatementKey, Entry>,
+    tick: u64,
+    /// Statements to close with the next batch
+    evicted: Vec<String>,
+    /// Statement used by the batch in flight, and whether its Parse is
+    /// still unconfirmed
+    in_flight: Option<(StatementKey, bool)>,
+    /// Random per-connection part of the statement names, so connections
+    /// sharing a backend through a pooler, from this process or any other,
+    /// never reuse each other's statements
+    nonce: u64,
+    /// Statements named so far
+    named: u64,
+}
+
+impl StatementCache {
+    /// Cache holding up to `capacity` statements (0 disables it)
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            nonce: rand::random(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether queries should use named statements
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.capacity > 0 && !self.bypassed
+    }
+
+    /// Find or allocate the statement for `sql` with `param_types`
+    ///
+    /// A miss evicts the least recently used statement when the cache is full;
+    /// send `take_evicted()` as Close messages before the Parse.
+    pub(crate) fn lookup(&mut self, sql: &str, param_types: &[u32]) -> Lookup {
+        self.tick += 1;
+        let key = StatementKey {
+            sql: normalize_whitespace(sql),
+            param_types: param_types.to_vec(),
+        };
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.last_used = self.tick;
+            let name = entry.name.clone();
+            self.in_flight = Some((key, false));
+            return Lookup::Hit(name);
+        }
+
+        if self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(entry) = oldest.and_then(|key| self.entries.remove(&key)) {
+                self.evicted.push(entry.name);
+            }
+        }
+
+        self.named += 1;
+        let name = format!("{}{:016x}_{}", STATEMENT_PREFIX, self.nonce, self.named);
+        self.entries.insert(
+            key.clone(),
+            Entry {
+                name: name.clone(),
+                last_used: self.tick,
+            },
+        );
+        let sql = key.sql.clone();
+        self.in_flight = Some((key, true));
+        Lookup::Miss { name, sql }
+    }
+
+    /// Statements evicted since the last call, to be closed on the server
+    pub(crate) fn take_evicted(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.evicted)
+    }
+
+    /// The next batch does not use a cached statement
+    pub(crate) fn clear_in_flight(&mut self) {
+        self.in_flight = None;
+    }
+
+    /// `ParseComplete` received: the statement in flight exists on the server
+    pub(crate) fn parsed(&mut self) {
+        if let Some((_, ref mut parsing)) = self.in_flight {
+            *parsing = false;
+        }
+    }
+
+    /// The batch in flight failed with SQLSTATE `code`
+    pub(crate) fn failed(&mut self, code: Option<&str>) -> Recovery {
+        let Some((key, parsing)) = self.in_flight.take() else {
+            return Recovery::None;
+        };
+        match code {
+            Some("26000") | Some("42P05") => {
+                self.bypassed = true;
+                self.entries.clear();
+                self.evicted.clear();
+                Recovery::Unnamed
+            }
+            // The Parse failed or never ran, so there is nothing to close
+            _ if parsing => {
+                self.entries.remove(&key);
+                Recovery::None
+            }
+            Some("0A000") => {
+                if let Some(entry) = self.entries.remove(&key) {
+                    self.evicted.push(entry.name);
+                }
+                Recovery::Reprepare
+            }
+            _ => Recovery::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn miss_name(lookup: Lookup) -> String {
+        match lookup {
+            Lookup::Miss { name, .. } => name,
+            Lookup::Hit(name) => panic!("unexpected hit for {}", name),
+        }
+    }
+
+    #[test]
+    fn test_lookup_and_eviction() {
+        let mut cache = StatementCache::new(2);
+        assert!(cache.is_enabled());
+        assert!(!StatementCache::new(0).is_enabled());
+
+        let a = miss_name(cache.lookup("SELECT data FROM v WHERE a = $1", &[25]));
+        assert!(a.starts_with(STATEMENT_PREFIX));
+        // Layout does not matter, parameter types do
+        assert_eq!(
+            cache.lookup("SELECT data\n  FROM v WHERE a = $1", &[25]),
+            Lookup::Hit(a.clone())
+        );
+        let b = miss_name(cache.lookup("SELECT data FROM v WHERE a = $1", &[20]));
+        assert_ne!(a, b);
+        assert!(cache.take_evicted().is_empty());
+
+        // Another connection's cache names its statements differently
+        let other =
+            miss_name(StatementCache::new(2).lookup("SELECT data FROM v WHERE a = $1", &[25]));
+        assert_ne!(a, other);
+        assert!(a.len() < 64, "{} exceeds NAMEDATALEN", a);
+
+        // `a` was used before `b`, so it is the least recently used
+        miss_name(cache.lookup("SELECT 1", &[]));
+        assert_eq!(cache.take_evicted(), [a]);
+        assert_eq!(
+            cache.lookup("SELECT data FROM v WHERE a = $1", &[20]),
+            Lookup::Hit(b)
+        );
+    }
+
+    #[test]
+    fn test_failed_parse_is_forgotten() {
+        let mut cache = StatementCache::new(4);
+        miss_name(cache.lookup("SELEC 1", &[]));
+        assert_eq!(cache.failed(Some("42601")), Recovery::None);
+        miss_name(cache.lookup("SELEC 1", &[]));
+
+        // A parsed statement survives execution errors
+        cache.parsed();
+        assert_eq!(cache.failed(Some("22012")), Recovery::None);
+        assert!(matches!(cache.lookup("SELEC 1", &[]), Lookup::Hit(_)));
+
+        // Errors outside a cached batch are ignored
+        cache.clear_in_flight();
+        assert_eq!(cache.failed(Some("26000")), Recovery::None);
+        assert!(cache.is_enabled());
+    }
+
+    #[test]
+    fn test_result_type_change_reprepares() {
+        let mut cache = StatementCache::new(4);
+        let name = miss_name(cache.lookup("SELECT * FROM t", &[]));
+        cache.parsed();
+        assert!(matches!(
+            cache.lookup("SELECT * FROM t", &[]),
+            Lookup::Hit(_)
+        ));
+        assert_eq!(cache.failed(Some("0A000")), Recovery::Reprepare);
+        assert_eq!(cache.take_evicted(), std::slice::from_ref(&name));
+        assert_ne!(miss_name(cache.lookup("SELECT * FROM t", &[])), name);
+    }
+
+    #[test]
+    fn test_pooler_disables_cache() {
+        let mut cache = StatementCache::new(4);
+        miss_name(cache.lookup("SELECT 1", &[]));
+        cache.parsed();
+        cache.lookup("SELECT 1", &[]);
+        assert_eq!(cache.failed(Some("26000")), Recovery::Unnamed);
+        assert!(!cache.is_enabled());
+        assert!(cache.take_evicted().is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+//! Tracing spans following the OpenTelemetry database conventions
+//!
+//! `FraiseClient` connections run inside a `connect` span with `dns`, `tcp`,
+//! `tls` and `auth` children. Every query runs inside a `query` span carrying
+//! `db.system`, `db.name`, `db.operation`, the entity and a sanitized statement;
+//! the span is entered by the reader task and records `rows` and `bytes` before
+//! it closes. Pause and resume are recorded as events on the query span.
+
+use super::cursor::ExecutionMode;
+use tracing::Span;
+
+/// Value of the `db.system` attribute
+pub(crate) const DB_SYSTEM: &str = "postgresql";
+
+/// Span covering one query, from submission until its reader task finishes
+pub(crate) fn query_span(
+    database: &str,
+    entity: &str,
+    statement: &str,
+    mode: ExecutionMode,
+    chunk_size: usize,
+) -> Span {
+    tracing::info_span!(
+        "query",
+        db.system = DB_SYSTEM,
+        db.name = %database,
+        db.operation = %operation(statement),
+        db.statement = %sanitize_statement(statement),
+        entity = %entity,
+        execution_mode = %mode,
+        chunk_size = chunk_size,
+        rows = tracing::field::Empty,
+        bytes = tracing::field::Empty,
+    )
+}
+
+/// Record the row and byte totals on the current query span
+pub(crate) fn record_query_totals(rows: u64, bytes: u64) {
+    let span = Span::current();
+    span.record("rows", rows);
+    span.record("bytes", bytes);
+}
+
+/// Leading SQL keyword of a statement, uppercased (e.g. `SELECT`)
+pub(crate) fn operation(statement: &str) -> String {
+    statement
+        .trim_start_matches(|c: char| c.is_whitespace() || c == '(')
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase()
+}
+
+/// Replace literal values in a statement with `?`
+///
+/// String, escape-string, dollar-quoted and numeric literals are replaced, so
+/// filter values never reach the trace backend. Identifiers, quoted identifiers,
+/// `$n` placeholders and the JSON keys and array indexes on the right of `->`,
+/// `->>`, `#>` and `#>>` (schema, not data) are kept.
+pub(crate) fn sanitize_statement(sql: &str) -> String {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let prev = out.chars().last();
+        match c {
+            '"' => {
+                let end = quoted_end(&chars, i, '"', false);
+                out.extend(&chars[i..end]);
+                i = end;
+            }
+            '\'' => {
+                let end = quoted_end(&chars, i, '\'', false);
+                if follows_json_accessor(&out) {
+                    out.extend(&chars[i..end]);
+                } else {
+                    out.push('?');
+                }
+                i = end;
+            }
+            'E' | 'e'
+                if chars.get(i + 1) == Some(&'\'') && !prev.is_some_and(is_identifier_char) =>
+            {
+                i = quoted_end(&chars, i + 1, '\'', true);
+                out.push('?');
+            }
+            '$' if !prev.is_some_and(is_identifier_char) => match dollar_tag(&chars, i) {
+                Some(tag) => {
+                    let body = i + tag.len();
+                    i = find(&chars, body, &tag).map_or(chars.len(), |pos| pos + tag.len());
+                    out.push('?');
+                }
+                None => {
+                    // `$n` placeholder
+                    out.push(c);
+                    i += 1;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        out.push(chars[i]);
+                        i += 1;
+                    }
+                }
+            },
+            c if c.is_ascii_digit() && !prev.is_some_and(is_identifier_char) => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric() || chars[i] == '.' || chars[i] == '_')
+                {
+                    i += 1;
+                }
+                // Array index, possibly negative (`data->0`, `data->-1`)
+                let accessor = out.strip_suffix('-').unwrap_or(&out);
+                if follows_json_accessor(accessor)
+                    && chars[start..i].iter().all(|c| c.is_ascii_digit())
+                {
+                    out.extend(&chars[start..i]);
+                } else {
+                    out.push('?');
+                }
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Index just past the quoted token starting at `start`
+///
+/// Doubled quotes are escapes; with `backslashes`, so is `\` (escape strings).
+fn quoted_end(chars: &[char], start: usize, quote: char, backslashes: bool) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        if backslashes && chars[i] == '\\' {
+            i += 2;
+        } else if chars[i] == quote {
+            if chars.get(i + 1) == Some(&quote) {
+                i += 2;
+            } else {
+                return i + 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    chars.len()
+}
+
+/// Opening tag of a dollar-quoted string (`$$` or `$tag$`) at `start`
+fn dollar_tag(chars: &[char], start: usize) -> Option<Vec<char>> {
+    let mut i = start + 1;
+    while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+        if i == start + 1 && chars[i].is_ascii_digit() {
+            return None;
+        }
+        i += 1;
+    }
+    (chars.get(i) == Some(&'$')).then(|| chars[start..=i].to_vec())
+}
+
+fn find(chars: &[char], from: usize, needle: &[char]) -> Option<usize> {
+    (from..chars.len()).find(|&i| chars[i..].starts_with(needle))
+}
+
+/// Whether the output so far ends with a JSON path accessor
+fn follows_json_accessor(out: &str) -> bool {
+    let trimmed = out.trim_end();
+    trimmed.ends_with("->")
+        || trimmed.ends_with("->>")
+        || trimmed.ends_with("#>")
+        || trimmed.ends_with("#>>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_replaces_values_keeps_keys() {
+        assert_eq!(
+            sanitize_statement(
+                "SELECT data FROM v_user WHERE data->>'email' = 'a@b.c' AND (data->'age')::int > 30 LIMIT 10"
+            ),
+            "SELECT data FROM v_user WHERE data->>'email' = ? AND (data->'age')::int > ? LIMIT ?"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_escapes_and_quoting() {
+        assert_eq!(
+            sanitize_statement("SELECT 'it''s', E'a\\'b', $$x'y$$, $tag$ $$ $tag$ FROM \"t'1\""),
+            "SELECT ?, ?, ?, ? FROM \"t'1\""
+        );
+    }
+
+    #[test]
+    fn test_sanitize_keeps_identifiers_and_placeholders() {
+        assert_eq!(
+            sanitize_statement(
+                "SELECT data FROM tv_user2 WHERE data #>> '{a,b}' = $1 OFFSET 1.5e3"
+            ),
+            "SELECT data FROM tv_user2 WHERE data #>> '{a,b}' = $1 OFFSET ?"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_keeps_array_indexes() {
+        assert_eq!(
+            sanitize_statement(
+                "SELECT data FROM t WHERE data->0->>'id' = '7' AND data->'tags'->>-1 = 'x' \
+                 AND data -> 2 IS NOT NULL AND (data->>'n')::int - 1 > 0 AND data->'a' = '1'"
+            ),
+            "SELECT data FROM t WHERE data->0->>'id' = ? AND data->'tags'->>-1 = ? \
+             AND data -> 2 IS NOT NULL AND (data->>'n')::int - ? > ? AND data->'a' = ?"
+        );
+    }
+
+    #[test]
+    fn test_operation() {
+        assert_eq!(operation("  select data FROM v_user"), "SELECT");
+        assert_eq!(operation("(SELECT 1)"), "SELECT");
+        assert_eq!(operation(""), "");
+    }
+}
@@ -0,0 +1,186 @@
+//! Read-only transaction control and exported snapshots
+//!
+//! fraiseql-wire does not support writes, but reading several entities from one
+//! MVCC snapshot needs a transaction: one connection exports a snapshot with
+//! `pg_export_snapshot()` and other connections import it with
+//! `SET TRANSACTION SNAPSHOT`, so N streams on N connections see identical data.
+
+use crate::protocol::constants::tx_status;
+use crate::{Error, Result};
+use std::fmt;
+
+/// Transaction isolation level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IsolationLevel {
+    /// READ COMMITTED (Postgres default; each statement sees a new snapshot)
+    ReadCommitted,
+    /// REPEATABLE READ (one snapshot for the whole transaction)
+    #[default]
+    RepeatableRead,
+    /// SERIALIZABLE
+    Serializable,
+}
+
+impl IsolationLevel {
+    /// SQL keyword form, as used in `BEGIN ISOLATION LEVEL ...`
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::ReadCommitted => "READ COMMITTED",
+            Self::RepeatableRead => "REPEATABLE READ",
+            Self::Serializable => "SERIALIZABLE",
+        }
+    }
+
+    /// Whether the whole transaction reads from a single snapshot
+    ///
+    /// Postgres only allows `SET TRANSACTION SNAPSHOT` at these levels.
+    pub fn uses_transaction_snapshot(&self) -> bool {
+        !matches!(self, Self::ReadCommitted)
+    }
+}
+
+impl fmt::Display for IsolationLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_sql())
+    }
+}
+
+/// Transaction status reported by the server in `ReadyForQuery`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionStatus {
+    /// Not in a transaction block
+    Idle,
+    /// In a transaction block
+    InTransaction,
+    /// In a failed transaction block (queries rejected until rollback)
+    Failed,
+}
+
+impl TransactionStatus {
+    /// Decode the `ReadyForQuery` status byte
+    pub fn from_byte(status: u8) -> Result<Self> {
+        match status {
+            tx_status::IDLE => Ok(Self::Idle),
+            tx_status::IN_TRANSACTION => Ok(Self::InTransaction),
+            tx_status::FAILED => Ok(Self::Failed),
+            other => Err(Error::Protocol(format!(
+                "unknown transaction status byte: 0x{:02X}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Identifier of a snapshot exported with `pg_export_snapshot()`
+///
+/// Postgres formats these as dash-separated hex groups (e.g. `00000003-0000001B-1`).
+/// The identifier is validated before being interpolated into
+/// `SET TRANSACTION SNAPSHOT`, since that statement cannot take a parameter.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SnapshotId(String);
+
+impl SnapshotId {
+    /// Parse and validate a snapshot identifier
+    pub fn parse(id: impl Into<String>) -> Result<Self> {
+        let id = id.into();
+        let valid = !id.is_empty()
+            && id.len() <= 64
+            && id
+                .split('-')
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_hexdigit()));
+        if !valid {
+            return Err(Error::Config(format!("invalid snapshot id '{}'", id)));
+        }
+        Ok(Self(id))
+    }
+
+    /// The identifier as returned by `pg_export_snapshot()`
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::str::FromStr for SnapshotId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+/// Build a `BEGIN` statement for a read-only transaction
+pub(crate) fn begin_sql(isolation: IsolationLevel) -> String {
+    format!("BEGIN ISOLATION LEVEL {} READ ONLY", isolation.as_sql())
+}
+
+/// Build a `SET TRANSACTION SNAPSHOT` statement
+pub(crate) fn set_snapshot_sql(snapshot: &SnapshotId) -> String {
+    // SnapshotId is validated to hex digits and dashes, so quoting is safe
+    format!("SET TRANSACTION SNAPSHOT '{}'", snapshot.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_isolation_level_sql() {
+        assert_eq!(IsolationLevel::ReadCommitted.as_sql(), "READ COMMITTED");
+        assert_eq!(
+            IsolationLevel::RepeatableRead.to_string(),
+            "REPEATABLE READ"
+        );
+        assert_eq!(IsolationLevel::default(), IsolationLevel::RepeatableRead);
+        assert!(!IsolationLevel::ReadCommitted.uses_transaction_snapshot());
+        assert!(IsolationLevel::Serializable.uses_transaction_snapshot());
+    }
+
+    #[test]
+    fn test_begin_sql() {
+        assert_eq!(
+            begin_sql(IsolationLevel::RepeatableRead),
+            "BEGIN ISOLATION LEVEL REPEATABLE READ READ ONLY"
+        );
+    }
+
+    #[test]
+    fn test_transaction_status_from_byte() {
+        assert_eq!(
+            TransactionStatus::from_byte(b'I').unwrap(),
+            TransactionStatus::Idle
+        );
+        assert_eq!(
+            TransactionStatus::from_byte(b'T').unwrap(),
+            TransactionStatus::InTransaction
+        );
+        assert_eq!(
+            TransactionStatus::from_byte(b'E').unwrap(),
+            TransactionStatus::Failed
+        );
+        assert!(TransactionStatus::from_byte(b'X').is_err());
+    }
+
+    #[test]
+    fn test_snapshot_id_valid() {
+        let id = SnapshotId::parse("00000003-0000001B-1").unwrap();
+        assert_eq!(id.as_str(), "00000003-0000001B-1");
+        assert_eq!(
+            set_snapshot_sql(&id),
+            "SET TRANSACTION SNAPSHOT '00000003-0000001B-1'"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_id_rejects_injection() {
+        assert!(SnapshotId::parse("").is_err());
+        assert!(SnapshotId::parse("0000-").is_err());
+        assert!(SnapshotId::parse("1'; DROP TABLE users; --").is_err());
+        assert!("00000003-0000001G-1".parse::<SnapshotId>().is_err());
+    }
+}
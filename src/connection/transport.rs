@@ -3,9 +3,11 @@
 use crate::Result;
 use bytes::BytesMut;
 use sha2::Digest;
-use std::path::Path;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpStream, UnixStream};
+use tracing::Instrument;
 
 /// TCP stream variant: plain or TLS-encrypted
 #[allow(clippy::large_enum_variant)]
@@ -81,6 +83,30 @@ impl TcpVariant {
     }
 }
 
+/// Server address of an open transport, used to send CancelRequests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PeerAddress {
+    /// TCP peer (TLS or plain)
+    Tcp(SocketAddr),
+    /// Unix socket path
+    Unix(PathBuf),
+}
+
+impl PeerAddress {
+    /// Open a new plain transport to the same server
+    ///
+    /// CancelRequest is processed before authentication, so plain TCP is
+    /// accepted even when the original session uses TLS.
+    pub(crate) async fn connect(&self) -> Result<Transport> {
+        match self {
+            PeerAddress::Tcp(addr) => Ok(Transport::Tcp(TcpVariant::Plain(
+                TcpStream::connect(addr).await?,
+            ))),
+            PeerAddress::Unix(path) => Transport::connect_unix(path).await,
+        }
+    }
+}
+
 /// Transport layer abstraction
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
@@ -94,7 +120,7 @@ pub enum Transport {
 impl Transport {
     /// Connect via plain TCP
     pub async fn connect_tcp(host: &str, port: u16) -> Result<Self> {
-        let stream = TcpStream::connect((host, port)).await?;
+        let stream = connect_tcp_stream(host, port).await?;
         Ok(Transport::Tcp(TcpVariant::Plain(stream)))
     }
 
@@ -104,7 +130,7 @@ impl Transport {
         port: u16,
         tls_config: &crate::connection::TlsConfig,
     ) -> Result<Self> {
-        let tcp_stream = TcpStream::connect((host, port)).await?;
+        let tcp_stream = connect_tcp_stream(host, port).await?;
 
         // Parse server name for TLS handshake (SNI)
         let server_name = crate::connection::parse_server_name(host)?;
@@ -116,6 +142,7 @@ impl Transport {
         let tls_connector = tokio_rustls::TlsConnector::from(client_config);
         let tls_stream = tls_connector
             .connect(server_name, tcp_stream)
+            .instrument(tracing::info_span!("tls"))
             .await
             .map_err(|e| crate::Error::Config(format!("TLS handshake failed: {}", e)))?;
 
@@ -199,6 +226,25 @@ impl Transport {
         Ok(())
     }
 
+    /// Address of the server end of the transport
+    ///
+    /// Returns `None` for an unnamed Unix socket.
+    pub(crate) fn peer_address(&self) -> Option<PeerAddress> {
+        match self {
+            Transport::Tcp(TcpVariant::Plain(stream)) => {
+                stream.peer_addr().ok().map(PeerAddress::Tcp)
+            }
+            Transport::Tcp(TcpVariant::Tls(stream)) => {
+                stream.get_ref().0.peer_addr().ok().map(PeerAddress::Tcp)
+            }
+            Transport::Unix(stream) => stream
+                .peer_addr()
+                .ok()?
+                .as_pathname()
+                .map(|path| PeerAddress::Unix(path.to_path_buf())),
+        }
+    }
+
     /// Extract channel binding data from the transport (if TLS is active).
     ///
     /// Returns `None` for plain TCP or Unix socket connections.
@@ -210,6 +256,41 @@ impl Transport {
     }
 }
 
+/// Resolve `host` and connect to the first address that accepts, in `dns` and `tcp` spans
+async fn connect_tcp_stream(host: &str, port: u16) -> Result<TcpStream> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .instrument(tracing::info_span!("dns", host = %host))
+        .await?
+        .collect();
+
+    async {
+        let mut last_error = None;
+        for addr in &addrs {
+            match TcpStream::connect(addr).await {
+                Ok(stream) => {
+                    tracing::Span::current().record("peer", tracing::field::display(addr));
+                    return Ok(stream);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("could not resolve '{}' to any address", host),
+                )
+            })
+            .into())
+    }
+    .instrument(tracing::info_span!(
+        "tcp",
+        port,
+        peer = tracing::field::Empty
+    ))
+    .await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,5 +1,6 @@
 //! Error types for fraiseql-wire
 
+use crate::client::GuardViolation;
 use std::io;
 use thiserror::Error;
 
@@ -71,6 +72,18 @@ pub enum Error {
         details: String,
     },
 
+    /// Invalid filter document
+    ///
+    /// Returned by `WhereOperator::from_filter_json` with the location of the
+    /// offending element (e.g. `$.or[1].status.eq`).
+    #[error("invalid filter at {path}: {message}")]
+    InvalidFilter {
+        /// JSON path of the offending element
+        path: String,
+        /// What is wrong with it
+        message: String,
+    },
+
     /// Memory limit exceeded
     ///
     /// **Terminal error**: The consumer cannot keep pace with data arrival.
@@ -92,6 +105,15 @@ pub enum Error {
         /// Current estimated memory in bytes (items_buffered * 2048)
         estimated_memory: usize,
     },
+
+    /// Query refused by a cost guard
+    ///
+    /// Returned before execution when the query plan violates a limit set with
+    /// `max_estimated_cost()`, `max_estimated_rows()` or `forbid_seq_scan_on()`.
+    ///
+    /// NOT retriable: the same query will be planned the same way.
+    #[error("query rejected: {0}")]
+    QueryRejected(GuardViolation),
 }
 
 /// Result type alias using fraiseql-wire Error
@@ -187,7 +209,9 @@ impl Error {
             Error::InvalidState { .. } => "invalid_state",
             Error::ConnectionClosed => "connection_closed",
             Error::Deserialization { .. } => "deserialization",
+            Error::InvalidFilter { .. } => "invalid_filter",
             Error::MemoryLimitExceeded { .. } => "memory_limit_exceeded",
+            Error::QueryRejected(_) => "query_rejected",
         }
     }
 }
@@ -333,4 +357,17 @@ mod tests {
         };
         assert!(!err.is_retriable());
     }
+
+    #[test]
+    fn test_query_rejected_error() {
+        let err = Error::QueryRejected(GuardViolation::SeqScan {
+            relation: "public.orders".to_string(),
+        });
+        assert_eq!(
+            err.to_string(),
+            "query rejected: plan contains a sequential scan on public.orders"
+        );
+        assert_eq!(err.category(), "query_rejected");
+        assert!(!err.is_retriable());
+    }
 }
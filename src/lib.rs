@@ -26,9 +26,14 @@ pub mod stream;
 pub mod util;
 
 // Re-export commonly used types
-pub use client::FraiseClient;
+pub use client::{
+    EntityRef, ExplainOptions, FraiseClient, GuardViolation, QueryPlan, RenderedQuery,
+};
 pub use error::{Error, Result};
-pub use operators::{Field, OrderByClause, SortOrder, Value, WhereOperator};
+pub use operators::{
+    Aggregate, DistanceMetric, Field, Headline, KeysetCursor, OrderByClause, Projection, SortOrder,
+    TextQuery, TextRank, ToSqlParam, Value, WhereOperator,
+};
 
 /// Library version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
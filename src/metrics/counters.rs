@@ -267,6 +267,37 @@ pub fn stream_pause_timeout_expired(entity: &str) {
     .increment(1);
 }
 
+/// Record a prepared statement cache hit (the statement was not parsed again)
+pub fn statement_cache_hit(entity: &str) {
+    counter!(
+        "fraiseql_statement_cache_hits_total",
+        labels::ENTITY => entity.to_string(),
+    )
+    .increment(1);
+}
+
+/// Record a prepared statement cache miss (the statement was parsed)
+pub fn statement_cache_miss(entity: &str) {
+    counter!(
+        "fraiseql_statement_cache_misses_total",
+        labels::ENTITY => entity.to_string(),
+    )
+    .increment(1);
+}
+
+/// Record a prepared statement cache invalidation
+///
+/// # Labels
+/// - `reason`: `result_type_changed` (SQLSTATE 0A000, the statement is
+///   prepared again) or `pooler` (the cache is switched off)
+pub fn statement_cache_invalidated(reason: &str) {
+    counter!(
+        "fraiseql_statement_cache_invalidations_total",
+        labels::REASON => reason.to_string(),
+    )
+    .increment(1);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -293,6 +324,13 @@ mod tests {
         rows_processed("test_entity", 5, "error");
     }
 
+    #[test]
+    fn test_statement_cache_counters() {
+        statement_cache_hit("test_entity");
+        statement_cache_miss("test_entity");
+        statement_cache_invalidated("pooler");
+    }
+
     #[test]
     fn test_error_occurred() {
         error_occurred("protocol", labels::PHASE_QUERY);
@@ -0,0 +1,107 @@
+//! Aggregates over the rows a query matches
+//!
+//! Used by `QueryBuilder::aggregate(field, Aggregate)`, which applies the
+//! builder's filters and returns the result as a JSON scalar:
+//!
+//! ```ignore
+//! let newest = client
+//!     .query::<Value>("orders")
+//!     .where_sql("data->>'status' = 'paid'")
+//!     .aggregate(Field::JsonbField("created_at".into()), Aggregate::Max)
+//!     .await?;
+//! ```
+
+use super::Field;
+
+/// Aggregate function for `QueryBuilder::aggregate`
+///
+/// JSONB fields are aggregated as follows: `Min`/`Max` order `jsonb`
+/// values (numbers numerically, strings by collation, values of different
+/// JSON types by type; JSON `null` is skipped), `Sum`/`Avg` cast the text
+/// value to `numeric`, and `Count` counts values that are neither missing
+/// nor JSON `null`. Direct columns use their native type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    /// Number of non-null values
+    Count,
+
+    /// Smallest value
+    Min,
+
+    /// Largest value
+    Max,
+
+    /// Sum of the values
+    Sum,
+
+    /// Average of the values
+    Avg,
+}
+
+impl Aggregate {
+    /// The Postgres function name
+    pub fn function(&self) -> &'static str {
+        match self {
+            Aggregate::Count => "count",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+            Aggregate::Sum => "sum",
+            Aggregate::Avg => "avg",
+        }
+    }
+
+    /// Query returning the aggregate of `field` over the `rows` subquery as a
+    /// single `jsonb` value (or no row); validate the field first
+    ///
+    /// `jsonb` has no `min`/`max` aggregates, so JSONB fields take the first
+    /// value in `jsonb` order instead.
+    pub(crate) fn query_sql(&self, field: &Field, rows: &str) -> String {
+        let function = self.function();
+        let jsonb = !matches!(field, Field::DirectColumn(_));
+        let expr = match self {
+            Aggregate::Min | Aggregate::Max if jsonb => {
+                let value = field.to_jsonb_sql();
+                return format!(
+                    "SELECT {} FROM ({}) AS matched WHERE {} <> 'null'::jsonb ORDER BY 1 {} LIMIT 1",
+                    value,
+                    rows,
+                    value,
+                    if *self == Aggregate::Min { "ASC" } else { "DESC" }
+                );
+            }
+            Aggregate::Count => format!("to_jsonb(count({}))", field.to_sql()),
+            Aggregate::Sum | Aggregate::Avg if jsonb => {
+                format!("to_jsonb({}({}::numeric))", function, field.to_sql())
+            }
+            _ => format!("to_jsonb({}({}))", function, field.to_sql()),
+        };
+        format!("SELECT {} FROM ({}) AS matched", expr, rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_sql() {
+        let price = Field::JsonbField("price".into());
+        assert_eq!(
+            Aggregate::Count.query_sql(&price, "SELECT * FROM t"),
+            "SELECT to_jsonb(count((data->>'price'))) FROM (SELECT * FROM t) AS matched"
+        );
+        assert_eq!(
+            Aggregate::Max.query_sql(&price, "SELECT * FROM t"),
+            "SELECT (data->'price') FROM (SELECT * FROM t) AS matched \
+             WHERE (data->'price') <> 'null'::jsonb ORDER BY 1 DESC LIMIT 1"
+        );
+        assert_eq!(
+            Aggregate::Avg.query_sql(&Field::JsonbPath(vec!["a".into(), "b".into()]), "r"),
+            "SELECT to_jsonb(avg((data->'a'->>'b')::numeric)) FROM (r) AS matched"
+        );
+        assert_eq!(
+            Aggregate::Min.query_sql(&Field::DirectColumn("created_at".into()), "r"),
+            "SELECT to_jsonb(min(created_at)) FROM (r) AS matched"
+        );
+    }
+}
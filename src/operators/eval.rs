@@ -0,0 +1,882 @@
+//! In-memory evaluation of WHERE operators
+//!
+//! `WhereOperator::matches` applies an operator to a JSON row the way Postgres
+//! applies the SQL generated by `sql_gen` to the same row. A JSONB field reads
+//! as its text extraction (`->>`): strings as-is, other scalars as their JSON
+//! text, and a missing key or JSON `null` as SQL NULL. Comparisons cast that
+//! text to the type of the value (`numeric`, `bigint`, `boolean`, `uuid`,
+//! `timestamptz`, `date` or `text`), and failing casts are errors just like on
+//! the server. Timestamps without an offset are read as UTC, where the server
+//! applies the session `TimeZone`. `Value::Json` compares the JSON value itself
+//! (`->`) with `jsonb` ordering, and JSONB containment and key existence follow
+//! `jsonb` `@>` and `?` semantics.
+//!
+//! Predicates use SQL three-valued logic: a comparison with NULL is unknown,
+//! `NOT unknown` is unknown, and only rows where the whole predicate is true
+//! match. Text compares byte-wise, as the generated SQL does with `COLLATE
+//! "C"`. Strings inside `Value::Json` comparisons also compare byte-wise, while
+//! the server orders `jsonb` strings in the database collation, so those agree
+//! only in a `C`-collated database.
+//!
+//! Vector, full-text, network and SQL/JSON path operators need server-side
+//! types and return an error, as do direct columns, which are not part of
+//! streamed rows.
+
+use super::{scalar, Field, Value, WhereOperator};
+use crate::{Error, Result};
+use serde_json::Value as Json;
+use std::cmp::Ordering;
+
+impl WhereOperator {
+    /// Whether `row` satisfies this operator
+    ///
+    /// # Errors
+    ///
+    /// - `Error::Config` for operators that cannot be evaluated in memory
+    ///   (vector, full-text, network and SQL/JSON path operators, direct
+    ///   columns, raw SQL)
+    /// - `Error::Config` when a field cannot be cast to the compared type, like
+    ///   Postgres' "invalid input syntax" errors
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let op = WhereOperator::from_filter_json(&json!({"status": {"eq": "active"}}))?;
+    /// let stream = client
+    ///     .query::<Project>("projects")
+    ///     .where_rust(move |row| op.matches(row).unwrap_or(false))
+    ///     .execute()
+    ///     .await?;
+    /// ```
+    pub fn matches(&self, row: &Json) -> Result<bool> {
+        Ok(self.evaluate(row)? == Some(true))
+    }
+
+    /// Three-valued result: `None` is SQL unknown (NULL)
+    fn evaluate(&self, row: &Json) -> Result<Option<bool>> {
+        match self {
+            WhereOperator::Eq(field, value) => match value {
+                Value::Null => Ok(Some(text(row, field)?.is_none())),
+                _ => compare(row, field, value, |o| o == Ordering::Equal),
+            },
+            WhereOperator::Neq(field, value) => match value {
+                Value::Null => Ok(Some(text(row, field)?.is_some())),
+                _ => compare(row, field, value, |o| o != Ordering::Equal),
+            },
+            WhereOperator::Gt(field, value) => compare(row, field, value, |o| o.is_gt()),
+            WhereOperator::Gte(field, value) => compare(row, field, value, |o| o.is_ge()),
+            WhereOperator::Lt(field, value) => compare(row, field, value, |o| o.is_lt()),
+            WhereOperator::Lte(field, value) => compare(row, field, value, |o| o.is_le()),
+
+            WhereOperator::In(field, values) => in_list(row, field, values),
+            WhereOperator::Nin(field, values) => Ok(in_list(row, field, values)?.map(|b| !b)),
+
+            WhereOperator::Contains(field, s) => like(row, field, &format!("%{}%", s), false),
+            WhereOperator::Icontains(field, s) => like(row, field, &format!("%{}%", s), true),
+            WhereOperator::Startswith(field, s) => like(row, field, &format!("{}%", s), false),
+            WhereOperator::Endswith(field, s) => like(row, field, &format!("%{}", s), false),
+            WhereOperator::Like(field, pattern) => like(row, field, pattern, false),
+            WhereOperator::Ilike(field, pattern) => like(row, field, pattern, true),
+
+            WhereOperator::ArrayContains(field, value) => {
+                let Some(array) = array(row, field)? else {
+                    return Ok(None);
+                };
+                let wanted = array_operand(std::slice::from_ref(value))?;
+                Ok(Some(wanted.iter().all(|w| contains(&array, w))))
+            }
+            WhereOperator::ArrayContainedBy(field, value) => {
+                let Some(array) = array(row, field)? else {
+                    return Ok(None);
+                };
+                let allowed = array_operand(std::slice::from_ref(value))?;
+                Ok(Some(array.iter().all(|a| contains(&allowed, a))))
+            }
+            WhereOperator::ArrayOverlaps(field, values) => {
+                let Some(array) = array(row, field)? else {
+                    return Ok(None);
+                };
+                let other = array_operand(values)?;
+                Ok(Some(array.iter().any(|a| contains(&other, a))))
+            }
+
+            WhereOperator::LenEq(field, n) => length(row, field, |len| len == *n),
+            WhereOperator::LenGt(field, n) => length(row, field, |len| len > *n),
+            WhereOperator::LenGte(field, n) => length(row, field, |len| len >= *n),
+            WhereOperator::LenLt(field, n) => length(row, field, |len| len < *n),
+            WhereOperator::LenLte(field, n) => length(row, field, |len| len <= *n),
+
+            WhereOperator::IsNull(field, is_null) => {
+                Ok(Some(text(row, field)?.is_none() == *is_null))
+            }
+
+            WhereOperator::JsonContains(field, value) => {
+                field.validate().map_err(Error::Config)?;
+                let path = match field {
+                    Field::JsonbField(name) => std::slice::from_ref(name),
+                    Field::JsonbPath(path) => path.as_slice(),
+                    Field::DirectColumn(name) => return Err(direct_column(name)),
+                };
+                let document = path.iter().rev().fold(value.clone(), |inner, key| {
+                    let mut object = serde_json::Map::new();
+                    object.insert(key.clone(), inner);
+                    Json::Object(object)
+                });
+                Ok(Some(jsonb_contains(row, &document, true)))
+            }
+            WhereOperator::HasKey(field, key) => {
+                has_keys(row, field, std::slice::from_ref(key), true)
+            }
+            WhereOperator::HasAnyKey(field, keys) => has_keys(row, field, keys, false),
+            WhereOperator::HasAllKeys(field, keys) => has_keys(row, field, keys, true),
+
+            WhereOperator::And(ops) => {
+                let mut result = Some(true);
+                for op in ops {
+                    match op.evaluate(row)? {
+                        Some(false) => return Ok(Some(false)),
+                        None => result = None,
+                        Some(true) => {}
+                    }
+                }
+                Ok(result)
+            }
+            WhereOperator::Or(ops) => {
+                let mut result = Some(false);
+                for op in ops {
+                    match op.evaluate(row)? {
+                        Some(true) => return Ok(Some(true)),
+                        None => result = None,
+                        Some(false) => {}
+                    }
+                }
+                Ok(result)
+            }
+            WhereOperator::Not(op) => Ok(op.evaluate(row)?.map(|b| !b)),
+
+            WhereOperator::L2Distance { .. }
+            | WhereOperator::CosineDistance { .. }
+            | WhereOperator::InnerProduct { .. }
+            | WhereOperator::JaccardDistance { .. }
+            | WhereOperator::Matches { .. }
+            | WhereOperator::PlainQuery { .. }
+            | WhereOperator::PhraseQuery { .. }
+            | WhereOperator::WebsearchQuery { .. }
+            | WhereOperator::IsIPv4(_)
+            | WhereOperator::IsIPv6(_)
+            | WhereOperator::IsPrivate(_)
+            | WhereOperator::IsLoopback(_)
+            | WhereOperator::InSubnet { .. }
+            | WhereOperator::ContainsSubnet { .. }
+            | WhereOperator::ContainsIP { .. }
+            | WhereOperator::IPRangeOverlap { .. }
+            | WhereOperator::JsonPathExists { .. }
+            | WhereOperator::JsonPathMatch { .. } => Err(unsupported(self.name())),
+        }
+    }
+}
+
+fn unsupported(what: &str) -> Error {
+    Error::Config(format!("{} is unsupported in memory", what))
+}
+
+/// The JSON value of `field` in `row`; `None` when missing or `null`
+fn lookup<'a>(row: &'a Json, field: &Field) -> Result<Option<&'a Json>> {
+    field.validate().map_err(Error::Config)?;
+    let found = match field {
+        Field::JsonbField(name) => row.get(name),
+        Field::JsonbPath(path) => path.iter().try_fold(row, |value, key| value.get(key)),
+        Field::DirectColumn(name) => return Err(direct_column(name)),
+    };
+    Ok(found.filter(|value| !value.is_null()))
+}
+
+fn direct_column(name: &str) -> Error {
+    unsupported(&format!(
+        "direct column '{}' (not part of the streamed row)",
+        name
+    ))
+}
+
+/// The JSON value of `field` in `row` (`->`); `None` only when missing
+fn lookup_json<'a>(row: &'a Json, field: &Field) -> Result<Option<&'a Json>> {
+    match field {
+        Field::JsonbField(name) => Ok(row.get(name)),
+        Field::JsonbPath(path) => Ok(path.iter().try_fold(row, |value, key| value.get(key))),
+        Field::DirectColumn(_) => lookup(row, field),
+    }
+}
+
+/// `jsonb` ordering: Object > Array > Boolean > Number > String > Null, with
+/// arrays and objects ordered by size first; at the top level, an empty array
+/// sorts before any scalar and other arrays after
+fn jsonb_cmp(a: &Json, b: &Json) -> Ordering {
+    let scalar = |v: &Json| !matches!(v, Json::Array(_) | Json::Object(_));
+    match (a, b) {
+        (Json::Array(items), other) if scalar(other) => {
+            if items.is_empty() {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        }
+        (other, Json::Array(_)) if scalar(other) => jsonb_cmp(b, a).reverse(),
+        _ => jsonb_value_cmp(a, b),
+    }
+}
+
+fn jsonb_value_cmp(a: &Json, b: &Json) -> Ordering {
+    let rank = |v: &Json| match v {
+        Json::Null => 0,
+        Json::String(_) => 1,
+        Json::Number(_) => 2,
+        Json::Bool(_) => 3,
+        Json::Array(_) => 4,
+        Json::Object(_) => 5,
+    };
+    match (a, b) {
+        (Json::String(x), Json::String(y)) => x.as_bytes().cmp(y.as_bytes()),
+        (Json::Number(x), Json::Number(y)) => {
+            let exact = |n: &serde_json::Number| scalar::parse_decimal(&n.to_string());
+            exact(x).cmp(&exact(y))
+        }
+        (Json::Bool(x), Json::Bool(y)) => x.cmp(y),
+        (Json::Array(x), Json::Array(y)) => x.len().cmp(&y.len()).then_with(|| {
+            x.iter()
+                .zip(y)
+                .map(|(x, y)| jsonb_value_cmp(x, y))
+                .find(|o| o.is_ne())
+                .unwrap_or(Ordering::Equal)
+        }),
+        (Json::Object(x), Json::Object(y)) => {
+            // Keys in storage order: shorter keys first
+            fn sorted(map: &serde_json::Map<String, Json>) -> Vec<&String> {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort_by(|k, l| k.len().cmp(&l.len()).then_with(|| k.cmp(l)));
+                keys
+            }
+            x.len().cmp(&y.len()).then_with(|| {
+                sorted(x)
+                    .into_iter()
+                    .zip(sorted(y))
+                    .map(|(k, l)| {
+                        k.as_bytes()
+                            .cmp(l.as_bytes())
+                            .then_with(|| jsonb_value_cmp(&x[k], &y[l]))
+                    })
+                    .find(|o| o.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// `jsonb` containment (`a @> b`); at the top level an array also contains a
+/// scalar equal to one of its elements
+fn jsonb_contains(a: &Json, b: &Json, top: bool) -> bool {
+    match (a, b) {
+        (Json::Object(x), Json::Object(y)) => y
+            .iter()
+            .all(|(k, v)| x.get(k).is_some_and(|u| jsonb_contains(u, v, false))),
+        (Json::Array(x), Json::Array(y)) => y
+            .iter()
+            .all(|v| x.iter().any(|u| jsonb_contains(u, v, false))),
+        (Json::Array(x), other) if top && !other.is_object() => {
+            x.iter().any(|u| jsonb_contains(u, other, false))
+        }
+        (Json::Array(_) | Json::Object(_), _) | (_, Json::Array(_) | Json::Object(_)) => false,
+        _ => jsonb_value_cmp(a, b).is_eq(),
+    }
+}
+
+/// Key existence (`?`, `?|`, `?&`): an object key, a string array element or
+/// an equal string scalar
+fn has_keys(row: &Json, field: &Field, keys: &[String], all: bool) -> Result<Option<bool>> {
+    let Some(value) = lookup_json(row, field)? else {
+        return Ok(None);
+    };
+    let has = |key: &String| match value {
+        Json::Object(object) => object.contains_key(key),
+        Json::Array(items) => items.iter().any(|item| item.as_str() == Some(key.as_str())),
+        Json::String(s) => s == key,
+        _ => false,
+    };
+    Ok(Some(if all {
+        keys.iter().all(has)
+    } else {
+        keys.iter().any(has)
+    }))
+}
+
+/// Text extraction (`->>`) of `field`; `None` is SQL NULL
+fn text(row: &Json, field: &Field) -> Result<Option<String>> {
+    Ok(lookup(row, field)?.map(json_text))
+}
+
+fn json_text(value: &Json) -> String {
+    match value {
+        Json::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Compare `field` with `value` after casting to the value's type
+fn compare(
+    row: &Json,
+    field: &Field,
+    value: &Value,
+    accept: impl Fn(Ordering) -> bool,
+) -> Result<Option<bool>> {
+    Ok(order(row, field, value)?.map(accept))
+}
+
+/// Order of `field` relative to `value`; `None` when either is NULL
+fn order(row: &Json, field: &Field, value: &Value) -> Result<Option<Ordering>> {
+    if let Value::Json(expected) = value {
+        // `->` extraction: only a missing key is SQL NULL
+        field.validate().map_err(Error::Config)?;
+        return Ok(lookup_json(row, field)?.map(|actual| jsonb_cmp(actual, expected)));
+    }
+    let Some(text) = text(row, field)? else {
+        return Ok(None);
+    };
+    compare_text(&text, value)
+}
+
+/// Order of `text` relative to `value`; `None` when `value` is NULL
+fn compare_text(text: &str, value: &Value) -> Result<Option<Ordering>> {
+    value.validate().map_err(Error::Config)?;
+    match value {
+        Value::Null => Ok(None),
+        Value::String(s) => Ok(Some(text.as_bytes().cmp(s.as_bytes()))),
+        Value::Number(n) => {
+            let parsed = parse_numeric(text)?;
+            Ok(parsed.partial_cmp(n))
+        }
+        Value::Int(n) => Ok(Some(
+            parse_typed(text, "bigint", scalar::parse_int8)?.cmp(n),
+        )),
+        Value::Decimal(s) => {
+            let parsed = parse_typed(text, "numeric", scalar::parse_decimal)?;
+            Ok(Some(parsed.cmp(&parse_typed(
+                s,
+                "numeric",
+                scalar::parse_decimal,
+            )?)))
+        }
+        Value::Uuid(s) => {
+            let parsed = parse_typed(text, "uuid", scalar::parse_uuid)?;
+            Ok(Some(parsed.cmp(&parse_typed(
+                s,
+                "uuid",
+                scalar::parse_uuid,
+            )?)))
+        }
+        Value::Timestamp(s) => {
+            let parsed = parse_typed(text, "timestamp with time zone", scalar::parse_timestamp)?;
+            Ok(Some(parsed.cmp(&parse_typed(
+                s,
+                "timestamp with time zone",
+                scalar::parse_timestamp,
+            )?)))
+        }
+        Value::Date(s) => {
+            let parsed = parse_typed(text, "date", scalar::parse_date)?;
+            Ok(Some(parsed.cmp(&parse_typed(
+                s,
+                "date",
+                scalar::parse_date,
+            )?)))
+        }
+        Value::Json(_) => unreachable!("jsonb values are compared by order()"),
+        Value::Bool(b) => Ok(Some(parse_bool(text)?.cmp(b))),
+        Value::Array(_) => Err(unsupported(
+            "comparing a scalar with an array value (use the array operators)",
+        )),
+        Value::FloatArray(_) => Err(unsupported("comparing with a vector")),
+        Value::RawSql(_) => Err(unsupported("raw SQL")),
+    }
+}
+
+fn parse_numeric(text: &str) -> Result<f64> {
+    let trimmed = text.trim();
+    let valid = !trimmed.is_empty()
+        && trimmed
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E'));
+    trimmed
+        .parse::<f64>()
+        .ok()
+        .filter(|_| valid)
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "invalid input syntax for type numeric: \"{}\"",
+                text
+            ))
+        })
+}
+
+/// `text` cast to a typed scalar, failing like Postgres' input functions
+fn parse_typed<T>(text: &str, type_name: &str, parse: impl Fn(&str) -> Option<T>) -> Result<T> {
+    parse(text).ok_or_else(|| {
+        Error::Config(format!(
+            "invalid input syntax for type {}: \"{}\"",
+            type_name, text
+        ))
+    })
+}
+
+/// Postgres boolean input: `t`/`true`/`yes`/`on`/`1` and their negations
+fn parse_bool(text: &str) -> Result<bool> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "t" | "true" | "y" | "yes" | "on" | "1" => Ok(true),
+        "f" | "false" | "n" | "no" | "off" | "0" => Ok(false),
+        _ => Err(Error::Config(format!(
+            "invalid input syntax for type boolean: \"{}\"",
+            text
+        ))),
+    }
+}
+
+/// `field IN (values)`: unknown if `field` is NULL, or if nothing matched and
+/// the list contains NULL
+fn in_list(row: &Json, field: &Field, values: &[Value]) -> Result<Option<bool>> {
+    if values.is_empty() {
+        return Err(Error::Config("IN requires at least one value".into()));
+    }
+    let mut result = Some(false);
+    for value in values {
+        match order(row, field, value)? {
+            Some(Ordering::Equal) => return Ok(Some(true)),
+            Some(_) => {}
+            None => result = None,
+        }
+    }
+    Ok(result)
+}
+
+/// `field [I]LIKE pattern`
+fn like(row: &Json, field: &Field, pattern: &str, case_insensitive: bool) -> Result<Option<bool>> {
+    let Some(text) = text(row, field)? else {
+        return Ok(None);
+    };
+    let (text, pattern) = if case_insensitive {
+        (text.to_lowercase(), pattern.to_lowercase())
+    } else {
+        (text, pattern.to_string())
+    };
+    let text: Vec<char> = text.chars().collect();
+    let pattern = parse_like(&pattern)?;
+    Ok(Some(like_match(&text, &pattern)))
+}
+
+#[derive(Debug, PartialEq)]
+enum LikeToken {
+    Literal(char),
+    /// `_`
+    AnyChar,
+    /// `%`
+    AnyRun,
+}
+
+/// Tokenize a LIKE pattern; `\` escapes the next character
+fn parse_like(pattern: &str) -> Result<Vec<LikeToken>> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '\\' => LikeToken::Literal(chars.next().ok_or_else(|| {
+                Error::Config("LIKE pattern must not end with escape character".into())
+            })?),
+            '_' => LikeToken::AnyChar,
+            '%' => LikeToken::AnyRun,
+            c => LikeToken::Literal(c),
+        });
+    }
+    Ok(tokens)
+}
+
+fn like_match(text: &[char], pattern: &[LikeToken]) -> bool {
+    // Greedy matching with backtracking to the last `%`
+    let (mut t, mut p) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(LikeToken::AnyRun) => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(LikeToken::AnyChar) => {
+                t += 1;
+                p += 1;
+            }
+            Some(LikeToken::Literal(c)) if *c == text[t] => {
+                t += 1;
+                p += 1;
+            }
+            _ => match backtrack {
+                Some((run, start)) => {
+                    p = run + 1;
+                    t = start + 1;
+                    backtrack = Some((run, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|token| *token == LikeToken::AnyRun)
+}
+
+/// Elements of the JSON array at `field` as text (`None` for null elements)
+fn array(row: &Json, field: &Field) -> Result<Option<Vec<Option<String>>>> {
+    match lookup(row, field)? {
+        None => Ok(None),
+        Some(Json::Array(items)) => Ok(Some(
+            items
+                .iter()
+                .map(|item| (!item.is_null()).then(|| json_text(item)))
+                .collect(),
+        )),
+        Some(other) => Err(Error::Config(format!(
+            "malformed array literal: \"{}\"",
+            json_text(other)
+        ))),
+    }
+}
+
+/// Elements of an array operand; nested arrays are flattened like Postgres'
+/// multi-dimensional containment
+fn array_operand(values: &[Value]) -> Result<Vec<Option<String>>> {
+    let mut elements = Vec::new();
+    for value in values {
+        match value {
+            Value::Null => elements.push(None),
+            Value::String(s) => elements.push(Some(s.clone())),
+            Value::Number(n) => elements.push(Some(n.to_string())),
+            Value::Bool(b) => elements.push(Some(b.to_string())),
+            Value::Int(n) => elements.push(Some(n.to_string())),
+            Value::Decimal(s) => elements.push(Some(s.clone())),
+            Value::Uuid(_) | Value::Timestamp(_) | Value::Date(_) | Value::Json(_) => {
+                return Err(unsupported(
+                    "a typed array operand (compared as its text form)",
+                ))
+            }
+            Value::Array(items) => elements.extend(array_operand(items)?),
+            Value::FloatArray(_) => return Err(unsupported("a vector array operand")),
+            Value::RawSql(_) => return Err(unsupported("raw SQL")),
+        }
+    }
+    Ok(elements)
+}
+
+/// Array membership; NULL equals nothing
+fn contains(array: &[Option<String>], element: &Option<String>) -> bool {
+    element.is_some() && array.contains(element)
+}
+
+/// `array_length(field, 1)`, which is NULL for empty arrays
+fn length(row: &Json, field: &Field, accept: impl Fn(usize) -> bool) -> Result<Option<bool>> {
+    Ok(array(row, field)?
+        .filter(|items| !items.is_empty())
+        .map(|items| accept(items.len())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn field(name: &str) -> Field {
+        Field::JsonbField(name.to_string())
+    }
+
+    #[test]
+    fn test_comparisons_cast_to_value_type() {
+        let row = json!({"n": 10, "s": "10", "b": true, "t": "abc"});
+        assert!(WhereOperator::Gt(field("n"), Value::Number(9.5))
+            .matches(&row)
+            .unwrap());
+        // Text comparison of "10" and "9" is byte-wise
+        assert!(!WhereOperator::Gt(field("s"), Value::String("9".into()))
+            .matches(&row)
+            .unwrap());
+        assert!(WhereOperator::Gt(field("s"), Value::Number(9.0))
+            .matches(&row)
+            .unwrap());
+        assert!(WhereOperator::Eq(field("b"), Value::Bool(true))
+            .matches(&row)
+            .unwrap());
+        assert!(WhereOperator::Eq(field("n"), Value::String("10".into()))
+            .matches(&row)
+            .unwrap());
+        assert!(WhereOperator::Gt(field("t"), Value::Number(1.0))
+            .matches(&row)
+            .is_err());
+    }
+
+    #[test]
+    fn test_three_valued_logic() {
+        let row = json!({"a": null});
+        let gt = WhereOperator::Gt(field("a"), Value::Number(1.0));
+        assert!(!gt.matches(&row).unwrap());
+        assert!(!WhereOperator::Not(Box::new(gt.clone()))
+            .matches(&row)
+            .unwrap());
+        assert!(
+            WhereOperator::Or(vec![gt.clone(), WhereOperator::IsNull(field("a"), true)])
+                .matches(&row)
+                .unwrap()
+        );
+        assert!(!WhereOperator::Nin(field("a"), vec![Value::Number(1.0)])
+            .matches(&row)
+            .unwrap());
+
+        let row = json!({"a": 2});
+        let nin = WhereOperator::Nin(field("a"), vec![Value::Number(1.0), Value::Null]);
+        assert!(!nin.matches(&row).unwrap());
+        assert!(
+            WhereOperator::In(field("a"), vec![Value::Number(2.0), Value::Null])
+                .matches(&row)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_like_patterns() {
+        let text: Vec<char> = "50% off_now".chars().collect();
+        let matches = |p: &str| like_match(&text, &parse_like(p).unwrap());
+        assert!(matches("50\\% off\\_now"));
+        assert!(matches("%off%"));
+        assert!(matches("5_%w"));
+        assert!(!matches("50\\%"));
+        assert!(!matches("%x%"));
+        assert!(matches("%%%"));
+        assert!(parse_like("abc\\").is_err());
+
+        let row = json!({"s": "Hello World"});
+        assert!(WhereOperator::Icontains(field("s"), "WORLD".into())
+            .matches(&row)
+            .unwrap());
+        assert!(!WhereOperator::Contains(field("s"), "WORLD".into())
+            .matches(&row)
+            .unwrap());
+        assert!(WhereOperator::Startswith(field("s"), "He".into())
+            .matches(&row)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_array_operators() {
+        let row = json!({"tags": ["a", "b", null], "empty": []});
+        let tags = || field("tags");
+        assert!(
+            WhereOperator::ArrayContains(tags(), Value::String("a".into()))
+                .matches(&row)
+                .unwrap()
+        );
+        assert!(!WhereOperator::ArrayContains(tags(), Value::Null)
+            .matches(&row)
+            .unwrap());
+        assert!(!WhereOperator::ArrayContainedBy(
+            tags(),
+            Value::Array(vec![Value::String("a".into()), Value::String("b".into())])
+        )
+        .matches(&row)
+        .unwrap());
+        assert!(
+            WhereOperator::ArrayOverlaps(tags(), vec![Value::String("b".into())])
+                .matches(&row)
+                .unwrap()
+        );
+        assert!(WhereOperator::LenEq(tags(), 3).matches(&row).unwrap());
+        // array_length of an empty array is NULL
+        assert!(!WhereOperator::LenLt(field("empty"), 1)
+            .matches(&row)
+            .unwrap());
+        assert!(WhereOperator::LenEq(field("s"), 0)
+            .matches(&json!({"s": "x"}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_unsupported_in_memory() {
+        let row = json!({"ip": "127.0.0.1"});
+        let err = WhereOperator::IsLoopback(field("ip"))
+            .matches(&row)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("IsLoopback is unsupported in memory"));
+        assert!(
+            WhereOperator::Eq(Field::DirectColumn("id".into()), Value::Number(1.0))
+                .matches(&row)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let row = json!({
+            "id": 9_007_199_254_740_993_i64,
+            "price": "12.50",
+            "u": "A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11",
+            "at": "2024-01-02T10:00:00+02:00",
+            "day": "2024-01-02",
+            "meta": {"k": [1, 2]},
+            "nothing": null,
+        });
+        let check = |op: WhereOperator| op.matches(&row).unwrap();
+
+        assert!(check(WhereOperator::Eq(
+            field("id"),
+            Value::Int(9_007_199_254_740_993)
+        )));
+        assert!(!check(WhereOperator::Eq(
+            field("id"),
+            Value::Int(9_007_199_254_740_992)
+        )));
+        assert!(check(WhereOperator::Eq(
+            field("price"),
+            Value::Decimal("1.25e1".into())
+        )));
+        assert!(check(WhereOperator::Eq(
+            field("u"),
+            Value::Uuid("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".into())
+        )));
+        assert!(check(WhereOperator::Lt(
+            field("at"),
+            Value::Timestamp("2024-01-02T09:00:00Z".into())
+        )));
+        assert!(check(WhereOperator::Gte(
+            field("at"),
+            Value::Date("2024-01-02".into())
+        )));
+        assert!(check(WhereOperator::Eq(
+            field("day"),
+            Value::Date("2024-01-02".into())
+        )));
+        assert!(WhereOperator::Eq(field("price"), Value::Int(12))
+            .matches(&row)
+            .is_err());
+
+        // jsonb comparisons see JSON null, and order by type then size
+        assert!(check(WhereOperator::Eq(
+            field("nothing"),
+            Value::Json(json!(null))
+        )));
+        assert!(check(WhereOperator::Eq(
+            field("meta"),
+            Value::Json(json!({"k": [1.0, 2]}))
+        )));
+        assert!(check(WhereOperator::Gt(
+            field("meta"),
+            Value::Json(json!([1, 2, 3]))
+        )));
+        assert!(check(WhereOperator::Lt(
+            field("id"),
+            Value::Json(json!(true))
+        )));
+        assert!(!check(WhereOperator::Eq(
+            field("missing"),
+            Value::Json(json!(null))
+        )));
+        assert!(jsonb_cmp(&json!([]), &json!(null)).is_lt());
+        assert!(jsonb_cmp(&json!({"c": 1, "aa": 1}), &json!({"b": 1, "d": 1})).is_gt());
+    }
+
+    #[test]
+    fn test_jsonb_operators() {
+        let row = json!({
+            "tags": ["a", "b", 1],
+            "owner": {"team": {"id": 1, "name": "core"}},
+            "s": "k",
+            "n": 1,
+            "nothing": null,
+        });
+        let root = || Field::JsonbPath(vec![]);
+        let eval = |op: WhereOperator| op.evaluate(&row).unwrap();
+
+        // Containment wraps the value under the field path
+        assert_eq!(
+            eval(WhereOperator::JsonContains(
+                Field::JsonbPath(vec!["owner".into(), "team".into()]),
+                json!({"id": 1.0})
+            )),
+            Some(true)
+        );
+        assert_eq!(
+            eval(WhereOperator::JsonContains(
+                field("tags"),
+                json!(["b", "a", "a"])
+            )),
+            Some(true)
+        );
+        // Below the top level, an array does not contain a bare scalar
+        assert_eq!(
+            eval(WhereOperator::JsonContains(field("tags"), json!("a"))),
+            Some(false)
+        );
+        assert!(jsonb_contains(&json!(["a", "b"]), &json!("a"), true));
+        assert_eq!(
+            eval(WhereOperator::JsonContains(root(), json!({"owner": {}}))),
+            Some(true)
+        );
+        assert_eq!(
+            eval(WhereOperator::JsonContains(field("missing"), json!(null))),
+            Some(false)
+        );
+
+        // Key existence: object keys, string array elements, string scalars
+        assert_eq!(
+            eval(WhereOperator::HasKey(root(), "owner".into())),
+            Some(true)
+        );
+        assert_eq!(
+            eval(WhereOperator::HasKey(field("tags"), "a".into())),
+            Some(true)
+        );
+        assert_eq!(
+            eval(WhereOperator::HasKey(field("tags"), "1".into())),
+            Some(false)
+        );
+        assert_eq!(
+            eval(WhereOperator::HasKey(field("s"), "k".into())),
+            Some(true)
+        );
+        assert_eq!(
+            eval(WhereOperator::HasKey(field("n"), "1".into())),
+            Some(false)
+        );
+        assert_eq!(
+            eval(WhereOperator::HasKey(field("nothing"), "k".into())),
+            Some(false)
+        );
+        assert_eq!(
+            eval(WhereOperator::HasKey(field("missing"), "k".into())),
+            None
+        );
+        assert_eq!(
+            eval(WhereOperator::HasAnyKey(
+                field("tags"),
+                vec!["x".into(), "b".into()]
+            )),
+            Some(true)
+        );
+        assert_eq!(
+            eval(WhereOperator::HasAllKeys(
+                field("tags"),
+                vec!["x".into(), "b".into()]
+            )),
+            Some(false)
+        );
+
+        assert!(WhereOperator::JsonPathExists {
+            field: root(),
+            path: "$.tags".into(),
+            vars: None,
+        }
+        .matches(&row)
+        .is_err());
+    }
+}
@@ -3,6 +3,7 @@
 //! Provides type-safe representations of database fields and values
 //! to prevent SQL injection and improve API ergonomics.
 
+use super::scalar;
 use std::fmt;
 
 /// Represents a field reference in a WHERE clause or ORDER BY
@@ -76,9 +77,12 @@ impl Field {
     }
 
     /// Generate SQL for this field
+    ///
+    /// JSONB fields are extracted as text (`->>`); see `to_jsonb_sql()` for the
+    /// `jsonb` value.
     pub fn to_sql(&self) -> String {
         match self {
-            Field::JsonbField(name) => format!("(data->'{}')", name),
+            Field::JsonbField(name) => format!("(data->>'{}')", name),
             Field::DirectColumn(name) => name.clone(),
             Field::JsonbPath(path) => {
                 if path.is_empty() {
@@ -100,12 +104,28 @@ impl Field {
             }
         }
     }
+
+    /// Generate SQL for this field as `jsonb` (`->` at every step)
+    ///
+    /// Used where JSON types matter: comparisons with `Value::Json` and array
+    /// operators. Direct columns render as in `to_sql()`.
+    pub fn to_jsonb_sql(&self) -> String {
+        match self {
+            Field::JsonbField(name) => format!("(data->'{}')", name),
+            Field::DirectColumn(name) => name.clone(),
+            Field::JsonbPath(path) if path.is_empty() => "data".to_string(),
+            Field::JsonbPath(path) => {
+                let steps: Vec<String> = path.iter().map(|s| format!("->'{}'", s)).collect();
+                format!("(data{})", steps.concat())
+            }
+        }
+    }
 }
 
 impl fmt::Display for Field {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Field::JsonbField(name) => write!(f, "data->'{}'", name),
+            Field::JsonbField(name) => write!(f, "data->>'{}'", name),
             Field::DirectColumn(name) => write!(f, "{}", name),
             Field::JsonbPath(path) => {
                 write!(f, "data")?;
@@ -132,15 +152,42 @@ impl fmt::Display for Field {
 /// Value::Bool(true)
 /// Value::Null
 /// Value::Array(vec![Value::String("a".to_string()), Value::String("b".to_string())])
+/// Value::Int(9_007_199_254_740_993)
+/// Value::Timestamp("2024-01-02T10:00:00Z".to_string())
 /// ```
+///
+/// Each value determines the cast applied to JSONB text when comparing (see
+/// `sql_gen`). `Decimal`, `Uuid`, `Timestamp` and `Date` hold the value's text
+/// form, checked by `validate()`.
 #[derive(Debug, Clone)]
 pub enum Value {
     /// String value
     String(String),
 
-    /// Numeric value (f64 can represent i64, u64, f32 with precision)
+    /// Numeric value, compared as `numeric`
+    ///
+    /// Integers above 2^53 lose precision; use `Int` or `Decimal` for IDs and money.
     Number(f64),
 
+    /// 64-bit integer, compared as `bigint`
+    Int(i64),
+
+    /// Exact decimal in its text form (e.g. `"12.50"`), compared as `numeric`
+    Decimal(String),
+
+    /// UUID in its text form, compared as `uuid`
+    Uuid(String),
+
+    /// Timestamp in ISO 8601 form (e.g. `"2024-01-02T10:00:00Z"`), compared as
+    /// `timestamptz`
+    Timestamp(String),
+
+    /// Date in `YYYY-MM-DD` form, compared as `date`
+    Date(String),
+
+    /// JSON value, compared as `jsonb` with the field extracted by `->`
+    Json(serde_json::Value),
+
     /// Boolean value
     Bool(bool),
 
@@ -166,14 +213,43 @@ impl Value {
         matches!(self, Value::Null)
     }
 
+    /// Validate the text form of `Decimal`, `Uuid`, `Timestamp` and `Date` values
+    ///
+    /// Array elements are validated recursively.
+    pub fn validate(&self) -> Result<(), String> {
+        let (type_name, valid) = match self {
+            Value::Decimal(s) => ("numeric", scalar::parse_decimal(s).is_some()),
+            Value::Uuid(s) => ("uuid", scalar::parse_uuid(s).is_some()),
+            Value::Timestamp(s) => ("timestamptz", scalar::parse_timestamp(s).is_some()),
+            Value::Date(s) => ("date", scalar::parse_date(s).is_some()),
+            Value::Array(items) => return items.iter().try_for_each(Value::validate),
+            _ => return Ok(()),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid {} value: {}",
+                type_name,
+                self.to_sql_literal()
+            ))
+        }
+    }
+
     /// Convert value to SQL literal
     ///
     /// For parameterized queries, prefer using parameter placeholders ($1, $2, etc.)
     /// This is primarily for documentation and debugging.
     pub fn to_sql_literal(&self) -> String {
         match self {
-            Value::String(s) => format!("'{}'", s.replace('\'', "''")),
+            Value::String(s) => quote(s),
             Value::Number(n) => n.to_string(),
+            Value::Int(n) => n.to_string(),
+            Value::Decimal(s) => format!("{}::numeric", quote(s)),
+            Value::Uuid(s) => format!("{}::uuid", quote(s)),
+            Value::Timestamp(s) => format!("{}::timestamptz", quote(s)),
+            Value::Date(s) => format!("{}::date", quote(s)),
+            Value::Json(json) => format!("{}::jsonb", quote(&json.to_string())),
             Value::Bool(b) => b.to_string(),
             Value::Null => "NULL".to_string(),
             Value::Array(arr) => {
@@ -182,11 +258,100 @@ impl Value {
             }
             Value::FloatArray(arr) => {
                 let items: Vec<String> = arr.iter().map(|f| f.to_string()).collect();
-                format!("[{}]", items.join(", "))
+                format!("'[{}]'", items.join(","))
             }
             Value::RawSql(sql) => sql.clone(),
         }
     }
+
+    /// Text-format encoding of this value as an extended-protocol parameter
+    ///
+    /// Returns `None` for NULL. Arrays use the Postgres array literal syntax and
+    /// float arrays the pgvector syntax. Pair with `type_oid()` in `Parse`.
+    ///
+    /// # Errors
+    ///
+    /// Fails for `RawSql`, which cannot be bound, and for values rejected by
+    /// `validate()`.
+    pub fn to_param(&self) -> Result<Option<String>, String> {
+        self.validate()?;
+        Ok(match self {
+            Value::Null => None,
+            Value::String(s)
+            | Value::Decimal(s)
+            | Value::Uuid(s)
+            | Value::Timestamp(s)
+            | Value::Date(s) => Some(s.clone()),
+            Value::Number(n) if n.is_nan() => Some("NaN".to_string()),
+            Value::Number(n) if n.is_infinite() => {
+                Some(if *n > 0.0 { "Infinity" } else { "-Infinity" }.to_string())
+            }
+            Value::Number(n) => Some(n.to_string()),
+            Value::Int(n) => Some(n.to_string()),
+            Value::Bool(b) => Some(if *b { "t" } else { "f" }.to_string()),
+            Value::Json(json) => Some(json.to_string()),
+            Value::Array(items) => {
+                let mut elements = Vec::with_capacity(items.len());
+                for item in items {
+                    elements.push(match (item, item.to_param()?) {
+                        (_, None) => "NULL".to_string(),
+                        (Value::Array(_), Some(nested)) => nested,
+                        (_, Some(text)) => {
+                            format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+                        }
+                    });
+                }
+                Some(format!("{{{}}}", elements.join(",")))
+            }
+            Value::FloatArray(arr) => {
+                let items: Vec<String> = arr.iter().map(|f| f.to_string()).collect();
+                Some(format!("[{}]", items.join(",")))
+            }
+            Value::RawSql(_) => return Err("Raw SQL cannot be bound as a parameter".to_string()),
+        })
+    }
+
+    /// Postgres type OID to declare for this value as a parameter
+    ///
+    /// `0` leaves the type to the server: NULL, vectors (pgvector has no fixed
+    /// OID), raw SQL, and empty or mixed arrays.
+    pub fn type_oid(&self) -> u32 {
+        match self {
+            Value::String(_) => 25,      // text
+            Value::Number(_) => 1700,    // numeric
+            Value::Int(_) => 20,         // int8
+            Value::Decimal(_) => 1700,   // numeric
+            Value::Bool(_) => 16,        // bool
+            Value::Uuid(_) => 2950,      // uuid
+            Value::Timestamp(_) => 1184, // timestamptz
+            Value::Date(_) => 1082,      // date
+            Value::Json(_) => 3802,      // jsonb
+            Value::Null | Value::FloatArray(_) | Value::RawSql(_) => 0,
+            Value::Array(items) => {
+                let mut oids = items.iter().filter(|v| !v.is_null()).map(Value::type_oid);
+                let element = oids.next().unwrap_or(0);
+                if oids.any(|oid| oid != element) {
+                    return 0;
+                }
+                match element {
+                    25 => 1009,
+                    1700 => 1231,
+                    20 => 1016,
+                    16 => 1000,
+                    2950 => 2951,
+                    1184 => 1185,
+                    1082 => 1182,
+                    3802 => 3807,
+                    _ => 0,
+                }
+            }
+        }
+    }
+}
+
+/// Quote a string as a SQL literal
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
 }
 
 impl fmt::Display for Value {
@@ -248,7 +413,8 @@ mod tests {
     #[test]
     fn test_field_to_sql_jsonb() {
         let field = Field::JsonbField("name".to_string());
-        assert_eq!(field.to_sql(), "(data->'name')");
+        assert_eq!(field.to_sql(), "(data->>'name')");
+        assert_eq!(field.to_jsonb_sql(), "(data->'name')");
     }
 
     #[test]
@@ -261,6 +427,7 @@ mod tests {
     fn test_field_to_sql_path() {
         let field = Field::JsonbPath(vec!["user".to_string(), "name".to_string()]);
         assert_eq!(field.to_sql(), "(data->'user'->>'name')");
+        assert_eq!(field.to_jsonb_sql(), "(data->'user'->'name')");
     }
 
     #[test]
@@ -276,4 +443,58 @@ mod tests {
         let val = Value::String("O'Brien".to_string());
         assert_eq!(val.to_sql_literal(), "'O''Brien'");
     }
+
+    #[test]
+    fn test_typed_values() {
+        assert_eq!(Value::Int(i64::MAX).to_sql_literal(), "9223372036854775807");
+        assert_eq!(
+            Value::Decimal("12.50".into()).to_sql_literal(),
+            "'12.50'::numeric"
+        );
+        assert_eq!(
+            Value::Json(serde_json::json!({"a": "it's"})).to_sql_literal(),
+            r#"'{"a":"it''s"}'::jsonb"#
+        );
+
+        assert!(Value::Uuid("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".into())
+            .validate()
+            .is_ok());
+        assert!(Value::Uuid("a0eebc99".into()).validate().is_err());
+        assert!(Value::Decimal("1; DROP".into()).validate().is_err());
+        assert!(Value::Date("2024-02-30".into()).validate().is_err());
+        assert!(Value::Array(vec![Value::Timestamp("yesterday".into())])
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_param_encoding() {
+        assert_eq!(Value::Null.to_param(), Ok(None));
+        assert_eq!(Value::Bool(false).to_param(), Ok(Some("f".into())));
+        assert_eq!(Value::Number(f64::NAN).to_param(), Ok(Some("NaN".into())));
+        assert_eq!(
+            Value::Array(vec![
+                Value::String(r#"a "quoted" \ b"#.into()),
+                Value::Null,
+                Value::Array(vec![Value::Int(1)]),
+            ])
+            .to_param(),
+            Ok(Some(r#"{"a \"quoted\" \\ b",NULL,{"1"}}"#.into()))
+        );
+        assert_eq!(
+            Value::FloatArray(vec![0.5, 1.0]).to_param(),
+            Ok(Some("[0.5,1]".into()))
+        );
+        assert!(Value::RawSql("now()".into()).to_param().is_err());
+
+        assert_eq!(Value::Uuid(String::new()).type_oid(), 2950);
+        assert_eq!(
+            Value::Array(vec![Value::Null, Value::String("a".into())]).type_oid(),
+            1009
+        );
+        assert_eq!(
+            Value::Array(vec![Value::Int(1), Value::String("a".into())]).type_oid(),
+            0
+        );
+    }
 }
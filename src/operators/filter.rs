@@ -0,0 +1,630 @@
+//! JSON filter documents
+//!
+//! Parses fraiseql/GraphQL-style filter objects into `WhereOperator` trees:
+//!
+//! ```json
+//! {
+//!   "status": { "eq": "active" },
+//!   "tags": { "overlaps": ["rust", "postgres"] },
+//!   "author": { "name": { "startswith": "A" } },
+//!   "or": [ { "score": { "gte": 10 } }, { "pinned": { "eq": true } } ]
+//! }
+//! ```
+//!
+//! - Keys are field names, except `and` (array), `or` (array) and `not` (object).
+//! - A field maps to an object of operators, or to nested fields (a JSONB path).
+//! - Several keys in one object are combined with AND.
+//! - Operator keys are the `WhereOperator::name()`s, matched ignoring case and
+//!   underscores (`eq`, `arrayContains`, `is_null`, `isIPv4`, ...), plus the
+//!   aliases `overlaps` and `containedBy`.
+//!
+//! Errors report the JSON path of the offending element, e.g. `$.or[1].score.gte`.
+
+use super::text_search::validate_language;
+use super::{Field, Value, WhereOperator};
+use crate::{Error, Result};
+use serde::de::{Deserialize, Deserializer};
+use serde_json::{Map, Value as Json};
+
+impl WhereOperator {
+    /// Parse a JSON filter document (see the `filter` module docs)
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let op = WhereOperator::from_filter_json(&json!({
+    ///     "status": {"eq": "active"},
+    ///     "or": [{"score": {"gt": 10}}, {"tags": {"overlaps": ["a", "b"]}}],
+    /// }))?;
+    /// ```
+    pub fn from_filter_json(filter: &Json) -> Result<Self> {
+        parse_filter(filter, "$")
+    }
+}
+
+impl<'de> Deserialize<'de> for WhereOperator {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let filter = Json::deserialize(deserializer)?;
+        WhereOperator::from_filter_json(&filter).map_err(serde::de::Error::custom)
+    }
+}
+
+fn invalid(path: &str, message: impl Into<String>) -> Error {
+    Error::InvalidFilter {
+        path: path.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Combine conditions with AND, unwrapping a single condition
+fn all_of(mut ops: Vec<WhereOperator>) -> WhereOperator {
+    if ops.len() == 1 {
+        ops.remove(0)
+    } else {
+        WhereOperator::And(ops)
+    }
+}
+
+/// Parse a filter object (field names and logical operators)
+fn parse_filter(filter: &Json, path: &str) -> Result<WhereOperator> {
+    let object = filter
+        .as_object()
+        .ok_or_else(|| invalid(path, "expected a filter object"))?;
+    let mut ops = Vec::new();
+    for (key, value) in object {
+        let at = format!("{}.{}", path, key);
+        match key.as_str() {
+            "and" | "or" => {
+                let items = value
+                    .as_array()
+                    .ok_or_else(|| invalid(&at, "expected an array of filters"))?;
+                let parsed = items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| parse_filter(item, &format!("{}[{}]", at, i)))
+                    .collect::<Result<Vec<_>>>()?;
+                ops.push(if key == "and" {
+                    WhereOperator::And(parsed)
+                } else {
+                    WhereOperator::Or(parsed)
+                });
+            }
+            "not" => ops.push(WhereOperator::Not(Box::new(parse_filter(value, &at)?))),
+            _ => ops.push(parse_field(std::slice::from_ref(key), value, &at)?),
+        }
+    }
+    Ok(all_of(ops))
+}
+
+/// Parse the conditions on one field, or descend into nested fields
+fn parse_field(path_segments: &[String], conditions: &Json, path: &str) -> Result<WhereOperator> {
+    let field = match path_segments {
+        [name] => Field::JsonbField(name.clone()),
+        _ => Field::JsonbPath(path_segments.to_vec()),
+    };
+    field.validate().map_err(|e| invalid(path, e))?;
+
+    let object = conditions
+        .as_object()
+        .ok_or_else(|| invalid(path, "expected an object of operators or nested fields"))?;
+    if object.is_empty() {
+        return Err(invalid(path, "expected at least one operator"));
+    }
+    let operators = object.keys().filter(|k| operator_kind(k).is_some()).count();
+    let mut ops = Vec::new();
+    if operators == 0 {
+        for (key, value) in object {
+            let mut nested = path_segments.to_vec();
+            nested.push(key.clone());
+            ops.push(parse_field(&nested, value, &format!("{}.{}", path, key))?);
+        }
+    } else if operators == object.len() {
+        for (key, operand) in object {
+            let kind = operator_kind(key).expect("counted as operator");
+            ops.push(parse_operator(
+                kind,
+                &field,
+                operand,
+                &format!("{}.{}", path, key),
+            )?);
+        }
+    } else {
+        let unknown = object.keys().find(|k| operator_kind(k).is_none());
+        return Err(invalid(
+            path,
+            format!(
+                "unknown operator '{}' (operators and nested fields cannot be mixed)",
+                unknown.map(String::as_str).unwrap_or_default()
+            ),
+        ));
+    }
+    Ok(all_of(ops))
+}
+
+/// Every operator that applies to a field, by `WhereOperator::name()`
+const OPERATOR_NAMES: &[&str] = &[
+    "Eq",
+    "Neq",
+    "Gt",
+    "Gte",
+    "Lt",
+    "Lte",
+    "In",
+    "Nin",
+    "Contains",
+    "ArrayContains",
+    "ArrayContainedBy",
+    "ArrayOverlaps",
+    "LenEq",
+    "LenGt",
+    "LenGte",
+    "LenLt",
+    "LenLte",
+    "Icontains",
+    "Startswith",
+    "Endswith",
+    "Like",
+    "Ilike",
+    "IsNull",
+    "L2Distance",
+    "CosineDistance",
+    "InnerProduct",
+    "JaccardDistance",
+    "Matches",
+    "PlainQuery",
+    "PhraseQuery",
+    "WebsearchQuery",
+    "IsIPv4",
+    "IsIPv6",
+    "IsPrivate",
+    "IsLoopback",
+    "InSubnet",
+    "ContainsSubnet",
+    "ContainsIP",
+    "IPRangeOverlap",
+    "JsonContains",
+    "HasKey",
+    "HasAnyKey",
+    "HasAllKeys",
+    "JsonPathExists",
+    "JsonPathMatch",
+];
+
+/// Resolve an operator key to its `WhereOperator::name()`
+fn operator_kind(key: &str) -> Option<&'static str> {
+    let normalized: String = key
+        .chars()
+        .filter(|&c| c != '_')
+        .flat_map(char::to_lowercase)
+        .collect();
+    match normalized.as_str() {
+        "overlaps" => return Some("ArrayOverlaps"),
+        "containedby" => return Some("ArrayContainedBy"),
+        _ => {}
+    }
+    OPERATOR_NAMES
+        .iter()
+        .copied()
+        .find(|name| name.to_lowercase() == normalized)
+}
+
+fn parse_operator(kind: &str, field: &Field, operand: &Json, path: &str) -> Result<WhereOperator> {
+    let f = field.clone();
+    let op = match kind {
+        "Eq" => WhereOperator::Eq(f, scalar(operand, path)?),
+        "Neq" => WhereOperator::Neq(f, scalar(operand, path)?),
+        "Gt" => WhereOperator::Gt(f, scalar(operand, path)?),
+        "Gte" => WhereOperator::Gte(f, scalar(operand, path)?),
+        "Lt" => WhereOperator::Lt(f, scalar(operand, path)?),
+        "Lte" => WhereOperator::Lte(f, scalar(operand, path)?),
+        "In" => WhereOperator::In(f, scalars(operand, path)?),
+        "Nin" => WhereOperator::Nin(f, scalars(operand, path)?),
+        "Contains" => WhereOperator::Contains(f, string(operand, path)?),
+        "ArrayContains" => WhereOperator::ArrayContains(f, scalar(operand, path)?),
+        "ArrayContainedBy" => WhereOperator::ArrayContainedBy(f, scalar(operand, path)?),
+        "ArrayOverlaps" => WhereOperator::ArrayOverlaps(f, scalars(operand, path)?),
+        "LenEq" => WhereOperator::LenEq(f, length(operand, path)?),
+        "LenGt" => WhereOperator::LenGt(f, length(operand, path)?),
+        "LenGte" => WhereOperator::LenGte(f, length(operand, path)?),
+        "LenLt" => WhereOperator::LenLt(f, length(operand, path)?),
+        "LenLte" => WhereOperator::LenLte(f, length(operand, path)?),
+        "Icontains" => WhereOperator::Icontains(f, string(operand, path)?),
+        "Startswith" => WhereOperator::Startswith(f, string(operand, path)?),
+        "Endswith" => WhereOperator::Endswith(f, string(operand, path)?),
+        "Like" => WhereOperator::Like(f, string(operand, path)?),
+        "Ilike" => WhereOperator::Ilike(f, string(operand, path)?),
+        "IsNull" => WhereOperator::IsNull(f, boolean(operand, path)?),
+        "L2Distance" | "CosineDistance" | "InnerProduct" => {
+            let object = object(operand, path, &["vector", "threshold"])?;
+            let vector = floats(&object["vector"], &format!("{}.vector", path))?;
+            let threshold = threshold(&object["threshold"], &format!("{}.threshold", path))?;
+            match kind {
+                "L2Distance" => WhereOperator::L2Distance {
+                    field: f,
+                    vector,
+                    threshold,
+                },
+                "CosineDistance" => WhereOperator::CosineDistance {
+                    field: f,
+                    vector,
+                    threshold,
+                },
+                _ => WhereOperator::InnerProduct {
+                    field: f,
+                    vector,
+                    threshold,
+                },
+            }
+        }
+        "JaccardDistance" => {
+            let object = object(operand, path, &["set", "threshold"])?;
+            let set = strings(&object["set"], &format!("{}.set", path))?;
+            let threshold = threshold(&object["threshold"], &format!("{}.threshold", path))?;
+            WhereOperator::JaccardDistance {
+                field: f,
+                set,
+                threshold,
+            }
+        }
+        "Matches" | "PhraseQuery" | "WebsearchQuery" => {
+            let (query, language) = text_query(operand, path)?;
+            match kind {
+                "Matches" => WhereOperator::Matches {
+                    field: f,
+                    query,
+                    language,
+                },
+                "PhraseQuery" => WhereOperator::PhraseQuery {
+                    field: f,
+                    query,
+                    language,
+                },
+                _ => WhereOperator::WebsearchQuery {
+                    field: f,
+                    query,
+                    language,
+                },
+            }
+        }
+        "PlainQuery" => WhereOperator::PlainQuery {
+            field: f,
+            query: string(operand, path)?,
+        },
+        "IsIPv4" | "IsIPv6" | "IsPrivate" | "IsLoopback" => {
+            let op = match kind {
+                "IsIPv4" => WhereOperator::IsIPv4(f),
+                "IsIPv6" => WhereOperator::IsIPv6(f),
+                "IsPrivate" => WhereOperator::IsPrivate(f),
+                _ => WhereOperator::IsLoopback(f),
+            };
+            if boolean(operand, path)? {
+                op
+            } else {
+                WhereOperator::Not(Box::new(op))
+            }
+        }
+        "InSubnet" => WhereOperator::InSubnet {
+            field: f,
+            subnet: string(operand, path)?,
+        },
+        "ContainsSubnet" => WhereOperator::ContainsSubnet {
+            field: f,
+            subnet: string(operand, path)?,
+        },
+        "ContainsIP" => WhereOperator::ContainsIP {
+            field: f,
+            ip: string(operand, path)?,
+        },
+        "IPRangeOverlap" => WhereOperator::IPRangeOverlap {
+            field: f,
+            range: string(operand, path)?,
+        },
+        "JsonContains" => WhereOperator::JsonContains(f, operand.clone()),
+        "HasKey" => WhereOperator::HasKey(f, string(operand, path)?),
+        "HasAnyKey" | "HasAllKeys" => {
+            let keys = strings(operand, path)?;
+            if keys.is_empty() {
+                return Err(invalid(path, "expected at least one key"));
+            }
+            match kind {
+                "HasAnyKey" => WhereOperator::HasAnyKey(f, keys),
+                _ => WhereOperator::HasAllKeys(f, keys),
+            }
+        }
+        "JsonPathExists" | "JsonPathMatch" => {
+            let (json_path, vars) = json_path(operand, path)?;
+            match kind {
+                "JsonPathExists" => WhereOperator::JsonPathExists {
+                    field: f,
+                    path: json_path,
+                    vars,
+                },
+                _ => WhereOperator::JsonPathMatch {
+                    field: f,
+                    path: json_path,
+                    vars,
+                },
+            }
+        }
+        other => unreachable!("operator {} missing from parse_operator", other),
+    };
+    Ok(op)
+}
+
+fn scalar(operand: &Json, path: &str) -> Result<Value> {
+    match operand {
+        Json::String(s) => Ok(Value::String(s.clone())),
+        // Exact, so that bigint IDs and money keep every digit
+        Json::Number(n) => Ok(Value::Decimal(n.to_string())),
+        Json::Bool(b) => Ok(Value::Bool(*b)),
+        Json::Null => Ok(Value::Null),
+        Json::Array(_) | Json::Object(_) => {
+            Err(invalid(path, "expected a string, number, boolean or null"))
+        }
+    }
+}
+
+fn scalars(operand: &Json, path: &str) -> Result<Vec<Value>> {
+    operand
+        .as_array()
+        .ok_or_else(|| invalid(path, "expected an array"))?
+        .iter()
+        .enumerate()
+        .map(|(i, item)| scalar(item, &format!("{}[{}]", path, i)))
+        .collect()
+}
+
+fn string(operand: &Json, path: &str) -> Result<String> {
+    operand
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| invalid(path, "expected a string"))
+}
+
+fn strings(operand: &Json, path: &str) -> Result<Vec<String>> {
+    operand
+        .as_array()
+        .ok_or_else(|| invalid(path, "expected an array of strings"))?
+        .iter()
+        .enumerate()
+        .map(|(i, item)| string(item, &format!("{}[{}]", path, i)))
+        .collect()
+}
+
+fn boolean(operand: &Json, path: &str) -> Result<bool> {
+    operand
+        .as_bool()
+        .ok_or_else(|| invalid(path, "expected a boolean"))
+}
+
+fn length(operand: &Json, path: &str) -> Result<usize> {
+    operand
+        .as_u64()
+        .and_then(|n| usize::try_from(n).ok())
+        .ok_or_else(|| invalid(path, "expected a non-negative integer"))
+}
+
+fn threshold(operand: &Json, path: &str) -> Result<f32> {
+    operand
+        .as_f64()
+        .map(|n| n as f32)
+        .ok_or_else(|| invalid(path, "expected a number"))
+}
+
+fn floats(operand: &Json, path: &str) -> Result<Vec<f32>> {
+    operand
+        .as_array()
+        .ok_or_else(|| invalid(path, "expected an array of numbers"))?
+        .iter()
+        .enumerate()
+        .map(|(i, item)| threshold(item, &format!("{}[{}]", path, i)))
+        .collect()
+}
+
+/// An object with exactly the `required` keys
+fn object<'a>(operand: &'a Json, path: &str, required: &[&str]) -> Result<&'a Map<String, Json>> {
+    let object = operand.as_object().ok_or_else(|| {
+        invalid(
+            path,
+            format!("expected an object with {}", required.join(" and ")),
+        )
+    })?;
+    if let Some(missing) = required.iter().find(|k| !object.contains_key(**k)) {
+        return Err(invalid(path, format!("missing '{}'", missing)));
+    }
+    if let Some(extra) = object.keys().find(|k| !required.contains(&k.as_str())) {
+        return Err(invalid(&format!("{}.{}", path, extra), "unexpected key"));
+    }
+    Ok(object)
+}
+
+/// `"query"` or `{"query": "...", "language": "..."}`
+fn text_query(operand: &Json, path: &str) -> Result<(String, Option<String>)> {
+    if let Some(query) = operand.as_str() {
+        return Ok((query.to_string(), None));
+    }
+    let object = operand
+        .as_object()
+        .ok_or_else(|| invalid(path, "expected a string or an object with query"))?;
+    if let Some(extra) = object.keys().find(|k| *k != "query" && *k != "language") {
+        return Err(invalid(&format!("{}.{}", path, extra), "unexpected key"));
+    }
+    let query = string(
+        object.get("query").unwrap_or(&Json::Null),
+        &format!("{}.query", path),
+    )?;
+    let language = match object.get("language") {
+        None | Some(Json::Null) => None,
+        Some(language) => {
+            let lang_path = format!("{}.language", path);
+            let language = string(language, &lang_path)?;
+            validate_language(&language).map_err(|e| invalid(&lang_path, e))?;
+            Some(language)
+        }
+    };
+    Ok((query, language))
+}
+
+/// `"path"` or `{"path": "...", "vars": {...}}`
+fn json_path(operand: &Json, path: &str) -> Result<(String, Option<Json>)> {
+    if let Some(json_path) = operand.as_str() {
+        return Ok((json_path.to_string(), None));
+    }
+    let object = operand
+        .as_object()
+        .ok_or_else(|| invalid(path, "expected a string or an object with path"))?;
+    if let Some(extra) = object.keys().find(|k| *k != "path" && *k != "vars") {
+        return Err(invalid(&format!("{}.{}", path, extra), "unexpected key"));
+    }
+    let json_path = string(
+        object.get("path").unwrap_or(&Json::Null),
+        &format!("{}.path", path),
+    )?;
+    let vars = match object.get("vars") {
+        None | Some(Json::Null) => None,
+        Some(vars @ Json::Object(_)) => Some(vars.clone()),
+        Some(_) => return Err(invalid(&format!("{}.vars", path), "expected an object")),
+    };
+    Ok((json_path, vars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operators::generate_where_operator_sql;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn sql(op: &WhereOperator) -> String {
+        let mut index = 0;
+        let mut params = HashMap::new();
+        generate_where_operator_sql(op, &mut index, &mut params).unwrap()
+    }
+
+    fn error_path(filter: Json) -> String {
+        match WhereOperator::from_filter_json(&filter) {
+            Err(Error::InvalidFilter { path, .. }) => path,
+            other => panic!("expected InvalidFilter, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_filter_document() {
+        let op = WhereOperator::from_filter_json(&json!({
+            "status": {"eq": "active"},
+            "tags": {"overlaps": ["a", "b"]},
+            "or": [{"score": {"gte": 10, "lt": 20}}, {"author": {"name": {"is_null": true}}}],
+        }))
+        .unwrap();
+        assert_eq!(op.name(), "And");
+        assert_eq!(
+            sql(&op),
+            "((((data->>'score')::numeric >= $1) AND ((data->>'score')::numeric < $2)) \
+             OR ((data->'author'->>'name') IS NULL)) \
+             AND ((data->>'status') = $3) \
+             AND ((CASE WHEN jsonb_typeof((data->'tags')) <> 'null' \
+             THEN ARRAY(SELECT jsonb_array_elements_text((data->'tags'))) END) && ARRAY[$4, $5]::text[])"
+        );
+    }
+
+    #[test]
+    fn test_every_operator_name_parses() {
+        let operands: HashMap<&str, Json> = [
+            ("In", json!([1])),
+            ("Nin", json!([1])),
+            ("ArrayOverlaps", json!(["a"])),
+            ("LenEq", json!(1)),
+            ("LenGt", json!(1)),
+            ("LenGte", json!(1)),
+            ("LenLt", json!(1)),
+            ("LenLte", json!(1)),
+            ("IsNull", json!(false)),
+            ("L2Distance", json!({"vector": [0.1], "threshold": 0.5})),
+            ("CosineDistance", json!({"vector": [0.1], "threshold": 0.5})),
+            ("InnerProduct", json!({"vector": [0.1], "threshold": 0.5})),
+            ("JaccardDistance", json!({"set": ["a"], "threshold": 0.5})),
+            ("IsIPv4", json!(true)),
+            ("IsIPv6", json!(true)),
+            ("IsPrivate", json!(true)),
+            ("IsLoopback", json!(true)),
+            ("HasAnyKey", json!(["a"])),
+            ("HasAllKeys", json!(["a"])),
+        ]
+        .into_iter()
+        .collect();
+        for name in OPERATOR_NAMES {
+            let operand = operands.get(name).cloned().unwrap_or(json!("x"));
+            let camel = name[..1].to_lowercase() + &name[1..];
+            let op = WhereOperator::from_filter_json(&json!({ "f": { camel: operand } }))
+                .unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert_eq!(op.name(), *name);
+        }
+    }
+
+    #[test]
+    fn test_operator_variants() {
+        let op = WhereOperator::from_filter_json(&json!({
+            "body": {"websearchQuery": {"query": "rust -go", "language": "simple"}},
+        }))
+        .unwrap();
+        assert_eq!(
+            sql(&op),
+            "(data->>'body') @@ websearch_to_tsquery('simple', $1)"
+        );
+
+        let op = WhereOperator::from_filter_json(&json!({
+            "owner": {"jsonContains": {"team": "core"}},
+            "meta": {"jsonPathMatch": {"path": "$.score > $min", "vars": {"min": 3}}},
+        }))
+        .unwrap();
+        assert_eq!(
+            sql(&op),
+            "(jsonb_path_match((data->'meta'), $1::jsonpath, $2::jsonb, true)) AND (data @> $3::jsonb)"
+        );
+
+        let op = WhereOperator::from_filter_json(&json!({"ip": {"isPrivate": false}})).unwrap();
+        assert_eq!(op.name(), "Not");
+
+        let op = WhereOperator::from_filter_json(&json!({"not": {"n": {"eq": null}}})).unwrap();
+        assert_eq!(sql(&op), "NOT ((data->>'n') IS NULL)");
+
+        let op = WhereOperator::from_filter_json(&json!({})).unwrap();
+        assert_eq!(sql(&op), "TRUE");
+    }
+
+    #[test]
+    fn test_errors_are_path_qualified() {
+        assert_eq!(
+            error_path(json!({"or": [{"a": {"eq": 1}}, {"score": {"gte": [1]}}]})),
+            "$.or[1].score.gte"
+        );
+        assert_eq!(error_path(json!({"a": {"eq": 1, "bogus": 2}})), "$.a");
+        assert_eq!(error_path(json!({"bad-name": {"eq": 1}})), "$.bad-name");
+        assert_eq!(error_path(json!({"a": {"b'c": {"eq": 1}}})), "$.a.b'c");
+        assert_eq!(error_path(json!({"and": {"a": {"eq": 1}}})), "$.and");
+        assert_eq!(
+            error_path(json!({"v": {"l2Distance": {"vector": [1]}}})),
+            "$.v.l2Distance"
+        );
+        assert_eq!(
+            error_path(json!({"t": {"matches": {"query": "x", "language": "en'; --"}}})),
+            "$.t.matches.language"
+        );
+        assert_eq!(error_path(json!({"a": {"lenGt": -1}})), "$.a.lenGt");
+        assert_eq!(error_path(json!({"a": {"hasAnyKey": []}})), "$.a.hasAnyKey");
+        assert_eq!(
+            error_path(json!({"a": {"jsonPathExists": {"path": "$.x", "vars": [1]}}})),
+            "$.a.jsonPathExists.vars"
+        );
+        assert_eq!(error_path(json!([])), "$");
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let op: WhereOperator = serde_json::from_str(r#"{"id": {"in": [1, 2]}}"#).unwrap();
+        assert_eq!(sql(&op), "(data->>'id')::numeric IN ($1, $2)");
+
+        let err = serde_json::from_str::<WhereOperator>(r#"{"id": {"in": 3}}"#).unwrap_err();
+        assert!(err.to_string().contains("$.id.in"), "{}", err);
+    }
+}
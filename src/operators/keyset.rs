@@ -0,0 +1,523 @@
+//! Keyset (seek) pagination
+//!
+//! Instead of `OFFSET n`, a page starts right after (or before) the row a cursor
+//! points to, using the same ORDER BY keys:
+//!
+//! ```text
+//! WHERE ((data->'created_at'), (data->'id')) < ($1, $2)
+//! ```
+//!
+//! The cursor's key values are bound as `jsonb` parameters, never written into
+//! the SQL, so every page of a query runs the same statement.
+//!
+//! The cost of a page does not grow with its depth, and pages stay stable when
+//! rows are inserted before the cursor. Rows whose key is NULL are placed
+//! according to each clause's `NullsHandling` (Postgres defaults: `NULLS LAST`
+//! for `ASC`, `NULLS FIRST` for `DESC`).
+//!
+//! Keys are compared exactly as `OrderByClause::to_sql()` sorts them: as
+//! `jsonb`, or as `->>` text in the clause's collation when it has one (the
+//! cursor value is then read back as text with `$n #>> '{}'`). The ordering
+//! should end with a unique key (e.g. `id`) so that every row has a distinct
+//! position.
+
+use super::order_by::{FieldSource, NullsHandling, OrderByClause, SortOrder};
+use crate::{Error, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// Opaque position in a keyset-paginated result
+///
+/// Holds the sort key values of one row. Encoded as a URL-safe token suitable
+/// for Relay-style `after`/`before` arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeysetCursor {
+    /// Field names of the ordering the cursor was taken from
+    #[serde(rename = "k")]
+    keys: Vec<String>,
+    /// JSON text of each key value; `None` when the key is missing or SQL NULL
+    #[serde(rename = "v")]
+    values: Vec<Option<String>>,
+}
+
+impl KeysetCursor {
+    /// Cursor pointing at `row` under `clauses`
+    pub fn from_row(clauses: &[OrderByClause], row: &Value) -> Self {
+        Self {
+            keys: clauses.iter().map(|c| c.field.clone()).collect(),
+            values: clauses
+                .iter()
+                .map(|c| row.get(&c.field).map(Value::to_string))
+                .collect(),
+        }
+    }
+
+    /// Encode as an opaque URL-safe token
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("cursor serializes to JSON");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Decode a token produced by `encode()`
+    pub fn decode(token: &str) -> Result<Self> {
+        let invalid = || Error::Config(format!("invalid keyset cursor '{}'", token));
+        let json = URL_SAFE_NO_PAD.decode(token).map_err(|_| invalid())?;
+        let cursor: Self = serde_json::from_slice(&json).map_err(|_| invalid())?;
+        let well_formed = cursor.keys.len() == cursor.values.len()
+            && cursor
+                .values
+                .iter()
+                .flatten()
+                .all(|text| serde_json::from_str::<Value>(text).is_ok());
+        if !well_formed {
+            return Err(invalid());
+        }
+        Ok(cursor)
+    }
+
+    /// Key values of the row, in ordering order (`Value::Null` for SQL NULL)
+    pub fn values(&self) -> Vec<Value> {
+        self.values
+            .iter()
+            .map(|v| {
+                v.as_deref()
+                    .and_then(|text| serde_json::from_str(text).ok())
+                    .unwrap_or(Value::Null)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for KeysetCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+impl FromStr for KeysetCursor {
+    type Err = Error;
+
+    fn from_str(token: &str) -> Result<Self> {
+        Self::decode(token)
+    }
+}
+
+/// Which side of the cursor a page lies on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SeekDirection {
+    /// Rows after the cursor
+    After,
+    /// Rows before the cursor
+    Before,
+}
+
+/// Check that `clauses` can drive keyset pagination
+pub(crate) fn validate_keyset_order(clauses: &[OrderByClause]) -> Result<()> {
+    if clauses.is_empty() {
+        return Err(Error::Config(
+            "keyset pagination requires at least one ORDER BY clause".into(),
+        ));
+    }
+    for clause in clauses {
+        clause.validate().map_err(Error::Config)?;
+        if clause.is_computed() {
+            return Err(Error::Config(format!(
+                "keyset pagination cannot seek on the distance or rank of '{}'",
+                clause.field
+            )));
+        }
+        if clause.field_source == FieldSource::DirectColumn {
+            return Err(Error::Config(format!(
+                "keyset pagination requires JSONB payload keys; direct column '{}' \
+                 cannot be read back from streamed rows",
+                clause.field
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether cursors can be taken from rows streamed under `clauses`
+pub(crate) fn tracks_cursor(clauses: &[OrderByClause]) -> bool {
+    !clauses.is_empty()
+        && clauses
+            .iter()
+            .all(|c| c.field_source == FieldSource::JsonbPayload && !c.is_computed())
+}
+
+/// The same ordering, reversed (direction and NULL placement)
+pub(crate) fn reversed(clauses: &[OrderByClause]) -> Vec<OrderByClause> {
+    clauses
+        .iter()
+        .map(|clause| {
+            let nulls = if nulls_last(clause) {
+                NullsHandling::First
+            } else {
+                NullsHandling::Last
+            };
+            let direction = match clause.direction {
+                SortOrder::Asc => SortOrder::Desc,
+                SortOrder::Desc => SortOrder::Asc,
+            };
+            OrderByClause {
+                direction,
+                nulls_handling: Some(nulls),
+                ..clause.clone()
+            }
+        })
+        .collect()
+}
+
+/// WHERE predicate selecting the rows on `direction`'s side of `cursor`, and
+/// the cursor values bound to its placeholders (numbered from `first_param + 1`)
+///
+/// All keys sharing one direction with a non-NULL cursor compile to a single
+/// row-value comparison; otherwise the lexicographic comparison is expanded.
+/// Rows with a NULL key that sorts past the cursor are added explicitly, since
+/// comparisons with NULL never hold.
+pub(crate) fn seek_predicate(
+    clauses: &[OrderByClause],
+    cursor: &KeysetCursor,
+    direction: SeekDirection,
+    first_param: usize,
+) -> Result<(String, Vec<super::Value>)> {
+    let fields: Vec<&str> = clauses.iter().map(|c| c.field.as_str()).collect();
+    if cursor.keys != fields {
+        return Err(Error::Config(format!(
+            "keyset cursor was taken for ordering ({}) but the query orders by ({})",
+            cursor.keys.join(", "),
+            fields.join(", ")
+        )));
+    }
+
+    let clauses = match direction {
+        SeekDirection::After => clauses.to_vec(),
+        SeekDirection::Before => reversed(clauses),
+    };
+    let mut params = Vec::new();
+    let mut keys = Vec::with_capacity(clauses.len());
+    for (clause, value) in clauses.iter().zip(&cursor.values) {
+        let json = match value {
+            Some(text) => Some(
+                serde_json::from_str::<Value>(text)
+                    .map_err(|_| Error::Config("invalid keyset cursor value".into()))?,
+            ),
+            None => None,
+        };
+        let placeholder = match json {
+            // `->>` turns a JSON null into SQL NULL
+            Some(Value::Null) if clause.collation.is_some() => None,
+            Some(json) => {
+                params.push(super::Value::Json(json));
+                let placeholder = format!("${}", first_param + params.len());
+                Some(match clause.collation {
+                    Some(_) => format!("({} #>> '{{}}')", placeholder),
+                    None => placeholder,
+                })
+            }
+            None => None,
+        };
+        keys.push(Key::new(clause, placeholder));
+    }
+
+    let mut terms = Vec::new();
+    let uniform = keys.iter().all(|k| k.literal.is_some())
+        && keys.iter().all(|k| k.operator == keys[0].operator);
+    if uniform {
+        terms.push(if keys.len() == 1 {
+            format!(
+                "{} {} {}",
+                keys[0].expr,
+                keys[0].operator,
+                literal(&keys[0])
+            )
+        } else {
+            format!(
+                "({}) {} ({})",
+                keys.iter()
+                    .map(|k| k.expr.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                keys[0].operator,
+                keys.iter().map(literal).collect::<Vec<_>>().join(", ")
+            )
+        });
+    } else {
+        for (i, key) in keys.iter().enumerate() {
+            let beyond = match &key.literal {
+                Some(lit) => format!("{} {} {}", key.expr, key.operator, lit),
+                None if !key.nulls_last => format!("{} IS NOT NULL", key.expr),
+                // Nothing sorts after a NULL placed last
+                None => continue,
+            };
+            terms.push(conjunction(&keys[..i], beyond));
+        }
+    }
+    for (i, key) in keys.iter().enumerate() {
+        if key.literal.is_some() && key.nulls_last {
+            terms.push(conjunction(&keys[..i], format!("{} IS NULL", key.expr)));
+        }
+    }
+
+    let predicate = match terms.len() {
+        0 => "FALSE".to_string(),
+        1 => terms.remove(0),
+        _ => terms
+            .iter()
+            .map(|t| format!("({})", t))
+            .collect::<Vec<_>>()
+            .join(" OR "),
+    };
+    Ok((predicate, params))
+}
+
+/// One sort key with its cursor value, normalized to "after" semantics
+struct Key {
+    expr: String,
+    /// Placeholder of the cursor value; `None` when it is SQL NULL
+    literal: Option<String>,
+    operator: &'static str,
+    nulls_last: bool,
+}
+
+impl Key {
+    fn new(clause: &OrderByClause, placeholder: Option<String>) -> Self {
+        // Same expression the ORDER BY sorts on: text in the collation, else jsonb
+        let mut expr = clause.expression();
+        if let Some(ref collation) = clause.collation {
+            expr.push_str(&format!(" COLLATE \"{}\"", collation));
+        }
+        Self {
+            expr,
+            literal: placeholder,
+            operator: match clause.direction {
+                SortOrder::Asc => ">",
+                SortOrder::Desc => "<",
+            },
+            nulls_last: nulls_last(clause),
+        }
+    }
+}
+
+fn literal(key: &Key) -> &str {
+    key.literal.as_deref().unwrap_or("NULL")
+}
+
+/// `prefix keys equal to the cursor AND last`
+fn conjunction(prefix: &[Key], last: String) -> String {
+    let mut parts: Vec<String> = prefix
+        .iter()
+        .map(|k| match &k.literal {
+            Some(lit) => format!("{} = {}", k.expr, lit),
+            None => format!("{} IS NULL", k.expr),
+        })
+        .collect();
+    parts.push(last);
+    parts.join(" AND ")
+}
+
+/// Whether NULLs sort after every other value for this clause
+fn nulls_last(clause: &OrderByClause) -> bool {
+    match clause.nulls_handling {
+        Some(NullsHandling::Last) => true,
+        Some(NullsHandling::First) => false,
+        None => clause.direction == SortOrder::Asc,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cursor(clauses: &[OrderByClause], row: Value) -> KeysetCursor {
+        KeysetCursor::from_row(clauses, &row)
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let clauses = [
+            OrderByClause::jsonb_field("name", SortOrder::Asc),
+            OrderByClause::jsonb_field("id", SortOrder::Asc),
+        ];
+        let c = cursor(&clauses, json!({"name": "O'Neil", "id": 7}));
+        let token = c.encode();
+        assert!(token
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_'));
+        let decoded: KeysetCursor = token.parse().unwrap();
+        assert_eq!(decoded, c);
+        assert_eq!(decoded.values(), vec![json!("O'Neil"), json!(7)]);
+        assert!(KeysetCursor::decode("not a cursor!").is_err());
+    }
+
+    #[test]
+    fn test_uniform_asc_nulls_first_is_row_comparison() {
+        let clauses = [
+            OrderByClause::jsonb_field("score", SortOrder::Asc).with_nulls(NullsHandling::First),
+            OrderByClause::jsonb_field("id", SortOrder::Asc).with_nulls(NullsHandling::First),
+        ];
+        let c = cursor(&clauses, json!({"score": 10, "id": 3}));
+        // Placeholders continue after the query's own parameters
+        let (sql, params) = seek_predicate(&clauses, &c, SeekDirection::After, 2).unwrap();
+        assert_eq!(sql, "((data->'score'), (data->'id')) > ($3, $4)");
+        assert!(matches!(&params[..], [
+            crate::operators::Value::Json(score),
+            crate::operators::Value::Json(id),
+        ] if *score == json!(10) && *id == json!(3)));
+    }
+
+    #[test]
+    fn test_desc_default_nulls_first() {
+        // DESC defaults to NULLS FIRST, so nothing NULL comes after the cursor
+        let clauses = [OrderByClause::jsonb_field("id", SortOrder::Desc)];
+        let c = cursor(&clauses, json!({"id": 3}));
+        assert_eq!(
+            seek_predicate(&clauses, &c, SeekDirection::After, 0)
+                .unwrap()
+                .0,
+            "(data->'id') < $1"
+        );
+    }
+
+    #[test]
+    fn test_asc_default_nulls_last_adds_null_terms() {
+        let clauses = [
+            OrderByClause::jsonb_field("score", SortOrder::Asc),
+            OrderByClause::jsonb_field("id", SortOrder::Asc),
+        ];
+        let c = cursor(&clauses, json!({"score": 10, "id": 3}));
+        assert_eq!(
+            seek_predicate(&clauses, &c, SeekDirection::After, 0)
+                .unwrap()
+                .0,
+            "(((data->'score'), (data->'id')) > ($1, $2)) \
+             OR ((data->'score') IS NULL) \
+             OR ((data->'score') = $1 AND (data->'id') IS NULL)"
+        );
+    }
+
+    #[test]
+    fn test_mixed_directions_expand() {
+        let clauses = [
+            OrderByClause::jsonb_field("score", SortOrder::Desc),
+            OrderByClause::jsonb_field("id", SortOrder::Asc).with_nulls(NullsHandling::First),
+        ];
+        let c = cursor(&clauses, json!({"score": 10, "id": 3}));
+        assert_eq!(
+            seek_predicate(&clauses, &c, SeekDirection::After, 0)
+                .unwrap()
+                .0,
+            "((data->'score') < $1) \
+             OR ((data->'score') = $1 AND (data->'id') > $2)"
+        );
+    }
+
+    #[test]
+    fn test_null_cursor_value() {
+        let clauses = [
+            OrderByClause::jsonb_field("score", SortOrder::Asc).with_nulls(NullsHandling::First),
+            OrderByClause::jsonb_field("id", SortOrder::Asc),
+        ];
+        let c = cursor(&clauses, json!({"id": 3}));
+        assert_eq!(
+            seek_predicate(&clauses, &c, SeekDirection::After, 0)
+                .unwrap()
+                .0,
+            "((data->'score') IS NOT NULL) \
+             OR ((data->'score') IS NULL AND (data->'id') > $1) \
+             OR ((data->'score') IS NULL AND (data->'id') IS NULL)"
+        );
+
+        // NULL placed last: only ties on the NULL key can follow
+        let clauses = [
+            OrderByClause::jsonb_field("score", SortOrder::Asc),
+            OrderByClause::jsonb_field("id", SortOrder::Desc),
+        ];
+        let c = cursor(&clauses, json!({"id": 3}));
+        assert_eq!(
+            seek_predicate(&clauses, &c, SeekDirection::After, 0)
+                .unwrap()
+                .0,
+            "(data->'score') IS NULL AND (data->'id') < $1"
+        );
+    }
+
+    #[test]
+    fn test_before_reverses_ordering() {
+        let clauses = [OrderByClause::jsonb_field("id", SortOrder::Asc)];
+        let c = cursor(&clauses, json!({"id": 3}));
+        // ASC NULLS LAST reversed is DESC NULLS FIRST
+        assert_eq!(
+            seek_predicate(&clauses, &c, SeekDirection::Before, 0)
+                .unwrap()
+                .0,
+            "(data->'id') < $1"
+        );
+        let rev = reversed(&clauses);
+        assert_eq!(rev[0].to_sql().unwrap(), "(data->'id') DESC NULLS FIRST");
+    }
+
+    #[test]
+    fn test_collated_key_compares_as_text() {
+        let clauses = [
+            OrderByClause::jsonb_field("name", SortOrder::Asc).with_collation("C"),
+            OrderByClause::jsonb_field("id", SortOrder::Asc),
+        ];
+        let c = cursor(&clauses, json!({"name": "b", "id": 3}));
+        let (sql, params) = seek_predicate(&clauses, &c, SeekDirection::After, 0).unwrap();
+        assert_eq!(
+            sql,
+            "(((data->>'name') COLLATE \"C\", (data->'id')) > (($1 #>> '{}'), $2)) \
+             OR ((data->>'name') COLLATE \"C\" IS NULL) \
+             OR ((data->>'name') COLLATE \"C\" = ($1 #>> '{}') AND (data->'id') IS NULL)"
+        );
+        assert!(matches!(&params[..], [
+            crate::operators::Value::Json(name),
+            crate::operators::Value::Json(id),
+        ] if *name == json!("b") && *id == json!(3)));
+
+        // A JSON null reads back as SQL NULL through `->>`
+        let c = cursor(&clauses, json!({"name": null, "id": 3}));
+        let (sql, params) = seek_predicate(&clauses, &c, SeekDirection::After, 0).unwrap();
+        assert_eq!(
+            sql,
+            "((data->>'name') COLLATE \"C\" IS NULL AND (data->'id') > $1) \
+             OR ((data->>'name') COLLATE \"C\" IS NULL AND (data->'id') IS NULL)"
+        );
+        assert!(matches!(&params[..], [
+            crate::operators::Value::Json(id),
+        ] if *id == json!(3)));
+    }
+
+    #[test]
+    fn test_cursor_for_other_ordering_rejected() {
+        let clauses = [OrderByClause::jsonb_field("id", SortOrder::Asc)];
+        let c = cursor(&clauses, json!({"id": 3}));
+        let other = [OrderByClause::jsonb_field("name", SortOrder::Asc)];
+        assert!(seek_predicate(&other, &c, SeekDirection::After, 0).is_err());
+    }
+
+    #[test]
+    fn test_validate_keyset_order() {
+        assert!(validate_keyset_order(&[]).is_err());
+        assert!(
+            validate_keyset_order(&[OrderByClause::direct_column("id", SortOrder::Asc)]).is_err()
+        );
+        assert!(
+            validate_keyset_order(&[OrderByClause::jsonb_field("bad'", SortOrder::Asc)]).is_err()
+        );
+        assert!(validate_keyset_order(&[OrderByClause::jsonb_field("id", SortOrder::Asc)]).is_ok());
+
+        let nearest = OrderByClause {
+            field_source: FieldSource::JsonbPayload,
+            ..OrderByClause::distance("e", crate::operators::DistanceMetric::L2, vec![1.0])
+        };
+        assert!(validate_keyset_order(std::slice::from_ref(&nearest)).is_err());
+        assert!(!tracks_cursor(&[nearest]));
+    }
+}
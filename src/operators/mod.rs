@@ -35,13 +35,36 @@
 //! - **Vector Distance**: L2Distance, CosineDistance, InnerProduct, JaccardDistance
 //! - **Full-Text Search**: Matches, PlainQuery, PhraseQuery, WebsearchQuery
 //! - **Network**: IsIPv4, IsIPv6, IsPrivate, IsLoopback, InSubnet, ContainsSubnet, ContainsIP, IPRangeOverlap
+//! - **JSONB**: JsonContains, HasKey, HasAnyKey, HasAllKeys, JsonPathExists, JsonPathMatch
+//! - **Logical**: And, Or, Not
+//!
+//! Operator trees can also be parsed from JSON filter documents with
+//! `WhereOperator::from_filter_json` (see [`filter`]) and evaluated against rows
+//! in memory with `WhereOperator::matches` (see [`eval`]). Typed SELECT
+//! projections are built with [`Projection`], full-text ranking and
+//! highlighting with [`text_search`], and aggregates with [`Aggregate`].
 
+pub mod aggregate;
+pub mod eval;
 pub mod field;
+pub mod filter;
+pub mod keyset;
 pub mod order_by;
+pub mod param;
+pub mod projection;
+pub(crate) mod scalar;
 pub mod sql_gen;
+pub mod text_search;
 pub mod where_operator;
 
+pub use aggregate::Aggregate;
 pub use field::{Field, Value};
-pub use order_by::{Collation, FieldSource, NullsHandling, OrderByClause, SortOrder};
+pub use keyset::KeysetCursor;
+pub use order_by::{
+    Collation, DistanceMetric, FieldSource, NullsHandling, OrderByClause, SortOrder, VectorDistance,
+};
+pub use param::ToSqlParam;
+pub use projection::Projection;
 pub use sql_gen::generate_where_operator_sql;
+pub use text_search::{Headline, QueryParser, TextQuery, TextRank};
 pub use where_operator::WhereOperator;
@@ -1,8 +1,11 @@
 //! ORDER BY clause specification
 //!
 //! Type-safe representation of ORDER BY clauses with support for
-//! collation, NULLS FIRST/LAST, and mixed JSONB/direct column ordering.
+//! collation, NULLS FIRST/LAST, mixed JSONB/direct column ordering,
+//! pgvector nearest-neighbour ordering, and full-text relevance ranking.
 
+use super::param::ParamBinder;
+use super::{TextRank, Value};
 use std::fmt;
 
 /// Represents a complete ORDER BY clause
@@ -12,6 +15,8 @@ use std::fmt;
 /// - PostgreSQL collations
 /// - NULLS FIRST/LAST handling
 /// - Mixed multi-field ordering
+/// - Vector distance (pgvector `<->`, `<=>`, `<#>`)
+/// - Full-text relevance (`ts_rank`, `ts_rank_cd`)
 ///
 /// # Examples
 ///
@@ -23,6 +28,8 @@ use std::fmt;
 ///     direction: SortOrder::Asc,
 ///     collation: Some("en-US".to_string()),
 ///     nulls_handling: None,
+///     distance: None,
+///     rank: None,
 /// }
 ///
 /// // Order by direct column with NULLS LAST
@@ -32,6 +39,8 @@ use std::fmt;
 ///     direction: SortOrder::Desc,
 ///     collation: None,
 ///     nulls_handling: Some(NullsHandling::Last),
+///     distance: None,
+///     rank: None,
 /// }
 /// ```
 #[derive(Debug, Clone)]
@@ -47,11 +56,27 @@ pub struct OrderByClause {
 
     /// Optional collation name (e.g., "en-US", "C", "de_DE.UTF-8")
     ///
-    /// When specified, generates: `field COLLATE "collation_name"`
+    /// When specified, generates: `field COLLATE "collation_name"`. `jsonb` has
+    /// no collation, so a JSONB field is then sorted as text:
+    /// `(data->>'field') COLLATE "collation_name"`.
     pub collation: Option<String>,
 
     /// Optional NULLS handling
     pub nulls_handling: Option<NullsHandling>,
+
+    /// Order by the distance between the field and a vector instead of the
+    /// field itself
+    ///
+    /// When specified, generates: `(field <=> '[...]'::vector)`; queries built
+    /// by `QueryBuilder` bind the vector as a `$n::vector` parameter instead
+    pub distance: Option<VectorDistance>,
+
+    /// Order by the full-text relevance of the field instead of the field
+    /// itself
+    ///
+    /// When specified, generates: `ts_rank(to_tsvector('english', field), query)`;
+    /// queries built by `QueryBuilder` bind the search text as a parameter
+    pub rank: Option<TextRank>,
 }
 
 impl OrderByClause {
@@ -63,6 +88,8 @@ impl OrderByClause {
             direction,
             collation: None,
             nulls_handling: None,
+            distance: None,
+            rank: None,
         }
     }
 
@@ -74,9 +101,60 @@ impl OrderByClause {
             direction,
             collation: None,
             nulls_handling: None,
+            distance: None,
+            rank: None,
+        }
+    }
+
+    /// Create a nearest-neighbour ORDER BY clause on a pgvector column
+    ///
+    /// Orders by the distance between the column and `vector`, nearest first,
+    /// which pgvector HNSW and IVFFlat indexes serve when combined with a LIMIT
+    /// (see `QueryBuilder::nearest`). Set `field_source` to
+    /// `FieldSource::JsonbPayload` to order by a JSONB field cast to `vector`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let clause = OrderByClause::distance("embedding", DistanceMetric::Cosine, vec![0.1, 0.2]);
+    /// assert_eq!(clause.to_sql()?, "(embedding <=> '[0.1,0.2]'::vector) ASC");
+    /// ```
+    pub fn distance(field: impl Into<String>, metric: DistanceMetric, vector: Vec<f32>) -> Self {
+        Self {
+            distance: Some(VectorDistance { metric, vector }),
+            ..Self::direct_column(field, SortOrder::Asc)
         }
     }
 
+    /// Create a relevance ORDER BY clause on a JSONB text field, most relevant
+    /// first
+    ///
+    /// The field is converted with `to_tsvector` using the query's text search
+    /// configuration. Set `field_source` to `FieldSource::DirectColumn` to rank
+    /// a `tsvector` column as-is.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let clause = OrderByClause::text_rank("body", TextRank::new(TextQuery::plain("rust")));
+    /// assert_eq!(
+    ///     clause.to_sql()?,
+    ///     "ts_rank(to_tsvector('english', (data->>'body')), plainto_tsquery('english', 'rust')) DESC"
+    /// );
+    /// ```
+    pub fn text_rank(field: impl Into<String>, rank: TextRank) -> Self {
+        Self {
+            rank: Some(rank),
+            ..Self::jsonb_field(field, SortOrder::Desc)
+        }
+    }
+
+    /// Whether this clause orders by a computed value (distance or rank)
+    /// rather than the field itself
+    pub(crate) fn is_computed(&self) -> bool {
+        self.distance.is_some() || self.rank.is_some()
+    }
+
     /// Add collation to this clause
     pub fn with_collation(mut self, collation: impl Into<String>) -> Self {
         self.collation = Some(collation.into());
@@ -124,6 +202,24 @@ impl OrderByClause {
             }
         }
 
+        if self.is_computed() && self.collation.is_some() {
+            return Err("Distance and rank ordering cannot have a collation".to_string());
+        }
+        if let Some(ref rank) = self.rank {
+            if self.distance.is_some() {
+                return Err("A clause cannot order by both distance and rank".to_string());
+            }
+            rank.validate()?;
+        }
+        if let Some(ref distance) = self.distance {
+            if distance.vector.is_empty() {
+                return Err("Distance vector cannot be empty".to_string());
+            }
+            if !distance.vector.iter().all(|f| f.is_finite()) {
+                return Err("Distance vector must contain finite values".to_string());
+            }
+        }
+
         Ok(())
     }
 
@@ -131,18 +227,22 @@ impl OrderByClause {
     ///
     /// # Examples
     ///
-    /// - JSONB with collation: `(data->'name') COLLATE "en-US" ASC`
+    /// - JSONB: `(data->'name') ASC`
+    /// - JSONB with collation, sorted as text: `(data->>'name') COLLATE "en-US" ASC`
     /// - Direct column: `created_at DESC`
     /// - With NULLS: `status ASC NULLS LAST`
+    /// - Distance: `(embedding <-> '[1,2]'::vector) ASC`
+    /// - Rank: `ts_rank(search, plainto_tsquery('english', 'rust')) DESC`
     pub fn to_sql(&self) -> Result<String, String> {
-        self.validate()?;
+        self.to_sql_with(&mut ParamBinder::inline())
+    }
 
-        let field_expr = match self.field_source {
-            FieldSource::JsonbPayload => format!("(data->'{}')", self.field),
-            FieldSource::DirectColumn => self.field.clone(),
-        };
+    /// `to_sql()` with the distance vector or search text bound through
+    /// `binder`
+    pub(crate) fn to_sql_with(&self, binder: &mut ParamBinder) -> Result<String, String> {
+        self.validate()?;
 
-        let mut sql = field_expr;
+        let mut sql = self.expression_with(binder);
 
         // Add collation if specified
         if let Some(ref collation) = self.collation {
@@ -169,6 +269,150 @@ impl OrderByClause {
 
         Ok(sql)
     }
+
+    /// The sort expression, without collation, direction or NULLS handling
+    pub(crate) fn expression(&self) -> String {
+        self.expression_with(&mut ParamBinder::inline())
+    }
+
+    /// `expression()` with the distance vector or search text bound through
+    /// `binder`
+    pub(crate) fn expression_with(&self, binder: &mut ParamBinder) -> String {
+        if let Some(ref rank) = self.rank {
+            let document = match self.field_source {
+                FieldSource::JsonbPayload => rank
+                    .query
+                    .tsvector_sql(&format!("(data->>'{}')", self.field)),
+                FieldSource::DirectColumn => self.field.clone(),
+            };
+            return rank.to_sql(&document, binder);
+        }
+        match (&self.distance, self.field_source) {
+            // Collations only apply to text
+            (None, FieldSource::JsonbPayload) if self.collation.is_some() => {
+                format!("(data->>'{}')", self.field)
+            }
+            (None, FieldSource::JsonbPayload) => format!("(data->'{}')", self.field),
+            (None, FieldSource::DirectColumn) => self.field.clone(),
+            (Some(distance), source) => {
+                let field_expr = match source {
+                    FieldSource::JsonbPayload => format!("(data->>'{}')::vector", self.field),
+                    FieldSource::DirectColumn => self.field.clone(),
+                };
+                format!(
+                    "({} {} {}::vector)",
+                    field_expr,
+                    distance.metric.operator(),
+                    binder.bind(Value::FloatArray(distance.vector.clone()))
+                )
+            }
+        }
+    }
+
+    /// Parse a comma-separated ORDER BY list in the form `to_sql()` renders
+    ///
+    /// Each item is a column name, `data->'field'` or `data->>'field' COLLATE
+    /// "name"` (optionally parenthesized), followed by optional `COLLATE "name"`
+    /// (columns only), `ASC`/`DESC` and `NULLS FIRST`/`LAST`. Every parsed clause
+    /// is validated. Anything else, such as functions, casts, `->>` without a
+    /// collation, a collation on `jsonb` or subqueries, is rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let clauses = OrderByClause::parse_list("data->>'name' COLLATE \"C\", created_at DESC")?;
+    /// assert_eq!(clauses[1].field_source, FieldSource::DirectColumn);
+    /// ```
+    pub fn parse_list(order_by: &str) -> Result<Vec<Self>, String> {
+        order_by.split(',').map(Self::parse_item).collect()
+    }
+
+    fn parse_item(item: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid ORDER BY item: {}", item.trim());
+        let mut rest = item.trim();
+
+        let mut nulls_handling = None;
+        if let Some(r) = strip_keyword_suffix(rest, "NULLS FIRST") {
+            nulls_handling = Some(NullsHandling::First);
+            rest = r;
+        } else if let Some(r) = strip_keyword_suffix(rest, "NULLS LAST") {
+            nulls_handling = Some(NullsHandling::Last);
+            rest = r;
+        }
+
+        let mut direction = SortOrder::Asc;
+        if let Some(r) = strip_keyword_suffix(rest, "DESC") {
+            direction = SortOrder::Desc;
+            rest = r;
+        } else if let Some(r) = strip_keyword_suffix(rest, "ASC") {
+            rest = r;
+        }
+
+        let mut collation = None;
+        if let Some(idx) = rest.to_ascii_uppercase().rfind(" COLLATE ") {
+            let name = rest[idx + " COLLATE ".len()..].trim();
+            let name = name
+                .strip_prefix('"')
+                .and_then(|n| n.strip_suffix('"'))
+                .unwrap_or(name);
+            collation = Some(name.to_string());
+            rest = rest[..idx].trim_end();
+        }
+
+        let mut expr = rest;
+        while let Some(inner) = expr.strip_prefix('(').and_then(|e| e.strip_suffix(')')) {
+            expr = inner.trim();
+        }
+        let (field, field_source) = match expr.strip_prefix("data") {
+            Some(path) if path.trim_start().starts_with("->") => {
+                let path = path.trim_start();
+                // `->>` is how a collated key renders; `jsonb` takes no collation
+                let key = match path.strip_prefix("->>") {
+                    Some(key) if collation.is_some() => key,
+                    Some(_) => return Err(invalid()),
+                    None if collation.is_some() => {
+                        return Err(format!(
+                            "{} (collations are not supported by type jsonb; use data->>'field')",
+                            invalid()
+                        ))
+                    }
+                    None => &path[2..],
+                };
+                let key = key.trim_start();
+                let key = key
+                    .strip_prefix('\'')
+                    .and_then(|k| k.strip_suffix('\''))
+                    .ok_or_else(invalid)?;
+                (key, FieldSource::JsonbPayload)
+            }
+            _ => (expr, FieldSource::DirectColumn),
+        };
+
+        let clause = Self {
+            field: field.to_string(),
+            field_source,
+            direction,
+            collation,
+            nulls_handling,
+            distance: None,
+            rank: None,
+        };
+        clause
+            .validate()
+            .map_err(|e| format!("{} ({})", invalid(), e))?;
+        Ok(clause)
+    }
+}
+
+/// Strip a trailing keyword (case-insensitive) preceded by whitespace
+fn strip_keyword_suffix<'a>(s: &'a str, keyword: &str) -> Option<&'a str> {
+    let split = s.len().checked_sub(keyword.len())?;
+    let (head, tail) = (s.get(..split)?, s.get(split..)?);
+    if tail.eq_ignore_ascii_case(keyword) && head.ends_with(char::is_whitespace) {
+        Some(head.trim_end())
+    } else {
+        None
+    }
 }
 
 impl fmt::Display for OrderByClause {
@@ -237,6 +481,46 @@ impl fmt::Display for NullsHandling {
     }
 }
 
+/// Vector distance to order by (see `OrderByClause::distance`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorDistance {
+    /// Distance metric
+    pub metric: DistanceMetric,
+
+    /// Vector to measure the distance to
+    pub vector: Vec<f32>,
+}
+
+/// pgvector distance metric
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceMetric {
+    /// Euclidean distance: `<->`
+    L2,
+
+    /// Cosine distance: `<=>`
+    Cosine,
+
+    /// Negative inner product: `<#>` (ascending puts the largest product first)
+    InnerProduct,
+}
+
+impl DistanceMetric {
+    /// The pgvector operator for this metric
+    pub fn operator(&self) -> &'static str {
+        match self {
+            DistanceMetric::L2 => "<->",
+            DistanceMetric::Cosine => "<=>",
+            DistanceMetric::InnerProduct => "<#>",
+        }
+    }
+}
+
+impl fmt::Display for DistanceMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.operator())
+    }
+}
+
 /// PostgreSQL collation specifications
 ///
 /// Supports common collations and custom names.
@@ -292,7 +576,7 @@ mod tests {
     fn test_ordering_with_collation() {
         let clause = OrderByClause::jsonb_field("name", SortOrder::Asc).with_collation("en-US");
         let sql = clause.to_sql().unwrap();
-        assert_eq!(sql, "(data->'name') COLLATE \"en-US\" ASC");
+        assert_eq!(sql, "(data->>'name') COLLATE \"en-US\" ASC");
     }
 
     #[test]
@@ -309,7 +593,7 @@ mod tests {
             .with_collation("C")
             .with_nulls(NullsHandling::First);
         let sql = clause.to_sql().unwrap();
-        assert_eq!(sql, "(data->'email') COLLATE \"C\" DESC NULLS FIRST");
+        assert_eq!(sql, "(data->>'email') COLLATE \"C\" DESC NULLS FIRST");
     }
 
     #[test]
@@ -338,6 +622,109 @@ mod tests {
         assert!(clause.validate().is_err());
     }
 
+    #[test]
+    fn test_parse_list() {
+        let clauses = OrderByClause::parse_list(
+            "(data->>'name') COLLATE \"en-US\" ASC, created_at desc nulls last, data -> 'id'",
+        )
+        .unwrap();
+        let rendered: Vec<String> = clauses.iter().map(|c| c.to_sql().unwrap()).collect();
+        assert_eq!(
+            rendered,
+            vec![
+                "(data->>'name') COLLATE \"en-US\" ASC",
+                "created_at DESC NULLS LAST",
+                "(data->'id') ASC",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_list_rejects_expressions() {
+        for raw in [
+            "",
+            "data->>'name'",
+            "data->'name' COLLATE \"C\"",
+            "lower(name)",
+            "(data->'n')::int",
+            "id; DROP TABLE users",
+            "data->'a''b'",
+            "name COLLATE \"x\"\"y\"",
+            "(SELECT 1)",
+        ] {
+            assert!(OrderByClause::parse_list(raw).is_err(), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_distance_ordering() {
+        let clause = OrderByClause::distance("embedding", DistanceMetric::Cosine, vec![0.5, -1.0]);
+        assert_eq!(
+            clause.to_sql().unwrap(),
+            "(embedding <=> '[0.5,-1]'::vector) ASC"
+        );
+
+        let clause = OrderByClause {
+            field_source: FieldSource::JsonbPayload,
+            ..OrderByClause::distance("embedding", DistanceMetric::InnerProduct, vec![1.0])
+        };
+        assert_eq!(
+            clause.to_sql().unwrap(),
+            "((data->>'embedding')::vector <#> '[1]'::vector) ASC"
+        );
+
+        assert!(OrderByClause::distance("e", DistanceMetric::L2, vec![])
+            .validate()
+            .is_err());
+        assert!(
+            OrderByClause::distance("e", DistanceMetric::L2, vec![f32::NAN])
+                .validate()
+                .is_err()
+        );
+        assert!(OrderByClause::distance("e", DistanceMetric::L2, vec![1.0])
+            .with_collation("C")
+            .validate()
+            .is_err());
+        assert!(
+            OrderByClause::distance("e-1", DistanceMetric::L2, vec![1.0])
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_rank_ordering() {
+        use crate::operators::TextQuery;
+
+        let clause = OrderByClause::text_rank(
+            "body",
+            TextRank::new(TextQuery::websearch("rust").language("simple")).cover_density(),
+        );
+        assert_eq!(
+            clause.to_sql().unwrap(),
+            "ts_rank_cd(to_tsvector('simple', (data->>'body')), \
+             websearch_to_tsquery('simple', 'rust')) DESC"
+        );
+
+        let clause = OrderByClause {
+            field_source: FieldSource::DirectColumn,
+            ..OrderByClause::text_rank("search", TextRank::new(TextQuery::plain("rust")))
+        };
+        assert_eq!(
+            clause.to_sql().unwrap(),
+            "ts_rank(search, plainto_tsquery('english', 'rust')) DESC"
+        );
+
+        let bad = TextRank::new(TextQuery::plain("rust").language("x'y"));
+        assert!(OrderByClause::text_rank("body", bad).validate().is_err());
+        assert!(
+            OrderByClause::text_rank("body", TextRank::new(TextQuery::plain("rust")))
+                .with_collation("C")
+                .validate()
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_sort_order_display() {
         assert_eq!(SortOrder::Asc.to_string(), "ASC");
@@ -0,0 +1,409 @@
+//! Bound parameters for hand-written SQL
+//!
+//! `QueryBuilder::where_sql_params` binds values to `$n` placeholders with the
+//! extended protocol instead of interpolating them. Values are anything
+//! implementing [`ToSqlParam`], which converts into a [`Value`] sent in text
+//! format with its `type_oid()`:
+//!
+//! ```ignore
+//! let stream = client
+//!     .query::<Value>("orders")
+//!     .where_sql_params("data->>'region' = $1 AND (data->>'score')::int > $2", &[&"eu", &10])
+//!     .execute()
+//!     .await?;
+//! ```
+//!
+//! Placeholders are numbered from `$1` in each predicate and renumbered when
+//! the query is built, so predicates never clash.
+
+use super::Value;
+
+/// A value that can be bound to a `$n` placeholder
+///
+/// Implemented for [`Value`], strings, integers, floats, booleans,
+/// `serde_json::Value` (bound as `jsonb`) and `Option<T>` (`None` is NULL).
+/// Strings are declared as `text`; cast in SQL (`$1::date`) or use a typed
+/// `Value` (e.g. `Value::Date`) for other types.
+pub trait ToSqlParam {
+    /// The value to bind
+    fn to_sql_param(&self) -> Value;
+}
+
+impl ToSqlParam for Value {
+    fn to_sql_param(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl<T: ToSqlParam + ?Sized> ToSqlParam for &T {
+    fn to_sql_param(&self) -> Value {
+        (**self).to_sql_param()
+    }
+}
+
+impl<T: ToSqlParam> ToSqlParam for Option<T> {
+    fn to_sql_param(&self) -> Value {
+        self.as_ref().map_or(Value::Null, ToSqlParam::to_sql_param)
+    }
+}
+
+impl ToSqlParam for str {
+    fn to_sql_param(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl ToSqlParam for String {
+    fn to_sql_param(&self) -> Value {
+        Value::String(self.clone())
+    }
+}
+
+impl ToSqlParam for bool {
+    fn to_sql_param(&self) -> Value {
+        Value::Bool(*self)
+    }
+}
+
+macro_rules! int_param {
+    ($($ty:ty),*) => {
+        $(impl ToSqlParam for $ty {
+            fn to_sql_param(&self) -> Value {
+                Value::Int(i64::from(*self))
+            }
+        })*
+    };
+}
+
+int_param!(i8, i16, i32, i64, u8, u16, u32);
+
+impl ToSqlParam for f32 {
+    fn to_sql_param(&self) -> Value {
+        Value::Number(f64::from(*self))
+    }
+}
+
+impl ToSqlParam for f64 {
+    fn to_sql_param(&self) -> Value {
+        Value::Number(*self)
+    }
+}
+
+impl ToSqlParam for serde_json::Value {
+    fn to_sql_param(&self) -> Value {
+        Value::Json(self.clone())
+    }
+}
+
+/// Binds the values of generated SQL expressions (distance vectors, text
+/// search queries and headline options) to placeholders
+///
+/// Placeholders are numbered after the `first` parameters already bound. A
+/// value bound twice reuses its placeholder, so an expression repeated in
+/// ORDER BY and the projection sends its value once. An inline binder writes
+/// SQL literals instead, for renderings that carry no parameters.
+#[derive(Debug)]
+pub(crate) struct ParamBinder {
+    first: usize,
+    params: Vec<Value>,
+    inline: bool,
+}
+
+impl ParamBinder {
+    /// Bind placeholders numbered from `$(first + 1)`
+    pub(crate) fn new(first: usize) -> Self {
+        Self {
+            first,
+            params: Vec::new(),
+            inline: false,
+        }
+    }
+
+    /// Write values as SQL literals
+    pub(crate) fn inline() -> Self {
+        Self {
+            inline: true,
+            ..Self::new(0)
+        }
+    }
+
+    /// Placeholder (or literal) for `value`
+    pub(crate) fn bind(&mut self, value: Value) -> String {
+        if self.inline {
+            return value.to_sql_literal();
+        }
+        let same = |bound: &Value| {
+            bound.type_oid() == value.type_oid() && bound.to_param() == value.to_param()
+        };
+        let index = match self.params.iter().position(same) {
+            Some(index) => index,
+            None => {
+                self.params.push(value);
+                self.params.len() - 1
+            }
+        };
+        format!("${}", self.first + index + 1)
+    }
+
+    /// The bound values, in placeholder order
+    pub(crate) fn into_params(self) -> Vec<Value> {
+        self.params
+    }
+}
+
+/// Renumber the `$n` placeholders of `sql` to `$(n + offset)`
+///
+/// Placeholders inside string literals, quoted identifiers, dollar-quoted
+/// strings and comments are left alone. Every placeholder must be in
+/// `1..=count` and every parameter must be used.
+pub(crate) fn renumber_placeholders(
+    sql: &str,
+    offset: usize,
+    count: usize,
+) -> Result<String, String> {
+    let bytes = sql.as_bytes();
+    let mut out = String::with_capacity(sql.len());
+    let mut used = vec![false; count];
+    let mut copied = 0;
+    let mut i = 0;
+
+    // `$` and `E` only start a token when not part of an identifier
+    let in_word = |i: usize| {
+        i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || matches!(bytes[i - 1], b'_' | b'$'))
+    };
+
+    while i < bytes.len() {
+        let end = match bytes[i] {
+            b'\'' => {
+                let escapes = i > 0 && matches!(bytes[i - 1], b'E' | b'e') && !in_word(i - 1);
+                Some(quoted_end(bytes, i, escapes))
+            }
+            b'"' => Some(quoted_end(bytes, i, false)),
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                Some(sql[i..].find('\n').map_or(bytes.len(), |n| i + n + 1))
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => Some(block_comment_end(bytes, i)),
+            b'$' if !in_word(i) => {
+                let digits = bytes[i + 1..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .count();
+                if digits > 0 {
+                    let n: usize = sql[i + 1..i + 1 + digits]
+                        .parse()
+                        .map_err(|_| format!("invalid placeholder in: {}", sql))?;
+                    if n == 0 || n > count {
+                        return Err(format!(
+                            "placeholder ${} has no parameter ({} given)",
+                            n, count
+                        ));
+                    }
+                    used[n - 1] = true;
+                    out.push_str(&sql[copied..i]);
+                    out.push_str(&format!("${}", n + offset));
+                    i += 1 + digits;
+                    copied = i;
+                    continue;
+                }
+                dollar_quoted_end(sql, i)
+            }
+            _ => None,
+        };
+        i = end.unwrap_or(i + 1);
+    }
+    out.push_str(&sql[copied..]);
+
+    if let Some(unused) = used.iter().position(|used| !used) {
+        return Err(format!("parameter ${} is not used", unused + 1));
+    }
+    Ok(out)
+}
+
+/// Collapse runs of whitespace to a single space and trim, leaving string
+/// literals, quoted identifiers, dollar-quoted strings and comments untouched
+///
+/// Queries that differ only in layout normalize to the same text.
+pub(crate) fn normalize_whitespace(sql: &str) -> String {
+    let bytes = sql.as_bytes();
+    let mut out = String::with_capacity(sql.len());
+    let mut i = 0;
+
+    let in_word = |i: usize| {
+        i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || matches!(bytes[i - 1], b'_' | b'$'))
+    };
+
+    while i < bytes.len() {
+        let end = match bytes[i] {
+            b if b.is_ascii_whitespace() => {
+                let run = bytes[i..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_whitespace())
+                    .count();
+                // A line comment already ends with its newline
+                let separated = out.is_empty() || out.ends_with('\n');
+                if !separated && i + run < bytes.len() {
+                    out.push(' ');
+                }
+                i += run;
+                continue;
+            }
+            b'\'' => {
+                let escapes = i > 0 && matches!(bytes[i - 1], b'E' | b'e') && !in_word(i - 1);
+                Some(quoted_end(bytes, i, escapes))
+            }
+            b'"' => Some(quoted_end(bytes, i, false)),
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                Some(sql[i..].find('\n').map_or(bytes.len(), |n| i + n + 1))
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => Some(block_comment_end(bytes, i)),
+            b'$' if !in_word(i) => dollar_quoted_end(sql, i),
+            _ => None,
+        };
+        // Copy whole tokens, or a single (possibly multi-byte) character
+        let end = end.unwrap_or_else(|| i + sql[i..].chars().next().map_or(1, char::len_utf8));
+        out.push_str(&sql[i..end]);
+        i = end;
+    }
+    out
+}
+
+/// End of a quoted string or identifier starting at `start` (doubled quotes
+/// are escapes, as are backslashes in `E'...'` strings)
+fn quoted_end(bytes: &[u8], start: usize, backslash_escapes: bool) -> usize {
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if backslash_escapes => i += 2,
+            b if b == quote && bytes.get(i + 1) == Some(&quote) => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// End of a (possibly nested) block comment starting at `start`
+fn block_comment_end(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// End of a `$tag$...$tag$` string starting at `start`, if one starts there
+fn dollar_quoted_end(sql: &str, start: usize) -> Option<usize> {
+    let rest = &sql[start + 1..];
+    let tag_len = rest.find('$')?;
+    let tag = &rest[..tag_len];
+    let mut chars = tag.chars();
+    let valid = match chars.next() {
+        None => true,
+        Some(c) => {
+            (c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+        }
+    };
+    if !valid {
+        return None;
+    }
+    let delimiter = &sql[start..start + tag_len + 2];
+    let body = start + delimiter.len();
+    Some(
+        sql[body..]
+            .find(delimiter)
+            .map_or(sql.len(), |n| body + n + delimiter.len()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renumber_placeholders() {
+        assert_eq!(
+            renumber_placeholders("data->>'region' = $1 AND score > $2", 3, 2).unwrap(),
+            "data->>'region' = $4 AND score > $5"
+        );
+        assert_eq!(
+            renumber_placeholders("a = $2 OR b = $1 OR c = $1", 1, 2).unwrap(),
+            "a = $3 OR b = $2 OR c = $2"
+        );
+
+        // Literals, identifiers, dollar quotes and comments are skipped
+        let sql = "a = $1 AND b = '$2' AND \"$3\" = E'\\'$4' AND c = $$ $5 $$ \
+                   AND d = $x$ $6 $x$ -- $7\n AND /* $8 /* $9 */ */ e = $1 AND f$1 = 1";
+        assert_eq!(
+            renumber_placeholders(sql, 10, 1).unwrap(),
+            sql.replace("a = $1", "a = $11")
+                .replace("e = $1", "e = $11")
+        );
+
+        assert!(renumber_placeholders("a = $1 AND b = $3", 0, 2).is_err());
+        assert!(renumber_placeholders("a = $0", 0, 1).is_err());
+        assert!(renumber_placeholders("a = $1", 0, 2).is_err());
+        assert!(renumber_placeholders("a = '$1'", 0, 1).is_err());
+        assert_eq!(renumber_placeholders("a = 1", 0, 0).unwrap(), "a = 1");
+    }
+
+    #[test]
+    fn test_normalize_whitespace() {
+        assert_eq!(
+            normalize_whitespace("  SELECT data\n  FROM \"v\"\tWHERE a = $1 \n"),
+            "SELECT data FROM \"v\" WHERE a = $1"
+        );
+        let kept = "a = '  x  ' AND \"a  b\" = $$ y  $$ AND /* c  d */ e = 1";
+        assert_eq!(normalize_whitespace(kept), kept);
+        // The newline ending a line comment is part of the comment
+        assert_eq!(
+            normalize_whitespace("SELECT 1 -- one\n   + 2"),
+            "SELECT 1 -- one\n+ 2"
+        );
+        assert_eq!(normalize_whitespace("é  ü"), "é ü");
+    }
+
+    #[test]
+    fn test_to_sql_param() {
+        assert!(matches!("eu".to_sql_param(), Value::String(s) if s == "eu"));
+        assert!(matches!(10i32.to_sql_param(), Value::Int(10)));
+        assert!(matches!(2.5f64.to_sql_param(), Value::Number(n) if n == 2.5));
+        assert!(matches!(None::<i64>.to_sql_param(), Value::Null));
+        assert!(matches!(Some(true).to_sql_param(), Value::Bool(true)));
+        assert!(matches!(
+            serde_json::json!({"a": 1}).to_sql_param(),
+            Value::Json(_)
+        ));
+        let params: [&dyn ToSqlParam; 2] = [&"eu", &Value::Date("2024-01-02".into())];
+        assert!(matches!(params[1].to_sql_param(), Value::Date(_)));
+    }
+
+    #[test]
+    fn test_param_binder() {
+        let mut binder = ParamBinder::new(2);
+        assert_eq!(binder.bind(Value::FloatArray(vec![1.0, 2.5])), "$3");
+        assert_eq!(binder.bind(Value::String("1".into())), "$4");
+        // Same value, same placeholder; same text with another type, a new one
+        assert_eq!(binder.bind(Value::FloatArray(vec![1.0, 2.5])), "$3");
+        assert_eq!(binder.bind(Value::Int(1)), "$5");
+        assert_eq!(binder.into_params().len(), 3);
+
+        let mut inline = ParamBinder::inline();
+        assert_eq!(inline.bind(Value::FloatArray(vec![1.0, 2.5])), "'[1,2.5]'");
+        assert!(inline.into_params().is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+//! Typed SQL projections
+//!
+//! A [`Projection`] describes the shape of the `data` column returned by a query,
+//! built from [`Field`]s instead of raw SQL. It renders to `jsonb_build_object(...)`
+//! with every key quoted and every path validated:
+//!
+//! ```ignore
+//! let projection = Projection::new()
+//!     .field("id")
+//!     .rename("title", "name")
+//!     .nested("owner", |p| p.field("name").field("email"));
+//!
+//! // jsonb_build_object('id', data->'id', 'title', data->'name',
+//! //     'owner', jsonb_build_object('name', data->'owner'->'name',
+//! //         'email', data->'owner'->'email'))
+//! ```
+//!
+//! JSONB values are extracted with `->`, so numbers, booleans, arrays and objects
+//! keep their JSON type; missing keys project as JSON `null`.
+
+use super::Field;
+use crate::{Error, Result};
+use std::collections::HashSet;
+
+/// Maximum key/value pairs per `jsonb_build_object` call (Postgres caps a
+/// function call at 100 arguments)
+const MAX_PAIRS: usize = 50;
+
+/// A value in a projection
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    /// A single field
+    Field(Field),
+    /// A nested object
+    Object(Projection),
+}
+
+/// Typed projection rendering to `jsonb_build_object(...)`
+///
+/// Fields added with [`field`](Self::field) and [`rename`](Self::rename) are
+/// resolved relative to the enclosing [`nested`](Self::nested) object, so a
+/// projection mirrors the shape of the document it selects from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Projection {
+    /// JSONB path this projection is nested under
+    base: Vec<String>,
+    /// Output keys and their values, in order
+    entries: Vec<(String, Entry)>,
+}
+
+impl Projection {
+    /// Create an empty projection
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select a JSONB field under its own name
+    pub fn field(self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.rename(name.clone(), name)
+    }
+
+    /// Select a JSONB field under another key
+    pub fn rename(self, key: impl Into<String>, name: impl Into<String>) -> Self {
+        let mut path = self.base.clone();
+        path.push(name.into());
+        self.field_as(key, Field::JsonbPath(path))
+    }
+
+    /// Select a direct database column under its own name
+    pub fn column(self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.field_as(name.clone(), Field::DirectColumn(name))
+    }
+
+    /// Select any field under `key`
+    ///
+    /// Unlike [`field`](Self::field), `field` is not resolved relative to the
+    /// enclosing nested object.
+    pub fn field_as(mut self, key: impl Into<String>, field: Field) -> Self {
+        self.entries.push((key.into(), Entry::Field(field)));
+        self
+    }
+
+    /// Add a nested object under `key`, whose fields are resolved under the
+    /// JSONB field of the same name
+    pub fn nested(mut self, key: impl Into<String>, build: impl FnOnce(Self) -> Self) -> Self {
+        let key = key.into();
+        let mut base = self.base.clone();
+        base.push(key.clone());
+        let object = build(Self {
+            base,
+            entries: Vec::new(),
+        });
+        self.entries.push((key, Entry::Object(object)));
+        self
+    }
+
+    /// Validate keys and field paths
+    ///
+    /// Fails on an invalid field name (see `Field::validate`), on a key
+    /// repeated within one object, or on a key containing a NUL byte.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        let mut seen = HashSet::new();
+        for (key, entry) in &self.entries {
+            if key.contains('\0') {
+                return Err(format!("Invalid projection key: {:?}", key));
+            }
+            if !seen.insert(key) {
+                return Err(format!("Duplicate projection key: {}", key));
+            }
+            match entry {
+                Entry::Field(field) => field.validate()?,
+                Entry::Object(object) => {
+                    Field::JsonbPath(object.base.clone()).validate()?;
+                    object.validate()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the projection as a JSONB expression
+    ///
+    /// Objects with more than 50 keys are split across several
+    /// `jsonb_build_object` calls concatenated with `||`.
+    pub fn to_sql(&self) -> Result<String> {
+        self.validate().map_err(Error::Config)?;
+        Ok(self.render())
+    }
+
+    fn render(&self) -> String {
+        if self.entries.is_empty() {
+            return "'{}'::jsonb".to_string();
+        }
+        let calls: Vec<String> = self
+            .entries
+            .chunks(MAX_PAIRS)
+            .map(|chunk| {
+                let args: Vec<String> = chunk
+                    .iter()
+                    .map(|(key, entry)| {
+                        let value = match entry {
+                            Entry::Field(field) => field_sql(field),
+                            Entry::Object(object) => object.render(),
+                        };
+                        format!("'{}', {}", key.replace('\'', "''"), value)
+                    })
+                    .collect();
+                format!("jsonb_build_object({})", args.join(", "))
+            })
+            .collect();
+        if calls.len() == 1 {
+            calls.into_iter().next().unwrap_or_default()
+        } else {
+            format!("({})", calls.join(" || "))
+        }
+    }
+}
+
+/// SQL for a projected field, keeping JSONB values as JSONB
+fn field_sql(field: &Field) -> String {
+    match field {
+        Field::JsonbField(name) => format!("data->'{}'", name),
+        Field::DirectColumn(name) => name.clone(),
+        Field::JsonbPath(path) => {
+            let mut sql = String::from("data");
+            for segment in path {
+                sql.push_str(&format!("->'{}'", segment));
+            }
+            sql
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_and_nesting() {
+        let projection = Projection::new()
+            .field("id")
+            .rename("title", "name")
+            .nested("owner", |p| {
+                p.field("name")
+                    .nested("team", |p| p.rename("team_id", "id"))
+            })
+            .column("created_at")
+            .field_as(
+                "score",
+                Field::JsonbPath(vec!["stats".into(), "score".into()]),
+            );
+
+        assert_eq!(
+            projection.to_sql().unwrap(),
+            "jsonb_build_object('id', data->'id', 'title', data->'name', \
+             'owner', jsonb_build_object('name', data->'owner'->'name', \
+             'team', jsonb_build_object('team_id', data->'owner'->'team'->'id')), \
+             'created_at', created_at, 'score', data->'stats'->'score')"
+        );
+    }
+
+    #[test]
+    fn test_key_quoting() {
+        let projection = Projection::new().field_as("it's", Field::JsonbField("name".into()));
+        assert_eq!(
+            projection.to_sql().unwrap(),
+            "jsonb_build_object('it''s', data->'name')"
+        );
+        assert_eq!(Projection::new().to_sql().unwrap(), "'{}'::jsonb");
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(Projection::new().field("name'; DROP").to_sql().is_err());
+        assert!(Projection::new()
+            .nested("bad key", |p| p.field("id"))
+            .to_sql()
+            .is_err());
+        assert!(Projection::new().column("a-b").to_sql().is_err());
+        assert!(Projection::new()
+            .field("id")
+            .rename("id", "other")
+            .to_sql()
+            .is_err());
+        assert!(Projection::new()
+            .field_as("a\0b", Field::JsonbField("id".into()))
+            .to_sql()
+            .is_err());
+        // The same key may appear in different objects
+        assert!(Projection::new()
+            .field("id")
+            .nested("owner", |p| p.field("id"))
+            .to_sql()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_argument_limit_chaining() {
+        let projection = (0..120).fold(Projection::new(), |p, i| p.field(format!("f{}", i)));
+        let sql = projection.to_sql().unwrap();
+
+        assert!(sql.starts_with("(jsonb_build_object('f0', data->'f0'"));
+        assert_eq!(sql.matches("jsonb_build_object(").count(), 3);
+        assert_eq!(sql.matches(" || ").count(), 2);
+        assert!(sql.contains("'f49', data->'f49') || jsonb_build_object('f50', data->'f50'"));
+
+        let exact = (0..50).fold(Projection::new(), |p, i| p.field(format!("f{}", i)));
+        assert!(!exact.to_sql().unwrap().contains("||"));
+    }
+}
@@ -0,0 +1,327 @@
+//! Parsers for the text form of typed scalar values
+//!
+//! Shared by `Value::validate` and in-memory evaluation. Each parser accepts the
+//! ISO forms Postgres accepts for its type and returns a value ordered the way
+//! Postgres orders it, or `None` on invalid input.
+
+use std::cmp::Ordering;
+
+/// An exact decimal: `0.d1d2d3... × 10^exponent`, without leading or trailing zeros
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Decimal {
+    negative: bool,
+    digits: Vec<u8>,
+    exponent: i64,
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let sign = |d: &Decimal| match (d.digits.is_empty(), d.negative) {
+            (true, _) => 0,
+            (false, true) => -1,
+            (false, false) => 1,
+        };
+        let magnitude = self
+            .exponent
+            .cmp(&other.exponent)
+            .then_with(|| self.digits.cmp(&other.digits));
+        match sign(self).cmp(&sign(other)) {
+            Ordering::Equal if sign(self) < 0 => magnitude.reverse(),
+            Ordering::Equal if sign(self) > 0 => magnitude,
+            ordering => ordering,
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// `numeric` input: `[+-]digits[.digits][e[+-]digits]`, surrounding whitespace allowed
+pub(crate) fn parse_decimal(text: &str) -> Option<Decimal> {
+    let text = text.trim();
+    let (negative, rest) = match text.as_bytes().first()? {
+        b'-' => (true, &text[1..]),
+        b'+' => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let (mantissa, exponent) = match rest.find(['e', 'E']) {
+        Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+        None => (rest, None),
+    };
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int_part.len() + frac_part.len() == 0 || !all_digits(int_part) || !all_digits(frac_part) {
+        return None;
+    }
+    let exponent: i64 = match exponent {
+        Some(e) => {
+            let unsigned = e.strip_prefix(['+', '-']).unwrap_or(e);
+            if unsigned.is_empty() || !all_digits(unsigned) {
+                return None;
+            }
+            e.parse().ok()?
+        }
+        None => 0,
+    };
+
+    let mut digits: Vec<u8> = int_part
+        .bytes()
+        .chain(frac_part.bytes())
+        .map(|b| b - b'0')
+        .collect();
+    let mut exponent = exponent.checked_add(int_part.len() as i64)?;
+    let leading = digits.iter().take_while(|d| **d == 0).count();
+    digits.drain(..leading);
+    exponent -= leading as i64;
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    if digits.is_empty() {
+        exponent = 0;
+    }
+    Some(Decimal {
+        negative: negative && !digits.is_empty(),
+        digits,
+        exponent,
+    })
+}
+
+/// `bigint` input: `[+-]digits`, surrounding whitespace allowed
+pub(crate) fn parse_int8(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let unsigned = text.strip_prefix(['+', '-']).unwrap_or(text);
+    if unsigned.is_empty() || !unsigned.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// `uuid` input: 32 hex digits, optionally in braces, with hyphens allowed after
+/// any group of four digits
+pub(crate) fn parse_uuid(text: &str) -> Option<u128> {
+    let inner = text
+        .strip_prefix('{')
+        .and_then(|t| t.strip_suffix('}'))
+        .unwrap_or(text);
+    let mut value: u128 = 0;
+    let mut count = 0;
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '-' {
+            if count == 0 || count % 4 != 0 || matches!(chars.peek(), None | Some('-')) {
+                return None;
+            }
+            continue;
+        }
+        value = (value << 4) | u128::from(c.to_digit(16)?);
+        count += 1;
+        if count > 32 {
+            return None;
+        }
+    }
+    (count == 32).then_some(value)
+}
+
+/// `date` input: `YYYY-MM-DD`, optionally followed by a time that is ignored
+///
+/// Returns days since 1970-01-01.
+pub(crate) fn parse_date(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let (days, rest) = date_prefix(text)?;
+    match rest.chars().next() {
+        None => Some(days),
+        Some('T' | 't' | ' ') if parse_timestamp(text).is_some() => Some(days),
+        _ => None,
+    }
+}
+
+/// `timestamptz` input: `YYYY-MM-DD[( |T)HH:MM[:SS[.fraction]]][Z|±HH[[:]MM]]`
+///
+/// Returns microseconds since the Unix epoch. Input without an offset is read
+/// as UTC.
+pub(crate) fn parse_timestamp(text: &str) -> Option<i128> {
+    let text = text.trim();
+    let (days, rest) = date_prefix(text)?;
+    let mut micros = i128::from(days) * 86_400_000_000;
+    let rest = match rest.strip_prefix(['T', 't', ' ']) {
+        Some(time) => {
+            let (time_micros, rest) = time_of_day(time)?;
+            micros += time_micros;
+            rest
+        }
+        None => rest,
+    };
+    let offset = match rest.trim_start() {
+        "" => 0,
+        "Z" | "z" => 0,
+        zone => {
+            let negative = zone.starts_with('-');
+            let zone = zone.strip_prefix(['+', '-'])?;
+            let (hours, minutes) = match zone.len() {
+                2 => (zone, "00"),
+                4 => (zone.get(..2)?, zone.get(2..)?),
+                5 if zone.get(2..3) == Some(":") => (&zone[..2], &zone[3..]),
+                _ => return None,
+            };
+            let seconds =
+                i128::from(two_digits(hours)?) * 3600 + i128::from(two_digits(minutes)?) * 60;
+            if negative {
+                -seconds
+            } else {
+                seconds
+            }
+        }
+    };
+    Some(micros - offset * 1_000_000)
+}
+
+/// Leading `YYYY-MM-DD` as days since the epoch, and the remaining text
+fn date_prefix(text: &str) -> Option<(i64, &str)> {
+    let bytes = text.as_bytes();
+    if bytes.len() < 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    let year = text
+        .get(..4)
+        .filter(|y| y.bytes().all(|b| b.is_ascii_digit()))?;
+    let year: i64 = year.parse().ok()?;
+    let month = two_digits(text.get(5..7)?)?;
+    let day = two_digits(text.get(8..10)?)?;
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let month_days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if day == 0 || day > month_days {
+        return None;
+    }
+    Some((days_from_civil(year, month, day), &text[10..]))
+}
+
+/// `HH:MM[:SS[.fraction]]` as microseconds, and the remaining text
+fn time_of_day(text: &str) -> Option<(i128, &str)> {
+    let bytes = text.as_bytes();
+    if bytes.len() < 5 || bytes[2] != b':' {
+        return None;
+    }
+    let hours = two_digits(text.get(..2)?)?;
+    let minutes = two_digits(text.get(3..5)?)?;
+    let mut rest = &text[5..];
+    let mut seconds = 0;
+    let mut fraction = 0;
+    if let Some(after) = rest.strip_prefix(':') {
+        seconds = two_digits(after.get(..2)?)?;
+        rest = &after[2..];
+        if let Some(after) = rest.strip_prefix('.') {
+            let len = after.bytes().take_while(u8::is_ascii_digit).count();
+            if len == 0 {
+                return None;
+            }
+            // Round to microseconds
+            let digits = format!("{:0<7}", &after[..len.min(7)]);
+            fraction = (digits.parse::<i128>().ok()? + 5) / 10;
+            rest = &after[len..];
+        }
+    }
+    let end_of_day = hours == 24 && minutes == 0 && seconds == 0 && fraction == 0;
+    if (hours > 23 && !end_of_day) || minutes > 59 || seconds > 60 {
+        return None;
+    }
+    let micros = (i128::from(hours) * 3600 + i128::from(minutes) * 60 + i128::from(seconds))
+        * 1_000_000
+        + fraction;
+    Some((micros, rest))
+}
+
+fn two_digits(text: &str) -> Option<u32> {
+    if text.len() != 2 || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal_order() {
+        let d = |s: &str| parse_decimal(s).unwrap_or_else(|| panic!("{}", s));
+        assert_eq!(d("12.50"), d("1.25e1"));
+        assert_eq!(d("-0.0"), d("0"));
+        assert!(d("9007199254740993") > d("9007199254740992"));
+        assert!(d("-2") < d("-1.5"));
+        assert!(d("0.001") < d("0.01"));
+        assert!(d("-0.5") < d("0"));
+        assert!(d(" 10 ") > d("9.999"));
+        for bad in ["", "-", ".", "1e", "1.2.3", "NaN", "1 2", "0x10"] {
+            assert!(parse_decimal(bad).is_none(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_int8() {
+        assert_eq!(parse_int8(" +42 "), Some(42));
+        assert_eq!(parse_int8("-9223372036854775808"), Some(i64::MIN));
+        assert_eq!(parse_int8("9223372036854775808"), None);
+        assert_eq!(parse_int8("1.0"), None);
+    }
+
+    #[test]
+    fn test_uuid() {
+        let canonical = parse_uuid("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11");
+        assert!(canonical.is_some());
+        assert_eq!(
+            parse_uuid("{A0EEBC99-9C0B4EF8-BB6D6BB9-BD380A11}"),
+            canonical
+        );
+        assert_eq!(parse_uuid("a0eebc999c0b4ef8bb6d6bb9bd380a11"), canonical);
+        assert_eq!(parse_uuid("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a1"), None);
+        assert_eq!(parse_uuid("a0eeb-c999c0b4ef8bb6d6bb9bd380a11"), None);
+        assert_eq!(parse_uuid("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11-"), None);
+    }
+
+    #[test]
+    fn test_dates_and_timestamps() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2000-03-01"), Some(11_017));
+        assert_eq!(
+            parse_date("2024-02-29T23:00:00-05:00"),
+            parse_date("2024-02-29")
+        );
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2024-1-02"), None);
+
+        let ts = |s: &str| parse_timestamp(s).unwrap_or_else(|| panic!("{}", s));
+        assert_eq!(ts("1970-01-01T00:00:01Z"), 1_000_000);
+        assert_eq!(ts("2024-01-02 10:00:00+02"), ts("2024-01-02T08:00:00Z"));
+        assert_eq!(ts("2024-01-02T10:00-0130"), ts("2024-01-02T11:30:00"));
+        assert_eq!(ts("2024-01-02T00:00:00.0000005"), ts("2024-01-02") + 1);
+        assert_eq!(ts("2024-01-01T24:00:00"), ts("2024-01-02"));
+        for bad in [
+            "2024-01-02T25:00",
+            "2024-01-02T10",
+            "2024-01-02Tx",
+            "2024-01-02 +5",
+        ] {
+            assert!(parse_timestamp(bad).is_none(), "{}", bad);
+        }
+    }
+}
@@ -9,25 +9,37 @@
 //! JSONB fields extracted with `->>` are always text. When comparing with non-string values,
 //! we apply explicit type casting:
 //!
-//! - String comparisons: No cast needed (text = text)
-//! - Numeric comparisons: Cast to integer or float (text::integer > $1)
+//! - String comparisons: No cast needed (text = text); `<`, `<=`, `>` and `>=`
+//!   compare in `COLLATE "C"` (byte order), not the database collation
+//! - Numeric comparisons: Cast to numeric, or bigint for `Value::Int` (text::numeric > $1)
 //! - Boolean comparisons: Cast to boolean (text::boolean = true)
-//! - Array comparisons: No special handling (uses array operators)
+//! - UUID, timestamp and date comparisons: Cast to uuid, timestamptz or date
+//! - JSON comparisons: No cast; the field is extracted as jsonb with `->` (jsonb = $1::jsonb)
+//! - Array operators: JSONB arrays are expanded to `text[]` with
+//!   `jsonb_array_elements_text`, and operands are cast to `text[]`
 //!
 //! Direct columns use native types from the database schema.
 
+use super::text_search::DEFAULT_LANGUAGE;
 use super::{Field, Value, WhereOperator};
 use crate::Result;
 use std::collections::HashMap;
 
 /// Infers the PostgreSQL type cast needed for a value
 ///
-/// Returns the type cast suffix (e.g., "::integer", "::text") if needed
+/// Returns the type cast suffix (e.g., "::numeric", "::uuid") applied to JSONB
+/// text compared with the value
 fn infer_type_cast(value: &Value) -> &'static str {
     match value {
-        Value::String(_) => "::text",
-        Value::Number(_) => "::numeric", // numeric handles both int and float
+        Value::String(_) => "",           // text = text
+        Value::Number(_) => "::numeric",  // numeric handles both int and float
+        Value::Int(_) => "::bigint",      // exact 64-bit integers
+        Value::Decimal(_) => "::numeric", // exact decimals
         Value::Bool(_) => "::boolean",
+        Value::Uuid(_) => "::uuid",
+        Value::Timestamp(_) => "::timestamptz",
+        Value::Date(_) => "::date",
+        Value::Json(_) => "",       // compared as jsonb (see `operand`)
         Value::Null => "",          // no cast for NULL
         Value::Array(_) => "",      // arrays handled by operators
         Value::FloatArray(_) => "", // vector operators handle their own casting
@@ -35,6 +47,133 @@ fn infer_type_cast(value: &Value) -> &'static str {
     }
 }
 
+/// Bind `value` as the next parameter and return its placeholder
+fn bind(value: Value, param_index: &mut usize, params: &mut HashMap<usize, Value>) -> String {
+    *param_index += 1;
+    let placeholder = match value {
+        Value::Json(_) => format!("${}::jsonb", param_index),
+        _ => format!("${}", param_index),
+    };
+    params.insert(*param_index, value);
+    placeholder
+}
+
+/// Left operand for comparing `field` with `value`
+///
+/// JSONB fields are extracted as text (`->>`) and cast to the value's type,
+/// except for `Value::Json`, which compares the `jsonb` value (`->`). Direct
+/// columns use native types.
+fn operand(field: &Field, value: &Value) -> String {
+    match (field, value) {
+        (Field::DirectColumn(_), _) => field.to_sql(),
+        (_, Value::Json(_)) => field.to_jsonb_sql(),
+        _ => format!("{}{}", field.to_sql(), infer_type_cast(value)),
+    }
+}
+
+fn comparison(
+    field: &Field,
+    op: &str,
+    value: &Value,
+    param_index: &mut usize,
+    params: &mut HashMap<usize, Value>,
+) -> String {
+    let lhs = operand(field, value);
+    let placeholder = bind(value.clone(), param_index, params);
+    format!("{} {} {}", lhs, op, placeholder)
+}
+
+/// Ordering comparison; JSONB text is compared byte-wise (`COLLATE "C"`), so
+/// the result does not depend on the database collation
+fn range(
+    field: &Field,
+    op: &str,
+    value: &Value,
+    param_index: &mut usize,
+    params: &mut HashMap<usize, Value>,
+) -> String {
+    if !matches!(value, Value::String(_)) || matches!(field, Field::DirectColumn(_)) {
+        return comparison(field, op, value, param_index, params);
+    }
+    let lhs = operand(field, value);
+    let placeholder = bind(value.clone(), param_index, params);
+    format!("{} COLLATE \"C\" {} {}", lhs, op, placeholder)
+}
+
+/// `field [NOT] IN (values)`
+///
+/// Values needing different casts expand to `(a = $1 OR b = $2)`, which has the
+/// same NULL semantics.
+fn in_list(
+    field: &Field,
+    values: &[Value],
+    negate: bool,
+    param_index: &mut usize,
+    params: &mut HashMap<usize, Value>,
+) -> Result<String> {
+    if values.is_empty() {
+        return Err(crate::Error::InvalidSchema(
+            "IN requires at least one value".into(),
+        ));
+    }
+    let mut operands: Vec<String> = values
+        .iter()
+        .filter(|v| !v.is_null())
+        .map(|v| operand(field, v))
+        .collect();
+    operands.dedup();
+    if operands.len() <= 1 {
+        let lhs = operands.pop().unwrap_or_else(|| field.to_sql());
+        let placeholders: Vec<String> = values
+            .iter()
+            .map(|v| bind(v.clone(), param_index, params))
+            .collect();
+        let not = if negate { "NOT " } else { "" };
+        return Ok(format!("{} {}IN ({})", lhs, not, placeholders.join(", ")));
+    }
+    let terms: Vec<String> = values
+        .iter()
+        .map(|v| comparison(field, "=", v, param_index, params))
+        .collect();
+    let any = format!("({})", terms.join(" OR "));
+    Ok(if negate { format!("NOT {}", any) } else { any })
+}
+
+/// `value` nested under `path`: `{"a": {"b": value}}`
+fn nest(path: &[String], value: &serde_json::Value) -> serde_json::Value {
+    path.iter().rev().fold(value.clone(), |inner, key| {
+        let mut object = serde_json::Map::new();
+        object.insert(key.clone(), inner);
+        serde_json::Value::Object(object)
+    })
+}
+
+/// Elements of an array field as `text[]`
+///
+/// A missing key or JSON `null` is SQL NULL; a scalar or object fails like
+/// `jsonb_array_elements_text`.
+fn array_sql(field: &Field) -> String {
+    match field {
+        Field::DirectColumn(name) => name.clone(),
+        _ => {
+            let json = field.to_jsonb_sql();
+            format!(
+                "(CASE WHEN jsonb_typeof({0}) <> 'null' \
+                 THEN ARRAY(SELECT jsonb_array_elements_text({0})) END)",
+                json
+            )
+        }
+    }
+}
+
+/// Cast of array operands compared with `array_sql`
+fn array_cast(field: &Field) -> &'static str {
+    match field {
+        Field::DirectColumn(_) => "", // direct columns use native types
+        _ => "::text[]",
+    }
+}
+
 /// Generates SQL from a WHERE operator with parameter binding support
 ///
 /// # Parameters
@@ -54,7 +193,7 @@ fn infer_type_cast(value: &Value) -> &'static str {
 /// let mut params = HashMap::new();
 /// let op = WhereOperator::Eq(Field::JsonbField("name".to_string()), Value::String("John".to_string()));
 /// let sql = generate_where_operator_sql(&op, &mut param_index, &mut params)?;
-/// assert_eq!(sql, "(data->'name') = $1");
+/// assert_eq!(sql, "(data->>'name') = $1");
 /// assert_eq!(params[&1], Value::String("John".to_string()));
 /// ```
 pub fn generate_where_operator_sql(
@@ -67,190 +206,93 @@ pub fn generate_where_operator_sql(
     match operator {
         // ============ Comparison Operators ============
         // These operators work on both JSONB and direct columns.
-        // For JSONB text extraction, we apply type casting for proper comparison.
+        // JSONB text is cast to the type of the value (see `operand`).
         WhereOperator::Eq(field, value) => {
-            let field_sql = field.to_sql();
             if value.is_null() {
-                Ok(format!("{} IS NULL", field_sql))
+                Ok(format!("{} IS NULL", field.to_sql()))
             } else {
-                let param_num = *param_index + 1;
-                *param_index += 1;
-                params.insert(param_num, value.clone());
-                // JSONB fields need type cast for non-string comparisons
-                let cast = match field {
-                    Field::JsonbField(_) | Field::JsonbPath(_) => infer_type_cast(value),
-                    Field::DirectColumn(_) => "", // direct columns use native types
-                };
-                Ok(format!("{}{} = ${}", field_sql, cast, param_num))
+                Ok(comparison(field, "=", value, param_index, params))
             }
         }
 
         WhereOperator::Neq(field, value) => {
-            let field_sql = field.to_sql();
             if value.is_null() {
-                Ok(format!("{} IS NOT NULL", field_sql))
+                Ok(format!("{} IS NOT NULL", field.to_sql()))
             } else {
-                let param_num = *param_index + 1;
-                *param_index += 1;
-                params.insert(param_num, value.clone());
-                let cast = match field {
-                    Field::JsonbField(_) | Field::JsonbPath(_) => infer_type_cast(value),
-                    Field::DirectColumn(_) => "",
-                };
-                Ok(format!("{}{} != ${}", field_sql, cast, param_num))
+                Ok(comparison(field, "!=", value, param_index, params))
             }
         }
 
-        WhereOperator::Gt(field, value) => {
-            let field_sql = field.to_sql();
-            let param_num = *param_index + 1;
-            *param_index += 1;
-            params.insert(param_num, value.clone());
-            let cast = match field {
-                Field::JsonbField(_) | Field::JsonbPath(_) => infer_type_cast(value),
-                Field::DirectColumn(_) => "",
-            };
-            Ok(format!("{}{} > ${}", field_sql, cast, param_num))
-        }
-
-        WhereOperator::Gte(field, value) => {
-            let field_sql = field.to_sql();
-            let param_num = *param_index + 1;
-            *param_index += 1;
-            params.insert(param_num, value.clone());
-            let cast = match field {
-                Field::JsonbField(_) | Field::JsonbPath(_) => infer_type_cast(value),
-                Field::DirectColumn(_) => "",
-            };
-            Ok(format!("{}{} >= ${}", field_sql, cast, param_num))
-        }
-
-        WhereOperator::Lt(field, value) => {
-            let field_sql = field.to_sql();
-            let param_num = *param_index + 1;
-            *param_index += 1;
-            params.insert(param_num, value.clone());
-            let cast = match field {
-                Field::JsonbField(_) | Field::JsonbPath(_) => infer_type_cast(value),
-                Field::DirectColumn(_) => "",
-            };
-            Ok(format!("{}{} < ${}", field_sql, cast, param_num))
-        }
-
-        WhereOperator::Lte(field, value) => {
-            let field_sql = field.to_sql();
-            let param_num = *param_index + 1;
-            *param_index += 1;
-            params.insert(param_num, value.clone());
-            let cast = match field {
-                Field::JsonbField(_) | Field::JsonbPath(_) => infer_type_cast(value),
-                Field::DirectColumn(_) => "",
-            };
-            Ok(format!("{}{} <= ${}", field_sql, cast, param_num))
-        }
+        WhereOperator::Gt(field, value) => Ok(range(field, ">", value, param_index, params)),
+        WhereOperator::Gte(field, value) => Ok(range(field, ">=", value, param_index, params)),
+        WhereOperator::Lt(field, value) => Ok(range(field, "<", value, param_index, params)),
+        WhereOperator::Lte(field, value) => Ok(range(field, "<=", value, param_index, params)),
 
         // ============ Array Operators ============
-        WhereOperator::In(field, values) => {
-            let field_sql = field.to_sql();
-            let placeholders: Vec<String> = values
-                .iter()
-                .map(|v| {
-                    let param_num = *param_index + 1;
-                    *param_index += 1;
-                    params.insert(param_num, v.clone());
-                    format!("${}", param_num)
-                })
-                .collect();
-            Ok(format!("{} IN ({})", field_sql, placeholders.join(", ")))
-        }
-
-        WhereOperator::Nin(field, values) => {
-            let field_sql = field.to_sql();
-            let placeholders: Vec<String> = values
-                .iter()
-                .map(|v| {
-                    let param_num = *param_index + 1;
-                    *param_index += 1;
-                    params.insert(param_num, v.clone());
-                    format!("${}", param_num)
-                })
-                .collect();
-            Ok(format!(
-                "{} NOT IN ({})",
-                field_sql,
-                placeholders.join(", ")
-            ))
-        }
+        WhereOperator::In(field, values) => in_list(field, values, false, param_index, params),
+        WhereOperator::Nin(field, values) => in_list(field, values, true, param_index, params),
 
         WhereOperator::Contains(field, substring) => {
             let field_sql = field.to_sql();
-            let param_num = *param_index + 1;
-            *param_index += 1;
-            params.insert(param_num, Value::String(substring.clone()));
+            let placeholder = bind(Value::String(substring.clone()), param_index, params);
             Ok(format!(
-                "{} LIKE '%' || ${}::text || '%'",
-                field_sql, param_num
+                "{} LIKE '%' || {}::text || '%'",
+                field_sql, placeholder
             ))
         }
 
         WhereOperator::ArrayContains(field, value) => {
-            let field_sql = field.to_sql();
-            let param_num = *param_index + 1;
-            *param_index += 1;
-            params.insert(param_num, value.clone());
-            Ok(format!("{} @> ARRAY[${}]", field_sql, param_num))
+            let placeholder = bind(value.clone(), param_index, params);
+            Ok(format!(
+                "{} @> ARRAY[{}]{}",
+                array_sql(field),
+                placeholder,
+                array_cast(field)
+            ))
         }
 
         WhereOperator::ArrayContainedBy(field, value) => {
-            let field_sql = field.to_sql();
-            let param_num = *param_index + 1;
-            *param_index += 1;
-            params.insert(param_num, value.clone());
-            Ok(format!("{} <@ ARRAY[${}]", field_sql, param_num))
+            let placeholder = bind(value.clone(), param_index, params);
+            Ok(format!(
+                "{} <@ ARRAY[{}]{}",
+                array_sql(field),
+                placeholder,
+                array_cast(field)
+            ))
         }
 
         WhereOperator::ArrayOverlaps(field, values) => {
-            let field_sql = field.to_sql();
             let placeholders: Vec<String> = values
                 .iter()
-                .map(|v| {
-                    let param_num = *param_index + 1;
-                    *param_index += 1;
-                    params.insert(param_num, v.clone());
-                    format!("${}", param_num)
-                })
+                .map(|v| bind(v.clone(), param_index, params))
                 .collect();
             Ok(format!(
-                "{} && ARRAY[{}]",
-                field_sql,
-                placeholders.join(", ")
+                "{} && ARRAY[{}]{}",
+                array_sql(field),
+                placeholders.join(", "),
+                array_cast(field)
             ))
         }
 
         // ============ Array Length Operators ============
         WhereOperator::LenEq(field, len) => {
-            let field_sql = field.to_sql();
-            Ok(format!("array_length({}, 1) = {}", field_sql, len))
+            Ok(format!("array_length({}, 1) = {}", array_sql(field), len))
         }
 
         WhereOperator::LenGt(field, len) => {
-            let field_sql = field.to_sql();
-            Ok(format!("array_length({}, 1) > {}", field_sql, len))
+            Ok(format!("array_length({}, 1) > {}", array_sql(field), len))
         }
 
         WhereOperator::LenGte(field, len) => {
-            let field_sql = field.to_sql();
-            Ok(format!("array_length({}, 1) >= {}", field_sql, len))
+            Ok(format!("array_length({}, 1) >= {}", array_sql(field), len))
         }
 
         WhereOperator::LenLt(field, len) => {
-            let field_sql = field.to_sql();
-            Ok(format!("array_length({}, 1) < {}", field_sql, len))
+            Ok(format!("array_length({}, 1) < {}", array_sql(field), len))
         }
 
         WhereOperator::LenLte(field, len) => {
-            let field_sql = field.to_sql();
-            Ok(format!("array_length({}, 1) <= {}", field_sql, len))
+            Ok(format!("array_length({}, 1) <= {}", array_sql(field), len))
         }
 
         // ============ String Operators ============
@@ -358,7 +400,7 @@ pub fn generate_where_operator_sql(
             set,
             threshold,
         } => {
-            let field_sql = field.to_sql();
+            let field_sql = array_sql(field);
             let param_num = *param_index + 1;
             *param_index += 1;
             let value_array: Vec<Value> = set.iter().map(|s| Value::String(s.clone())).collect();
@@ -379,7 +421,7 @@ pub fn generate_where_operator_sql(
             let param_num = *param_index + 1;
             *param_index += 1;
             params.insert(param_num, Value::String(query.clone()));
-            let lang = language.as_deref().unwrap_or("english");
+            let lang = language.as_deref().unwrap_or(DEFAULT_LANGUAGE);
             Ok(format!(
                 "{} @@ plainto_tsquery('{}', ${})",
                 field_sql, lang, param_num
@@ -391,10 +433,7 @@ pub fn generate_where_operator_sql(
             let param_num = *param_index + 1;
             *param_index += 1;
             params.insert(param_num, Value::String(query.clone()));
-            Ok(format!(
-                "{} @@ plainto_tsquery(${})::tsvector",
-                field_sql, param_num
-            ))
+            Ok(format!("{} @@ plainto_tsquery(${})", field_sql, param_num))
         }
 
         WhereOperator::PhraseQuery {
@@ -406,7 +445,7 @@ pub fn generate_where_operator_sql(
             let param_num = *param_index + 1;
             *param_index += 1;
             params.insert(param_num, Value::String(query.clone()));
-            let lang = language.as_deref().unwrap_or("english");
+            let lang = language.as_deref().unwrap_or(DEFAULT_LANGUAGE);
             Ok(format!(
                 "{} @@ phraseto_tsquery('{}', ${})",
                 field_sql, lang, param_num
@@ -422,7 +461,7 @@ pub fn generate_where_operator_sql(
             let param_num = *param_index + 1;
             *param_index += 1;
             params.insert(param_num, Value::String(query.clone()));
-            let lang = language.as_deref().unwrap_or("english");
+            let lang = language.as_deref().unwrap_or(DEFAULT_LANGUAGE);
             Ok(format!(
                 "{} @@ websearch_to_tsquery('{}', ${})",
                 field_sql, lang, param_num
@@ -488,6 +527,85 @@ pub fn generate_where_operator_sql(
             params.insert(param_num, Value::String(range.clone()));
             Ok(format!("{}::inet && ${}::inet", field_sql, param_num))
         }
+
+        // ============ JSONB Operators ============
+        WhereOperator::JsonContains(field, value) => {
+            // Nest the value under the field so the test runs against `data`
+            let (target, document) = match field {
+                Field::DirectColumn(_) => (field.to_sql(), value.clone()),
+                Field::JsonbField(name) => {
+                    ("data".to_string(), nest(std::slice::from_ref(name), value))
+                }
+                Field::JsonbPath(path) => ("data".to_string(), nest(path, value)),
+            };
+            let placeholder = bind(Value::Json(document), param_index, params);
+            Ok(format!("{} @> {}", target, placeholder))
+        }
+
+        WhereOperator::HasKey(field, key) => {
+            let placeholder = bind(Value::String(key.clone()), param_index, params);
+            Ok(format!("{} ? {}", field.to_jsonb_sql(), placeholder))
+        }
+
+        WhereOperator::HasAnyKey(field, keys) | WhereOperator::HasAllKeys(field, keys) => {
+            let op = match operator {
+                WhereOperator::HasAnyKey(..) => "?|",
+                _ => "?&",
+            };
+            let keys = keys.iter().map(|k| Value::String(k.clone())).collect();
+            let placeholder = bind(Value::Array(keys), param_index, params);
+            Ok(format!(
+                "{} {} {}::text[]",
+                field.to_jsonb_sql(),
+                op,
+                placeholder
+            ))
+        }
+
+        WhereOperator::JsonPathExists { field, path, vars }
+        | WhereOperator::JsonPathMatch { field, path, vars } => {
+            let (op, function) = match operator {
+                WhereOperator::JsonPathExists { .. } => ("@?", "jsonb_path_exists"),
+                _ => ("@@", "jsonb_path_match"),
+            };
+            let field_sql = field.to_jsonb_sql();
+            let path = bind(Value::String(path.clone()), param_index, params);
+            match vars {
+                None => Ok(format!("{} {} {}::jsonpath", field_sql, op, path)),
+                Some(vars) => {
+                    let vars = bind(Value::Json(vars.clone()), param_index, params);
+                    // silent => true: errors yield no match, as with the operator
+                    Ok(format!(
+                        "{}({}, {}::jsonpath, {}, true)",
+                        function, field_sql, path, vars
+                    ))
+                }
+            }
+        }
+
+        // ============ Logical Operators ============
+        WhereOperator::And(ops) | WhereOperator::Or(ops) => {
+            let (joiner, empty) = match operator {
+                WhereOperator::And(_) => (" AND ", "TRUE"),
+                _ => (" OR ", "FALSE"),
+            };
+            if ops.is_empty() {
+                return Ok(empty.to_string());
+            }
+            let parts = ops
+                .iter()
+                .map(|op| {
+                    generate_where_operator_sql(op, param_index, params)
+                        .map(|sql| format!("({})", sql))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(parts.join(joiner))
+        }
+
+        WhereOperator::Not(op) => {
+            let sql = generate_where_operator_sql(op, param_index, params)?;
+            Ok(format!("NOT ({})", sql))
+        }
     }
 }
 
@@ -504,8 +622,8 @@ mod tests {
             Value::String("John".to_string()),
         );
         let sql = generate_where_operator_sql(&op, &mut param_index, &mut params).unwrap();
-        // JSONB string fields get ::text cast for proper text comparison
-        assert_eq!(sql, "(data->'name')::text = $1");
+        // JSONB fields are extracted as text; strings need no cast
+        assert_eq!(sql, "(data->>'name') = $1");
         assert_eq!(param_index, 1);
     }
 
@@ -529,7 +647,11 @@ mod tests {
         let mut params = HashMap::new();
         let op = WhereOperator::LenEq(Field::JsonbField("tags".to_string()), 5);
         let sql = generate_where_operator_sql(&op, &mut param_index, &mut params).unwrap();
-        assert_eq!(sql, "array_length((data->'tags'), 1) = 5");
+        assert_eq!(
+            sql,
+            "array_length((CASE WHEN jsonb_typeof((data->'tags')) <> 'null' \
+             THEN ARRAY(SELECT jsonb_array_elements_text((data->'tags'))) END), 1) = 5"
+        );
         assert_eq!(param_index, 0); // No parameters for length operators
     }
 
@@ -539,7 +661,7 @@ mod tests {
         let mut params = HashMap::new();
         let op = WhereOperator::IsIPv4(Field::JsonbField("ip".to_string()));
         let sql = generate_where_operator_sql(&op, &mut param_index, &mut params).unwrap();
-        assert_eq!(sql, "family((data->'ip')::inet) = 4");
+        assert_eq!(sql, "family((data->>'ip')::inet) = 4");
     }
 
     #[test]
@@ -554,7 +676,7 @@ mod tests {
         let sql = generate_where_operator_sql(&op, &mut param_index, &mut params).unwrap();
         assert_eq!(
             sql,
-            "l2_distance((data->'embedding')::vector, $1::vector) < 0.5"
+            "l2_distance((data->>'embedding')::vector, $1::vector) < 0.5"
         );
         assert_eq!(param_index, 1);
     }
@@ -571,7 +693,188 @@ mod tests {
             ],
         );
         let sql = generate_where_operator_sql(&op, &mut param_index, &mut params).unwrap();
-        assert_eq!(sql, "(data->'status') IN ($1, $2)");
+        assert_eq!(sql, "(data->>'status') IN ($1, $2)");
         assert_eq!(param_index, 2);
     }
+
+    #[test]
+    fn test_logical_operators() {
+        let mut param_index = 0;
+        let mut params = HashMap::new();
+        let op = WhereOperator::Or(vec![
+            WhereOperator::IsLoopback(Field::JsonbField("ip".to_string())),
+            WhereOperator::Not(Box::new(WhereOperator::And(vec![
+                WhereOperator::Gt(Field::JsonbField("n".to_string()), Value::Number(1.0)),
+                WhereOperator::IsNull(Field::JsonbField("m".to_string()), true),
+            ]))),
+        ]);
+        let sql = generate_where_operator_sql(&op, &mut param_index, &mut params).unwrap();
+        assert_eq!(
+            sql,
+            "((family((data->>'ip')::inet) = 4 AND (data->>'ip')::inet << '127.0.0.0/8'::inet) \
+             OR (family((data->>'ip')::inet) = 6 AND (data->>'ip')::inet << '::1/128'::inet)) \
+             OR (NOT (((data->>'n')::numeric > $1) AND ((data->>'m') IS NULL)))"
+        );
+        assert_eq!(param_index, 1);
+
+        let empty = WhereOperator::Or(vec![]);
+        let sql = generate_where_operator_sql(&empty, &mut param_index, &mut params).unwrap();
+        assert_eq!(sql, "FALSE");
+    }
+
+    #[test]
+    fn test_typed_value_casts() {
+        let sql = |op: &WhereOperator| {
+            let mut param_index = 0;
+            let mut params = HashMap::new();
+            generate_where_operator_sql(op, &mut param_index, &mut params).unwrap()
+        };
+        let f = || Field::JsonbField("v".to_string());
+        assert_eq!(
+            sql(&WhereOperator::Eq(f(), Value::Int(9_007_199_254_740_993))),
+            "(data->>'v')::bigint = $1"
+        );
+        assert_eq!(
+            sql(&WhereOperator::Gte(f(), Value::Decimal("12.50".into()))),
+            "(data->>'v')::numeric >= $1"
+        );
+        assert_eq!(
+            sql(&WhereOperator::Eq(
+                f(),
+                Value::Uuid("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".into())
+            )),
+            "(data->>'v')::uuid = $1"
+        );
+        assert_eq!(
+            sql(&WhereOperator::Lt(
+                Field::JsonbPath(vec!["meta".into(), "created_at".into()]),
+                Value::Timestamp("2024-01-02T00:00:00Z".into())
+            )),
+            "(data->'meta'->>'created_at')::timestamptz < $1"
+        );
+        assert_eq!(
+            sql(&WhereOperator::Eq(f(), Value::Date("2024-01-02".into()))),
+            "(data->>'v')::date = $1"
+        );
+        assert_eq!(
+            sql(&WhereOperator::Eq(
+                f(),
+                Value::Json(serde_json::json!({"a": 1}))
+            )),
+            "(data->'v') = $1::jsonb"
+        );
+
+        // Text ordering is byte-wise whatever the database collation
+        assert_eq!(
+            sql(&WhereOperator::Gt(f(), Value::String("m".into()))),
+            "(data->>'v') COLLATE \"C\" > $1"
+        );
+        assert_eq!(
+            sql(&WhereOperator::Lte(
+                Field::DirectColumn("name".into()),
+                Value::String("m".into())
+            )),
+            "name <= $1"
+        );
+
+        // Values needing different casts expand to equalities
+        assert_eq!(
+            sql(&WhereOperator::Nin(
+                f(),
+                vec![Value::Int(1), Value::String("x".into()), Value::Null]
+            )),
+            "NOT ((data->>'v')::bigint = $1 OR (data->>'v') = $2 OR (data->>'v') = $3)"
+        );
+
+        // Invalid text forms are rejected before any SQL is generated
+        let mut param_index = 0;
+        let mut params = HashMap::new();
+        let op = WhereOperator::Eq(f(), Value::Date("2024-13-01".into()));
+        assert!(generate_where_operator_sql(&op, &mut param_index, &mut params).is_err());
+    }
+
+    #[test]
+    fn test_jsonb_operators() {
+        use serde_json::json;
+        let generate = |op: &WhereOperator| {
+            let mut param_index = 0;
+            let mut params = HashMap::new();
+            let sql = generate_where_operator_sql(op, &mut param_index, &mut params).unwrap();
+            (sql, params)
+        };
+        let path = || Field::JsonbPath(vec!["owner".into(), "team".into()]);
+
+        // Containment is tested against `data` so GIN indexes apply
+        let (sql, params) = generate(&WhereOperator::JsonContains(path(), json!({"id": 1})));
+        assert_eq!(sql, "data @> $1::jsonb");
+        assert!(
+            matches!(params.get(&1), Some(Value::Json(v)) if *v == json!({"owner": {"team": {"id": 1}}}))
+        );
+        let (sql, _) = generate(&WhereOperator::JsonContains(
+            Field::DirectColumn("doc".into()),
+            json!(["a"]),
+        ));
+        assert_eq!(sql, "doc @> $1::jsonb");
+
+        let (sql, params) = generate(&WhereOperator::HasKey(Field::JsonbPath(vec![]), "k".into()));
+        assert_eq!(sql, "data ? $1");
+        assert!(matches!(params.get(&1), Some(Value::String(k)) if k == "k"));
+        let (sql, _) = generate(&WhereOperator::HasAnyKey(
+            path(),
+            vec!["a".into(), "b".into()],
+        ));
+        assert_eq!(sql, "(data->'owner'->'team') ?| $1::text[]");
+        let (sql, _) = generate(&WhereOperator::HasAllKeys(path(), vec!["a".into()]));
+        assert_eq!(sql, "(data->'owner'->'team') ?& $1::text[]");
+
+        let (sql, _) = generate(&WhereOperator::JsonPathExists {
+            field: Field::JsonbPath(vec![]),
+            path: "$.tags[*] ? (@ == \"rust\")".into(),
+            vars: None,
+        });
+        assert_eq!(sql, "data @? $1::jsonpath");
+        let (sql, params) = generate(&WhereOperator::JsonPathMatch {
+            field: Field::JsonbPath(vec![]),
+            path: "$.score > $min".into(),
+            vars: Some(json!({"min": 10})),
+        });
+        assert_eq!(sql, "jsonb_path_match(data, $1::jsonpath, $2::jsonb, true)");
+        assert!(matches!(params.get(&2), Some(Value::Json(v)) if *v == json!({"min": 10})));
+
+        // Empty key lists and non-object vars are rejected
+        let mut param_index = 0;
+        let mut params = HashMap::new();
+        let op = WhereOperator::HasAnyKey(path(), vec![]);
+        assert!(generate_where_operator_sql(&op, &mut param_index, &mut params).is_err());
+        let op = WhereOperator::JsonPathExists {
+            field: path(),
+            path: "$.a".into(),
+            vars: Some(json!([1])),
+        };
+        assert!(generate_where_operator_sql(&op, &mut param_index, &mut params).is_err());
+    }
+
+    #[test]
+    fn test_text_search_operators() {
+        let mut param_index = 0;
+        let mut params = HashMap::new();
+        let body = || Field::JsonbField("body".to_string());
+
+        let op = WhereOperator::PlainQuery {
+            field: body(),
+            query: "rust".into(),
+        };
+        assert_eq!(
+            generate_where_operator_sql(&op, &mut param_index, &mut params).unwrap(),
+            "(data->>'body') @@ plainto_tsquery($1)"
+        );
+
+        // The configuration name is interpolated, so it must be validated
+        let op = WhereOperator::Matches {
+            field: body(),
+            query: "rust".into(),
+            language: Some("english', $1) OR true OR ('".into()),
+        };
+        assert!(generate_where_operator_sql(&op, &mut param_index, &mut params).is_err());
+    }
 }
@@ -0,0 +1,379 @@
+//! Full-text search ranking and highlighting
+//!
+//! [`TextQuery`] describes a `tsquery` the way the full-text WHERE operators
+//! build it: a parser (`plainto_tsquery`, `phraseto_tsquery` or
+//! `websearch_to_tsquery`) and a text search configuration, `english` unless
+//! given. It drives relevance ordering with [`TextRank`]
+//! (`OrderByClause::text_rank`) and snippets with [`Headline`]
+//! (`QueryBuilder::headline`):
+//!
+//! ```ignore
+//! let query = TextQuery::websearch("rust -go").language("simple");
+//!
+//! let stream = client
+//!     .query::<Post>("posts")
+//!     .where_sql("to_tsvector('simple', data->>'body') @@ websearch_to_tsquery('simple', 'rust -go')")
+//!     .order_by_clause(OrderByClause::text_rank("body", TextRank::new(query.clone()).cover_density()))
+//!     .headline("snippet", Headline::new(Field::JsonbField("body".into()), query))
+//!     .execute()
+//!     .await?;
+//! ```
+
+use super::param::ParamBinder;
+use super::{Field, Value};
+
+/// Text search configuration used when none is given
+pub(crate) const DEFAULT_LANGUAGE: &str = "english";
+
+/// Validate a text search configuration name
+///
+/// Configuration names are interpolated as `regconfig` literals, so only
+/// ASCII letters, digits and underscores are accepted.
+pub(crate) fn validate_language(language: &str) -> Result<(), String> {
+    if language.is_empty()
+        || !language
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(format!(
+            "Invalid text search configuration name: {}",
+            language
+        ));
+    }
+    Ok(())
+}
+
+/// Function that parses the query text into a `tsquery`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueryParser {
+    /// `plainto_tsquery`: all words must match (as `WhereOperator::Matches`)
+    #[default]
+    Plain,
+
+    /// `phraseto_tsquery`: words must match in order (as `PhraseQuery`)
+    Phrase,
+
+    /// `websearch_to_tsquery`: quotes, `or` and `-` (as `WebsearchQuery`)
+    Websearch,
+}
+
+impl QueryParser {
+    /// The Postgres function name
+    pub fn function(&self) -> &'static str {
+        match self {
+            QueryParser::Plain => "plainto_tsquery",
+            QueryParser::Phrase => "phraseto_tsquery",
+            QueryParser::Websearch => "websearch_to_tsquery",
+        }
+    }
+}
+
+/// A full-text query: text, parser and configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextQuery {
+    /// The search text
+    pub query: String,
+
+    /// How the text is parsed
+    pub parser: QueryParser,
+
+    /// Text search configuration (default: english)
+    pub language: Option<String>,
+}
+
+impl TextQuery {
+    /// A `plainto_tsquery` query
+    pub fn plain(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            parser: QueryParser::Plain,
+            language: None,
+        }
+    }
+
+    /// A `phraseto_tsquery` query
+    pub fn phrase(query: impl Into<String>) -> Self {
+        Self {
+            parser: QueryParser::Phrase,
+            ..Self::plain(query)
+        }
+    }
+
+    /// A `websearch_to_tsquery` query
+    pub fn websearch(query: impl Into<String>) -> Self {
+        Self {
+            parser: QueryParser::Websearch,
+            ..Self::plain(query)
+        }
+    }
+
+    /// Set the text search configuration
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Validate the configuration name
+    pub fn validate(&self) -> Result<(), String> {
+        match self.language {
+            Some(ref language) => validate_language(language),
+            None => Ok(()),
+        }
+    }
+
+    /// The configuration name, quoted as a literal
+    fn language_sql(&self) -> String {
+        format!("'{}'", self.language.as_deref().unwrap_or(DEFAULT_LANGUAGE))
+    }
+
+    /// `parser('language', $n)` with the text bound through `binder`; call
+    /// `validate()` first
+    pub(crate) fn to_sql(&self, binder: &mut ParamBinder) -> String {
+        format!(
+            "{}({}, {})",
+            self.parser.function(),
+            self.language_sql(),
+            binder.bind(Value::String(self.query.clone()))
+        )
+    }
+
+    /// `to_tsvector('language', text)`
+    pub(crate) fn tsvector_sql(&self, text: &str) -> String {
+        format!("to_tsvector({}, {})", self.language_sql(), text)
+    }
+}
+
+/// Relevance ranking for ORDER BY (see `OrderByClause::text_rank`)
+///
+/// Renders `ts_rank([weights,] document, query[, normalization])`, or
+/// `ts_rank_cd` with [`cover_density`](Self::cover_density).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextRank {
+    /// The query to rank against
+    pub query: TextQuery,
+
+    /// Use `ts_rank_cd` (cover density) instead of `ts_rank`
+    pub cover_density: bool,
+
+    /// Weights of lexemes labelled D, C, B and A, each in `0.0..=1.0`
+    /// (Postgres default: `{0.1, 0.2, 0.4, 1.0}`)
+    pub weights: Option<[f32; 4]>,
+
+    /// Document length normalization bit mask (0 to 63, see the Postgres
+    /// `ts_rank` documentation)
+    pub normalization: u32,
+}
+
+impl TextRank {
+    /// Rank with `ts_rank`, default weights and no normalization
+    pub fn new(query: TextQuery) -> Self {
+        Self {
+            query,
+            cover_density: false,
+            weights: None,
+            normalization: 0,
+        }
+    }
+
+    /// Rank with `ts_rank_cd`
+    pub fn cover_density(mut self) -> Self {
+        self.cover_density = true;
+        self
+    }
+
+    /// Set the weights of lexemes labelled D, C, B and A
+    pub fn weights(mut self, weights: [f32; 4]) -> Self {
+        self.weights = Some(weights);
+        self
+    }
+
+    /// Set the normalization bit mask (e.g. `2 | 32`)
+    pub fn normalization(mut self, normalization: u32) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Validate the configuration name, weights and normalization
+    pub fn validate(&self) -> Result<(), String> {
+        self.query.validate()?;
+        if let Some(weights) = self.weights {
+            if !weights.iter().all(|w| (0.0..=1.0).contains(w)) {
+                return Err("Rank weights must be between 0 and 1".to_string());
+            }
+        }
+        if self.normalization > 63 {
+            return Err(format!(
+                "Invalid rank normalization: {}",
+                self.normalization
+            ));
+        }
+        Ok(())
+    }
+
+    /// The rank of `document` (a `tsvector`), with the query text bound
+    /// through `binder`; call `validate()` first
+    pub(crate) fn to_sql(&self, document: &str, binder: &mut ParamBinder) -> String {
+        let mut args = Vec::new();
+        if let Some(weights) = self.weights {
+            let weights: Vec<String> = weights.iter().map(f32::to_string).collect();
+            args.push(format!("'{{{}}}'::real[]", weights.join(",")));
+        }
+        args.push(document.to_string());
+        args.push(self.query.to_sql(binder));
+        if self.normalization != 0 {
+            args.push(self.normalization.to_string());
+        }
+        let function = if self.cover_density {
+            "ts_rank_cd"
+        } else {
+            "ts_rank"
+        };
+        format!("{}({})", function, args.join(", "))
+    }
+}
+
+/// A `ts_headline` snippet of a text field (see `QueryBuilder::headline`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Headline {
+    /// The text to highlight
+    pub field: Field,
+
+    /// The query whose matches are highlighted
+    pub query: TextQuery,
+
+    /// `ts_headline` options (e.g. `MaxWords=20, StartSel=<em>, StopSel=</em>`)
+    pub options: Option<String>,
+}
+
+impl Headline {
+    /// Highlight matches of `query` in `field` with the default options
+    pub fn new(field: Field, query: TextQuery) -> Self {
+        Self {
+            field,
+            query,
+            options: None,
+        }
+    }
+
+    /// Set the `ts_headline` options
+    pub fn options(mut self, options: impl Into<String>) -> Self {
+        self.options = Some(options.into());
+        self
+    }
+
+    /// Validate the field and configuration name
+    pub fn validate(&self) -> Result<(), String> {
+        self.field.validate()?;
+        self.query.validate()
+    }
+
+    /// `ts_headline('language', field, query[, $n])`, with the query text and
+    /// options bound through `binder`; call `validate()` first
+    pub(crate) fn to_sql(&self, binder: &mut ParamBinder) -> String {
+        let mut sql = format!(
+            "ts_headline({}, {}, {}",
+            self.query.language_sql(),
+            self.field.to_sql(),
+            self.query.to_sql(binder)
+        );
+        if let Some(ref options) = self.options {
+            sql.push_str(", ");
+            sql.push_str(&binder.bind(Value::String(options.clone())));
+        }
+        sql.push(')');
+        sql
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(binder: ParamBinder) -> Vec<String> {
+        binder
+            .into_params()
+            .into_iter()
+            .map(|value| match value {
+                Value::String(text) => text,
+                other => panic!("expected a text parameter, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_query_and_rank_sql() {
+        let mut binder = ParamBinder::new(1);
+        let query = TextQuery::websearch("it's \"rust\"").language("simple");
+        assert_eq!(
+            query.to_sql(&mut binder),
+            "websearch_to_tsquery('simple', $2)"
+        );
+        assert_eq!(texts(binder), ["it's \"rust\""]);
+
+        let mut binder = ParamBinder::new(0);
+        assert_eq!(
+            TextRank::new(TextQuery::plain("rust")).to_sql("doc", &mut binder),
+            "ts_rank(doc, plainto_tsquery('english', $1))"
+        );
+        assert_eq!(
+            TextRank::new(TextQuery::phrase("fast io"))
+                .cover_density()
+                .weights([0.0, 0.25, 0.5, 1.0])
+                .normalization(2 | 32)
+                .to_sql("doc", &mut binder),
+            "ts_rank_cd('{0,0.25,0.5,1}'::real[], doc, phraseto_tsquery('english', $2), 34)"
+        );
+        assert_eq!(texts(binder), ["rust", "fast io"]);
+    }
+
+    #[test]
+    fn test_query_text_is_never_inlined() {
+        let text = r"o'neil \' OR 1=1 -- \\";
+        let mut binder = ParamBinder::new(0);
+        let sql = TextRank::new(TextQuery::plain(text)).to_sql("doc", &mut binder);
+        assert_eq!(sql, "ts_rank(doc, plainto_tsquery('english', $1))");
+        assert_eq!(texts(binder), [text]);
+    }
+
+    #[test]
+    fn test_headline_sql() {
+        let headline = Headline::new(
+            Field::JsonbField("body".into()),
+            TextQuery::plain("rust").language("simple"),
+        )
+        .options("StartSel=<b>, StopSel=</b>, MaxWords=5");
+        let mut binder = ParamBinder::new(0);
+        assert_eq!(
+            headline.to_sql(&mut binder),
+            "ts_headline('simple', (data->>'body'), plainto_tsquery('simple', $1), $2)"
+        );
+        assert_eq!(
+            texts(binder),
+            ["rust", "StartSel=<b>, StopSel=</b>, MaxWords=5"]
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(TextQuery::plain("x").language("english").validate().is_ok());
+        assert!(TextQuery::plain("x")
+            .language("en'); DROP TABLE t; --")
+            .validate()
+            .is_err());
+        assert!(TextQuery::plain("x").language("").validate().is_err());
+        let rank = TextRank::new(TextQuery::plain("x"));
+        assert!(rank
+            .clone()
+            .weights([0.1, 0.2, 0.4, 1.5])
+            .validate()
+            .is_err());
+        assert!(rank.clone().weights([f32::NAN; 4]).validate().is_err());
+        assert!(rank.clone().normalization(64).validate().is_err());
+        assert!(rank.normalization(63).validate().is_ok());
+        assert!(
+            Headline::new(Field::JsonbField("bad-name".into()), TextQuery::plain("x"))
+                .validate()
+                .is_err()
+        );
+    }
+}
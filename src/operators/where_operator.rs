@@ -4,6 +4,7 @@
 //! Supports 25+ operators across 5 categories with both JSONB and direct column sources.
 
 use super::field::{Field, Value};
+use super::text_search::validate_language;
 
 /// WHERE clause operators
 ///
@@ -20,6 +21,8 @@ use super::field::{Field, Value};
 /// - **Vector Distance**: L2Distance, CosineDistance, InnerProduct, JaccardDistance
 /// - **Full-Text Search**: Matches, PlainQuery, PhraseQuery, WebsearchQuery
 /// - **Network**: IsIPv4, IsIPv6, IsPrivate, IsLoopback, InSubnet, ContainsSubnet, ContainsIP, IPRangeOverlap
+/// - **JSONB**: JsonContains, HasKey, HasAnyKey, HasAllKeys, JsonPathExists, JsonPathMatch
+/// - **Logical**: And, Or, Not
 #[derive(Debug, Clone)]
 pub enum WhereOperator {
     // ============ Comparison Operators ============
@@ -250,6 +253,64 @@ pub enum WhereOperator {
         /// The IP range to check for overlap
         range: String,
     },
+
+    // ============ JSONB Operators ============
+    /// JSONB containment: `data @> '{"field": value}'`
+    ///
+    /// The value is nested under the field's path and tested against the whole
+    /// `data` document, so GIN indexes on `data` (`jsonb_ops` or
+    /// `jsonb_path_ops`) apply. `Field::JsonbPath(vec![])` tests `data` itself;
+    /// direct columns generate `column @> value`.
+    JsonContains(Field, serde_json::Value),
+
+    /// Key exists: `field ? key`
+    ///
+    /// True when `key` is a top-level key of the object, or a string element of
+    /// the array. Served by `jsonb_ops` GIN indexes on `data` itself
+    /// (`Field::JsonbPath(vec![])`).
+    HasKey(Field, String),
+
+    /// Any of the keys exists: `field ?| keys`
+    HasAnyKey(Field, Vec<String>),
+
+    /// All of the keys exist: `field ?& keys`
+    HasAllKeys(Field, Vec<String>),
+
+    /// SQL/JSON path returns an item: `field @? path`
+    ///
+    /// With `vars`, generates `jsonb_path_exists(field, path, vars, true)`, which
+    /// cannot use an index.
+    JsonPathExists {
+        /// The JSONB field to query (`Field::JsonbPath(vec![])` for `data`)
+        field: Field,
+        /// SQL/JSON path expression (e.g. `$.tags[*] ? (@ == $tag)`)
+        path: String,
+        /// Optional object of variables referenced as `$name` in the path
+        vars: Option<serde_json::Value>,
+    },
+
+    /// SQL/JSON path predicate holds: `field @@ path`
+    ///
+    /// With `vars`, generates `jsonb_path_match(field, path, vars, true)`, which
+    /// cannot use an index.
+    JsonPathMatch {
+        /// The JSONB field to query (`Field::JsonbPath(vec![])` for `data`)
+        field: Field,
+        /// SQL/JSON path predicate (e.g. `$.score > $min`)
+        path: String,
+        /// Optional object of variables referenced as `$name` in the path
+        vars: Option<serde_json::Value>,
+    },
+
+    // ============ Logical Operators ============
+    /// All operators hold: `(a) AND (b)`; an empty list is `TRUE`
+    And(Vec<WhereOperator>),
+
+    /// Any operator holds: `(a) OR (b)`; an empty list is `FALSE`
+    Or(Vec<WhereOperator>),
+
+    /// Negation: `NOT (a)`
+    Not(Box<WhereOperator>),
 }
 
 impl WhereOperator {
@@ -295,24 +356,41 @@ impl WhereOperator {
             WhereOperator::ContainsSubnet { .. } => "ContainsSubnet",
             WhereOperator::ContainsIP { .. } => "ContainsIP",
             WhereOperator::IPRangeOverlap { .. } => "IPRangeOverlap",
+            WhereOperator::JsonContains(_, _) => "JsonContains",
+            WhereOperator::HasKey(_, _) => "HasKey",
+            WhereOperator::HasAnyKey(_, _) => "HasAnyKey",
+            WhereOperator::HasAllKeys(_, _) => "HasAllKeys",
+            WhereOperator::JsonPathExists { .. } => "JsonPathExists",
+            WhereOperator::JsonPathMatch { .. } => "JsonPathMatch",
+            WhereOperator::And(_) => "And",
+            WhereOperator::Or(_) => "Or",
+            WhereOperator::Not(_) => "Not",
         }
     }
 
     /// Validate operator for basic correctness
     pub fn validate(&self) -> Result<(), String> {
         match self {
-            WhereOperator::Eq(f, _)
-            | WhereOperator::Neq(f, _)
-            | WhereOperator::Gt(f, _)
-            | WhereOperator::Gte(f, _)
-            | WhereOperator::Lt(f, _)
-            | WhereOperator::Lte(f, _)
-            | WhereOperator::In(f, _)
-            | WhereOperator::Nin(f, _)
-            | WhereOperator::Contains(f, _)
-            | WhereOperator::ArrayContains(f, _)
-            | WhereOperator::ArrayContainedBy(f, _)
-            | WhereOperator::ArrayOverlaps(f, _)
+            WhereOperator::Eq(f, v)
+            | WhereOperator::Neq(f, v)
+            | WhereOperator::Gt(f, v)
+            | WhereOperator::Gte(f, v)
+            | WhereOperator::Lt(f, v)
+            | WhereOperator::Lte(f, v)
+            | WhereOperator::ArrayContains(f, v)
+            | WhereOperator::ArrayContainedBy(f, v) => {
+                f.validate()?;
+                v.validate()
+            }
+
+            WhereOperator::In(f, values)
+            | WhereOperator::Nin(f, values)
+            | WhereOperator::ArrayOverlaps(f, values) => {
+                f.validate()?;
+                values.iter().try_for_each(Value::validate)
+            }
+
+            WhereOperator::Contains(f, _)
             | WhereOperator::LenEq(f, _)
             | WhereOperator::LenGt(f, _)
             | WhereOperator::LenGte(f, _)
@@ -329,10 +407,7 @@ impl WhereOperator {
             | WhereOperator::CosineDistance { field, .. }
             | WhereOperator::InnerProduct { field, .. }
             | WhereOperator::JaccardDistance { field, .. }
-            | WhereOperator::Matches { field, .. }
             | WhereOperator::PlainQuery { field, .. }
-            | WhereOperator::PhraseQuery { field, .. }
-            | WhereOperator::WebsearchQuery { field, .. }
             | WhereOperator::IsIPv4(field)
             | WhereOperator::IsIPv6(field)
             | WhereOperator::IsPrivate(field)
@@ -340,7 +415,52 @@ impl WhereOperator {
             | WhereOperator::InSubnet { field, .. }
             | WhereOperator::ContainsSubnet { field, .. }
             | WhereOperator::ContainsIP { field, .. }
-            | WhereOperator::IPRangeOverlap { field, .. } => field.validate(),
+            | WhereOperator::IPRangeOverlap { field, .. }
+            | WhereOperator::JsonContains(field, _)
+            | WhereOperator::HasKey(field, _) => field.validate(),
+
+            WhereOperator::Matches {
+                field, language, ..
+            }
+            | WhereOperator::PhraseQuery {
+                field, language, ..
+            }
+            | WhereOperator::WebsearchQuery {
+                field, language, ..
+            } => {
+                field.validate()?;
+                match language {
+                    Some(language) => validate_language(language),
+                    None => Ok(()),
+                }
+            }
+
+            WhereOperator::HasAnyKey(field, keys) | WhereOperator::HasAllKeys(field, keys) => {
+                field.validate()?;
+                if keys.is_empty() {
+                    return Err(format!("{} requires at least one key", self.name()));
+                }
+                Ok(())
+            }
+
+            WhereOperator::JsonPathExists { field, path, vars }
+            | WhereOperator::JsonPathMatch { field, path, vars } => {
+                field.validate()?;
+                if path.trim().is_empty() {
+                    return Err(format!("{} requires a path", self.name()));
+                }
+                match vars {
+                    Some(vars) if !vars.is_object() => {
+                        Err(format!("{} vars must be a JSON object", self.name()))
+                    }
+                    _ => Ok(()),
+                }
+            }
+
+            WhereOperator::And(ops) | WhereOperator::Or(ops) => {
+                ops.iter().try_for_each(WhereOperator::validate)
+            }
+            WhereOperator::Not(op) => op.validate(),
         }
     }
 }
@@ -6,6 +6,9 @@ pub const PROTOCOL_VERSION: i32 = 0x0003_0000;
 /// SSLRequest code (80877103 = 1234 << 16 | 5679)
 pub const SSL_REQUEST_CODE: i32 = 0x04D2_162F;
 
+/// CancelRequest code (80877102 = 1234 << 16 | 5678)
+pub const CANCEL_REQUEST_CODE: i32 = 0x04D2_162E;
+
 /// Message type tags
 pub mod tags {
     /// Authentication request
@@ -20,6 +23,9 @@ pub mod tags {
     /// Data row
     pub const DATA_ROW: u8 = b'D';
 
+    /// Empty query response
+    pub const EMPTY_QUERY_RESPONSE: u8 = b'I';
+
     /// Error response
     pub const ERROR_RESPONSE: u8 = b'E';
 
@@ -34,6 +40,18 @@ pub mod tags {
 
     /// Row description
     pub const ROW_DESCRIPTION: u8 = b'T';
+
+    /// Parse complete
+    pub const PARSE_COMPLETE: u8 = b'1';
+
+    /// Bind complete
+    pub const BIND_COMPLETE: u8 = b'2';
+
+    /// No data
+    pub const NO_DATA: u8 = b'n';
+
+    /// Close complete
+    pub const CLOSE_COMPLETE: u8 = b'3';
 }
 
 /// Authentication types
@@ -61,11 +61,16 @@ pub fn decode_message(data: &mut BytesMut) -> io::Result<(BackendMessage, usize)
         tags::BACKEND_KEY_DATA => decode_backend_key_data(msg_data)?,
         tags::COMMAND_COMPLETE => decode_command_complete(msg_data)?,
         tags::DATA_ROW => decode_data_row(msg_data)?,
+        tags::EMPTY_QUERY_RESPONSE => BackendMessage::EmptyQueryResponse,
         tags::ERROR_RESPONSE => decode_error_response(msg_data)?,
         tags::NOTICE_RESPONSE => decode_notice_response(msg_data)?,
         tags::PARAMETER_STATUS => decode_parameter_status(msg_data)?,
         tags::READY_FOR_QUERY => decode_ready_for_query(msg_data)?,
         tags::ROW_DESCRIPTION => decode_row_description(msg_data)?,
+        tags::PARSE_COMPLETE => BackendMessage::ParseComplete,
+        tags::BIND_COMPLETE => BackendMessage::BindComplete,
+        tags::NO_DATA => BackendMessage::NoData,
+        tags::CLOSE_COMPLETE => BackendMessage::CloseComplete,
         _ => {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -406,4 +411,13 @@ mod tests {
         }
         assert_eq!(consumed, 6); // 1 tag + 4 len + 1 status
     }
+
+    #[test]
+    fn test_decode_empty_query_response() {
+        let mut data = BytesMut::from(&[b'I', 0, 0, 0, 4][..]);
+
+        let (msg, consumed) = decode_message(&mut data).unwrap();
+        assert!(matches!(msg, BackendMessage::EmptyQueryResponse));
+        assert_eq!(consumed, 5);
+    }
 }
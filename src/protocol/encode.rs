@@ -30,6 +30,39 @@ pub fn encode_message(msg: &FrontendMessage) -> io::Result<BytesMut> {
         FrontendMessage::SslRequest => {
             encode_ssl_request(&mut buf)?;
         }
+        FrontendMessage::CancelRequest {
+            process_id,
+            secret_key,
+        } => {
+            encode_cancel_request(&mut buf, *process_id, *secret_key)?;
+        }
+        FrontendMessage::Parse {
+            name,
+            query,
+            param_types,
+        } => {
+            encode_parse(&mut buf, name, query, param_types)?;
+        }
+        FrontendMessage::Bind {
+            portal,
+            statement,
+            params,
+        } => {
+            encode_bind(&mut buf, portal, statement, params)?;
+        }
+        FrontendMessage::DescribePortal(portal) => {
+            encode_target(&mut buf, b'D', b'P', portal)?;
+        }
+        FrontendMessage::CloseStatement(name) => {
+            encode_target(&mut buf, b'C', b'S', name)?;
+        }
+        FrontendMessage::Execute { portal, max_rows } => {
+            encode_execute(&mut buf, portal, *max_rows)?;
+        }
+        FrontendMessage::Sync => {
+            buf.put_u8(b'S');
+            buf.put_i32(4); // Length includes itself
+        }
     }
 
     Ok(buf)
@@ -125,6 +158,122 @@ fn encode_ssl_request(buf: &mut BytesMut) -> io::Result<()> {
     Ok(())
 }
 
+fn encode_cancel_request(buf: &mut BytesMut, process_id: i32, secret_key: i32) -> io::Result<()> {
+    buf.put_i32(16); // Length (includes itself)
+    buf.put_i32(super::constants::CANCEL_REQUEST_CODE);
+    buf.put_i32(process_id);
+    buf.put_i32(secret_key);
+    Ok(())
+}
+
+/// Put a null-terminated string, rejecting embedded nulls
+fn put_cstr(buf: &mut BytesMut, value: &str) -> io::Result<()> {
+    if value.contains('\0') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "string contains a null byte",
+        ));
+    }
+    buf.put(value.as_bytes());
+    buf.put_u8(0);
+    Ok(())
+}
+
+/// Convert a count to the protocol's `Int16`
+fn count_i16(count: usize, what: &str) -> io::Result<i16> {
+    i16::try_from(count).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("too many {} ({})", what, count),
+        )
+    })
+}
+
+fn encode_parse(
+    buf: &mut BytesMut,
+    name: &str,
+    query: &str,
+    param_types: &[u32],
+) -> io::Result<()> {
+    buf.put_u8(b'P');
+    let len_pos = buf.len();
+    buf.put_i32(0);
+
+    put_cstr(buf, name)?;
+    put_cstr(buf, query)?;
+    buf.put_i16(count_i16(param_types.len(), "parameters")?);
+    for oid in param_types {
+        buf.put_u32(*oid);
+    }
+
+    let len = buf.len() - len_pos;
+    buf[len_pos..len_pos + 4].copy_from_slice(&(len as i32).to_be_bytes());
+
+    Ok(())
+}
+
+fn encode_bind(
+    buf: &mut BytesMut,
+    portal: &str,
+    statement: &str,
+    params: &[Option<String>],
+) -> io::Result<()> {
+    buf.put_u8(b'B');
+    let len_pos = buf.len();
+    buf.put_i32(0);
+
+    put_cstr(buf, portal)?;
+    put_cstr(buf, statement)?;
+    // No parameter format codes: all parameters are text
+    buf.put_i16(0);
+    buf.put_i16(count_i16(params.len(), "parameters")?);
+    for param in params {
+        match param {
+            Some(value) => {
+                buf.put_i32(value.len() as i32);
+                buf.put(value.as_bytes());
+            }
+            None => buf.put_i32(-1),
+        }
+    }
+    // No result format codes: all columns are text
+    buf.put_i16(0);
+
+    let len = buf.len() - len_pos;
+    buf[len_pos..len_pos + 4].copy_from_slice(&(len as i32).to_be_bytes());
+
+    Ok(())
+}
+
+/// Describe or Close of a portal (`P`) or prepared statement (`S`)
+fn encode_target(buf: &mut BytesMut, tag: u8, kind: u8, name: &str) -> io::Result<()> {
+    buf.put_u8(tag);
+    let len_pos = buf.len();
+    buf.put_i32(0);
+
+    buf.put_u8(kind);
+    put_cstr(buf, name)?;
+
+    let len = buf.len() - len_pos;
+    buf[len_pos..len_pos + 4].copy_from_slice(&(len as i32).to_be_bytes());
+
+    Ok(())
+}
+
+fn encode_execute(buf: &mut BytesMut, portal: &str, max_rows: i32) -> io::Result<()> {
+    buf.put_u8(b'E');
+    let len_pos = buf.len();
+    buf.put_i32(0);
+
+    put_cstr(buf, portal)?;
+    buf.put_i32(max_rows);
+
+    let len = buf.len() - len_pos;
+    buf[len_pos..len_pos + 4].copy_from_slice(&(len as i32).to_be_bytes());
+
+    Ok(())
+}
+
 fn encode_sasl_response(buf: &mut BytesMut, data: &[u8]) -> io::Result<()> {
     buf.put_u8(b'p');
     let len_pos = buf.len();
@@ -174,4 +323,60 @@ mod tests {
         // SSL request code = 80877103 = 0x04D2162F
         assert_eq!(&buf[4..8], &[0x04, 0xD2, 0x16, 0x2F]);
     }
+
+    #[test]
+    fn test_encode_extended_query() {
+        let parse = encode_message(&FrontendMessage::Parse {
+            name: String::new(),
+            query: "SELECT $1".to_string(),
+            param_types: vec![25],
+        })
+        .unwrap();
+        assert_eq!(parse[0], b'P');
+        assert_eq!(&parse[5..16], b"\0SELECT $1\0");
+        assert_eq!(&parse[16..], &[0, 1, 0, 0, 0, 25]);
+
+        let bind = encode_message(&FrontendMessage::Bind {
+            portal: String::new(),
+            statement: String::new(),
+            params: vec![Some("ab".to_string()), None],
+        })
+        .unwrap();
+        assert_eq!(bind[0], b'B');
+        let len = i32::from_be_bytes([bind[1], bind[2], bind[3], bind[4]]);
+        assert_eq!(len, (bind.len() - 1) as i32);
+        assert_eq!(
+            &bind[5..],
+            &[0, 0, 0, 0, 0, 2, 0, 0, 0, 2, b'a', b'b', 0xFF, 0xFF, 0xFF, 0xFF, 0, 0]
+        );
+
+        let sync = encode_message(&FrontendMessage::Sync).unwrap();
+        assert_eq!(&sync[..], &[b'S', 0, 0, 0, 4]);
+
+        let close = encode_message(&FrontendMessage::CloseStatement("s1".to_string())).unwrap();
+        assert_eq!(&close[..], &[b'C', 0, 0, 0, 8, b'S', b's', b'1', 0]);
+
+        assert!(encode_message(&FrontendMessage::Parse {
+            name: String::new(),
+            query: "SELECT '\0'".to_string(),
+            param_types: vec![],
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_encode_cancel_request() {
+        let msg = FrontendMessage::CancelRequest {
+            process_id: 42,
+            secret_key: -1,
+        };
+        let buf = encode_message(&msg).unwrap();
+
+        assert_eq!(buf.len(), 16);
+        assert_eq!(&buf[0..4], &[0x00, 0x00, 0x00, 0x10]);
+        // Cancel request code = 80877102 = 0x04D2162E
+        assert_eq!(&buf[4..8], &[0x04, 0xD2, 0x16, 0x2E]);
+        assert_eq!(&buf[8..12], &42i32.to_be_bytes());
+        assert_eq!(&buf[12..16], &[0xFF, 0xFF, 0xFF, 0xFF]);
+    }
 }
@@ -38,6 +38,51 @@ pub enum FrontendMessage {
 
     /// SSLRequest message (TLS negotiation)
     SslRequest,
+
+    /// Parse message: prepare `query` as a statement (extended protocol)
+    Parse {
+        /// Statement name (empty for the unnamed statement)
+        name: String,
+        /// SQL with `$n` placeholders
+        query: String,
+        /// Parameter type OIDs (0 lets the server infer the type)
+        param_types: Vec<u32>,
+    },
+
+    /// Bind message: bind text-format parameters to a statement
+    Bind {
+        /// Portal name (empty for the unnamed portal)
+        portal: String,
+        /// Statement name (empty for the unnamed statement)
+        statement: String,
+        /// Parameter values in text format (`None` for NULL)
+        params: Vec<Option<String>>,
+    },
+
+    /// Describe message for a portal (replies with RowDescription or NoData)
+    DescribePortal(String),
+
+    /// Execute message: run a portal
+    Execute {
+        /// Portal name
+        portal: String,
+        /// Maximum number of rows (0 for no limit)
+        max_rows: i32,
+    },
+
+    /// Close message for a prepared statement (replies with CloseComplete)
+    CloseStatement(String),
+
+    /// Sync message: end of an extended query
+    Sync,
+
+    /// CancelRequest message, sent on a fresh connection
+    CancelRequest {
+        /// Backend process ID from `BackendKeyData`
+        process_id: i32,
+        /// Secret key from `BackendKeyData`
+        secret_key: i32,
+    },
 }
 
 /// Backend message (server → client)
@@ -54,12 +99,27 @@ pub enum BackendMessage {
         secret_key: i32,
     },
 
+    /// Parse complete (extended protocol)
+    ParseComplete,
+
+    /// Bind complete (extended protocol)
+    BindComplete,
+
+    /// Close complete (extended protocol)
+    CloseComplete,
+
+    /// No data (Describe of a statement that returns no rows)
+    NoData,
+
     /// Command complete
     CommandComplete(String),
 
     /// Data row
     DataRow(Vec<Option<Bytes>>),
 
+    /// Empty query response (reply to an empty query string)
+    EmptyQueryResponse,
+
     /// Error response
     ErrorResponse(ErrorFields),
 
@@ -5,13 +5,15 @@
 //!
 //! * Startup and authentication
 //! * Simple Query protocol
+//! * Extended Query protocol for bound parameters and cached prepared
+//!   statements (unnamed portal, text format)
 //! * Result streaming (RowDescription, DataRow)
 //! * Error handling
 //!
 //! Explicitly NOT supported:
-//! * Extended Query protocol (prepared statements)
+//! * Named portals and binary-format values
 //! * COPY protocol
-//! * Transactions
+//! * Read-write transactions (read-only snapshot transactions use Simple Query)
 //! * Multi-statement queries
 
 pub mod constants;
@@ -1,13 +1,15 @@
 //! JSON stream implementation
 
-use crate::protocol::BackendMessage;
+use crate::connection::{SessionInfo, ShutdownHandle};
+use crate::protocol::{BackendMessage, ErrorFields};
+use crate::stream::NoticeStream;
 use crate::{Error, Result};
 use bytes::Bytes;
 use futures::stream::Stream;
 use serde_json::Value;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::task::{Context, Poll};
 use std::time::Duration;
 use tokio::sync::{mpsc, Mutex, Notify};
@@ -85,6 +87,19 @@ pub struct JsonStream {
 
     // Sampling counter for metrics recording (sample 1 in N polls)
     poll_count: AtomicU64, // Counter for sampling metrics
+
+    // Session parameters, updated by the background task on ParameterStatus
+    session: Arc<RwLock<SessionInfo>>,
+
+    // Server notices raised during the query (taken by `notices()`)
+    notices: Option<NoticeStream>,
+
+    // Shutdown handle attached by the connection, and whether Cancelled was yielded
+    shutdown: Option<ShutdownHandle>,
+    shutdown_reported: bool,
+
+    // Query span, receiving pause/resume events
+    span: tracing::Span,
 }
 
 /// Pause/resume state (lazily allocated)
@@ -99,6 +114,7 @@ pub struct PauseResumeState {
 
 impl JsonStream {
     /// Create new JSON stream
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         receiver: mpsc::Receiver<Result<Value>>,
         cancel_tx: mpsc::Sender<()>,
@@ -106,6 +122,8 @@ impl JsonStream {
         max_memory: Option<usize>,
         _soft_limit_warn_threshold: Option<f32>,
         soft_limit_fail_threshold: Option<f32>,
+        session: Arc<RwLock<SessionInfo>>,
+        notices: mpsc::Receiver<ErrorFields>,
     ) -> Self {
         Self {
             receiver,
@@ -124,6 +142,14 @@ impl JsonStream {
 
             // Initialize sampling counter
             poll_count: AtomicU64::new(0),
+
+            session,
+            notices: Some(NoticeStream::new(notices)),
+
+            shutdown: None,
+            shutdown_reported: false,
+
+            span: tracing::Span::none(),
         }
     }
 
@@ -141,6 +167,23 @@ impl JsonStream {
         self.pause_resume.as_mut().unwrap()
     }
 
+    /// Allocate pause/resume state before the background task starts
+    ///
+    /// Reader tasks only observe `pause()` if they cloned the signals at spawn.
+    pub(crate) fn init_pause_resume(&mut self) {
+        self.ensure_pause_resume();
+    }
+
+    /// End the stream with `Error::Cancelled` once `handle` shuts down
+    pub(crate) fn set_shutdown(&mut self, handle: ShutdownHandle) {
+        self.shutdown = Some(handle);
+    }
+
+    /// Attach the query span that pause/resume events are recorded on
+    pub(crate) fn set_span(&mut self, span: tracing::Span) {
+        self.span = span;
+    }
+
     /// Get current stream state
     ///
     /// Returns the current state of the stream (Running, Paused, Completed, or Failed).
@@ -215,6 +258,8 @@ impl JsonStream {
     /// ```
     pub async fn pause(&mut self) -> Result<()> {
         let entity = self.entity.clone();
+        let span = self.span.clone();
+        let buffered = self.receiver.len();
 
         // Update lightweight atomic state first (fast path)
         self.state_atomic_set_paused();
@@ -231,6 +276,7 @@ impl JsonStream {
 
                 // Record metric
                 crate::metrics::counters::stream_paused(&entity);
+                tracing::info!(parent: &span, buffered, "pause");
                 Ok(())
             }
             StreamState::Paused => {
@@ -267,6 +313,8 @@ impl JsonStream {
         // Update lightweight atomic state first (fast path)
         // Check atomic state before borrowing pause_resume
         let current = self.state_atomic_get();
+        let span = self.span.clone();
+        let buffered = self.receiver.len();
 
         // Resume only makes sense if pause/resume was initialized
         if let Some(ref mut pr) = self.pause_resume {
@@ -289,6 +337,7 @@ impl JsonStream {
 
                     // Record metric
                     crate::metrics::counters::stream_resumed(&entity);
+                    tracing::info!(parent: &span, buffered, "resume");
                     Ok(())
                 }
                 StreamState::Running => {
@@ -366,6 +415,23 @@ impl JsonStream {
         self.state_atomic.store(STATE_PAUSED, Ordering::Release);
     }
 
+    /// Snapshot of the server session parameters
+    ///
+    /// Reflects `ParameterStatus` updates received while the query is streaming.
+    pub fn session_info(&self) -> SessionInfo {
+        self.session
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Take the side channel of server notices raised during this query
+    ///
+    /// Returns `None` if the notice stream has already been taken.
+    pub fn notices(&mut self) -> Option<NoticeStream> {
+        self.notices.take()
+    }
+
     /// Get current stream statistics
     ///
     /// Returns a snapshot of stream state without consuming any items.
@@ -429,6 +495,19 @@ impl Stream for JsonStream {
             crate::metrics::gauges::stream_buffered_items(&self.entity, occupancy as usize);
         }
 
+        // After shutdown, buffered rows are discarded: yield Cancelled once, then end
+        if self
+            .shutdown
+            .as_ref()
+            .is_some_and(ShutdownHandle::is_shutdown)
+        {
+            if self.shutdown_reported {
+                return Poll::Ready(None);
+            }
+            self.shutdown_reported = true;
+            return Poll::Ready(Some(Err(Error::Cancelled)));
+        }
+
         // Check memory limit BEFORE receiving (pre-enqueue strategy)
         // This stops consuming when buffer reaches limit
         if let Some(limit) = self.max_memory {
@@ -559,4 +638,31 @@ mod tests {
         assert_eq!(cloned.total_rows_yielded, stats.total_rows_yielded);
         assert_eq!(cloned.total_rows_filtered, stats.total_rows_filtered);
     }
+
+    #[tokio::test]
+    async fn test_shutdown_yields_cancelled_once() {
+        use futures::StreamExt;
+
+        let (tx, rx) = mpsc::channel(4);
+        tx.try_send(Ok(serde_json::json!({"n": 1}))).unwrap();
+        let (cancel_tx, _cancel_rx) = mpsc::channel(1);
+        let (_notice_tx, notice_rx) = mpsc::channel(1);
+        let mut stream = JsonStream::new(
+            rx,
+            cancel_tx,
+            "test".into(),
+            None,
+            None,
+            None,
+            Arc::new(RwLock::new(SessionInfo::default())),
+            notice_rx,
+        );
+        let handle = ShutdownHandle::new();
+        stream.set_shutdown(handle.clone());
+
+        assert!(stream.next().await.unwrap().is_ok());
+        handle.shutdown(Duration::from_millis(10)).await;
+        assert!(matches!(stream.next().await, Some(Err(Error::Cancelled))));
+        assert!(stream.next().await.is_none());
+    }
 }
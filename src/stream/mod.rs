@@ -5,7 +5,10 @@ mod chunking;
 mod filter;
 mod json_stream;
 mod memory_estimator;
+mod notice_stream;
+mod parallel_stream;
 mod query_stream;
+mod sort_key;
 mod typed_stream;
 
 pub use adaptive_chunking::AdaptiveChunking;
@@ -13,5 +16,8 @@ pub use chunking::{ChunkingStrategy, RowChunk};
 pub use filter::{FilteredStream, Predicate};
 pub use json_stream::{extract_json_bytes, parse_json, JsonStream, StreamState, StreamStats};
 pub use memory_estimator::{ConservativeEstimator, FixedEstimator, MemoryEstimator};
+pub use notice_stream::NoticeStream;
+pub(crate) use parallel_stream::ParallelStream;
 pub use query_stream::QueryStream;
+pub(crate) use sort_key::SortKey;
 pub use typed_stream::TypedJsonStream;
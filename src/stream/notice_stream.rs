@@ -0,0 +1,103 @@
+//! Side channel for server notices raised while a query streams
+
+use crate::protocol::ErrorFields;
+use futures::stream::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Stream of `NoticeResponse` messages received during a query
+///
+/// Ends when the query's background task finishes. Notices are buffered in a
+/// bounded channel; if it fills up because nobody drains it, newer notices are
+/// dropped (the connection's `NoticeHandler` still sees every notice).
+///
+/// # Examples
+///
+/// ```ignore
+/// let mut stream = client.query::<Value>("orders").execute().await?;
+/// let mut notices = stream.notices().expect("notices not yet taken");
+///
+/// while let Some(row) = stream.next().await {
+///     let row = row?;
+///     while let Some(notice) = notices.try_next() {
+///         eprintln!("{:?}: {}", notice.severity, notice);
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct NoticeStream {
+    // One receiver per connection (several for parallel scans)
+    receivers: Vec<mpsc::Receiver<ErrorFields>>,
+}
+
+impl NoticeStream {
+    pub(crate) fn new(receiver: mpsc::Receiver<ErrorFields>) -> Self {
+        Self {
+            receivers: vec![receiver],
+        }
+    }
+
+    /// Merge the notice streams of several connections into one
+    pub(crate) fn merge(streams: impl IntoIterator<Item = NoticeStream>) -> Self {
+        Self {
+            receivers: streams.into_iter().flat_map(|s| s.receivers).collect(),
+        }
+    }
+
+    /// Return the next buffered notice without waiting
+    pub fn try_next(&mut self) -> Option<ErrorFields> {
+        self.receivers.iter_mut().find_map(|rx| rx.try_recv().ok())
+    }
+}
+
+impl Stream for NoticeStream {
+    type Item = ErrorFields;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut i = 0;
+        while i < self.receivers.len() {
+            match self.receivers[i].poll_recv(cx) {
+                Poll::Ready(Some(notice)) => return Poll::Ready(Some(notice)),
+                Poll::Ready(None) => {
+                    self.receivers.swap_remove(i);
+                }
+                Poll::Pending => i += 1,
+            }
+        }
+        if self.receivers.is_empty() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    fn notice(message: &str) -> ErrorFields {
+        ErrorFields {
+            message: Some(message.into()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merged_stream_drains_all_receivers() {
+        let (tx1, rx1) = mpsc::channel(4);
+        let (tx2, rx2) = mpsc::channel(4);
+        let mut merged = NoticeStream::merge([NoticeStream::new(rx1), NoticeStream::new(rx2)]);
+
+        tx2.send(notice("b")).await.unwrap();
+        assert_eq!(merged.try_next().unwrap().message.as_deref(), Some("b"));
+        tx1.send(notice("a")).await.unwrap();
+        drop((tx1, tx2));
+
+        let rest: Vec<_> = merged.collect().await;
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].message.as_deref(), Some("a"));
+    }
+}
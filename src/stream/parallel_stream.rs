@@ -0,0 +1,375 @@
+//! Merged stream over the partitions of a parallel scan
+
+use crate::connection::SessionInfo;
+use crate::stream::sort_key::{compare_rows, SortKey};
+use crate::stream::{JsonStream, NoticeStream, StreamState, StreamStats};
+use crate::{Error, Result};
+use futures::stream::Stream;
+use serde_json::Value;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Per-item memory estimate, matching `JsonStream`
+const ESTIMATED_BYTES_PER_ITEM: usize = 2048;
+
+/// One partition of a parallel scan
+struct Partition {
+    stream: JsonStream,
+    /// Next row, held back while the k-way merge waits for other partitions
+    head: Option<Value>,
+    done: bool,
+}
+
+/// Stream merging the partitions of a parallel scan
+///
+/// Without sort keys, rows are yielded in arrival order, polling partitions
+/// round-robin. With sort keys, every partition is sorted by Postgres and the
+/// stream performs a k-way merge: it waits for one row from each live partition
+/// and yields the smallest.
+///
+/// The memory budget is global: the buffered rows of all partitions count
+/// against a single `max_memory`.
+pub(crate) struct ParallelStream {
+    entity: String,
+    partitions: Vec<Partition>,
+    sort_keys: Option<Vec<SortKey>>,
+    /// Rows still allowed by LIMIT (`None` = unbounded)
+    remaining: Option<usize>,
+    max_memory: Option<usize>,
+    soft_limit_fail_threshold: Option<f32>,
+    /// Partition polled first in unordered mode (rotated for fairness)
+    next_partition: usize,
+    rows_yielded: u64,
+}
+
+impl ParallelStream {
+    pub(crate) fn new(
+        entity: String,
+        streams: Vec<JsonStream>,
+        sort_keys: Option<Vec<SortKey>>,
+        limit: Option<usize>,
+        max_memory: Option<usize>,
+        soft_limit_fail_threshold: Option<f32>,
+    ) -> Self {
+        Self {
+            entity,
+            partitions: streams
+                .into_iter()
+                .map(|stream| Partition {
+                    stream,
+                    head: None,
+                    done: false,
+                })
+                .collect(),
+            sort_keys,
+            remaining: limit,
+            max_memory,
+            soft_limit_fail_threshold,
+            next_partition: 0,
+            rows_yielded: 0,
+        }
+    }
+
+    /// Pause every partition
+    pub(crate) async fn pause(&mut self) -> Result<()> {
+        for partition in self.partitions.iter_mut().filter(|p| !p.done) {
+            partition.stream.pause().await?;
+        }
+        Ok(())
+    }
+
+    /// Resume every partition
+    pub(crate) async fn resume(&mut self) -> Result<()> {
+        for partition in self.partitions.iter_mut().filter(|p| !p.done) {
+            partition.stream.resume().await?;
+        }
+        Ok(())
+    }
+
+    /// Pause every partition with a diagnostic reason
+    pub(crate) async fn pause_with_reason(&mut self, reason: &str) -> Result<()> {
+        tracing::debug!("pausing parallel stream: {}", reason);
+        self.pause().await
+    }
+
+    /// Combined statistics over all partitions
+    pub(crate) fn stats(&self) -> StreamStats {
+        let items_buffered = self.items_buffered();
+        StreamStats {
+            items_buffered,
+            estimated_memory: items_buffered * ESTIMATED_BYTES_PER_ITEM,
+            total_rows_yielded: self.rows_yielded,
+            total_rows_filtered: self
+                .partitions
+                .iter()
+                .map(|p| p.stream.stats().total_rows_filtered)
+                .sum(),
+        }
+    }
+
+    /// State of the first partition (all partitions share pause/resume)
+    pub(crate) fn state_snapshot(&self) -> StreamState {
+        self.partitions
+            .first()
+            .map(|p| p.stream.state_snapshot())
+            .unwrap_or(StreamState::Completed)
+    }
+
+    /// Buffered rows across all paused partitions
+    pub(crate) fn paused_occupancy(&self) -> usize {
+        self.partitions
+            .iter()
+            .map(|p| p.stream.paused_occupancy())
+            .sum()
+    }
+
+    /// Session parameters of the first partition's connection
+    pub(crate) fn session_info(&self) -> SessionInfo {
+        self.partitions
+            .first()
+            .map(|p| p.stream.session_info())
+            .unwrap_or_default()
+    }
+
+    /// Take the notices of all partitions, merged into one stream
+    pub(crate) fn notices(&mut self) -> Option<NoticeStream> {
+        let streams: Vec<NoticeStream> = self
+            .partitions
+            .iter_mut()
+            .filter_map(|p| p.stream.notices())
+            .collect();
+        if streams.is_empty() {
+            None
+        } else {
+            Some(NoticeStream::merge(streams))
+        }
+    }
+
+    fn items_buffered(&self) -> usize {
+        self.partitions
+            .iter()
+            .map(|p| p.stream.stats().items_buffered + usize::from(p.head.is_some()))
+            .sum()
+    }
+
+    /// Check the global memory budget (same semantics as `JsonStream`)
+    fn check_memory(&self) -> Result<()> {
+        let Some(limit) = self.max_memory else {
+            return Ok(());
+        };
+        let estimated_memory = self.items_buffered() * ESTIMATED_BYTES_PER_ITEM;
+        let threshold = match self.soft_limit_fail_threshold {
+            Some(fail_threshold) => (limit as f32 * fail_threshold) as usize,
+            None => limit,
+        };
+        if estimated_memory > threshold {
+            crate::metrics::counters::memory_limit_exceeded(&self.entity);
+            return Err(Error::MemoryLimitExceeded {
+                limit,
+                estimated_memory,
+            });
+        }
+        Ok(())
+    }
+
+    /// Yield the next row in arrival order
+    fn poll_unordered(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Value>>> {
+        let count = self.partitions.len();
+        for offset in 0..count {
+            let idx = (self.next_partition + offset) % count;
+            let partition = &mut self.partitions[idx];
+            if partition.done {
+                continue;
+            }
+            match Pin::new(&mut partition.stream).poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    self.next_partition = (idx + 1) % count;
+                    return Poll::Ready(Some(item));
+                }
+                Poll::Ready(None) => partition.done = true,
+                Poll::Pending => {}
+            }
+        }
+        if self.partitions.iter().all(|p| p.done) {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Yield the smallest head once every live partition has one
+    fn poll_merged(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Value>>> {
+        let mut waiting = false;
+        for partition in self.partitions.iter_mut() {
+            if partition.done || partition.head.is_some() {
+                continue;
+            }
+            match Pin::new(&mut partition.stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(value))) => partition.head = Some(value),
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => partition.done = true,
+                Poll::Pending => waiting = true,
+            }
+        }
+        if waiting {
+            return Poll::Pending;
+        }
+
+        let keys = self.sort_keys.as_deref().unwrap_or_default();
+        let smallest = self
+            .partitions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| p.head.as_ref().map(|head| (i, head)))
+            .min_by(|(_, a), (_, b)| compare_rows(keys, a, b))
+            .map(|(i, _)| i);
+
+        match smallest {
+            Some(i) => Poll::Ready(self.partitions[i].head.take().map(Ok)),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl Stream for ParallelStream {
+    type Item = Result<Value>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.remaining == Some(0) {
+            return Poll::Ready(None);
+        }
+
+        if let Err(e) = self.check_memory() {
+            return Poll::Ready(Some(Err(e)));
+        }
+
+        let item = if self.sort_keys.is_some() {
+            self.poll_merged(cx)
+        } else {
+            self.poll_unordered(cx)
+        };
+
+        if let Poll::Ready(Some(Ok(_))) = item {
+            self.rows_yielded += 1;
+            if let Some(remaining) = self.remaining.as_mut() {
+                *remaining -= 1;
+                if *remaining == 0 {
+                    // LIMIT reached: drop partitions to cancel their queries
+                    self.partitions.clear();
+                }
+            }
+        }
+        item
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use serde_json::json;
+    use std::sync::{Arc, RwLock};
+    use tokio::sync::mpsc;
+
+    /// Build a partition stream that yields `rows` and then ends
+    fn partition(rows: Vec<Value>) -> JsonStream {
+        let (tx, rx) = mpsc::channel(rows.len().max(1));
+        for row in rows {
+            tx.try_send(Ok(row)).unwrap();
+        }
+        let (cancel_tx, _cancel_rx) = mpsc::channel(1);
+        let (_notice_tx, notice_rx) = mpsc::channel(1);
+        JsonStream::new(
+            rx,
+            cancel_tx,
+            "test".into(),
+            None,
+            None,
+            None,
+            Arc::new(RwLock::new(SessionInfo::default())),
+            notice_rx,
+        )
+    }
+
+    fn numbered(ns: &[i64]) -> Vec<Value> {
+        ns.iter().map(|n| json!({ "n": n })).collect()
+    }
+
+    #[tokio::test]
+    async fn test_unordered_yields_every_row() {
+        let stream = ParallelStream::new(
+            "test".into(),
+            vec![partition(numbered(&[1, 2])), partition(numbered(&[3]))],
+            None,
+            None,
+            None,
+            None,
+        );
+        let mut rows: Vec<i64> = stream
+            .map(|r| r.unwrap()["n"].as_i64().unwrap())
+            .collect()
+            .await;
+        rows.sort();
+        assert_eq!(rows, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_merge_preserves_order() {
+        let keys = SortKey::parse_order_by("(data->>'n')::int DESC").unwrap();
+        let stream = ParallelStream::new(
+            "test".into(),
+            vec![
+                partition(numbered(&[9, 4, 1])),
+                partition(numbered(&[8, 7, 2])),
+                partition(numbered(&[])),
+                partition(numbered(&[6, 5, 3])),
+            ],
+            Some(keys),
+            None,
+            None,
+            None,
+        );
+        let rows: Vec<i64> = stream
+            .map(|r| r.unwrap()["n"].as_i64().unwrap())
+            .collect()
+            .await;
+        assert_eq!(rows, vec![9, 8, 7, 6, 5, 4, 3, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_limit_truncates_merged_output() {
+        let keys = SortKey::parse_order_by("(data->>'n')::int").unwrap();
+        let mut stream = ParallelStream::new(
+            "test".into(),
+            vec![partition(numbered(&[1, 3])), partition(numbered(&[2, 4]))],
+            Some(keys),
+            Some(3),
+            None,
+            None,
+        );
+        let mut rows = Vec::new();
+        while let Some(row) = stream.next().await {
+            rows.push(row.unwrap()["n"].as_i64().unwrap());
+        }
+        assert_eq!(rows, vec![1, 2, 3]);
+        assert_eq!(stream.stats().total_rows_yielded, 3);
+    }
+
+    #[tokio::test]
+    async fn test_global_memory_budget() {
+        // 2 + 2 buffered rows = 8 KiB across partitions, each under 5 KiB alone
+        let mut stream = ParallelStream::new(
+            "test".into(),
+            vec![partition(numbered(&[1, 2])), partition(numbered(&[3, 4]))],
+            None,
+            None,
+            Some(5000),
+            None,
+        );
+        assert_eq!(stream.stats().items_buffered, 4);
+        match stream.next().await {
+            Some(Err(Error::MemoryLimitExceeded { limit, .. })) => assert_eq!(limit, 5000),
+            other => panic!("expected MemoryLimitExceeded, got {:?}", other),
+        }
+    }
+}
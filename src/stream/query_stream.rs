@@ -4,7 +4,8 @@
 //! and type-safe deserialization. It exposes pause(), resume(), and stats() methods
 //! while implementing Stream<Item = Result<T>>.
 
-use crate::stream::JsonStream;
+use crate::operators::{KeysetCursor, OrderByClause};
+use crate::stream::{JsonStream, ParallelStream};
 use crate::{Error, Result};
 use futures::stream::Stream;
 use serde::de::DeserializeOwned;
@@ -16,6 +17,12 @@ use std::task::{Context, Poll};
 /// Type alias for Rust-side predicate function
 type Predicate = Box<dyn Fn(&Value) -> bool + Send>;
 
+/// Source of JSON rows: one connection, or the partitions of a parallel scan
+enum Inner {
+    Single(JsonStream),
+    Parallel(ParallelStream),
+}
+
 /// Query stream with pause/resume/stats capabilities
 ///
 /// This stream combines JsonStream (with control methods) with optional filtering
@@ -23,9 +30,15 @@ type Predicate = Box<dyn Fn(&Value) -> bool + Send>;
 /// while implementing Stream<Item = Result<T>>.
 pub struct QueryStream<T: DeserializeOwned + Unpin> {
     /// Inner JSON stream (provides pause/resume/stats)
-    inner: JsonStream,
+    inner: Inner,
     /// Optional Rust-side predicate for filtering
     predicate: Option<Predicate>,
+    /// Keyset ordering, when the cursor of the last yielded row is tracked
+    keyset_order: Option<Vec<OrderByClause>>,
+    /// Cursor of the first yielded row
+    first_cursor: Option<KeysetCursor>,
+    /// Cursor of the last yielded row
+    last_cursor: Option<KeysetCursor>,
     /// Type marker for deserialization target
     _phantom: PhantomData<T>,
 }
@@ -34,40 +47,118 @@ impl<T: DeserializeOwned + Unpin> QueryStream<T> {
     /// Create a new query stream
     pub fn new(inner: JsonStream, predicate: Option<Predicate>) -> Self {
         Self {
-            inner,
+            inner: Inner::Single(inner),
+            predicate,
+            keyset_order: None,
+            first_cursor: None,
+            last_cursor: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Create a query stream over the merged partitions of a parallel scan
+    pub(crate) fn parallel(inner: ParallelStream, predicate: Option<Predicate>) -> Self {
+        Self {
+            inner: Inner::Parallel(inner),
             predicate,
+            keyset_order: None,
+            first_cursor: None,
+            last_cursor: None,
             _phantom: PhantomData,
         }
     }
 
+    /// Track the keyset cursor of each yielded row under `clauses`
+    pub(crate) fn track_cursor(&mut self, clauses: Vec<OrderByClause>) {
+        self.keyset_order = Some(clauses);
+    }
+
+    /// Opaque cursor of the first row yielded
+    ///
+    /// Pass it to `QueryBuilder::before()` for the previous page (Relay's
+    /// `startCursor`). `None` under the same conditions as `last_cursor()`.
+    pub fn first_cursor(&self) -> Option<String> {
+        self.first_cursor.as_ref().map(KeysetCursor::encode)
+    }
+
+    /// Opaque cursor of the last row yielded so far
+    ///
+    /// Pass it to `QueryBuilder::after()` for the next page (Relay's
+    /// `endCursor`). `None` until a row has been yielded, or when
+    /// the query did not use `QueryBuilder::keyset_order()`.
+    pub fn last_cursor(&self) -> Option<String> {
+        self.last_cursor.as_ref().map(KeysetCursor::encode)
+    }
+
     /// Pause the stream
+    ///
+    /// For parallel scans, every partition is paused.
     pub async fn pause(&mut self) -> Result<()> {
-        self.inner.pause().await
+        match &mut self.inner {
+            Inner::Single(s) => s.pause().await,
+            Inner::Parallel(s) => s.pause().await,
+        }
     }
 
     /// Resume the stream
     pub async fn resume(&mut self) -> Result<()> {
-        self.inner.resume().await
+        match &mut self.inner {
+            Inner::Single(s) => s.resume().await,
+            Inner::Parallel(s) => s.resume().await,
+        }
     }
 
     /// Get stream statistics
+    ///
+    /// For parallel scans, buffered items and memory are summed over all partitions.
     pub fn stats(&self) -> crate::stream::StreamStats {
-        self.inner.stats()
+        match &self.inner {
+            Inner::Single(s) => s.stats(),
+            Inner::Parallel(s) => s.stats(),
+        }
     }
 
     /// Get current stream state snapshot
     pub fn state_snapshot(&self) -> crate::stream::StreamState {
-        self.inner.state_snapshot()
+        match &self.inner {
+            Inner::Single(s) => s.state_snapshot(),
+            Inner::Parallel(s) => s.state_snapshot(),
+        }
     }
 
     /// Get buffered rows when paused
     pub fn paused_occupancy(&self) -> usize {
-        self.inner.paused_occupancy()
+        match &self.inner {
+            Inner::Single(s) => s.paused_occupancy(),
+            Inner::Parallel(s) => s.paused_occupancy(),
+        }
+    }
+
+    /// Get server session parameters (kept current while streaming)
+    pub fn session_info(&self) -> crate::connection::SessionInfo {
+        match &self.inner {
+            Inner::Single(s) => s.session_info(),
+            Inner::Parallel(s) => s.session_info(),
+        }
+    }
+
+    /// Take the side channel of server notices (`RAISE NOTICE`, warnings)
+    ///
+    /// Yields the structured `ErrorFields` of each `NoticeResponse` received while
+    /// the query runs. Returns `None` if already taken.
+    pub fn notices(&mut self) -> Option<crate::stream::NoticeStream> {
+        match &mut self.inner {
+            Inner::Single(s) => s.notices(),
+            Inner::Parallel(s) => s.notices(),
+        }
     }
 
     /// Pause with diagnostic reason
     pub async fn pause_with_reason(&mut self, reason: &str) -> Result<()> {
-        self.inner.pause_with_reason(reason).await
+        match &mut self.inner {
+            Inner::Single(s) => s.pause_with_reason(reason).await,
+            Inner::Parallel(s) => s.pause_with_reason(reason).await,
+        }
     }
 
     /// Deserialize a JSON value to type T
@@ -87,8 +178,12 @@ impl<T: DeserializeOwned + Unpin> Stream for QueryStream<T> {
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         loop {
-            // Poll the inner JsonStream
-            match Pin::new(&mut self.inner).poll_next(cx) {
+            // Poll the inner stream
+            let polled = match &mut self.inner {
+                Inner::Single(s) => Pin::new(s).poll_next(cx),
+                Inner::Parallel(s) => Pin::new(s).poll_next(cx),
+            };
+            match polled {
                 Poll::Ready(Some(Ok(value))) => {
                     // Apply predicate if present
                     if let Some(ref predicate) = self.predicate {
@@ -98,6 +193,14 @@ impl<T: DeserializeOwned + Unpin> Stream for QueryStream<T> {
                         }
                     }
 
+                    if let Some(ref clauses) = self.keyset_order {
+                        let cursor = KeysetCursor::from_row(clauses, &value);
+                        if self.first_cursor.is_none() {
+                            self.first_cursor = Some(cursor.clone());
+                        }
+                        self.last_cursor = Some(cursor);
+                    }
+
                     // Deserialize to target type T
                     return Poll::Ready(Some(Self::deserialize_value(value)));
                 }
@@ -0,0 +1,598 @@
+//! Client-side evaluation of ORDER BY keys
+//!
+//! Ordered parallel scans stream each partition already sorted by Postgres and
+//! k-way merge them on the client. The merge has to compare rows exactly like
+//! the server did, so only ORDER BY expressions that can be evaluated on the
+//! `data` column are accepted:
+//!
+//! - `data->'a'->'b'` / `data#>'{a,b}'` (jsonb ordering)
+//! - `data->>'a' COLLATE "C"` / `data#>>'{a,b}' COLLATE "C"` (text ordering)
+//! - either text form cast to a numeric type, e.g. `(data->>'age')::int`
+//!
+//! each optionally followed by `ASC`/`DESC` and `NULLS FIRST/LAST`.
+//!
+//! Strings compare byte-wise on the client, which matches the server only under
+//! the `C` collation. Text keys must therefore say `COLLATE "C"`. jsonb keys
+//! cannot take a collation and compare strings in the database's default
+//! collation, so they are only merged on databases whose default collation is
+//! `C` (checked when the query runs). Numeric keys compare exactly.
+
+use crate::operators::scalar::{parse_decimal, Decimal};
+use crate::{Error, Result};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// How an extracted key is compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyKind {
+    /// `->` / `#>`: jsonb ordering
+    Jsonb,
+    /// `->>` / `#>>`: text ordering
+    Text,
+    /// Text extraction cast to a numeric type
+    Numeric,
+}
+
+/// One step of a JSON path
+#[derive(Debug, Clone, PartialEq)]
+enum PathStep {
+    Key(String),
+    Index(i64),
+}
+
+/// A parsed ORDER BY item
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SortKey {
+    path: Vec<PathStep>,
+    kind: KeyKind,
+    descending: bool,
+    nulls_first: bool,
+}
+
+impl SortKey {
+    /// Parse a comma-separated ORDER BY clause
+    pub(crate) fn parse_order_by(order_by: &str) -> Result<Vec<SortKey>> {
+        let items = split_top_level(order_by);
+        if items.iter().all(|item| item.trim().is_empty()) {
+            return Err(unsupported(order_by, "empty ORDER BY"));
+        }
+        items.iter().map(|item| Self::parse_item(item)).collect()
+    }
+
+    fn parse_item(item: &str) -> Result<SortKey> {
+        let mut expr = item.trim();
+
+        // Trailing modifiers: [COLLATE "x"] [ASC | DESC] [NULLS FIRST | LAST]
+        let mut nulls_first = None;
+        if let Some(rest) = strip_suffix_ci(expr, "NULLS FIRST") {
+            nulls_first = Some(true);
+            expr = rest;
+        } else if let Some(rest) = strip_suffix_ci(expr, "NULLS LAST") {
+            nulls_first = Some(false);
+            expr = rest;
+        }
+        let mut descending = false;
+        if let Some(rest) = strip_suffix_ci(expr, "DESC") {
+            descending = true;
+            expr = rest;
+        } else if let Some(rest) = strip_suffix_ci(expr, "ASC") {
+            expr = rest;
+        }
+        let mut collate_c = false;
+        for collation in ["COLLATE \"C\"", "COLLATE \"POSIX\""] {
+            if let Some(rest) = strip_suffix_ci(expr, collation) {
+                collate_c = true;
+                expr = rest;
+                break;
+            }
+        }
+        if find_ci(expr, "COLLATE").is_some() {
+            return Err(unsupported(
+                item,
+                "only the \"C\" collation can be reproduced client-side",
+            ));
+        }
+
+        let (path, kind) = parse_expr(expr).ok_or_else(|| {
+            unsupported(
+                item,
+                "expected data->/->>/#>/#>> paths, optionally cast to a numeric type",
+            )
+        })?;
+        if collate_c && kind != KeyKind::Text {
+            return Err(unsupported(item, "COLLATE only applies to text keys"));
+        }
+        if !collate_c && kind == KeyKind::Text {
+            return Err(unsupported(
+                item,
+                "text keys are compared byte-wise; add COLLATE \"C\" so the server sorts \
+                 partitions the same way",
+            ));
+        }
+
+        Ok(SortKey {
+            path,
+            kind,
+            descending,
+            // Postgres default: NULLS LAST for ASC, NULLS FIRST for DESC
+            nulls_first: nulls_first.unwrap_or(descending),
+        })
+    }
+
+    /// Whether this key only merges correctly when the database's default
+    /// collation is `C` (jsonb keys, whose strings cannot take a collation)
+    pub(crate) fn needs_c_database(&self) -> bool {
+        self.kind == KeyKind::Jsonb
+    }
+
+    /// Compare two rows on this key
+    fn compare(&self, a: &Value, b: &Value) -> Ordering {
+        let a = self.extract(a);
+        let b = self.extract(b);
+        match (a, b) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => {
+                if self.nulls_first {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            }
+            (Some(_), None) => {
+                if self.nulls_first {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                }
+            }
+            (Some(a), Some(b)) => {
+                let ord = match self.kind {
+                    KeyKind::Jsonb => compare_jsonb(a, b),
+                    KeyKind::Text => as_text(a).cmp(&as_text(b)),
+                    KeyKind::Numeric => as_number(a).cmp(&as_number(b)),
+                };
+                if self.descending {
+                    ord.reverse()
+                } else {
+                    ord
+                }
+            }
+        }
+    }
+
+    /// Extract the key, returning `None` for SQL NULL
+    fn extract<'a>(&self, row: &'a Value) -> Option<&'a Value> {
+        let mut current = row;
+        for step in &self.path {
+            current = match (step, current) {
+                (PathStep::Key(key), Value::Object(map)) => map.get(key)?,
+                (PathStep::Index(i), Value::Array(items)) => {
+                    let idx = if *i < 0 {
+                        items.len().checked_sub(i.unsigned_abs() as usize)?
+                    } else {
+                        *i as usize
+                    };
+                    items.get(idx)?
+                }
+                _ => return None,
+            };
+        }
+        // ->> and #>> yield SQL NULL for a JSON null
+        if self.kind != KeyKind::Jsonb && current.is_null() {
+            return None;
+        }
+        Some(current)
+    }
+}
+
+/// Compare two rows on a list of sort keys
+pub(crate) fn compare_rows(keys: &[SortKey], a: &Value, b: &Value) -> Ordering {
+    keys.iter()
+        .map(|key| key.compare(a, b))
+        .find(|ord| *ord != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+fn unsupported(item: &str, reason: &str) -> Error {
+    Error::Config(format!(
+        "ORDER BY '{}' cannot be merged across parallel partitions: {}",
+        item.trim(),
+        reason
+    ))
+}
+
+/// Text form of a JSON value as produced by `->>`
+fn as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Exact numeric value of a key (`None` sorts first; the cast would fail on
+/// the server anyway)
+fn as_number(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Number(n) => parse_decimal(&n.to_string()),
+        Value::String(s) => parse_decimal(s),
+        _ => None,
+    }
+}
+
+/// jsonb ordering: Object > Array > Boolean > Number > String > Null
+fn compare_jsonb(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::String(_) => 1,
+            Value::Number(_) => 2,
+            Value::Bool(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+
+    match (a, b) {
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Number(_), Value::Number(_)) => as_number(a).cmp(&as_number(b)),
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => x.len().cmp(&y.len()).then_with(|| {
+            x.iter()
+                .zip(y)
+                .map(|(l, r)| compare_jsonb(l, r))
+                .find(|ord| *ord != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        }),
+        (Value::Object(x), Value::Object(y)) => {
+            // jsonb stores keys sorted by length, then bytes
+            fn sorted(m: &serde_json::Map<String, Value>) -> Vec<&String> {
+                let mut keys: Vec<&String> = m.keys().collect();
+                keys.sort_by(|l, r| l.len().cmp(&r.len()).then_with(|| l.cmp(r)));
+                keys
+            }
+            let (kx, ky) = (sorted(x), sorted(y));
+            x.len().cmp(&y.len()).then_with(|| {
+                kx.iter()
+                    .zip(&ky)
+                    .map(|(l, r)| {
+                        l.len()
+                            .cmp(&r.len())
+                            .then_with(|| l.cmp(r))
+                            .then_with(|| compare_jsonb(&x[*l], &y[*r]))
+                    })
+                    .find(|ord| *ord != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Split on commas outside quotes and parentheses
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0i32;
+    let mut in_single = false;
+    let mut in_double = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '\'' if !in_double => in_single = !in_single,
+            '"' if !in_single => in_double = !in_double,
+            '(' if !in_single && !in_double => depth += 1,
+            ')' if !in_single && !in_double => depth -= 1,
+            ',' if depth == 0 && !in_single && !in_double => {
+                items.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&s[start..]);
+    items
+}
+
+fn find_ci(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .to_ascii_uppercase()
+        .find(&needle.to_ascii_uppercase())
+}
+
+/// Strip a case-insensitive keyword suffix preceded by whitespace
+fn strip_suffix_ci<'a>(s: &'a str, suffix: &str) -> Option<&'a str> {
+    let s = s.trim_end();
+    if s.len() < suffix.len() {
+        return None;
+    }
+    let split = s.len() - suffix.len();
+    if !s.is_char_boundary(split) || !s[split..].eq_ignore_ascii_case(suffix) {
+        return None;
+    }
+    let rest = &s[..split];
+    if rest.ends_with(char::is_whitespace) {
+        Some(rest.trim_end())
+    } else {
+        None
+    }
+}
+
+/// Parse `<path>` or `(<path>)::<numeric type>` / `<path>::<numeric type>`
+fn parse_expr(expr: &str) -> Option<(Vec<PathStep>, KeyKind)> {
+    let expr = expr.trim();
+    if let Some(idx) = expr.rfind("::") {
+        let ty = expr[idx + 2..].trim().to_ascii_lowercase();
+        let inner = strip_parens(expr[..idx].trim());
+        let (path, kind) = parse_path(inner)?;
+        if kind != KeyKind::Text {
+            return None;
+        }
+        return match ty.as_str() {
+            "text" | "varchar" => Some((path, KeyKind::Text)),
+            "int" | "int2" | "int4" | "int8" | "integer" | "smallint" | "bigint" | "numeric"
+            | "decimal" | "real" | "float4" | "float8" | "double precision" => {
+                Some((path, KeyKind::Numeric))
+            }
+            _ => None,
+        };
+    }
+    parse_path(strip_parens(expr))
+}
+
+fn strip_parens(mut s: &str) -> &str {
+    while s.starts_with('(') && s.ends_with(')') {
+        s = s[1..s.len() - 1].trim();
+    }
+    s
+}
+
+/// Parse `data` followed by `->`, `->>`, `#>`, `#>>` operators
+fn parse_path(expr: &str) -> Option<(Vec<PathStep>, KeyKind)> {
+    let mut rest = expr.strip_prefix("data")?.trim_start();
+    let mut path = Vec::new();
+    let mut kind = KeyKind::Jsonb;
+
+    if rest.is_empty() {
+        return Some((path, kind));
+    }
+
+    while !rest.is_empty() {
+        if kind == KeyKind::Text {
+            // Text extraction must be the final operator
+            return None;
+        }
+        let (op_text, multi, after) = if let Some(r) = rest.strip_prefix("#>>") {
+            (true, true, r)
+        } else if let Some(r) = rest.strip_prefix("#>") {
+            (false, true, r)
+        } else if let Some(r) = rest.strip_prefix("->>") {
+            (true, false, r)
+        } else if let Some(r) = rest.strip_prefix("->") {
+            (false, false, r)
+        } else {
+            return None;
+        };
+        let after = after.trim_start();
+
+        let (operand, remaining) = if after.starts_with('\'') {
+            let (lit, remaining) = parse_string_literal(after)?;
+            (Some(lit), remaining)
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_digit() || c == '-'))
+                .unwrap_or(after.len());
+            let index: i64 = after[..end].parse().ok()?;
+            if multi {
+                return None;
+            }
+            path.push(PathStep::Index(index));
+            (None, &after[end..])
+        };
+
+        if let Some(lit) = operand {
+            if multi {
+                let inner = lit.trim().strip_prefix('{')?.strip_suffix('}')?;
+                for part in inner.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+                    let part = part.trim_matches('"');
+                    path.push(match part.parse::<i64>() {
+                        Ok(i) => PathStep::Index(i),
+                        Err(_) => PathStep::Key(part.to_string()),
+                    });
+                }
+            } else {
+                path.push(PathStep::Key(lit));
+            }
+        }
+
+        if op_text {
+            kind = KeyKind::Text;
+        }
+        rest = remaining.trim_start();
+    }
+
+    Some((path, kind))
+}
+
+/// Parse a single-quoted SQL literal, returning its value and the remaining input
+fn parse_string_literal(s: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = s.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == '\'' {
+            if matches!(chars.peek(), Some((_, '\''))) {
+                chars.next();
+                value.push('\'');
+            } else {
+                return Some((value, &s[i + 1..]));
+            }
+        } else {
+            value.push(c);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sorted(order_by: &str, mut rows: Vec<Value>) -> Vec<Value> {
+        let keys = SortKey::parse_order_by(order_by).unwrap();
+        rows.sort_by(|a, b| compare_rows(&keys, a, b));
+        rows
+    }
+
+    #[test]
+    fn test_parse_text_key() {
+        let keys = SortKey::parse_order_by("data->>'name' COLLATE \"C\" ASC").unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].kind, KeyKind::Text);
+        assert_eq!(keys[0].path, vec![PathStep::Key("name".into())]);
+        assert!(!keys[0].descending);
+        assert!(!keys[0].nulls_first);
+    }
+
+    #[test]
+    fn test_parse_nested_and_cast() {
+        let keys = SortKey::parse_order_by(
+            "(data->'meta'->>'age')::int DESC, data#>>'{a,0}' COLLATE \"C\"",
+        )
+        .unwrap();
+        assert_eq!(keys[0].kind, KeyKind::Numeric);
+        assert_eq!(
+            keys[0].path,
+            vec![PathStep::Key("meta".into()), PathStep::Key("age".into())]
+        );
+        assert!(keys[0].descending);
+        assert!(keys[0].nulls_first);
+        assert_eq!(keys[1].kind, KeyKind::Text);
+        assert_eq!(
+            keys[1].path,
+            vec![PathStep::Key("a".into()), PathStep::Index(0)]
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_unsupported() {
+        assert!(SortKey::parse_order_by("created_at DESC").is_err());
+        assert!(SortKey::parse_order_by("lower(data->>'name')").is_err());
+        assert!(SortKey::parse_order_by("data->>'a'->>'b'").is_err());
+        assert!(SortKey::parse_order_by("data->>'name' COLLATE \"en_US\"").is_err());
+        // Text keys must be compared in the C collation on both sides
+        assert!(SortKey::parse_order_by("data->>'name'").is_err());
+        assert!(SortKey::parse_order_by("data->>'name'::text DESC").is_err());
+        assert!(SortKey::parse_order_by("data->'name' COLLATE \"C\"").is_err());
+        assert!(SortKey::parse_order_by("(data->'n')::int").is_err());
+        assert!(SortKey::parse_order_by("").is_err());
+    }
+
+    #[test]
+    fn test_numeric_ordering_with_nulls() {
+        let rows = vec![
+            json!({"n": "10"}),
+            json!({}),
+            json!({"n": "9"}),
+            json!({"n": 2}),
+        ];
+        let out = sorted("(data->>'n')::int", rows.clone());
+        assert_eq!(
+            out,
+            vec![
+                json!({"n": 2}),
+                json!({"n": "9"}),
+                json!({"n": "10"}),
+                json!({})
+            ]
+        );
+
+        let out = sorted("(data->>'n')::int DESC", rows.clone());
+        assert_eq!(out[0], json!({}));
+        assert_eq!(out[1], json!({"n": "10"}));
+
+        let out = sorted("(data->>'n')::int DESC NULLS LAST", rows);
+        assert_eq!(out[3], json!({}));
+    }
+
+    #[test]
+    fn test_text_ordering_is_bytewise() {
+        let rows = vec![json!({"s": "b"}), json!({"s": "B"}), json!({"s": "a"})];
+        let out = sorted("data->>'s' COLLATE \"C\"", rows);
+        assert_eq!(
+            out,
+            vec![json!({"s": "B"}), json!({"s": "a"}), json!({"s": "b"})]
+        );
+    }
+
+    #[test]
+    fn test_numeric_ordering_is_exact() {
+        // Equal as f64, distinct as numeric
+        let rows = vec![
+            json!({"n": 9_007_199_254_740_993i64}),
+            json!({"n": "9007199254740992.5"}),
+            json!({"n": 9_007_199_254_740_992i64}),
+        ];
+        let out = sorted("(data->>'n')::numeric", rows);
+        assert_eq!(
+            out,
+            vec![
+                json!({"n": 9_007_199_254_740_992i64}),
+                json!({"n": "9007199254740992.5"}),
+                json!({"n": 9_007_199_254_740_993i64}),
+            ]
+        );
+        assert_eq!(
+            sorted(
+                "data->'n'",
+                vec![
+                    json!({"n": 9_007_199_254_740_993i64}),
+                    json!({"n": 9_007_199_254_740_992i64})
+                ]
+            )[0],
+            json!({"n": 9_007_199_254_740_992i64})
+        );
+    }
+
+    #[test]
+    fn test_jsonb_type_ordering() {
+        let rows = vec![
+            json!({"v": {"a": 1}}),
+            json!({"v": [1]}),
+            json!({"v": true}),
+            json!({"v": 5}),
+            json!({"v": "x"}),
+            json!({"v": null}),
+        ];
+        let mut reversed = rows.clone();
+        reversed.reverse();
+        assert_eq!(sorted("data->'v'", rows), reversed);
+    }
+
+    #[test]
+    fn test_multiple_keys() {
+        let rows = vec![
+            json!({"a": "x", "b": 2}),
+            json!({"a": "x", "b": 1}),
+            json!({"a": "w", "b": 3}),
+        ];
+        let out = sorted(
+            "data->>'a' COLLATE \"POSIX\", (data->>'b')::numeric DESC",
+            rows,
+        );
+        assert_eq!(
+            out,
+            vec![
+                json!({"a": "w", "b": 3}),
+                json!({"a": "x", "b": 2}),
+                json!({"a": "x", "b": 1}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_ignores_commas_in_literals() {
+        assert_eq!(
+            split_top_level("data#>>'{a,b}', data->>'c'"),
+            vec!["data#>>'{a,b}'", " data->>'c'"]
+        );
+    }
+}
@@ -21,3 +21,1801 @@ async fn test_connect_and_query() {
 
     conn.close().await.expect("close");
 }
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_session_info_after_startup() {
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+
+    let mut conn = Connection::new(transport);
+
+    let config = ConnectionConfig::builder("postgres", "postgres")
+        .application_name("session_info_test")
+        .build();
+    conn.startup(&config, None, None).await.expect("startup");
+
+    let info = conn.session_info();
+    assert!(info.server_version.expect("server_version").major >= 10);
+    assert_eq!(info.client_encoding.as_deref(), Some("UTF8"));
+    assert_eq!(info.application_name.as_deref(), Some("session_info_test"));
+    assert_eq!(info.integer_datetimes, Some(true));
+    assert!(info.time_zone.is_some());
+    assert!(info.date_style.is_some());
+    assert!(info.process_id.is_some());
+
+    // Mid-session ParameterStatus updates are applied
+    conn.simple_query("SET TimeZone = 'Pacific/Auckland'")
+        .await
+        .expect("set timezone");
+    assert_eq!(
+        conn.session_info().time_zone.as_deref(),
+        Some("Pacific/Auckland")
+    );
+
+    conn.close().await.expect("close");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_non_utf8_client_encoding_refused() {
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+
+    let mut conn = Connection::new(transport);
+
+    let config = ConnectionConfig::new("postgres", "postgres").param("client_encoding", "LATIN1");
+    let result = conn.startup(&config, None, None).await;
+
+    assert!(
+        matches!(result, Err(fraiseql_wire::Error::Connection(ref msg)) if msg.contains("UTF8")),
+        "expected UTF8 refusal, got: {:?}",
+        result
+    );
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_parameter_status_during_stream() {
+    use futures::StreamExt;
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+
+    let mut conn = Connection::new(transport);
+    let config = ConnectionConfig::new("postgres", "postgres");
+    conn.startup(&config, None, None).await.expect("startup");
+
+    // set_config() makes the server report ParameterStatus while the query is streaming
+    let mut stream = conn
+        .streaming_query(
+            "SELECT jsonb_build_object('tz', set_config('TimeZone', 'Asia/Tokyo', false)) AS data",
+            16,
+            None,
+            None,
+            None,
+            false,
+            None,
+            None,
+        )
+        .await
+        .expect("query");
+
+    let row = stream.next().await.expect("row").expect("value");
+    assert_eq!(row["tz"], "Asia/Tokyo");
+    assert!(stream.next().await.is_none());
+    assert_eq!(
+        stream.session_info().time_zone.as_deref(),
+        Some("Asia/Tokyo")
+    );
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_notices_during_stream() {
+    use fraiseql_wire::connection::NoticeHandler;
+    use futures::StreamExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut conn = Connection::new(transport);
+
+    let handled = Arc::new(AtomicUsize::new(0));
+    let counter = handled.clone();
+    let config = ConnectionConfig::builder("postgres", "postgres")
+        .notice_handler(NoticeHandler::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }))
+        .build();
+    conn.startup(&config, None, None).await.expect("startup");
+
+    conn.simple_query(
+        "CREATE FUNCTION pg_temp.noisy(i int) RETURNS jsonb LANGUAGE plpgsql AS $$ \
+         BEGIN RAISE WARNING 'row % looks odd', i USING HINT = 'check input'; \
+         RETURN jsonb_build_object('i', i); END $$",
+    )
+    .await
+    .expect("create function");
+
+    let mut stream = conn
+        .streaming_query(
+            "SELECT pg_temp.noisy(i) AS data FROM generate_series(1, 3) i",
+            16,
+            None,
+            None,
+            None,
+            false,
+            None,
+            None,
+        )
+        .await
+        .expect("query");
+    let notices = stream.notices().expect("notice stream");
+    assert!(stream.notices().is_none());
+
+    let rows: Vec<_> = stream.collect().await;
+    assert_eq!(rows.len(), 3);
+    assert!(rows.iter().all(|r| r.is_ok()));
+
+    let notices: Vec<_> = notices.collect().await;
+    assert_eq!(notices.len(), 3);
+    assert_eq!(notices[0].severity.as_deref(), Some("WARNING"));
+    assert_eq!(notices[0].code.as_deref(), Some("01000"));
+    assert_eq!(notices[0].message.as_deref(), Some("row 1 looks odd"));
+    assert_eq!(notices[0].hint.as_deref(), Some("check input"));
+    assert_eq!(handled.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_query_in_exported_snapshot() {
+    use fraiseql_wire::client::PartitionStrategy;
+    use fraiseql_wire::connection::{IsolationLevel, TransactionStatus};
+    use fraiseql_wire::FraiseClient;
+    use futures::StreamExt;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+
+    async fn admin() -> Connection {
+        let transport = Transport::connect_tcp("localhost", 5432)
+            .await
+            .expect("connect");
+        let mut conn = Connection::new(transport);
+        conn.startup(&ConnectionConfig::new("postgres", "postgres"), None, None)
+            .await
+            .expect("startup");
+        conn
+    }
+
+    let mut setup = admin().await;
+    setup
+        .simple_query(
+            "DROP TABLE IF EXISTS snapshot_test; \
+             CREATE TABLE snapshot_test (data jsonb); \
+             INSERT INTO snapshot_test VALUES ('{\"n\": 1}')",
+        )
+        .await
+        .expect("setup");
+
+    let mut leader = FraiseClient::connect(URL).await.expect("leader");
+    assert!(leader
+        .begin_snapshot(IsolationLevel::ReadCommitted)
+        .await
+        .is_err());
+    let snapshot = leader
+        .begin_snapshot(IsolationLevel::RepeatableRead)
+        .await
+        .expect("begin_snapshot");
+
+    // Committed after the snapshot was taken: invisible to snapshot readers
+    setup
+        .simple_query(
+            "INSERT INTO snapshot_test \
+             SELECT jsonb_build_object('n', n) FROM generate_series(2, 10) n",
+        )
+        .await
+        .expect("insert");
+
+    let follower = FraiseClient::connect(URL).await.expect("follower");
+    let rows: Vec<_> = follower
+        .query_in_snapshot::<serde_json::Value>(&snapshot, "snapshot_test")
+        .await
+        .expect("import snapshot")
+        .execute()
+        .await
+        .expect("execute")
+        .collect()
+        .await;
+    assert_eq!(rows.len(), 1);
+
+    let fresh = FraiseClient::connect(URL).await.expect("fresh");
+    let rows: Vec<_> = fresh
+        .query::<serde_json::Value>("snapshot_test")
+        .execute()
+        .await
+        .expect("execute")
+        .collect()
+        .await;
+    assert_eq!(rows.len(), 10);
+
+    // Parallel partitions all import the snapshot
+    let follower = FraiseClient::connect(URL).await.expect("follower");
+    let rows: Vec<_> = follower
+        .query_in_snapshot::<serde_json::Value>(&snapshot, "snapshot_test")
+        .await
+        .expect("import snapshot")
+        .parallel(3, PartitionStrategy::hash("data->>'n'"))
+        .execute()
+        .await
+        .expect("execute")
+        .collect()
+        .await;
+    assert_eq!(rows.len(), 1);
+
+    leader.commit().await.expect("commit");
+
+    let mut check = admin().await;
+    check
+        .begin(IsolationLevel::RepeatableRead)
+        .await
+        .expect("begin");
+    assert_eq!(check.transaction_status(), TransactionStatus::InTransaction);
+    check.rollback().await.expect("rollback");
+    assert_eq!(check.transaction_status(), TransactionStatus::Idle);
+
+    setup
+        .simple_query("DROP TABLE snapshot_test")
+        .await
+        .expect("cleanup");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_parallel_scan() {
+    use fraiseql_wire::client::PartitionStrategy;
+    use fraiseql_wire::{FraiseClient, Value};
+    use futures::StreamExt;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut setup = Connection::new(transport);
+    setup
+        .startup(&ConnectionConfig::new("postgres", "postgres"), None, None)
+        .await
+        .expect("startup");
+    setup
+        .simple_query(
+            "DROP TABLE IF EXISTS parallel_test; \
+             CREATE TABLE parallel_test (data jsonb); \
+             INSERT INTO parallel_test \
+             SELECT jsonb_build_object('id', i::text, 'n', i) FROM generate_series(1, 2000) i",
+        )
+        .await
+        .expect("setup");
+
+    async fn collect_n(strategy: PartitionStrategy, n: usize, order: Option<&str>) -> Vec<i64> {
+        let client = FraiseClient::connect(URL).await.expect("connect");
+        let mut query = client
+            .query::<serde_json::Value>("parallel_test")
+            .where_sql("(data->>'n')::int > 1000 OR (data->>'n')::int <= 500")
+            .parallel(n, strategy);
+        if let Some(order) = order {
+            query = query.order_by(order);
+        }
+        let stream = query.execute().await.expect("execute");
+        stream
+            .map(|row| row.expect("row")["n"].as_i64().unwrap())
+            .collect()
+            .await
+    }
+
+    let expected: Vec<i64> = (1..=500).chain(1001..=2000).collect();
+
+    let mut rows = collect_n(PartitionStrategy::hash("data->>'id'"), 4, None).await;
+    rows.sort();
+    assert_eq!(rows, expected);
+
+    let mut rows = collect_n(PartitionStrategy::CtidBlocks, 3, None).await;
+    rows.sort();
+    assert_eq!(rows, expected);
+
+    let strategy = PartitionStrategy::range(
+        "(data->>'n')::int",
+        vec![Value::Number(700.0), Value::Number(1500.0)],
+    );
+    let rows = collect_n(strategy, 3, Some("(data->>'n')::int")).await;
+    assert_eq!(rows, expected);
+
+    let rows = collect_n(
+        PartitionStrategy::hash("data->>'id'"),
+        4,
+        Some("(data->>'n')::int DESC"),
+    )
+    .await;
+    assert_eq!(rows, expected.iter().rev().copied().collect::<Vec<_>>());
+
+    // LIMIT applies to the merged stream
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let stream = client
+        .query::<serde_json::Value>("parallel_test")
+        .order_by("(data->>'n')::int")
+        .limit(10)
+        .parallel(4, PartitionStrategy::hash("data->>'id'"))
+        .execute()
+        .await
+        .expect("execute");
+    let rows: Vec<i64> = stream
+        .map(|row| row.expect("row")["n"].as_i64().unwrap())
+        .collect()
+        .await;
+    assert_eq!(rows, (1..=10).collect::<Vec<_>>());
+
+    // Rows with a NULL partition key are read exactly once
+    setup
+        .simple_query(
+            "DROP TABLE IF EXISTS parallel_null_test; \
+             CREATE TABLE parallel_null_test AS \
+             SELECT CASE WHEN i % 3 = 0 THEN '{}'::jsonb ELSE jsonb_build_object('k', i) END \
+             || jsonb_build_object('n', i) AS data FROM generate_series(1, 30) i",
+        )
+        .await
+        .expect("setup");
+    for (n, strategy) in [
+        (4, PartitionStrategy::hash("data->>'k'")),
+        (
+            3,
+            PartitionStrategy::range(
+                "(data->>'k')::int",
+                vec![Value::Number(10.0), Value::Number(20.0)],
+            ),
+        ),
+    ] {
+        let client = FraiseClient::connect(URL).await.expect("connect");
+        let stream = client
+            .query::<serde_json::Value>("parallel_null_test")
+            .order_by("(data->>'n')::int")
+            .parallel(n, strategy)
+            .execute()
+            .await
+            .expect("execute");
+        let rows: Vec<i64> = stream
+            .map(|row| row.expect("row")["n"].as_i64().unwrap())
+            .collect()
+            .await;
+        assert_eq!(rows, (1..=30).collect::<Vec<_>>());
+    }
+    setup
+        .simple_query("DROP TABLE parallel_null_test")
+        .await
+        .expect("cleanup");
+
+    // Text keys merge in the C collation, which the server is told to use
+    async fn collect_ids(order: &str) -> Vec<String> {
+        let client = FraiseClient::connect(URL).await.expect("connect");
+        let stream = client
+            .query::<serde_json::Value>("parallel_test")
+            .where_sql("(data->>'n')::int > 1000 OR (data->>'n')::int <= 500")
+            .order_by(order)
+            .parallel(3, PartitionStrategy::hash("data->>'id'"))
+            .execute()
+            .await
+            .expect("execute");
+        stream
+            .map(|row| row.expect("row")["id"].as_str().unwrap().to_string())
+            .collect()
+            .await
+    }
+    let mut ids: Vec<String> = expected.iter().map(i64::to_string).collect();
+    ids.sort();
+    assert_eq!(collect_ids("data->>'id' COLLATE \"C\"").await, ids);
+    ids.reverse();
+    assert_eq!(collect_ids("data->>'id' COLLATE \"C\" DESC").await, ids);
+
+    // ORDER BY expressions the client cannot evaluate are rejected up front
+    for order in ["lower(data->>'id')", "data->>'id'"] {
+        let client = FraiseClient::connect(URL).await.expect("connect");
+        assert!(client
+            .query::<serde_json::Value>("parallel_test")
+            .order_by(order)
+            .parallel(2, PartitionStrategy::hash("data->>'id'"))
+            .execute()
+            .await
+            .is_err());
+    }
+
+    setup
+        .simple_query("DROP TABLE parallel_test")
+        .await
+        .expect("cleanup");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_parallel_jsonb_keys_need_c_collation() {
+    use fraiseql_wire::client::PartitionStrategy;
+    use fraiseql_wire::protocol::BackendMessage;
+    use fraiseql_wire::FraiseClient;
+    use futures::StreamExt;
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut admin = Connection::new(transport);
+    admin
+        .startup(&ConnectionConfig::new("postgres", "postgres"), None, None)
+        .await
+        .expect("startup");
+    admin
+        .simple_query("DROP DATABASE IF EXISTS fraiseql_icu_test")
+        .await
+        .expect("drop database");
+    let created = admin
+        .simple_query(
+            "CREATE DATABASE fraiseql_icu_test TEMPLATE template0 ENCODING 'UTF8' \
+             LOCALE_PROVIDER icu ICU_LOCALE 'en' LOCALE 'C'",
+        )
+        .await
+        .expect("create database");
+    assert!(
+        !created
+            .iter()
+            .any(|m| matches!(m, BackendMessage::ErrorResponse(_))),
+        "create database: {:?}",
+        created
+    );
+
+    const ICU_URL: &str = "postgres://postgres@localhost:5432/fraiseql_icu_test";
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut setup = Connection::new(transport);
+    setup
+        .startup(
+            &ConnectionConfig::new("fraiseql_icu_test", "postgres"),
+            None,
+            None,
+        )
+        .await
+        .expect("startup");
+    setup
+        .simple_query(
+            "CREATE TABLE names (data jsonb); \
+             INSERT INTO names SELECT jsonb_build_object('name', n) \
+             FROM unnest(ARRAY['b', 'B', 'a', 'A', 'c']) n",
+        )
+        .await
+        .expect("setup");
+
+    async fn names(order: &str) -> fraiseql_wire::Result<Vec<String>> {
+        let client = FraiseClient::connect(ICU_URL).await?;
+        let stream = client
+            .query::<serde_json::Value>("names")
+            .order_by(order)
+            .parallel(2, PartitionStrategy::hash("data->>'name'"))
+            .execute()
+            .await?;
+        Ok(stream
+            .map(|row| row.expect("row")["name"].as_str().unwrap().to_string())
+            .collect()
+            .await)
+    }
+
+    // jsonb strings sort in the ICU collation, which the client cannot reproduce
+    assert!(matches!(
+        names("data->'name'").await,
+        Err(fraiseql_wire::Error::Config(_))
+    ));
+    assert_eq!(
+        names("data->>'name' COLLATE \"C\"")
+            .await
+            .expect("collated"),
+        ["A", "B", "a", "b", "c"]
+    );
+
+    drop(setup);
+    admin
+        .simple_query("DROP DATABASE fraiseql_icu_test WITH (FORCE)")
+        .await
+        .expect("drop database");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_cursor_mode() {
+    use fraiseql_wire::connection::ExecutionMode;
+    use fraiseql_wire::FraiseClient;
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut setup = Connection::new(transport);
+    setup
+        .startup(&ConnectionConfig::new("postgres", "postgres"), None, None)
+        .await
+        .expect("startup");
+    setup
+        .simple_query(
+            "DROP TABLE IF EXISTS cursor_test; \
+             CREATE TABLE cursor_test (data jsonb); \
+             INSERT INTO cursor_test \
+             SELECT jsonb_build_object('n', i) FROM generate_series(1, 1000) i",
+        )
+        .await
+        .expect("setup");
+
+    // Full drain across many FETCHes, with a pause in the middle
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let mut stream = client
+        .query::<serde_json::Value>("cursor_test")
+        .order_by("(data->>'n')::int")
+        .execution_mode(ExecutionMode::Cursor)
+        .chunk_size(64)
+        .execute()
+        .await
+        .expect("execute");
+    let mut seen = Vec::new();
+    while let Some(row) = stream.next().await {
+        seen.push(row.expect("row")["n"].as_i64().unwrap());
+        if seen.len() == 100 {
+            stream.pause().await.expect("pause");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            stream.resume().await.expect("resume");
+        }
+    }
+    assert_eq!(seen, (1..=1000).collect::<Vec<_>>());
+
+    // Exact multiple of chunk_size: the final empty FETCH ends the stream
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let rows: Vec<_> = client
+        .query::<serde_json::Value>("cursor_test")
+        .execution_mode(ExecutionMode::Cursor)
+        .chunk_size(100)
+        .execute()
+        .await
+        .expect("execute")
+        .collect()
+        .await;
+    assert_eq!(rows.len(), 1000);
+
+    // Dropping a half-read stream closes the cursor and its transaction
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let mut stream = client
+        .query::<serde_json::Value>("cursor_test")
+        .execution_mode(ExecutionMode::Cursor)
+        .chunk_size(10)
+        .execute()
+        .await
+        .expect("execute");
+    stream.next().await.expect("first row").expect("row");
+    drop(stream);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let messages = setup
+        .simple_query(
+            "SELECT count(*)::text FROM pg_stat_activity \
+             WHERE state = 'idle in transaction' AND query LIKE 'FETCH%'",
+        )
+        .await
+        .expect("pg_stat_activity");
+    let open = messages.iter().find_map(|msg| match msg {
+        fraiseql_wire::protocol::BackendMessage::DataRow(fields) => fields[0].clone(),
+        _ => None,
+    });
+    assert_eq!(open.as_deref(), Some(&b"0"[..]));
+
+    // Errors from DECLARE surface from execute()
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    assert!(client
+        .query::<serde_json::Value>("cursor_test_missing")
+        .execution_mode(ExecutionMode::Cursor)
+        .execute()
+        .await
+        .is_err());
+
+    setup
+        .simple_query("DROP TABLE cursor_test")
+        .await
+        .expect("cleanup");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_ping_and_is_closed() {
+    use fraiseql_wire::protocol::BackendMessage;
+    use fraiseql_wire::FraiseClient;
+    use std::time::Duration;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+
+    let mut client = FraiseClient::connect(URL).await.expect("connect");
+    client.ping().await.expect("ping");
+    assert!(!client.is_closed());
+    let pid = client.session_info().process_id.expect("backend pid");
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut admin = Connection::new(transport);
+    admin
+        .startup(&ConnectionConfig::new("postgres", "postgres"), None, None)
+        .await
+        .expect("startup");
+    let messages = admin
+        .simple_query(&format!("SELECT pg_terminate_backend({})", pid))
+        .await
+        .expect("terminate");
+    assert!(!messages
+        .iter()
+        .any(|m| matches!(m, BackendMessage::ErrorResponse(_))));
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(client.is_closed());
+    assert!(client.ping().await.is_err());
+
+    // A failing check_on_connect query fails startup
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut conn = Connection::new(transport);
+    let config = ConnectionConfig::builder("postgres", "postgres")
+        .check_on_connect("SELECT 1/0")
+        .build();
+    let err = conn.startup(&config, None, None).await.unwrap_err();
+    assert!(err.to_string().contains("check_on_connect"));
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut conn = Connection::new(transport);
+    let config = ConnectionConfig::builder("postgres", "postgres")
+        .check_on_connect("SELECT 1")
+        .build();
+    conn.startup(&config, None, None).await.expect("startup");
+    conn.ping().await.expect("ping");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_graceful_shutdown() {
+    use fraiseql_wire::connection::{ExecutionMode, ShutdownHandle};
+    use fraiseql_wire::{Error, FraiseClient};
+    use futures::StreamExt;
+    use std::time::{Duration, Instant};
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut setup = Connection::new(transport);
+    setup
+        .startup(&ConnectionConfig::new("postgres", "postgres"), None, None)
+        .await
+        .expect("startup");
+    // 10ms per row: the query would run for minutes unless cancelled
+    setup
+        .simple_query(
+            "CREATE OR REPLACE VIEW shutdown_slow AS \
+             SELECT jsonb_build_object('n', i) AS data \
+             FROM generate_series(1, 100000) i, LATERAL pg_sleep(0.01)",
+        )
+        .await
+        .expect("setup");
+
+    let shutdown = ShutdownHandle::new();
+
+    let client = FraiseClient::connect(URL)
+        .await
+        .expect("connect")
+        .with_shutdown(shutdown.clone());
+    let mut streaming = client
+        .query::<serde_json::Value>("shutdown_slow")
+        .chunk_size(4)
+        .execute()
+        .await
+        .expect("execute");
+
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let mut cursor = client
+        .query::<serde_json::Value>("shutdown_slow")
+        .execution_mode(ExecutionMode::Cursor)
+        .chunk_size(4)
+        .shutdown(&shutdown)
+        .execute()
+        .await
+        .expect("execute");
+
+    streaming.next().await.expect("row").expect("row");
+    cursor.next().await.expect("row").expect("row");
+    assert_eq!(shutdown.active_streams(), 2);
+
+    let started = Instant::now();
+    let report = shutdown.shutdown(Duration::from_secs(5)).await;
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(report.drained, 2);
+    assert_eq!(report.forcibly_closed, 0);
+
+    for stream in [&mut streaming, &mut cursor] {
+        assert!(matches!(stream.next().await, Some(Err(Error::Cancelled))));
+        assert!(stream.next().await.is_none());
+    }
+
+    // Both backends are gone, not left running the query
+    let messages = setup
+        .simple_query(
+            "SELECT count(*)::text FROM pg_stat_activity \
+             WHERE query LIKE '%shutdown_slow%' AND pid <> pg_backend_pid()",
+        )
+        .await
+        .expect("pg_stat_activity");
+    let running = messages.iter().find_map(|msg| match msg {
+        fraiseql_wire::protocol::BackendMessage::DataRow(fields) => fields[0].clone(),
+        _ => None,
+    });
+    assert_eq!(running.as_deref(), Some(&b"0"[..]));
+
+    // Streams started after shutdown end immediately
+    let client = FraiseClient::connect(URL)
+        .await
+        .expect("connect")
+        .with_shutdown(shutdown.clone());
+    let mut late = client
+        .query::<serde_json::Value>("shutdown_slow")
+        .execute()
+        .await
+        .expect("execute");
+    assert!(matches!(late.next().await, Some(Err(Error::Cancelled))));
+
+    setup
+        .simple_query("DROP VIEW shutdown_slow")
+        .await
+        .expect("cleanup");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_keyset_pagination() {
+    use fraiseql_wire::operators::NullsHandling;
+    use fraiseql_wire::{FraiseClient, OrderByClause, SortOrder};
+    use futures::StreamExt;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut setup = Connection::new(transport);
+    setup
+        .startup(&ConnectionConfig::new("postgres", "postgres"), None, None)
+        .await
+        .expect("startup");
+    // Many ties on score, a NULL score on every 10th row and a missing one on every 15th
+    setup
+        .simple_query(
+            "DROP TABLE IF EXISTS keyset_test; \
+             CREATE TABLE keyset_test (data jsonb); \
+             INSERT INTO keyset_test \
+             SELECT jsonb_strip_nulls(jsonb_build_object('id', i, \
+                 'score', CASE WHEN i % 10 = 0 THEN NULL ELSE to_jsonb(i % 7) END, \
+                 'name', 'row ''' || i)) \
+                 || CASE WHEN i % 10 = 0 AND i % 15 <> 0 THEN '{\"score\": null}' ELSE '{}' END::jsonb \
+             FROM generate_series(1, 100) i",
+        )
+        .await
+        .expect("setup");
+
+    async fn fetch(
+        order: &[OrderByClause],
+        after: Option<String>,
+        before: Option<String>,
+        limit: Option<usize>,
+    ) -> (Vec<i64>, Option<String>, Option<String>) {
+        let client = FraiseClient::connect(URL).await.expect("connect");
+        let mut query = client
+            .query::<serde_json::Value>("keyset_test")
+            .where_sql("(data->>'id')::int <> 50")
+            .keyset_order(order.to_vec());
+        if let Some(after) = after {
+            query = query.after(after);
+        }
+        if let Some(before) = before {
+            query = query.before(before);
+        }
+        if let Some(limit) = limit {
+            query = query.limit(limit);
+        }
+        let mut stream = query.execute().await.expect("execute");
+        let mut ids = Vec::new();
+        while let Some(row) = stream.next().await {
+            ids.push(row.expect("row")["id"].as_i64().unwrap());
+        }
+        (ids, stream.first_cursor(), stream.last_cursor())
+    }
+
+    let orderings = [
+        vec![
+            OrderByClause::jsonb_field("score", SortOrder::Asc),
+            OrderByClause::jsonb_field("id", SortOrder::Asc),
+        ],
+        vec![
+            OrderByClause::jsonb_field("score", SortOrder::Desc),
+            OrderByClause::jsonb_field("id", SortOrder::Asc),
+        ],
+        vec![
+            OrderByClause::jsonb_field("score", SortOrder::Asc).with_nulls(NullsHandling::First),
+            OrderByClause::jsonb_field("id", SortOrder::Desc),
+        ],
+        vec![
+            OrderByClause::jsonb_field("score", SortOrder::Desc).with_nulls(NullsHandling::Last),
+            OrderByClause::jsonb_field("name", SortOrder::Desc),
+            OrderByClause::jsonb_field("id", SortOrder::Desc),
+        ],
+        // Collated keys sort and seek as text ("row '10" before "row '2")
+        vec![
+            OrderByClause::jsonb_field("name", SortOrder::Asc).with_collation("C"),
+            OrderByClause::jsonb_field("id", SortOrder::Asc),
+        ],
+        vec![
+            OrderByClause::jsonb_field("score", SortOrder::Desc).with_collation("C"),
+            OrderByClause::jsonb_field("id", SortOrder::Asc),
+        ],
+    ];
+
+    for order in &orderings {
+        let (all, _, _) = fetch(order, None, None, None).await;
+        assert_eq!(all.len(), 99);
+
+        // Forward: every row exactly once, in order
+        let mut forward: Vec<i64> = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (page, _, last) = fetch(order, cursor.clone(), None, Some(7)).await;
+            forward.extend(&page);
+            if page.len() < 7 {
+                break;
+            }
+            cursor = last;
+        }
+        assert_eq!(forward, all, "forward pages for {:?}", order);
+
+        // Backward from the last row, using each page's first cursor
+        let (_, _, last) = fetch(order, None, None, None).await;
+        let mut backward = vec![*all.last().unwrap()];
+        let mut cursor = last;
+        loop {
+            let (page, first, _) = fetch(order, None, cursor.clone(), Some(9)).await;
+            backward.splice(0..0, page.iter().copied());
+            if page.len() < 9 {
+                break;
+            }
+            cursor = first;
+        }
+        assert_eq!(backward, all, "backward pages for {:?}", order);
+
+        // Without a limit, before() returns everything preceding the cursor
+        let (_, _, tenth) = fetch(order, None, None, Some(10)).await;
+        let (head, _, _) = fetch(order, None, tenth, None).await;
+        assert_eq!(head, all[..9]);
+    }
+
+    // A cursor from another ordering is rejected
+    let (_, _, last) = fetch(&orderings[0], None, None, Some(3)).await;
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    assert!(client
+        .query::<serde_json::Value>("keyset_test")
+        .keyset_order(orderings[1][..1].to_vec())
+        .after(last.unwrap())
+        .execute()
+        .await
+        .is_err());
+
+    setup
+        .simple_query("DROP TABLE keyset_test")
+        .await
+        .expect("cleanup");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_typed_order_by() {
+    use fraiseql_wire::operators::NullsHandling;
+    use fraiseql_wire::{Error, FraiseClient, OrderByClause, SortOrder};
+    use futures::StreamExt;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+    const ENTITY: &str =
+        "(SELECT jsonb_build_object('g', CASE WHEN i % 4 = 0 THEN NULL ELSE i % 3 END, \
+                          'id', i) AS data FROM generate_series(1, 12) i) t";
+
+    async fn ids(stream: fraiseql_wire::stream::QueryStream<serde_json::Value>) -> Vec<i64> {
+        stream
+            .map(|row| row.expect("row")["id"].as_i64().unwrap())
+            .collect()
+            .await
+    }
+
+    // Multi-key ordering: g DESC NULLS LAST, then id DESC
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let stream = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .order_by_clause(
+            OrderByClause::jsonb_field("g", SortOrder::Desc).with_nulls(NullsHandling::Last),
+        )
+        .order_by_clause(OrderByClause::jsonb_field("id", SortOrder::Desc))
+        .execute()
+        .await
+        .expect("execute");
+    assert_eq!(
+        ids(stream).await,
+        vec![11, 5, 2, 10, 7, 1, 9, 6, 3, 12, 8, 4]
+    );
+
+    // Invalid clauses are rejected before anything is sent
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let result = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .order_by_clause(OrderByClause::jsonb_field("id') DESC; --", SortOrder::Asc))
+        .execute()
+        .await;
+    assert!(matches!(result, Err(Error::Config(_))));
+
+    // Strict mode accepts plain clauses and rejects arbitrary expressions
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let stream = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .strict_ordering(true)
+        .order_by("data->'id' DESC")
+        .limit(3)
+        .execute()
+        .await
+        .expect("execute");
+    assert_eq!(ids(stream).await, vec![12, 11, 10]);
+
+    // Collated keys sort as text, so "10" < "2"
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let stream = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .order_by_clause(OrderByClause::jsonb_field("id", SortOrder::Asc).with_collation("C"))
+        .limit(4)
+        .execute()
+        .await
+        .expect("execute");
+    assert_eq!(ids(stream).await, vec![1, 10, 11, 12]);
+
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let stream = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .strict_ordering(true)
+        .order_by("data->>'id' COLLATE \"C\" DESC")
+        .limit(2)
+        .execute()
+        .await
+        .expect("execute");
+    assert_eq!(ids(stream).await, vec![9, 8]);
+
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let result = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .strict_ordering(true)
+        .order_by("data->'id' COLLATE \"C\"")
+        .execute()
+        .await;
+    assert!(matches!(result, Err(Error::Config(_))));
+
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let result = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .strict_ordering(true)
+        .order_by("(SELECT 1)")
+        .execute()
+        .await;
+    assert!(matches!(result, Err(Error::Config(_))));
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_typed_projection() {
+    use fraiseql_wire::{FraiseClient, Projection};
+    use futures::StreamExt;
+    use serde_json::json;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+    const ENTITY: &str = "(SELECT jsonb_build_object('id', i, 'name', 'n' || i, \
+                              'owner', jsonb_build_object('name', 'o''' || i, 'age', i * 10), \
+                              'tags', jsonb_build_array(i, true)) AS data \
+                          FROM generate_series(1, 3) i) t";
+
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let rows: Vec<serde_json::Value> = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .projection(
+            Projection::new()
+                .field("id")
+                .rename("it's", "name")
+                .nested("owner", |p| p.field("name").field("missing"))
+                .field("tags"),
+        )
+        .order_by("data->'id'")
+        .limit(1)
+        .execute()
+        .await
+        .expect("execute")
+        .map(|row| row.expect("row"))
+        .collect()
+        .await;
+    assert_eq!(
+        rows,
+        vec![json!({
+            "id": 1,
+            "it's": "n1",
+            "owner": {"name": "o'1", "missing": null},
+            "tags": [1, true],
+        })]
+    );
+
+    // More keys than one jsonb_build_object call accepts
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let wide = (0..120).fold(Projection::new(), |p, i| p.rename(format!("k{}", i), "id"));
+    let rows: Vec<serde_json::Value> = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .projection(wide)
+        .order_by("data->'id'")
+        .limit(1)
+        .execute()
+        .await
+        .expect("execute")
+        .map(|row| row.expect("row"))
+        .collect()
+        .await;
+    let object = rows[0].as_object().expect("object");
+    assert_eq!(object.len(), 120);
+    assert!(object.values().all(|v| v == &json!(1)));
+
+    // Invalid paths are rejected before anything is sent
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let result = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .projection(Projection::new().field("id'); DROP TABLE t; --"))
+        .execute()
+        .await;
+    assert!(matches!(result, Err(fraiseql_wire::Error::Config(_))));
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running with the pgvector extension available
+async fn test_nearest_neighbours() {
+    use fraiseql_wire::protocol::BackendMessage;
+    use fraiseql_wire::{DistanceMetric, FraiseClient, Projection};
+    use futures::StreamExt;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+    const ENTITY: &str = "(SELECT jsonb_build_object('id', i) AS data, \
+                              ARRAY[i::real, 0]::vector AS embedding \
+                          FROM generate_series(1, 5) i) t";
+
+    // Invalid configurations are rejected before anything is sent
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let result = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .order_by("data->'id'")
+        .project_distance("distance")
+        .execute()
+        .await;
+    assert!(matches!(result, Err(fraiseql_wire::Error::Config(_))));
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let result = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .nearest(2, "embedding", DistanceMetric::L2, vec![f32::NAN, 0.0])
+        .execute()
+        .await;
+    assert!(matches!(result, Err(fraiseql_wire::Error::Config(_))));
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut setup = Connection::new(transport);
+    setup
+        .startup(&ConnectionConfig::new("postgres", "postgres"), None, None)
+        .await
+        .expect("startup");
+    let messages = setup
+        .simple_query("CREATE EXTENSION IF NOT EXISTS vector")
+        .await
+        .expect("setup");
+    assert!(
+        !messages
+            .iter()
+            .any(|m| matches!(m, BackendMessage::ErrorResponse(_))),
+        "pgvector is not available: {:?}",
+        messages
+    );
+
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let rows: Vec<serde_json::Value> = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .projection(Projection::new().field("id"))
+        .nearest(2, "embedding", DistanceMetric::L2, vec![2.25, 0.0])
+        .project_distance("distance")
+        .execute()
+        .await
+        .expect("execute")
+        .map(|row| row.expect("row"))
+        .collect()
+        .await;
+    let ids: Vec<i64> = rows.iter().map(|r| r["id"].as_i64().unwrap()).collect();
+    let distances: Vec<f64> = rows
+        .iter()
+        .map(|r| r["distance"].as_f64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![2, 3]);
+    assert!((distances[0] - 0.25).abs() < 1e-6, "{:?}", distances);
+    assert!((distances[1] - 0.75).abs() < 1e-6, "{:?}", distances);
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_text_rank_and_headline() {
+    use fraiseql_wire::{Field, FraiseClient, Headline, OrderByClause, TextQuery, TextRank};
+    use futures::StreamExt;
+    use serde_json::json;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+    const ENTITY: &str = "(VALUES \
+        ('{\"id\": 1, \"body\": \"Postgres streams JSON rows\"}'::jsonb), \
+        ('{\"id\": 2, \"body\": \"Rust streams rows; rust streams JSON from rust code\"}'), \
+        ('{\"id\": 3, \"body\": \"Streams of rust\"}')) AS t(data)";
+
+    let query = TextQuery::websearch("rust streams").language("english");
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let rows: Vec<serde_json::Value> = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .where_sql("to_tsvector('english', data->>'body') @@ websearch_to_tsquery('english', 'rust streams')")
+        .order_by_clause(OrderByClause::text_rank(
+            "body",
+            TextRank::new(query.clone()).normalization(1),
+        ))
+        .order_by_clause(OrderByClause::jsonb_field(
+            "id",
+            fraiseql_wire::SortOrder::Asc,
+        ))
+        .headline(
+            "snippet",
+            Headline::new(Field::JsonbField("body".into()), query)
+                .options("StartSel=[, StopSel=], MaxWords=3, MinWords=1"),
+        )
+        .execute()
+        .await
+        .expect("execute")
+        .map(|row| row.expect("row"))
+        .collect()
+        .await;
+
+    let ids: Vec<_> = rows.iter().map(|r| r["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(2), json!(3)]);
+    let snippet = rows[1]["snippet"].as_str().expect("snippet");
+    assert_eq!(snippet, "[Streams] of [rust]");
+
+    // Query text and options are bound, quotes and backslashes included
+    let query = TextQuery::plain(r#"rust's \' "streams\"#);
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let rows: Vec<serde_json::Value> = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .where_sql("data->>'id' <> '1'")
+        .order_by_clause(OrderByClause::text_rank(
+            "body",
+            TextRank::new(query.clone()),
+        ))
+        .headline(
+            "snippet",
+            Headline::new(Field::JsonbField("body".into()), query)
+                .options(r#"StartSel="'\", StopSel=\', MaxWords=3, MinWords=1"#),
+        )
+        .execute()
+        .await
+        .expect("execute")
+        .map(|row| row.expect("row"))
+        .collect()
+        .await;
+    let ids: Vec<_> = rows.iter().map(|r| r["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(2), json!(3)]);
+    assert_eq!(rows[1]["snippet"], json!(r"'\Streams\' of '\rust\'"));
+
+    // Invalid configuration names are rejected before anything is sent
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let result = client
+        .query_from_sql::<serde_json::Value>(ENTITY)
+        .order_by_clause(OrderByClause::text_rank(
+            "body",
+            TextRank::new(TextQuery::plain("x").language("english'")),
+        ))
+        .execute()
+        .await;
+    assert!(matches!(result, Err(fraiseql_wire::Error::Config(_))));
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_entity_quoting_schema_and_allowlist() {
+    use fraiseql_wire::{Error, FraiseClient};
+    use futures::StreamExt;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+
+    async fn ids(
+        client: FraiseClient,
+        entity: &str,
+    ) -> fraiseql_wire::Result<Vec<serde_json::Value>> {
+        let stream = client
+            .query::<serde_json::Value>(entity)
+            .order_by("data->>'id'")
+            .execute()
+            .await?;
+        Ok(stream
+            .map(|row| row.expect("row")["id"].clone())
+            .collect()
+            .await)
+    }
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut conn = Connection::new(transport);
+    conn.startup(&ConnectionConfig::new("postgres", "postgres"), None, None)
+        .await
+        .expect("startup");
+    conn.simple_query(
+        "DROP SCHEMA IF EXISTS entity_test CASCADE; \
+         CREATE SCHEMA entity_test; \
+         CREATE VIEW entity_test.\"MixedCase\" AS SELECT '{\"id\": \"mixed\"}'::jsonb AS data; \
+         CREATE VIEW entity_test.lower_case AS SELECT '{\"id\": \"lower\"}'::jsonb AS data",
+    )
+    .await
+    .expect("create views");
+
+    // Identifiers are quoted, so case is preserved
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let rows = ids(client, "entity_test.MixedCase")
+        .await
+        .expect("mixed case");
+    assert_eq!(rows, vec![serde_json::json!("mixed")]);
+
+    // Unqualified names resolve in the default schema
+    let client = FraiseClient::connect(URL)
+        .await
+        .expect("connect")
+        .default_schema("entity_test");
+    let rows = ids(client, "lower_case").await.expect("default schema");
+    assert_eq!(rows, vec![serde_json::json!("lower")]);
+
+    // Injection attempts never reach the server
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let result = ids(client, "entity_test.lower_case; DROP SCHEMA entity_test").await;
+    assert!(matches!(result, Err(Error::Config(_))));
+
+    // Only allowlisted entities can be queried
+    let allowlisted = || async {
+        FraiseClient::connect(URL)
+            .await
+            .expect("connect")
+            .default_schema("entity_test")
+            .allow_entities(["MixedCase"])
+    };
+    let rows = ids(allowlisted().await, "MixedCase")
+        .await
+        .expect("allowed");
+    assert_eq!(rows, vec![serde_json::json!("mixed")]);
+    let result = ids(allowlisted().await, "lower_case").await;
+    assert!(matches!(result, Err(Error::Config(_))));
+    let result = allowlisted()
+        .await
+        .query_from_sql::<serde_json::Value>("entity_test.lower_case")
+        .execute()
+        .await;
+    assert!(matches!(result, Err(Error::Config(_))));
+
+    conn.simple_query("DROP SCHEMA entity_test CASCADE")
+        .await
+        .expect("drop schema");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_where_sql_params() {
+    use fraiseql_wire::client::PartitionStrategy;
+    use fraiseql_wire::connection::ExecutionMode;
+    use fraiseql_wire::{Error, FraiseClient, Value};
+    use futures::StreamExt;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut setup = Connection::new(transport);
+    setup
+        .startup(&ConnectionConfig::new("postgres", "postgres"), None, None)
+        .await
+        .expect("startup");
+    setup
+        .simple_query(
+            "DROP TABLE IF EXISTS params_test; \
+             CREATE TABLE params_test (data jsonb); \
+             INSERT INTO params_test \
+             SELECT jsonb_build_object('id', i, 'region', CASE WHEN i % 2 = 0 THEN 'eu' ELSE 'us' END, \
+                                       'note', 'it''s $1') \
+             FROM generate_series(1, 100) i",
+        )
+        .await
+        .expect("setup");
+
+    async fn ids(
+        mode: ExecutionMode,
+        parallel: bool,
+        region: &str,
+    ) -> fraiseql_wire::Result<Vec<i64>> {
+        let client = FraiseClient::connect(URL).await.expect("connect");
+        let mut query = client
+            .query::<serde_json::Value>("params_test")
+            .where_sql_params("data->>'region' = $1", &[&region])
+            .where_sql("data->>'note' = 'it''s $1'")
+            .where_sql_params(
+                "(data->>'id')::int > $1 AND (data->>'id')::int <= $2",
+                &[&80, &Value::Int(90)],
+            )
+            .execution_mode(mode)
+            .chunk_size(3);
+        if parallel {
+            query = query.parallel(2, PartitionStrategy::CtidBlocks);
+        }
+        let stream = query.execute().await?;
+        let mut ids: Vec<i64> = stream
+            .map(|row| row.expect("row")["id"].as_i64().unwrap())
+            .collect()
+            .await;
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    let expected = vec![82, 84, 86, 88, 90];
+    for mode in [ExecutionMode::Streaming, ExecutionMode::Cursor] {
+        assert_eq!(ids(mode, false, "eu").await.expect("query"), expected);
+    }
+    assert_eq!(
+        ids(ExecutionMode::Streaming, true, "eu")
+            .await
+            .expect("parallel"),
+        expected
+    );
+
+    // Values are bound, never interpolated
+    let injected = ids(ExecutionMode::Streaming, false, "eu' OR '1'='1")
+        .await
+        .expect("query");
+    assert!(injected.is_empty());
+
+    // Server errors surface after the extended-protocol exchange
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let result = client
+        .query::<serde_json::Value>("params_test")
+        .where_sql_params("(data->>'id')::int = $1", &[&"not a number"])
+        .execute()
+        .await;
+    assert!(matches!(result, Err(Error::Sql(_))));
+
+    // Unbound placeholders are rejected before anything is sent
+    let client = FraiseClient::connect(URL).await.expect("connect");
+    let result = client
+        .query::<serde_json::Value>("params_test")
+        .where_sql_params("data->>'region' = $1", &[&"eu"])
+        .where_sql("data->>'region' = $2")
+        .execute()
+        .await;
+    assert!(matches!(result, Err(Error::Config(_))));
+
+    setup
+        .simple_query("DROP TABLE params_test")
+        .await
+        .expect("drop");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_count_exists_and_aggregate() {
+    use fraiseql_wire::{Aggregate, Error, Field, FraiseClient};
+    use serde_json::json;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut setup = Connection::new(transport);
+    setup
+        .startup(&ConnectionConfig::new("postgres", "postgres"), None, None)
+        .await
+        .expect("startup");
+    setup
+        .simple_query(
+            "DROP TABLE IF EXISTS aggregate_test; \
+             CREATE TABLE aggregate_test (data jsonb, created_at date); \
+             INSERT INTO aggregate_test \
+             SELECT jsonb_build_object('n', i, 'region', CASE WHEN i % 2 = 0 THEN 'eu' ELSE 'us' END, \
+                                       'score', CASE WHEN i = 1 THEN NULL ELSE i * 1.5 END), \
+                    DATE '2024-01-01' + i \
+             FROM generate_series(1, 10) i",
+        )
+        .await
+        .expect("setup");
+
+    let client = || async { FraiseClient::connect(URL).await.expect("connect") };
+    let eu = || async {
+        client()
+            .await
+            .query::<serde_json::Value>("aggregate_test")
+            .where_sql_params("data->>'region' = $1", &[&"eu"])
+    };
+
+    assert_eq!(eu().await.count().await.expect("count"), 5);
+    assert_eq!(
+        eu().await
+            .order_by("(data->>'n')::int")
+            .limit(2)
+            .offset(4)
+            .count()
+            .await
+            .expect("count page"),
+        1
+    );
+    assert!(eu().await.exists().await.expect("exists"));
+    assert!(!eu()
+        .await
+        .where_sql("(data->>'n')::int > 10")
+        .exists()
+        .await
+        .expect("exists"));
+
+    let score = || Field::JsonbField("score".into());
+    assert_eq!(
+        eu().await
+            .aggregate(score(), Aggregate::Max)
+            .await
+            .expect("max"),
+        json!(15.0)
+    );
+    assert_eq!(
+        eu().await
+            .aggregate(score(), Aggregate::Sum)
+            .await
+            .expect("sum"),
+        json!(45.0)
+    );
+    let all = || async { client().await.query::<serde_json::Value>("aggregate_test") };
+    // JSON null is skipped by min() and count()
+    assert_eq!(
+        all()
+            .await
+            .aggregate(score(), Aggregate::Min)
+            .await
+            .expect("min"),
+        json!(3.0)
+    );
+    assert_eq!(
+        all()
+            .await
+            .aggregate(score(), Aggregate::Count)
+            .await
+            .expect("count"),
+        json!(9)
+    );
+    assert_eq!(
+        all()
+            .await
+            .aggregate(Field::DirectColumn("created_at".into()), Aggregate::Max)
+            .await
+            .expect("max date"),
+        json!("2024-01-11")
+    );
+    assert_eq!(
+        all()
+            .await
+            .where_sql("false")
+            .aggregate(score(), Aggregate::Avg)
+            .await
+            .expect("empty avg"),
+        serde_json::Value::Null
+    );
+
+    let result = eu().await.where_rust(|_| true).count().await;
+    assert!(matches!(result, Err(Error::Config(_))));
+    let result = eu()
+        .await
+        .aggregate(Field::JsonbField("bad-name".into()), Aggregate::Max)
+        .await;
+    assert!(matches!(result, Err(Error::Config(_))));
+
+    setup
+        .simple_query("DROP TABLE aggregate_test")
+        .await
+        .expect("drop");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_explain_and_cost_guard() {
+    use fraiseql_wire::{Error, ExplainOptions, FraiseClient, GuardViolation};
+    use futures::StreamExt;
+
+    const URL: &str = "postgres://postgres@localhost:5432/postgres";
+
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut setup = Connection::new(transport);
+    setup
+        .startup(&ConnectionConfig::new("postgres", "postgres"), None, None)
+        .await
+        .expect("startup");
+    setup
+        .simple_query(
+            "DROP TABLE IF EXISTS explain_test; \
+             CREATE TABLE explain_test (id int PRIMARY KEY, data jsonb); \
+             INSERT INTO explain_test \
+             SELECT i, jsonb_build_object('n', i) FROM generate_series(1, 5000) i; \
+             ANALYZE explain_test",
+        )
+        .await
+        .expect("setup");
+
+    let query = || async {
+        FraiseClient::connect(URL)
+            .await
+            .expect("connect")
+            .query::<serde_json::Value>("explain_test")
+    };
+
+    let plan = query()
+        .await
+        .where_sql_params("(data->>'n')::int > $1", &[&4990])
+        .explain(ExplainOptions::new())
+        .await
+        .expect("explain");
+    assert_eq!(
+        plan.seq_scans()[0].relation.as_deref(),
+        Some("explain_test")
+    );
+    assert!(plan.total_cost() > 0.0);
+    assert!(plan.execution_time_ms.is_none());
+
+    let plan = query()
+        .await
+        .where_sql("id <= 10")
+        .explain(ExplainOptions::new().analyze().verbose())
+        .await
+        .expect("explain analyze");
+    assert!(plan.seq_scans().is_empty());
+    assert_eq!(plan.root.actual_rows, Some(10.0));
+    assert!(plan.execution_time_ms.is_some());
+
+    // An indexed lookup passes every guard and streams normally
+    let guarded = || async {
+        query()
+            .await
+            .max_estimated_cost(1000.0)
+            .max_estimated_rows(100)
+            .forbid_seq_scan_on(["public.explain_test"])
+    };
+    let rows: Vec<_> = guarded()
+        .await
+        .where_sql("id <= 10")
+        .execute()
+        .await
+        .expect("guarded query")
+        .collect()
+        .await;
+    assert_eq!(rows.len(), 10);
+
+    // A filter on unindexed data is refused before execution
+    let result = guarded()
+        .await
+        .where_sql_params("data->>'n' = $1", &[&"7"])
+        .execute()
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::QueryRejected(GuardViolation::SeqScan { ref relation }))
+            if relation == "public.explain_test"
+    ));
+
+    let result = query().await.max_estimated_rows(100).execute().await;
+    assert!(matches!(
+        result,
+        Err(Error::QueryRejected(GuardViolation::EstimatedRows {
+            limit: 100,
+            ..
+        }))
+    ));
+
+    let result = query().await.max_estimated_cost(1.0).execute().await;
+    assert!(matches!(
+        result,
+        Err(Error::QueryRejected(GuardViolation::EstimatedCost { .. }))
+    ));
+
+    setup
+        .simple_query("DROP TABLE explain_test")
+        .await
+        .expect("drop");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_statement_cache() {
+    use fraiseql_wire::connection::ExecutionMode;
+    use fraiseql_wire::protocol::BackendMessage;
+    use fraiseql_wire::{FraiseClient, Value};
+    use futures::StreamExt;
+
+    async fn connect(capacity: usize) -> Connection {
+        let transport = Transport::connect_tcp("localhost", 5432)
+            .await
+            .expect("connect");
+        let mut conn = Connection::new(transport);
+        let config = ConnectionConfig::builder("postgres", "postgres")
+            .statement_cache_capacity(capacity)
+            .build();
+        conn.startup(&config, None, None).await.expect("startup");
+        conn
+    }
+
+    fn rows(messages: &[BackendMessage]) -> Vec<Vec<String>> {
+        assert!(
+            !messages
+                .iter()
+                .any(|m| matches!(m, BackendMessage::ErrorResponse(_))),
+            "query failed: {:?}",
+            messages
+        );
+        messages
+            .iter()
+            .filter_map(|m| match m {
+                BackendMessage::DataRow(fields) => Some(
+                    fields
+                        .iter()
+                        .map(|f| {
+                            f.as_ref()
+                                .map_or("NULL".into(), |b| String::from_utf8_lossy(b).into())
+                        })
+                        .collect(),
+                ),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Statements prepared by this connection, read without the cache
+    async fn prepared(conn: &mut Connection) -> Vec<String> {
+        let messages = conn
+            .simple_query(
+                "SELECT statement FROM pg_prepared_statements \
+                 WHERE name LIKE 'fraiseql_s%' ORDER BY prepare_time",
+            )
+            .await
+            .expect("pg_prepared_statements");
+        rows(&messages).into_iter().map(|r| r[0].clone()).collect()
+    }
+
+    let mut conn = connect(2).await;
+    conn.simple_query(
+        "DROP TABLE IF EXISTS statement_cache_test; \
+         CREATE TABLE statement_cache_test (a int); \
+         INSERT INTO statement_cache_test SELECT generate_series(1, 10)",
+    )
+    .await
+    .expect("setup");
+
+    // Queries without bound values are not cached
+    conn.query_with_params("SELECT a FROM statement_cache_test WHERE a > 7", &[])
+        .await
+        .expect("unbound");
+    assert!(prepared(&mut conn).await.is_empty());
+
+    // One statement per shape, whatever the values and layout
+    let shape_a = "SELECT a FROM statement_cache_test WHERE a > $1 ORDER BY a";
+    for (min, layout) in [
+        (7, shape_a),
+        (
+            8,
+            "SELECT a FROM statement_cache_test\n  WHERE a > $1 ORDER BY a",
+        ),
+    ] {
+        let messages = conn
+            .query_with_params(layout, &[Value::Int(min)])
+            .await
+            .expect("query");
+        assert_eq!(rows(&messages).len(), 10 - min as usize);
+    }
+    assert_eq!(prepared(&mut conn).await, [shape_a]);
+
+    // The least recently used statement is closed beyond the capacity
+    let shape_b = "SELECT count(*) FROM statement_cache_test WHERE a < $1";
+    let shape_c = "SELECT * FROM statement_cache_test WHERE a >= $1 ORDER BY a";
+    conn.query_with_params(shape_b, &[Value::Int(3)])
+        .await
+        .expect("shape b");
+    let messages = conn
+        .query_with_params(shape_c, &[Value::Int(1)])
+        .await
+        .expect("shape c");
+    assert_eq!(rows(&messages).len(), 10);
+    assert_eq!(prepared(&mut conn).await, [shape_b, shape_c]);
+
+    // A result type change re-prepares the statement and retries
+    conn.simple_query("ALTER TABLE statement_cache_test ADD COLUMN b int DEFAULT 0")
+        .await
+        .expect("alter");
+    let messages = conn
+        .query_with_params(shape_c, &[Value::Int(1)])
+        .await
+        .expect("after alter");
+    assert_eq!(rows(&messages)[0], ["1", "0"]);
+    assert_eq!(prepared(&mut conn).await, [shape_b, shape_c]);
+
+    // Losing the statements (as behind a transaction-mode pooler) falls back
+    // to unnamed statements
+    conn.simple_query("DEALLOCATE ALL")
+        .await
+        .expect("deallocate");
+    let messages = conn
+        .query_with_params(shape_b, &[Value::Int(3)])
+        .await
+        .expect("after deallocate");
+    assert_eq!(rows(&messages), [["2"]]);
+    conn.query_with_params(shape_a, &[Value::Int(9)])
+        .await
+        .expect("unnamed");
+    assert!(prepared(&mut conn).await.is_empty());
+
+    // Both execution modes bind through the cache
+    let config = ConnectionConfig::builder("postgres", "postgres")
+        .statement_cache_capacity(8)
+        .build();
+    for mode in [ExecutionMode::Streaming, ExecutionMode::Cursor] {
+        let client = FraiseClient::connect_with_config(
+            "postgres://postgres@localhost:5432/postgres",
+            config.clone(),
+        )
+        .await
+        .expect("client");
+        let values: Vec<_> = client
+            .query_from_sql::<serde_json::Value>(
+                "(SELECT to_jsonb(t) AS data FROM statement_cache_test t) AS rows",
+            )
+            .where_sql_params("(data->>'a')::int > $1", &[&2])
+            .order_by("(data->>'a')::int")
+            .execution_mode(mode)
+            .chunk_size(3)
+            .execute()
+            .await
+            .expect("execute")
+            .collect()
+            .await;
+        assert_eq!(values.len(), 8, "{}", mode);
+        assert_eq!(values[0].as_ref().unwrap()["a"], 3);
+    }
+
+    conn.simple_query("DROP TABLE statement_cache_test")
+        .await
+        .expect("drop");
+}
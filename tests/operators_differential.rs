@@ -0,0 +1,462 @@
+//! Differential tests: `WhereOperator::matches` vs the SQL from `sql_gen`
+//!
+//! Each operator is evaluated in memory on the JSON rows of a table and by
+//! Postgres on the same rows. Every operator runs in SQL on `data` as generated
+//! by `sql_gen`. Operators on top-level fields also run against typed columns
+//! holding the text extraction of each JSON field (`s text COLLATE "C"`,
+//! `n numeric`, `b boolean`, `tags text[]`), so the server applies native
+//! Postgres semantics to the same values. The comparison runs in a database
+//! whose default collation is ICU `en`, not `C`, so text ordering that follows
+//! the database collation shows up as a disagreement. The JSONB operators are
+//! also checked to use GIN indexes on `data`.
+//!
+//! These tests require a running Postgres instance.
+
+use fraiseql_wire::connection::{Connection, ConnectionConfig, Transport};
+use fraiseql_wire::operators::{generate_where_operator_sql, Field, Value, WhereOperator};
+use fraiseql_wire::protocol::BackendMessage;
+use serde_json::json;
+use std::collections::HashMap;
+
+/// An operator, and the equivalent operator on typed columns
+struct Case {
+    rust: WhereOperator,
+    typed: Option<WhereOperator>,
+}
+
+/// Operator on top-level fields: JSONB in memory, typed columns in SQL
+fn typed(build: impl Fn(&dyn Fn(&str) -> Field) -> WhereOperator) -> Case {
+    Case {
+        rust: build(&|name| Field::JsonbField(name.to_string())),
+        typed: Some(build(&|name| Field::DirectColumn(name.to_string()))),
+    }
+}
+
+/// Operator on JSONB fields only
+fn same(op: WhereOperator) -> Case {
+    Case {
+        rust: op,
+        typed: None,
+    }
+}
+
+fn s(v: &str) -> Value {
+    Value::String(v.to_string())
+}
+
+fn jsonb(name: &str) -> Field {
+    Field::JsonbField(name.to_string())
+}
+
+fn path(segments: &[&str]) -> Field {
+    Field::JsonbPath(segments.iter().map(|s| s.to_string()).collect())
+}
+
+/// Render an operator with its parameters inlined as literals
+fn render(op: &WhereOperator) -> String {
+    let mut index = 0;
+    let mut params = HashMap::new();
+    let mut sql = generate_where_operator_sql(op, &mut index, &mut params).expect("sql_gen");
+    // Highest first, so that $1 does not clobber $10
+    for n in (1..=index).rev() {
+        sql = sql.replace(&format!("${}", n), &params[&n].to_sql_literal());
+    }
+    sql
+}
+
+fn rows() -> Vec<serde_json::Value> {
+    vec![
+        json!({"s": "apple", "n": 1, "b": true, "tags": ["a", "b"], "meta": {"k": "v", "score": 5},
+               "id": 9_007_199_254_740_993_i64, "price": "12.50", "u": "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+               "at": "2024-01-02T10:00:00+02:00", "day": "2024-01-02"}),
+        json!({"s": "Apple pie", "n": 2.5, "b": false, "tags": ["b"], "meta": {"k": "w", "score": "7"},
+               "id": 9_007_199_254_740_992_i64, "price": 12.5, "u": "{A0EEBC99-9C0B4EF8-BB6D6BB9-BD380A12}",
+               "at": "2024-01-02 07:59:59.9999995Z", "day": "2024-01-01T23:00:00"}),
+        json!({"s": "50% off_now", "n": -3, "tags": [], "meta": {}, "id": "-7", "price": "-0.001",
+               "at": "2024-01-02", "day": "2000-02-29"}),
+        json!({"s": "back\\slash", "n": 100, "b": true, "tags": ["a", null], "id": null, "price": null,
+               "u": null, "at": null, "day": null}),
+        json!({"s": null, "n": null, "b": null, "tags": null, "meta": {"k": null}}),
+        json!({}),
+        json!({"s": "ünïcode", "n": "42", "b": "yes", "tags": ["c", "a", "b"], "id": 42, "price": 1e3}),
+        json!({"s": "", "n": 0, "b": false, "tags": ["A"], "meta": {"k": "it's"}}),
+        json!({"s": "10", "n": 10, "tags": ["10", 10, true], "meta": {"score": "x"}}),
+    ]
+}
+
+fn cases() -> Vec<Case> {
+    use WhereOperator::*;
+    vec![
+        // Comparisons
+        typed(|f| Eq(f("s"), s("apple"))),
+        typed(|f| Eq(f("s"), Value::Null)),
+        typed(|f| Neq(f("s"), s("apple"))),
+        typed(|f| Neq(f("n"), Value::Null)),
+        typed(|f| Gt(f("s"), s("apple"))),
+        typed(|f| Lte(f("s"), s("Apple pie"))),
+        typed(|f| Gt(f("n"), Value::Number(1.0))),
+        typed(|f| Gte(f("n"), Value::Number(2.5))),
+        typed(|f| Lt(f("n"), Value::Number(0.0))),
+        typed(|f| Lte(f("n"), Value::Number(42.0))),
+        typed(|f| Eq(f("n"), Value::Number(42.0))),
+        typed(|f| Gt(f("n"), Value::Null)),
+        typed(|f| Eq(f("b"), Value::Bool(true))),
+        typed(|f| Neq(f("b"), Value::Bool(true))),
+        typed(|f| Gt(f("b"), Value::Bool(false))),
+        // IN / NOT IN
+        typed(|f| In(f("s"), vec![s("apple"), s("10"), Value::Null])),
+        typed(|f| In(f("n"), vec![Value::Number(1.0), Value::Number(100.0)])),
+        typed(|f| Nin(f("s"), vec![s("apple")])),
+        typed(|f| Nin(f("n"), vec![Value::Number(1.0), Value::Null])),
+        // LIKE family, with escapes
+        typed(|f| Contains(f("s"), "pp".into())),
+        typed(|f| Contains(f("s"), "%".into())),
+        typed(|f| Icontains(f("s"), "APP".into())),
+        typed(|f| Startswith(f("s"), "50\\%".into())),
+        typed(|f| Endswith(f("s"), "now".into())),
+        typed(|f| Like(f("s"), "a_p%".into())),
+        typed(|f| Like(f("s"), "%\\\\%".into())),
+        typed(|f| Like(f("s"), "".into())),
+        typed(|f| Ilike(f("s"), "APPLE%".into())),
+        // Arrays
+        typed(|f| ArrayContains(f("tags"), s("a"))),
+        typed(|f| ArrayContains(f("tags"), Value::Array(vec![s("a"), s("b")]))),
+        typed(|f| ArrayContainedBy(f("tags"), Value::Array(vec![s("a"), s("b")]))),
+        typed(|f| ArrayOverlaps(f("tags"), vec![s("b"), s("c")])),
+        typed(|f| ArrayOverlaps(f("tags"), vec![Value::Null])),
+        typed(|f| LenEq(f("tags"), 2)),
+        typed(|f| LenGt(f("tags"), 1)),
+        typed(|f| LenLt(f("tags"), 1)),
+        typed(|f| LenLte(f("tags"), 3)),
+        // Nulls
+        typed(|f| IsNull(f("s"), true)),
+        typed(|f| IsNull(f("tags"), false)),
+        // Logic, including NULL propagation through NOT
+        typed(|f| {
+            And(vec![
+                Gt(f("n"), Value::Number(0.0)),
+                Contains(f("s"), "a".into()),
+            ])
+        }),
+        typed(|f| Or(vec![Eq(f("b"), Value::Bool(true)), IsNull(f("n"), true)])),
+        typed(|f| Not(Box::new(Gt(f("n"), Value::Number(1.0))))),
+        typed(|f| {
+            Not(Box::new(Or(vec![
+                Eq(f("s"), s("apple")),
+                Eq(f("b"), Value::Bool(false)),
+            ])))
+        }),
+        typed(|_| And(vec![])),
+        typed(|_| Or(vec![])),
+        // Nested paths (text extraction in SQL as well)
+        same(Eq(path(&["meta", "k"]), s("v"))),
+        same(Eq(path(&["meta", "k"]), s("it's"))),
+        same(Like(path(&["meta", "k"]), "_".into())),
+        same(IsNull(path(&["meta", "k"]), true)),
+        same(Neq(path(&["meta", "k"]), Value::Null)),
+        // Fails on the non-numeric score, both in SQL and in memory
+        same(Gt(path(&["meta", "score"]), Value::Number(5.0))),
+        // Typed values
+        same(Eq(jsonb("id"), Value::Int(9_007_199_254_740_993))),
+        same(Gt(jsonb("id"), Value::Int(9_007_199_254_740_992))),
+        same(In(
+            jsonb("id"),
+            vec![Value::Int(-7), Value::Int(42), Value::Null],
+        )),
+        same(Eq(jsonb("price"), Value::Decimal("12.5".into()))),
+        same(Lt(jsonb("price"), Value::Decimal("1e-3".into()))),
+        same(Eq(
+            jsonb("u"),
+            Value::Uuid("A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11".into()),
+        )),
+        same(Gt(
+            jsonb("u"),
+            Value::Uuid("a0eebc999c0b4ef8bb6d6bb9bd380a11".into()),
+        )),
+        same(Gte(
+            jsonb("at"),
+            Value::Timestamp("2024-01-02T08:00:00Z".into()),
+        )),
+        same(Lt(jsonb("at"), Value::Date("2024-01-02".into()))),
+        same(Eq(jsonb("day"), Value::Date("2024-01-01".into()))),
+        same(Gt(
+            jsonb("day"),
+            Value::Timestamp("2000-02-29T12:00:00-01:00".into()),
+        )),
+        same(Nin(jsonb("id"), vec![Value::Decimal("42".into()), s("-7")])),
+        same(In(jsonb("id"), vec![Value::Int(42), s("-7"), Value::Null])),
+        // Fails on the decimal price, both in SQL and in memory
+        same(Eq(jsonb("price"), Value::Int(12))),
+        // jsonb comparisons (`->`)
+        same(Eq(
+            jsonb("meta"),
+            Value::Json(json!({"k": "v", "score": 5.0})),
+        )),
+        same(Gt(jsonb("meta"), Value::Json(json!({"k": "w"})))),
+        same(Eq(path(&["meta", "k"]), Value::Json(json!(null)))),
+        same(Lt(jsonb("tags"), Value::Json(json!("z")))),
+        same(Gte(jsonb("tags"), Value::Json(json!(["a", "b"])))),
+        same(Gt(jsonb("n"), Value::Json(json!(2)))),
+        same(Lte(jsonb("s"), Value::Json(json!(0)))),
+        same(In(
+            jsonb("b"),
+            vec![Value::Json(json!(true)), Value::Json(json!("yes"))],
+        )),
+        // JSONB containment and key existence
+        same(JsonContains(jsonb("meta"), json!({"k": "v"}))),
+        same(JsonContains(path(&["meta", "score"]), json!(5.0))),
+        same(JsonContains(jsonb("meta"), json!({}))),
+        same(JsonContains(jsonb("tags"), json!(["b", "a"]))),
+        same(JsonContains(jsonb("tags"), json!("a"))),
+        same(JsonContains(jsonb("tags"), json!([10, "10"]))),
+        same(JsonContains(jsonb("s"), json!(null))),
+        same(JsonContains(
+            Field::JsonbPath(vec![]),
+            json!({"b": true, "tags": ["a"]}),
+        )),
+        same(HasKey(Field::JsonbPath(vec![]), "meta".into())),
+        same(HasKey(jsonb("meta"), "k".into())),
+        same(HasKey(jsonb("tags"), "a".into())),
+        same(HasKey(jsonb("tags"), "10".into())),
+        same(HasKey(jsonb("s"), "apple".into())),
+        same(HasKey(jsonb("n"), "1".into())),
+        same(Not(Box::new(HasKey(jsonb("meta"), "score".into())))),
+        same(HasAnyKey(jsonb("meta"), vec!["score".into(), "x".into()])),
+        same(HasAllKeys(jsonb("meta"), vec!["k".into(), "score".into()])),
+        // Filter documents keep every digit of integers
+        same(
+            WhereOperator::from_filter_json(
+                &json!({"id": {"in": [9_007_199_254_740_993_i64, 42]}}),
+            )
+            .expect("filter"),
+        ),
+    ]
+}
+
+async fn connect(database: &str) -> Connection {
+    let transport = Transport::connect_tcp("localhost", 5432)
+        .await
+        .expect("connect");
+    let mut conn = Connection::new(transport);
+    conn.startup(&ConnectionConfig::new(database, "postgres"), None, None)
+        .await
+        .expect("startup");
+    conn
+}
+
+/// `COALESCE(predicate, false)` for each row, in id order, or the error
+async fn evaluate(
+    conn: &mut Connection,
+    predicate: &str,
+    row: Option<usize>,
+) -> Result<Vec<Option<bool>>, BackendMessage> {
+    let only = row.map_or(String::new(), |id| format!("WHERE id = {}", id));
+    let messages = conn
+        .simple_query(&format!(
+            "SELECT COALESCE(({}), false) FROM diff_test {} ORDER BY id",
+            predicate, only
+        ))
+        .await
+        .expect("query");
+    let mut results = Vec::new();
+    for message in messages {
+        match message {
+            BackendMessage::ErrorResponse(_) => return Err(message),
+            BackendMessage::DataRow(cols) => {
+                results.push(Some(cols[0].as_deref() == Some(b"t".as_slice())))
+            }
+            _ => {}
+        }
+    }
+    Ok(results)
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running with ICU support
+async fn test_matches_agrees_with_sql() {
+    let mut admin = connect("postgres").await;
+    admin
+        .simple_query("DROP DATABASE IF EXISTS fraiseql_diff_test")
+        .await
+        .expect("drop database");
+    let created = admin
+        .simple_query(
+            "CREATE DATABASE fraiseql_diff_test TEMPLATE template0 ENCODING 'UTF8' \
+             LOCALE_PROVIDER icu ICU_LOCALE 'en' LOCALE 'C'",
+        )
+        .await
+        .expect("create database");
+    assert!(
+        !created
+            .iter()
+            .any(|m| matches!(m, BackendMessage::ErrorResponse(_))),
+        "create database: {:?}",
+        created
+    );
+    let mut conn = connect("fraiseql_diff_test").await;
+
+    let rows = rows();
+    let values: Vec<String> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| format!("({}, '{}'::jsonb)", i, row.to_string().replace('\'', "''")))
+        .collect();
+    conn.simple_query(&format!(
+        "SET TimeZone = 'UTC'; \
+         CREATE TEMP TABLE diff_test (id int, data jsonb, s text COLLATE \"C\", n numeric, \
+             b boolean, tags text[]); \
+         INSERT INTO diff_test (id, data) VALUES {}; \
+         UPDATE diff_test SET s = data->>'s', n = (data->>'n')::numeric, \
+             b = (data->>'b')::boolean, \
+             tags = CASE WHEN jsonb_typeof(data->'tags') = 'array' \
+                 THEN ARRAY(SELECT jsonb_array_elements_text(data->'tags')) END",
+        values.join(", ")
+    ))
+    .await
+    .expect("setup");
+
+    let runs = cases().into_iter().flat_map(|case| {
+        let typed = case.typed.map(|typed| (case.rust.clone(), typed));
+        std::iter::once((case.rust.clone(), case.rust)).chain(typed)
+    });
+    for (rust_op, sql_op) in runs {
+        let predicate = render(&sql_op);
+        let rust: Vec<_> = rows.iter().map(|row| rust_op.matches(row).ok()).collect();
+        let sql = match evaluate(&mut conn, &predicate, None).await {
+            Ok(sql) => sql,
+            // Find the rows that fail: exactly those must fail in memory
+            Err(_) => {
+                let mut sql = Vec::with_capacity(rows.len());
+                for id in 0..rows.len() {
+                    sql.push(match evaluate(&mut conn, &predicate, Some(id)).await {
+                        Ok(result) => result[0],
+                        Err(_) => None,
+                    });
+                }
+                sql
+            }
+        };
+        assert_eq!(
+            rust, sql,
+            "matches() disagrees with SQL (None: error) for {:?}: {}",
+            rust_op, predicate
+        );
+    }
+
+    drop(conn);
+    admin
+        .simple_query("DROP DATABASE fraiseql_diff_test WITH (FORCE)")
+        .await
+        .expect("drop database");
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_jsonb_operators_use_gin_indexes() {
+    use WhereOperator::*;
+
+    let mut conn = connect("postgres").await;
+
+    conn.simple_query(
+        "CREATE TEMP TABLE gin_test (id int, data jsonb); \
+         INSERT INTO gin_test SELECT i, jsonb_build_object('owner', \
+             jsonb_build_object('team', 'team' || (i % 10)), 'score', i, \
+             'tags', jsonb_build_array('t' || (i % 7))) FROM generate_series(1, 1000) i; \
+         CREATE INDEX gin_test_path ON gin_test USING gin (data jsonb_path_ops); \
+         CREATE INDEX gin_test_ops ON gin_test USING gin (data); \
+         ANALYZE gin_test; \
+         SET enable_seqscan = off",
+    )
+    .await
+    .expect("setup");
+
+    let root = || Field::JsonbPath(vec![]);
+    let cases = vec![
+        (JsonContains(path(&["owner", "team"]), json!("team3")), 100),
+        (JsonContains(jsonb("tags"), json!(["t2"])), 143),
+        (HasKey(root(), "owner".into()), 1000),
+        (
+            HasAnyKey(root(), vec!["missing".into(), "score".into()]),
+            1000,
+        ),
+        (
+            HasAllKeys(root(), vec!["missing".into(), "score".into()]),
+            0,
+        ),
+        (
+            JsonPathExists {
+                field: root(),
+                path: "$.tags[*] ? (@ == \"t5\")".into(),
+                vars: None,
+            },
+            143,
+        ),
+        (
+            JsonPathMatch {
+                field: root(),
+                path: "$.score == 42".into(),
+                vars: None,
+            },
+            1,
+        ),
+    ];
+
+    let text = |messages: &[BackendMessage]| -> Vec<String> {
+        messages
+            .iter()
+            .filter_map(|m| match m {
+                BackendMessage::DataRow(cols) => cols[0]
+                    .as_deref()
+                    .map(|v| String::from_utf8_lossy(v).into_owned()),
+                BackendMessage::ErrorResponse(fields) => panic!("query failed: {:?}", fields),
+                _ => None,
+            })
+            .collect()
+    };
+    for (op, expected) in cases {
+        let predicate = render(&op);
+        let plan = text(
+            &conn
+                .simple_query(&format!(
+                    "EXPLAIN SELECT data FROM gin_test WHERE {}",
+                    predicate
+                ))
+                .await
+                .expect("explain"),
+        );
+        assert!(
+            plan.iter().any(|line| line.contains("Bitmap Index Scan")),
+            "{} did not use a GIN index: {:#?}",
+            predicate,
+            plan
+        );
+
+        let count = text(
+            &conn
+                .simple_query(&format!(
+                    "SELECT count(*) FROM gin_test WHERE {}",
+                    predicate
+                ))
+                .await
+                .expect("count"),
+        );
+        assert_eq!(count, vec![expected.to_string()], "{}", predicate);
+    }
+
+    // Variables go through jsonb_path_exists, which still filters correctly
+    let op = JsonPathExists {
+        field: root(),
+        path: "$.score ? (@ > $min)".into(),
+        vars: Some(json!({"min": 990})),
+    };
+    let count = text(
+        &conn
+            .simple_query(&format!(
+                "SELECT count(*) FROM gin_test WHERE {}",
+                render(&op)
+            ))
+            .await
+            .expect("count"),
+    );
+    assert_eq!(count, vec!["10".to_string()]);
+}
@@ -267,4 +267,91 @@ mod scram_integration {
 
         println!("✓ SCRAM authentication succeeded within timeout");
     }
+
+    /// Test that channel_binding=require fails closed on a plaintext connection
+    #[tokio::test]
+    #[ignore] // Requires PostgreSQL with SCRAM enabled
+    async fn test_scram_channel_binding_require_without_tls() {
+        let (db_url, _username, _password) = match get_scram_test_config() {
+            Some(cfg) => cfg,
+            None => {
+                eprintln!("Skipping test: SCRAM_TEST_DB_URL not set");
+                return;
+            }
+        };
+        if db_url.contains("sslmode=") {
+            eprintln!("Skipping test: SCRAM_TEST_DB_URL already configures TLS");
+            return;
+        }
+
+        let separator = if db_url.contains('?') { '&' } else { '?' };
+        let url = format!("{}{}channel_binding=require", db_url, separator);
+
+        match FraiseClient::connect(&url).await {
+            Ok(_) => panic!("channel_binding=require must not connect without TLS"),
+            Err(e) => {
+                assert!(
+                    matches!(e, fraiseql_wire::Error::Authentication(_)),
+                    "expected authentication error, got: {}",
+                    e
+                );
+            }
+        }
+
+        println!("✓ channel_binding=require rejected plaintext connection");
+    }
+
+    /// Stub provider handing out the configured SCRAM credentials
+    #[derive(Debug)]
+    struct EnvProvider {
+        username: String,
+        password: String,
+    }
+
+    impl fraiseql_wire::auth::CredentialProvider for EnvProvider {
+        fn credentials(
+            &self,
+        ) -> futures::future::BoxFuture<'_, fraiseql_wire::Result<fraiseql_wire::auth::Credentials>>
+        {
+            Box::pin(async move {
+                Ok(fraiseql_wire::auth::Credentials::new(
+                    self.username.clone(),
+                    self.password.clone(),
+                ))
+            })
+        }
+    }
+
+    /// Test that SCRAM authentication uses credentials from a provider
+    #[tokio::test]
+    #[ignore] // Requires PostgreSQL with SCRAM enabled
+    async fn test_scram_with_credential_provider() {
+        use fraiseql_wire::auth::CachedCredentialProvider;
+        use fraiseql_wire::connection::ConnectionConfig;
+        use std::sync::Arc;
+
+        let (db_url, username, password) = match get_scram_test_config() {
+            Some(cfg) => cfg,
+            None => {
+                eprintln!("Skipping test: SCRAM_TEST_DB_URL not set");
+                return;
+            }
+        };
+
+        let provider = Arc::new(CachedCredentialProvider::new(EnvProvider {
+            username,
+            password,
+        }));
+        // Static user/password are deliberately wrong: the provider must win
+        let config = ConnectionConfig::builder("postgres", "nobody")
+            .password("wrong")
+            .credential_provider(provider)
+            .build();
+
+        if let Err(e) = FraiseClient::connect_with_config(&db_url, config).await {
+            panic!("Failed to connect with provider credentials: {}", e);
+        }
+
+        println!("✓ SCRAM authentication succeeded with credential provider");
+    }
 }
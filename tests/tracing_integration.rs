@@ -0,0 +1,163 @@
+//! Integration tests for tracing spans
+//!
+//! These tests require a running Postgres instance.
+
+use fraiseql_wire::FraiseClient;
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// A closed or open span as seen by the recorder
+#[derive(Debug, Clone, Default)]
+struct RecordedSpan {
+    name: String,
+    parent: Option<String>,
+    fields: HashMap<String, String>,
+    events: Vec<String>,
+}
+
+#[derive(Default)]
+struct FieldVisitor(HashMap<String, String>);
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+/// Layer recording every span with its parent, fields and events
+#[derive(Clone, Default)]
+struct Recorder {
+    spans: Arc<Mutex<HashMap<u64, RecordedSpan>>>,
+}
+
+impl Recorder {
+    fn named(&self, name: &str) -> Vec<RecordedSpan> {
+        self.spans
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|s| s.name == name)
+            .cloned()
+            .collect()
+    }
+}
+
+impl<S> Layer<S> for Recorder
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        let parent = ctx
+            .span(id)
+            .and_then(|span| span.parent())
+            .map(|parent| parent.name().to_string());
+        self.spans.lock().unwrap().insert(
+            id.into_u64(),
+            RecordedSpan {
+                name: attrs.metadata().name().to_string(),
+                parent,
+                fields: visitor.0,
+                events: Vec::new(),
+            },
+        );
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&id.into_u64()) {
+            span.fields.extend(visitor.0);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let Some(message) = visitor.0.remove("message") else {
+            return;
+        };
+        if let Some(span) = ctx.event_span(event) {
+            if let Some(recorded) = self.spans.lock().unwrap().get_mut(&span.id().into_u64()) {
+                recorded.events.push(message);
+            }
+        }
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires Postgres running
+async fn test_connect_and_query_spans() {
+    let recorder = Recorder::default();
+    let subscriber = tracing_subscriber::registry().with(recorder.clone());
+    // Current-thread runtime: the reader task runs on this thread and sees the subscriber
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let client = FraiseClient::connect("postgres://postgres@localhost:5432/postgres")
+        .await
+        .expect("connect");
+
+    let connect = recorder.named("connect");
+    assert_eq!(connect.len(), 1);
+    assert_eq!(connect[0].fields["db.system"], "postgresql");
+    assert_eq!(connect[0].fields["db.name"], "postgres");
+    for child in ["dns", "tcp", "startup"] {
+        let spans = recorder.named(child);
+        assert_eq!(spans.len(), 1, "{} span", child);
+        assert_eq!(
+            spans[0].parent.as_deref(),
+            Some("connect"),
+            "{} parent",
+            child
+        );
+    }
+    let auth = recorder.named("auth");
+    assert_eq!(auth[0].parent.as_deref(), Some("startup"));
+    assert!(auth[0].fields.contains_key("mechanism"));
+
+    let mut stream = client
+        .query_from_sql::<serde_json::Value>(
+            "(SELECT jsonb_build_object('n', i) AS data FROM generate_series(1, 50) i) t",
+        )
+        .where_sql("(data->>'n')::int > 10")
+        .chunk_size(8)
+        .execute()
+        .await
+        .expect("execute");
+    stream.next().await.expect("row").expect("row");
+    stream.pause().await.expect("pause");
+    stream.resume().await.expect("resume");
+    let mut rows = 1;
+    while let Some(row) = stream.next().await {
+        row.expect("row");
+        rows += 1;
+    }
+    assert_eq!(rows, 40);
+    drop(stream);
+    tokio::task::yield_now().await;
+
+    let query = recorder.named("query");
+    assert_eq!(query.len(), 1);
+    let query = &query[0];
+    assert_eq!(query.fields["db.system"], "postgresql");
+    assert_eq!(query.fields["db.name"], "postgres");
+    assert_eq!(query.fields["db.operation"], "SELECT");
+    let statement = &query.fields["db.statement"];
+    assert!(statement.contains("(data->>'n')::int > ?"), "{}", statement);
+    assert!(!statement.contains("> 10"), "{}", statement);
+    assert_eq!(query.fields["rows"], "40");
+    assert!(query.fields["bytes"].parse::<u64>().unwrap() >= 40 * 8);
+    assert_eq!(query.events, vec!["pause", "resume"]);
+}